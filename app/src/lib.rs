@@ -20,6 +20,7 @@ use crate::pages::register::RegisterPage;
 use crate::pages::roadmap_page::RoadmapPage;
 use crate::pages::search_page::SearchPage;
 use crate::pages::subscriptions::manage_subscription_page::ManageSubscriptionsPage;
+use crate::pages::top_rated_page::TopRatedPage;
 use entities_lib::ShareableArgsValues;
 use entities_lib::entities::user::User;
 use leptos::hydration::HydrationScripts;
@@ -183,6 +184,7 @@ pub fn App() -> impl IntoView {
                                 <Route path=StaticSegment("/roadmap") view=RoadmapPage ssr=SsrMode::Async />
                                 <Route path=path!("/notifications") view=NotificationsPage />
                                 <Route path=path!("/most-recent/:page") view=MostRecentPage />
+                                <Route path=path!("/top-rated/:page") view=TopRatedPage />
                                 <Route path=path!("/manage/subscriptions") view=ManageSubscriptionsPage />
                                 <Route path=path!("/author/:author_id/:author_name/:page") view=AuthorPage ssr=SsrMode::Async />
                                 <Route path=path!("/reader/:reader_id/:reader_name/:page") view=ReaderPage ssr=SsrMode::Async/>
@@ -193,6 +195,7 @@ pub fn App() -> impl IntoView {
                                 <Route path=path!("/audiobook/:audiobook_id") view=AudiobookDetailedView ssr=SsrMode::Async/>
 
                                 <Route path=path!("/search/:search_string") view=SearchPage ssr=SsrMode::Async/>
+                                <Route path=path!("/search/:search_string/:page") view=SearchPage ssr=SsrMode::Async/>
 
                                 <Route path=StaticSegment("/register") view=RegisterPage />
                                 <Route path=StaticSegment("/login") view=AuthPage />
@@ -28,6 +28,7 @@ async fn get_audiobook_by_id(
         &pgpool,
         &cache,
         request_type,
+        &[],
         1, // Getting only one audiobook since we are retrieving by id.
     )
     .await
@@ -2,6 +2,7 @@ use crate::pages::get_counts;
 use crate::ui_components::ads::grid_ad::GridAd;
 use crate::ui_components::audiobook::audiobook_container::AudioBookCollectionContainer;
 use crate::ui_components::paginator::Paginator;
+use entities_lib::entities::hardcover::HardcoverAuthorProfile;
 use entities_lib::{
     Author, GetAudioBookRequestType, MetaRequest, MetaResponse, ShareableArgsValues,
     SubscriptionType,
@@ -32,6 +33,62 @@ impl TryFrom<&AuthorParam> for Author {
     }
 }
 
+/// Bio, picture and number of books of the author from Hardcover, if the author was enriched.
+#[component]
+fn HardcoverAuthorCard(author: Signal<Author>) -> impl IntoView {
+    let profile_op = Resource::new(author, move |entity| {
+        get_counts(MetaRequest::HardcoverAuthorProfile(entity))
+    });
+    let profile = move || match profile_op.get() {
+        Some(Ok(MetaResponse::HardcoverAuthorProfile(profile))) => profile,
+        _ => None,
+    };
+
+    view! {
+        <Transition fallback=|| ()>
+            {move || {
+                profile()
+                    .map(|HardcoverAuthorProfile { bio, image_url, books_count, url }| {
+                        view! {
+                            <div class="container is-max-desktop">
+                                <div class="box">
+                                    <article class="media">
+                                        {image_url
+                                            .map(|image_url| {
+                                                view! {
+                                                    <figure class="media-left">
+                                                        <p class="image is-96x96">
+                                                            <img src=image_url alt=move || author.get().name />
+                                                        </p>
+                                                    </figure>
+                                                }
+                                            })}
+                                        <div class="media-content">
+                                            <div class="content">
+                                                {bio.map(|bio| view! { <p>{bio}</p> })}
+                                                <p class="is-size-7 has-text-grey">
+                                                    {books_count.map(|count| format!("{count} books on "))}
+                                                    {url
+                                                        .map(|url| {
+                                                            view! {
+                                                                <a href=url target="_blank" rel="noopener noreferrer">
+                                                                    "Hardcover"
+                                                                </a>
+                                                            }
+                                                        })}
+                                                </p>
+                                            </div>
+                                        </div>
+                                    </article>
+                                </div>
+                            </div>
+                        }
+                    })
+            }}
+        </Transition>
+    }
+}
+
 #[component]
 pub fn AuthorPage() -> impl IntoView {
     let params = use_params::<AuthorParam>();
@@ -82,6 +139,7 @@ pub fn AuthorPage() -> impl IntoView {
         <div class="container is-max-desktop is-flex is-justify-content-center">
             <GridAd ad_slot="1117011249" />
         </div>
+        <HardcoverAuthorCard author=Signal::derive(author_entity) />
         <Paginator current_page=Signal::derive(current_page) n_pages=get_amount path=path/>
             <AudioBookCollectionContainer
                 title=section_title
@@ -2,6 +2,7 @@ use crate::pages::get_counts;
 use crate::ui_components::ads::grid_ad::GridAd;
 use crate::ui_components::audiobook::audiobook_container::AudioBookCollectionContainer;
 use crate::ui_components::paginator::Paginator;
+use entities_lib::entities::hardcover::{HardcoverSeriesBook, HardcoverSeriesProfile};
 use entities_lib::{
     GetAudioBookRequestType, MetaRequest, MetaResponse, Series, ShareableArgsValues,
     SubscriptionType,
@@ -36,6 +37,74 @@ impl TryFrom<&SeriesParam> for Series {
     }
 }
 
+/// Description and books of the series from Hardcover, if the series was enriched.
+#[component]
+fn HardcoverSeriesCard(series: Signal<Series>) -> impl IntoView {
+    let profile_op = Resource::new(series, move |entity| {
+        get_counts(MetaRequest::HardcoverSeriesProfile(entity))
+    });
+    let profile = move || match profile_op.get() {
+        Some(Ok(MetaResponse::HardcoverSeriesProfile(profile))) => profile,
+        _ => None,
+    };
+
+    view! {
+        <Transition fallback=|| ()>
+            {move || {
+                profile()
+                    .map(|HardcoverSeriesProfile { description, books_count, primary_books_count, url, books }| {
+                        let counts = match (primary_books_count, books_count) {
+                            (Some(primary), Some(total)) if primary != total => {
+                                Some(format!("{primary} books ({total} including novellas and extras) on "))
+                            }
+                            (_, Some(total)) => Some(format!("{total} books on ")),
+                            _ => None,
+                        };
+                        view! {
+                            <div class="container is-max-desktop">
+                                <div class="box content">
+                                    {description.map(|description| view! { <p>{description}</p> })}
+                                    <ul>
+                                        {books
+                                            .into_iter()
+                                            .map(|HardcoverSeriesBook { title, position, url }| {
+                                                let prefix = position
+                                                    .map(|position| format!("#{position} "))
+                                                    .unwrap_or_default();
+                                                view! {
+                                                    <li>
+                                                        {prefix}
+                                                        {match url {
+                                                            Some(url) => view! {
+                                                                <a href=url target="_blank" rel="noopener noreferrer">{title}</a>
+                                                            }.into_any(),
+                                                            None => title.into_any(),
+                                                        }}
+                                                    </li>
+                                                }
+                                            })
+                                            .collect_view()}
+                                    </ul>
+                                    <p class="is-size-7 has-text-grey">
+                                        {counts}
+                                        {url
+                                            .map(|url| {
+                                                view! {
+                                                    <a href=url target="_blank" rel="noopener noreferrer">
+                                                        "Hardcover"
+                                                    </a>
+                                                }
+                                            })}
+                                    </p>
+                                </div>
+                            </div>
+                        }
+                    })
+            }}
+        </Transition>
+    }
+}
+
 #[component]
 pub fn BySeriesPage() -> impl IntoView {
     let params = use_params::<SeriesParam>();
@@ -93,6 +162,7 @@ pub fn BySeriesPage() -> impl IntoView {
         <div class="container is-max-desktop is-flex is-justify-content-center">
             <GridAd ad_slot="1117011249" />
         </div>
+        <HardcoverSeriesCard series=Signal::derive(series_entity) />
         <Paginator current_page=Signal::derive(current_page) n_pages=get_amount path=path/>
         <AudioBookCollectionContainer
             title=Signal::derive(section_title)
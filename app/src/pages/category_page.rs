@@ -4,9 +4,11 @@ use crate::ui_components::audiobook::audiobook_container::AudioBookCollectionCon
 use crate::ui_components::paginator::Paginator;
 use entities_lib::{
     Category, GetAudioBookRequestType, MetaRequest, MetaResponse, ShareableArgsValues,
+    SubscriptionType,
 };
 use leptos::logging;
 use leptos::prelude::*;
+use leptos_router::components::A;
 use leptos_router::hooks::use_params;
 use leptos_router::params::Params;
 
@@ -59,12 +61,26 @@ pub fn CategoryPage() -> impl IntoView {
     let category_entity = move || category_data.get().0;
     let current_page = move || category_data.get().1;
 
+    // Whether the audiobooks of the subcategories are listed too.
+    let (include_subcategories, set_include_subcategories) = signal(true);
+
     let section_title = move || format!("Audiobooks with category {}", category_entity().value);
 
     // Resource now depends on the stable memoized category
-    let get_count_op = Resource::new(category_entity, move |cat| {
-        logging::debug_warn!("Performing request");
-        get_counts(MetaRequest::CountAudiobooksForCategory(cat))
+    let get_count_op = Resource::new(
+        move || (category_entity(), include_subcategories.get()),
+        move |(cat, include_subcategories)| {
+            logging::debug_warn!("Performing request");
+            if include_subcategories {
+                get_counts(MetaRequest::CountAudiobooksForCategoryTree(cat))
+            } else {
+                get_counts(MetaRequest::CountAudiobooksForCategory(cat))
+            }
+        },
+    );
+
+    let subcategories = Resource::new(category_entity, move |cat| {
+        get_counts(MetaRequest::SubcategoriesOf(cat))
     });
 
     Effect::new(move || {
@@ -89,13 +105,60 @@ pub fn CategoryPage() -> impl IntoView {
         <div class="container is-max-desktop is-flex is-justify-content-center">
             <GridAd ad_slot="1117011249" />
         </div>
+        <Suspense>
+            {move || {
+                subcategories
+                    .get()
+                    .and_then(|res| match res {
+                        Ok(MetaResponse::Categories(list)) if !list.is_empty() => Some(list),
+                        _ => None,
+                    })
+                    .map(|list| {
+                        view! {
+                            <div class="container is-max-desktop">
+                                <div class="tags are-normal">
+                                    {"Subcategories: "}
+                                    {list
+                                        .into_iter()
+                                        .map(|category| {
+                                            view! {
+                                                <A
+                                                    href=format!("/category/{}/{}/1", category.id, category.value)
+                                                    class:tag=true
+                                                    class:is-info=true
+                                                >
+                                                    {category.value}
+                                                </A>
+                                            }
+                                        })
+                                        .collect_view()}
+                                </div>
+                                <label class="checkbox">
+                                    <input
+                                        type="checkbox"
+                                        prop:checked=include_subcategories
+                                        on:change=move |ev| set_include_subcategories(event_target_checked(&ev))
+                                    />
+                                    " Include the audiobooks of the subcategories"
+                                </label>
+                            </div>
+                        }
+                    })
+            }}
+        </Suspense>
         <Paginator current_page=Signal::derive(current_page) n_pages=get_amount path=path/>
             <AudioBookCollectionContainer
                 title=Signal::derive(section_title)
                 request_type=Signal::derive(move || {
-                    GetAudioBookRequestType::ByCategory(category_entity(), current_page())
+                    if include_subcategories.get() {
+                        GetAudioBookRequestType::ByCategoryTree(category_entity(), current_page())
+                    } else {
+                        GetAudioBookRequestType::ByCategory(category_entity(), current_page())
+                    }
                 })
-                subscription_type=None
+                subscription_type=Some(Signal::derive(move || {
+                    SubscriptionType::ToCategory(category_entity())
+                }))
             />
         <Paginator current_page=Signal::derive(current_page) n_pages=get_amount path=path />
     }
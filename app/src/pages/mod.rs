@@ -14,6 +14,7 @@ pub(crate) mod register;
 pub(crate) mod roadmap_page;
 pub(crate) mod search_page;
 pub(crate) mod subscriptions;
+pub(crate) mod top_rated_page;
 
 use entities_lib::{MetaRequest, MetaResponse};
 use leptos::prelude::*;
@@ -24,10 +25,13 @@ pub(super) async fn get_counts(request: MetaRequest) -> Result<MetaResponse, Ser
     use shared::db_ops::parade::meta_ops::get_meta_cached;
     use shared::state::AppState;
 
+    use crate::utils::languages::current_user_languages;
+
     let state = AppState::get_app_state()?;
     let db_pool = state.database_connection_pool;
     let cache = state.meta_requests_cache;
-    get_meta_cached(&db_pool, &cache, request)
+    let languages = current_user_languages(&db_pool).await?;
+    get_meta_cached(&db_pool, &cache, request, &languages)
         .await
         .map_err(|e| ServerFnError::new(format!("{e:?}")))
 }
@@ -60,6 +60,7 @@ async fn get_notifications() -> Result<Vec<(UserNotification, AudiobookWithData)
         &pgpool,
         &cache,
         entities_lib::GetAudioBookRequestType::ByIdList(audiobook_ids),
+        &[],
         20,
     )
     .await
@@ -177,8 +178,10 @@ fn NotificationRow(
     mark_as_read_action: Action<UserNotification, Result<(), ServerFnError>>,
     delete_notification_action: Action<UserNotification, Result<(), ServerFnError>>,
 ) -> impl IntoView {
-    let (notification, (audiobook, authors, _categories, _keywords, _readers, _maybe_series)) =
-        item;
+    let (
+        notification,
+        (audiobook, authors, _categories, _keywords, _readers, _maybe_series, _maybe_rating),
+    ) = item;
 
     // Clone for the event closure
     let notification_for_closure = notification.clone();
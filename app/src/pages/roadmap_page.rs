@@ -34,11 +34,7 @@ pub fn RoadmapPage() -> impl IntoView {
                             </tr>
                             <tr>
                                 <td>"Recommendations of similar books"</td>
-                                <td>
-                                    <span class="tag is-info">"Planned"</span>
-                                    <br/>
-                                    <small>"Have the embeddings, missing the logic and surfacing it to the UI"</small>
-                                </td>
+                                <td><span class="tag is-success">"Done"</span></td>
                             </tr>
                             <tr>
                                 <td>"Add form for feedback"</td>
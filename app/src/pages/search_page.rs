@@ -1,29 +1,47 @@
 use crate::ui_components::ads::grid_ad::GridAd;
 use crate::ui_components::audiobook::audiobook_container::AudioBookCollectionContainer;
-use entities_lib::{GetAudioBookRequestType, SearchQuery};
+use crate::ui_components::paginator::Paginator;
+use crate::ui_components::score_breakdowns::ScoreBreakdownsTable;
+use crate::ui_components::search_facets::{SearchFacetsPanel, search_pages_path};
+use entities_lib::{GetAudioBookRequestType, SearchQuery, SearchResults, ShareableArgsValues};
 use leptos::prelude::*;
 use leptos::{Params, logging};
 use leptos_meta::Title;
-use leptos_router::hooks::use_params;
+use leptos_router::hooks::{use_params, use_query_map};
 use leptos_router::params::Params;
 
 #[allow(clippy::unused_async)]
 #[server(SearchAudiobooks, "/api")]
-async fn search_audiobooks_server_fn(search_query: SearchQuery) -> Result<Vec<i64>, ServerFnError> {
+async fn search_audiobooks_server_fn(
+    search_query: SearchQuery,
+    page: u32,
+) -> Result<SearchResults, ServerFnError> {
+    use entities_lib::Environment;
     use shared::db_ops::parade::search_ops::search_audiobooks;
     use shared::state::AppState;
     use tracing::{debug, info};
 
+    use crate::utils::languages::current_user_languages;
+
     debug!("Before getting app state");
     let state = AppState::get_app_state()?;
     debug!("Gotten app state");
     let _limit_audiobooks = state.shareable_args.max_search_results;
     debug!("Pulled what we needed out of the context.");
+    let mut search_query = search_query;
+    // The breakdowns reveal how the ranking is configured, so they're only shown in development.
+    search_query.debug &= state.shareable_args.environment == Environment::DEV;
+    if search_query.languages.is_empty() {
+        search_query.languages = current_user_languages(&state.database_connection_pool).await?;
+    }
     info!("Gotten results for query {:?}", search_query);
     search_audiobooks(
         &state.database_connection_pool,
+        &state.search_cache,
         &search_query,
+        page,
         &state.shareable_args,
+        &state.ranking,
         state.embedder,
     )
     .await
@@ -33,6 +51,7 @@ async fn search_audiobooks_server_fn(search_query: SearchQuery) -> Result<Vec<i6
 #[derive(Params, PartialEq)]
 struct SearchParam {
     search_string: Option<String>,
+    page: Option<u32>,
 }
 
 #[component]
@@ -46,29 +65,55 @@ pub fn SearchPage() -> impl IntoView {
             .and_then(|p| p.search_string.clone())
             .unwrap()
     };
+    let current_page = move || {
+        params
+            .read()
+            .as_ref()
+            .ok()
+            .and_then(|p| p.page)
+            .unwrap_or(1)
+    };
+    let shareable_args = use_context::<ReadSignal<Option<ShareableArgsValues>>>();
+    let query_map = use_query_map();
+    // The filters of the search are kept in the query parameters of the URL.
+    let filtered_search_query = Memo::new(move |_| {
+        let query_map = query_map.read();
+        SearchQuery::with_url_params(search_query(), |param| {
+            query_map.get_all(param).unwrap_or_default()
+        })
+    });
     let section_title = move || format!("Search results: {}", search_query());
-    let audiobook_ids: RwSignal<Option<Vec<i64>>> = RwSignal::new(None);
+    let search_results: RwSignal<Option<SearchResults>> = RwSignal::new(None);
+    let audiobook_ids = Signal::derive(move || {
+        search_results
+            .get()
+            .map(|search_results| search_results.audiobook_ids)
+    });
 
-    let get_audiobooks_ids_resource = Resource::new(search_query, move |query| {
-        let sq = SearchQuery {
-            search_string: query,
-        };
-        search_audiobooks_server_fn(sq)
+    let get_audiobooks_ids_resource = Resource::new(
+        move || (filtered_search_query.get(), current_page()),
+        |(search_query, page)| search_audiobooks_server_fn(search_query, page),
+    );
+    let n_pages = Signal::derive(move || {
+        let total_hits = search_results.get()?.total_hits;
+        let page_size = u32::try_from(shareable_args?.get()?.max_search_results).ok()?;
+        Some(total_hits.div_ceil(page_size.max(1)))
     });
+    let path = Memo::new(move |_| search_pages_path(&filtered_search_query.get()));
 
     Effect::new(move || {
         let result = get_audiobooks_ids_resource.get();
         match result {
             Some(Ok(data)) => {
-                logging::debug_warn!("Found {} audiobook ids", &data.len());
-                audiobook_ids.set(Some(data));
+                logging::debug_warn!("Found {} audiobook ids", &data.audiobook_ids.len());
+                search_results.set(Some(data));
             }
             Some(Err(e)) => {
                 logging::debug_warn!("{:?}", e);
-                audiobook_ids.set(None);
+                search_results.set(None);
             }
             None => {
-                audiobook_ids.set(None);
+                search_results.set(None);
             }
         }
     });
@@ -82,10 +127,30 @@ pub fn SearchPage() -> impl IntoView {
             when=move || audiobook_ids.get().is_some()
             fallback=move || view! { <p>"loading"</p> }
         >
+            {move || {
+                search_results
+                    .get()
+                    .map(|search_results| {
+                        view! {
+                            <SearchFacetsPanel
+                                search_query=filtered_search_query.get()
+                                facets=search_results.facets
+                            />
+                        }
+                    })
+            }}
             <Show
                 when=move || !audiobook_ids.get().unwrap().is_empty()
                 fallback=move || view! { <p>No audiobooks found.</p> }
             >
+                <p class="has-text-grey mb-2">
+                    {move || {
+                        search_results
+                            .get()
+                            .map(|search_results| format!("About {} results", search_results.total_hits))
+                    }}
+                </p>
+                <Paginator current_page=Signal::derive(current_page) n_pages=n_pages path=path/>
                 <AudioBookCollectionContainer
                     title=Signal::derive(move || {
                         format!("Search results for query: {}", search_query())
@@ -95,7 +160,18 @@ pub fn SearchPage() -> impl IntoView {
                     ))
                     subscription_type=None
                 />
+                <Paginator current_page=Signal::derive(current_page) n_pages=n_pages path=path/>
             </Show>
+            {move || {
+                search_results
+                    .get()
+                    .filter(|search_results| !search_results.score_breakdowns.is_empty())
+                    .map(|search_results| {
+                        view! {
+                            <ScoreBreakdownsTable score_breakdowns=search_results.score_breakdowns />
+                        }
+                    })
+            }}
 
         </Show>
     }
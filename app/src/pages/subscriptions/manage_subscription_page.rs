@@ -1,3 +1,4 @@
+use entities_lib::Language;
 use entities_lib::entities::subscription::Subscription;
 use leptos::prelude::*;
 use leptos_meta::Title;
@@ -53,6 +54,70 @@ async fn remove_subscription(sub: Subscription) -> Result<(), ServerFnError> {
         .map_err(|e| ServerFnError::new(format!("{e:?}")));
 }
 
+#[server(GetNotifyNewEditions, "/api")]
+async fn get_notify_new_editions() -> Result<bool, ServerFnError> {
+    use shared::auth_user::AuthSession;
+    use shared::db_ops::parade::subscription_ops::get_notify_new_editions;
+    use shared::state::AppState;
+
+    let state = AppState::get_app_state()?;
+    let pgpool = state.database_connection_pool;
+    let auth_session =
+        use_context::<AuthSession>().ok_or(ServerFnError::new("Couldn't find auth session"))?;
+    let user = auth_session
+        .current_user
+        .ok_or(ServerFnError::new("Couldn't find current user."))?;
+    get_notify_new_editions(&pgpool, user.id)
+        .await
+        .map_err(|e| ServerFnError::new(format!("{e:?}")))
+}
+
+#[server(SetNotifyNewEditions, "/api")]
+async fn set_notify_new_editions(notify_new_editions: bool) -> Result<(), ServerFnError> {
+    use shared::auth_user::AuthSession;
+    use shared::db_ops::parade::subscription_ops::set_notify_new_editions;
+    use shared::state::AppState;
+
+    let state = AppState::get_app_state()?;
+    let pgpool = state.database_connection_pool;
+    let auth_session =
+        use_context::<AuthSession>().ok_or(ServerFnError::new("Couldn't find auth session"))?;
+    let user = auth_session
+        .current_user
+        .ok_or(ServerFnError::new("Couldn't find current user."))?;
+    set_notify_new_editions(&pgpool, user.id, notify_new_editions)
+        .await
+        .map_err(|e| ServerFnError::new(format!("{e:?}")))
+}
+
+#[server(GetPreferredLanguages, "/api")]
+async fn get_preferred_languages() -> Result<Vec<Language>, ServerFnError> {
+    use shared::state::AppState;
+
+    use crate::utils::languages::current_user_languages;
+
+    let state = AppState::get_app_state()?;
+    current_user_languages(&state.database_connection_pool).await
+}
+
+#[server(SetPreferredLanguages, "/api")]
+async fn set_preferred_languages(languages: Vec<Language>) -> Result<(), ServerFnError> {
+    use shared::auth_user::AuthSession;
+    use shared::db_ops::parade::subscription_ops::set_preferred_languages;
+    use shared::state::AppState;
+
+    let state = AppState::get_app_state()?;
+    let pgpool = state.database_connection_pool;
+    let auth_session =
+        use_context::<AuthSession>().ok_or(ServerFnError::new("Couldn't find auth session"))?;
+    let user = auth_session
+        .current_user
+        .ok_or(ServerFnError::new("Couldn't find current user."))?;
+    set_preferred_languages(&pgpool, user.id, &languages)
+        .await
+        .map_err(|e| ServerFnError::new(format!("{e:?}")))
+}
+
 #[component]
 pub fn ManageSubscriptionsPage() -> impl IntoView {
     crate::utils::sidebar::use_hide_sidebar();
@@ -73,14 +138,93 @@ pub fn ManageSubscriptionsPage() -> impl IntoView {
         delete_action.dispatch(sub);
     };
 
+    let notify_new_editions_action = Action::new(move |notify_new_editions: &bool| {
+        set_notify_new_editions(*notify_new_editions)
+    });
+    let notify_new_editions_resource = Resource::new(
+        move || notify_new_editions_action.version().get(),
+        move |_| get_notify_new_editions(),
+    );
+
+    let preferred_languages_action =
+        Action::new(move |languages: &Vec<Language>| set_preferred_languages(languages.clone()));
+    let preferred_languages_resource = Resource::new(
+        move || preferred_languages_action.version().get(),
+        move |_| get_preferred_languages(),
+    );
+
     view! {
         <Title text="Manage subscriptions" />
         <div class="container">
+            <div class="field mb-5">
+                <label class="label">
+                    "Languages to show, all of them if none is selected. New subscriptions only notify these languages."
+                </label>
+                <Suspense>
+                    {move || {
+                        preferred_languages_resource
+                            .get()
+                            .map(|res| {
+                                let preferred = res.unwrap_or_default();
+                                Language::ALL
+                                    .into_iter()
+                                    .map(|language| {
+                                        let preferred = preferred.clone();
+                                        view! {
+                                            <label class="checkbox mr-4">
+                                                <input
+                                                    type="checkbox"
+                                                    class="mr-1"
+                                                    prop:checked=preferred.contains(&language)
+                                                    on:change=move |ev| {
+                                                        let mut languages = preferred.clone();
+                                                        if event_target_checked(&ev) {
+                                                            languages.push(language);
+                                                        } else {
+                                                            languages.retain(|l| *l != language);
+                                                        }
+                                                        preferred_languages_action.dispatch(languages);
+                                                    }
+                                                />
+                                                {language.name()}
+                                            </label>
+                                        }
+                                    })
+                                    .collect_view()
+                            })
+                    }}
+                </Suspense>
+            </div>
+            <div class="field mb-5">
+                <label class="checkbox">
+                    <Suspense>
+                        {move || {
+                            notify_new_editions_resource
+                                .get()
+                                .map(|res| {
+                                    view! {
+                                        <input
+                                            type="checkbox"
+                                            class="mr-2"
+                                            prop:checked=res.unwrap_or(false)
+                                            on:change=move |ev| {
+                                                notify_new_editions_action
+                                                    .dispatch(event_target_checked(&ev));
+                                            }
+                                        />
+                                    }
+                                })
+                        }}
+                    </Suspense>
+                    "Notify me of the new editions of the books I was already notified about"
+                </label>
+            </div>
             <table class="table is-hoverable is-fullwidth">
                 <thead>
                     <tr>
                         <th>"Type"</th>
                         <th>"Name"</th>
+                        <th>"Languages"</th>
                         <th>"Action"</th>
                     </tr>
                 </thead>
@@ -88,7 +232,7 @@ pub fn ManageSubscriptionsPage() -> impl IntoView {
                     <Suspense fallback=move || {
                         view! {
                             <tr>
-                                <td colspan="3">"Loading..."</td>
+                                <td colspan="4">"Loading..."</td>
                             </tr>
                         }
                     }>
@@ -100,7 +244,7 @@ pub fn ManageSubscriptionsPage() -> impl IntoView {
                                         Err(e) => {
                                             view! {
                                                 <tr>
-                                                    <td colspan="3">{format!("Error: {e}")}</td>
+                                                    <td colspan="4">{format!("Error: {e}")}</td>
                                                 </tr>
                                             }
                                                 .into_any()
@@ -109,7 +253,7 @@ pub fn ManageSubscriptionsPage() -> impl IntoView {
                                             if subs.is_empty() {
                                                 view! {
                                                     <tr>
-                                                        <td colspan="3">"No subscriptions found."</td>
+                                                        <td colspan="4">"No subscriptions found."</td>
                                                     </tr>
                                                 }
                                                     .into_any()
@@ -121,6 +265,7 @@ pub fn ManageSubscriptionsPage() -> impl IntoView {
                                                             <tr>
                                                                 <td>{sub.render_type()}</td>
                                                                 <td>{sub.render_name()}</td>
+                                                                <td>{sub.render_languages()}</td>
                                                                 <td>
                                                                     <button on:click=move |_| remove_sub(
                                                                         sub_for_handler.clone(),
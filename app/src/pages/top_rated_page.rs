@@ -0,0 +1,77 @@
+use crate::pages::get_counts;
+use crate::ui_components::audiobook::audiobook_container::AudioBookCollectionContainer;
+use crate::ui_components::paginator::Paginator;
+use entities_lib::{GetAudioBookRequestType, MetaRequest, MetaResponse, ShareableArgsValues};
+use leptos::logging;
+use leptos::prelude::*;
+use leptos_router::hooks::use_params;
+use leptos_router::params::Params;
+
+#[derive(Params, PartialEq, Debug, Clone)]
+struct TopRatedParam {
+    page: Option<u32>,
+}
+
+/// Lists the audiobooks with the best Hardcover rating, among the ones rated at least
+/// `top_rated_min_ratings` times.
+#[component]
+pub fn TopRatedPage() -> impl IntoView {
+    let params = use_params::<TopRatedParam>();
+
+    let shareable_args = use_context::<ReadSignal<Option<ShareableArgsValues>>>()
+        .expect("ShareableArgs context must be provided");
+
+    let (get_amount, set_amount) = signal::<Option<u32>>(None);
+    let page = Memo::new(move |_| match params.read().as_ref() {
+        Ok(v) => v.page.unwrap_or(1),
+        Err(_) => 1u32,
+    });
+    let min_ratings =
+        Memo::new(move |_| shareable_args.get().map(|args| args.top_rated_min_ratings));
+
+    let get_count_op = Resource::new(min_ratings, |min_ratings| async move {
+        match min_ratings {
+            Some(min_ratings) => {
+                Some(get_counts(MetaRequest::CountTopRatedAudiobooks(min_ratings)).await)
+            }
+            None => None,
+        }
+    });
+
+    Effect::new(move || {
+        let result = get_count_op.get().flatten();
+        if let Some(Err(e)) = result.clone() {
+            logging::debug_error!("Error: {e:?}");
+        }
+        let args_opt = shareable_args.get();
+
+        if let Some(Ok(MetaResponse::Count(amount))) = result
+            && let Some(args) = args_opt
+        {
+            let per_page = args.guest_user_audiobooks_per_homepage;
+            let pages = if per_page > 0 {
+                amount.div_ceil(per_page)
+            } else {
+                0
+            };
+            set_amount(Some(pages));
+        }
+    });
+
+    let path = Memo::new(move |_| "/top-rated/{}".to_string());
+
+    view! {
+        <Show when=move || min_ratings.get().is_some()>
+            <Paginator current_page=page n_pages=get_amount path=path/>
+                <AudioBookCollectionContainer
+                    title=Signal::derive(move || String::from("Top rated"))
+                    request_type=Signal::derive(move || GetAudioBookRequestType::TopRated(
+                        min_ratings.get().unwrap_or_default(),
+                        page(),
+                    ))
+                    subscription_type=None
+                />
+            <Paginator current_page=page n_pages=get_amount path=path/>
+        </Show>
+    }
+}
@@ -1,19 +1,28 @@
 use entities_lib::{AudiobookWithData, ShareableArgsValues};
 use leptos::prelude::*;
 
+use crate::ui_components::audiobook::edition_picker::EditionPicker;
 use crate::ui_components::audiobook::link_author::AuthorLinks;
 use crate::ui_components::audiobook::link_reader::ReaderLinks;
 use crate::ui_components::audiobook::link_series::SeriesLink;
+use crate::ui_components::audiobook::similar_audiobooks::SimilarAudiobooksCarousel;
 use crate::ui_components::audiobook::tag_category::CategoriesTag;
 use crate::ui_components::audiobook::tag_keyword::KeywordsTag;
+use crate::ui_components::audiobook::tag_rating::HardcoverRatingTag;
+use crate::utils::dates::print_runtime;
 
 #[component]
 pub fn AudioBookDetailedViewComponent(audiobook_with_data: AudiobookWithData) -> impl IntoView {
-    let (audiobook, authors, categories, keywords, readers, maybe_series) = audiobook_with_data;
+    let (audiobook, authors, categories, keywords, readers, maybe_series, maybe_rating) =
+        audiobook_with_data;
     let title = audiobook.title;
     let cover_url = audiobook.cover_url;
     let description = audiobook.description;
     let path = audiobook.path;
+    let maybe_volume = audiobook.series_volume;
+    let maybe_runtime = audiobook.runtime_seconds.map(print_runtime);
+    let editions = audiobook.editions;
+    let audiobook_id = audiobook.id;
 
     let (img_src, set_img_src) = signal(cover_url);
 
@@ -83,8 +92,11 @@ pub fn AudioBookDetailedViewComponent(audiobook_with_data: AudiobookWithData) ->
                         <div class="mb-4">
                             <AuthorLinks authors=authors />
                             <ReaderLinks readers=readers limit=3 />
-                            <SeriesLink maybe_series=maybe_series />
+                            <SeriesLink maybe_series=maybe_series maybe_volume=maybe_volume />
+                            <HardcoverRatingTag maybe_rating=maybe_rating />
+                            <EditionPicker editions=editions current_id=audiobook_id />
                         </div>
+                        {maybe_runtime.map(|runtime| view! { <p class="mb-4">{format!("Runtime: {runtime}")}</p> })}
 
                         <div class="content my-5" inner_html=description />
 
@@ -112,5 +124,6 @@ pub fn AudioBookDetailedViewComponent(audiobook_with_data: AudiobookWithData) ->
                 </div>
             </div>
         </section>
+        <SimilarAudiobooksCarousel audiobook_id=audiobook_id />
     }
 }
\ No newline at end of file
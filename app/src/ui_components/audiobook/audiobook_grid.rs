@@ -6,16 +6,19 @@ use crate::ui_components::subscriptions::subscriptions_panel::SubscriptionPanel;
 
 use leptos_router::components::A;
 
+use crate::ui_components::audiobook::edition_picker::EditionPicker;
 use crate::ui_components::audiobook::link_author::AuthorLinks;
 use crate::ui_components::audiobook::link_reader::ReaderLinks;
 use crate::ui_components::audiobook::link_series::SeriesLink;
 use crate::ui_components::audiobook::tag_category::CategoriesTag;
 use crate::ui_components::audiobook::tag_keyword::KeywordsTag;
+use crate::ui_components::audiobook::tag_rating::HardcoverRatingTag;
 
 #[allow(clippy::too_many_lines)]
 #[component]
 pub fn AudioBookComponentBox(audiobook_with_data: AudiobookWithData) -> impl IntoView {
-    let (audiobook, authors, categories, keywords, readers, maybe_series) = audiobook_with_data;
+    let (audiobook, authors, categories, keywords, readers, maybe_series, maybe_rating) =
+        audiobook_with_data;
     let title = audiobook.title;
     let cover_url = audiobook.cover_url;
     let very_short_description = audiobook.very_short_description;
@@ -55,6 +58,8 @@ pub fn AudioBookComponentBox(audiobook_with_data: AudiobookWithData) -> impl Int
                             <AuthorLinks authors=authors/>
                             <ReaderLinks readers=readers limit=3/>
                             <SeriesLink maybe_series=maybe_series/>
+                            <HardcoverRatingTag maybe_rating=maybe_rating/>
+                            <EditionPicker editions=audiobook.editions current_id=audiobook.id/>
                         </div>
                     </div>
                 </div>
@@ -2,16 +2,19 @@ use entities_lib::{AudiobookWithData, SubscriptionType};
 use leptos::prelude::*;
 use leptos_router::components::A;
 
+use crate::ui_components::audiobook::edition_picker::EditionPicker;
 use crate::ui_components::audiobook::link_author::AuthorLinks;
 use crate::ui_components::audiobook::link_reader::ReaderLinks;
 use crate::ui_components::audiobook::link_series::SeriesLink;
 use crate::ui_components::audiobook::tag_category::CategoriesTag;
 use crate::ui_components::audiobook::tag_keyword::KeywordsTag;
+use crate::ui_components::audiobook::tag_rating::HardcoverRatingTag;
 use crate::ui_components::subscriptions::subscriptions_panel::SubscriptionPanel;
 
 #[component]
 pub fn AudiobookListItem(audiobook_with_data: AudiobookWithData) -> impl IntoView {
-    let (audiobook, authors, categories, keywords, readers, maybe_series) = audiobook_with_data;
+    let (audiobook, authors, categories, keywords, readers, maybe_series, maybe_rating) =
+        audiobook_with_data;
     let fallback_image = "https://placehold.co/128x128?text=No+Cover";
     let (img_src, set_img_src) = signal(audiobook.cover_url.clone());
 
@@ -40,8 +43,10 @@ pub fn AudiobookListItem(audiobook_with_data: AudiobookWithData) -> impl IntoVie
                             <div class="is-size-7 mb-2 mt-1">
                                 <span class="mr-3"><AuthorLinks authors=authors/></span>
                                 <span class="mr-3"><ReaderLinks readers=readers limit=5/></span>
-                                <span><SeriesLink maybe_series=maybe_series/></span>
+                                <span class="mr-3"><SeriesLink maybe_series=maybe_series/></span>
+                                <span><HardcoverRatingTag maybe_rating=maybe_rating/></span>
                             </div>
+                            <EditionPicker editions=audiobook.editions current_id=audiobook.id/>
                             <span class="is-size-7" style="display: -webkit-box; -webkit-line-clamp: 2; -webkit-box-orient: vertical; overflow: hidden;">
                                 {audiobook.very_short_description}
                             </span>
@@ -2,14 +2,16 @@ use entities_lib::{AudiobookWithData, SubscriptionType};
 use leptos::prelude::*;
 use leptos_router::components::A;
 
+use crate::ui_components::audiobook::edition_picker::EditionPicker;
 use crate::ui_components::audiobook::link_author::AuthorLinks;
 use crate::ui_components::audiobook::link_reader::ReaderLinks;
 use crate::ui_components::audiobook::link_series::SeriesLink;
+use crate::ui_components::audiobook::tag_rating::HardcoverRatingTag;
 use crate::ui_components::subscriptions::subscriptions_panel::SubscriptionPanel;
 
 #[component]
 pub fn AudiobookRow(audiobook_with_data: AudiobookWithData) -> impl IntoView {
-    let (audiobook, authors, _, _, readers, maybe_series) = audiobook_with_data;
+    let (audiobook, authors, _, _, readers, maybe_series, maybe_rating) = audiobook_with_data;
     let fallback_image = "https://placehold.co/64x64?text=X";
     let (img_src, set_img_src) = signal(audiobook.cover_url.clone());
 
@@ -38,8 +40,12 @@ pub fn AudiobookRow(audiobook_with_data: AudiobookWithData) -> impl IntoView {
                 </A>
             </td>
             <td><AuthorLinks authors=authors/></td>
-            <td><ReaderLinks readers=readers limit=2/></td>
+            <td>
+                <ReaderLinks readers=readers limit=2/>
+                <EditionPicker editions=audiobook.editions current_id=audiobook.id/>
+            </td>
             <td><SeriesLink maybe_series=maybe_series/></td>
+            <td><HardcoverRatingTag maybe_rating=maybe_rating/></td>
         </tr>
     }
 }
@@ -88,6 +94,7 @@ pub(crate) fn AudioBookCollectionContainer_Table(
                                     <th>"Authors"</th>
                                     <th>"Readers"</th>
                                     <th>"Series"</th>
+                                    <th>"Rating"</th>
                                 </tr>
                             </thead>
                             <tbody>
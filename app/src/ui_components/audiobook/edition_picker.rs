@@ -0,0 +1,48 @@
+use entities_lib::Edition;
+use leptos::prelude::*;
+use leptos_router::NavigateOptions;
+use leptos_router::hooks::use_navigate;
+
+/// Lets the user switch between the editions of a work. Nothing is shown for the works with a
+/// single edition.
+#[component]
+pub fn EditionPicker(editions: Vec<Edition>, current_id: i64) -> impl IntoView {
+    (editions.len() > 1).then(|| {
+        let navigation = use_navigate();
+        let editions_count = editions.len();
+        view! {
+            <div class="field mt-2">
+                <div class="control has-icons-left">
+                    <div class="select is-small">
+                        <select
+                            title=format!("{editions_count} editions of this book")
+                            on:change=move |ev| {
+                                navigation(
+                                    format!("/audiobook/{}", event_target_value(&ev)).as_str(),
+                                    NavigateOptions::default(),
+                                );
+                            }
+                        >
+                            {editions
+                                .into_iter()
+                                .map(|edition| {
+                                    view! {
+                                        <option
+                                            value=edition.audiobook_id.to_string()
+                                            selected=edition.audiobook_id == current_id
+                                        >
+                                            {edition.label()}
+                                        </option>
+                                    }
+                                })
+                                .collect_view()}
+                        </select>
+                    </div>
+                    <span class="icon is-small is-left">
+                        <i class="fas fa-layer-group"></i>
+                    </span>
+                </div>
+            </div>
+        }
+    })
+}
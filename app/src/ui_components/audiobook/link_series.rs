@@ -3,10 +3,17 @@ use leptos::prelude::*;
 use leptos_router::components::A;
 
 #[component]
-pub fn SeriesLink(maybe_series: Option<Series>) -> impl IntoView {
+pub fn SeriesLink(
+    maybe_series: Option<Series>,
+    #[prop(default = None)] maybe_volume: Option<String>,
+) -> impl IntoView {
     maybe_series.map(|series| {
+        let prefix = match maybe_volume {
+            Some(volume) => format!("Book {volume} of \""),
+            None => "Part of \"".to_string(),
+        };
         view! {
-            {"Part of \""}
+            {prefix}
             <A href=format!("/series/{}/{}/1", series.id, series.title)>{series.title.clone()}</A>
             {"\" series"}
         }
@@ -3,19 +3,23 @@ pub mod audiobook_detailed_view;
 mod audiobook_grid;
 mod audiobook_list;
 mod audiobook_table;
+mod edition_picker;
 mod link_author;
 mod link_reader;
 mod link_series;
+mod similar_audiobooks;
 mod tag_category;
 mod tag_keyword;
+mod tag_rating;
 
 use entities_lib::{AudiobookWithData, GetAudioBookRequestType};
 use leptos::prelude::*;
 
 /// Retrieve the audiobooks from the given request type.
 ///
-/// The underlying implementation uses a cache of type `GetAudioBookRequestType ->
-/// Vec<AudiobookWithData>` so that reads are cached.
+/// The underlying implementation uses a cache of type `(GetAudioBookRequestType, LanguageFilter)
+/// -> Vec<AudiobookWithData>` so that reads are cached. Only the audiobooks in the preferred
+/// languages of the user are listed.
 #[server(GetAudiobooks, "/api")]
 pub(crate) async fn get_audiobooks(
     request_type: GetAudioBookRequestType,
@@ -25,6 +29,8 @@ pub(crate) async fn get_audiobooks(
     use shared::state::AppState;
     use tracing::info;
 
+    use crate::utils::languages::current_user_languages;
+
     info!("Before getting app state");
     let state = AppState::get_app_state()?;
     let pgpool = state.database_connection_pool;
@@ -38,8 +44,9 @@ pub(crate) async fn get_audiobooks(
     } else {
         state.shareable_args.user_audiobooks_per_homepage_section
     };
+    let languages = current_user_languages(&pgpool).await?;
     info!("Gotten here.");
-    get_audiobooks_cached(&pgpool, &cache, request_type, limit_audiobooks)
+    get_audiobooks_cached(&pgpool, &cache, request_type, &languages, limit_audiobooks)
         .await
         .map_err(|e| ServerFnError::new(format!("{e:?}")))
 }
@@ -0,0 +1,68 @@
+use entities_lib::GetAudioBookRequestType;
+use leptos::prelude::*;
+use leptos_router::components::A;
+
+use crate::ui_components::audiobook::get_audiobooks;
+
+/// A carousel of the audiobooks with a description similar to the one of the audiobook.
+#[component]
+pub fn SimilarAudiobooksCarousel(audiobook_id: i64) -> impl IntoView {
+    let fallback_image = "https://placehold.co/400x400?text=Cover+not+found";
+    let similar_audiobooks_resource = Resource::new(
+        move || audiobook_id,
+        |audiobook_id| get_audiobooks(GetAudioBookRequestType::SimilarTo(audiobook_id, 1)),
+    );
+
+    view! {
+        <Suspense>
+            {move || {
+                similar_audiobooks_resource
+                    .get()
+                    .and_then(Result::ok)
+                    .filter(|audiobooks| !audiobooks.is_empty())
+                    .map(|audiobooks| {
+                        view! {
+                            <section class="section pt-0">
+                                <div class="container">
+                                    <h2 class="title is-4">"Similar audiobooks"</h2>
+                                    <div class="similar-carousel">
+                                        {audiobooks
+                                            .into_iter()
+                                            .map(|(audiobook, authors, ..)| {
+                                                let authors = authors
+                                                    .into_iter()
+                                                    .map(|author| author.name)
+                                                    .collect::<Vec<_>>()
+                                                    .join(", ");
+                                                view! {
+                                                    <A
+                                                        href=format!("/audiobook/{}", audiobook.id)
+                                                        attr:class="similar-carousel-item"
+                                                    >
+                                                        <figure class="image is-square mb-2">
+                                                            <img
+                                                                src=audiobook
+                                                                    .cover_url
+                                                                    .unwrap_or_else(|| fallback_image.to_string())
+                                                                alt=format!("Cover for {}", audiobook.title)
+                                                                loading="lazy"
+                                                                style="object-fit: cover;"
+                                                            />
+                                                        </figure>
+                                                        <p class="is-size-7 has-text-weight-semibold audiobook-title">
+                                                            {audiobook.title}
+                                                        </p>
+                                                        <p class="is-size-7 audiobook-title">{authors}</p>
+                                                    </A>
+                                                }
+                                            })
+                                            .collect_view()}
+                                    </div>
+                                </div>
+                            </section>
+                        }
+                    })
+            }}
+        </Suspense>
+    }
+}
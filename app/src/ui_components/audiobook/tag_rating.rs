@@ -0,0 +1,16 @@
+use entities_lib::HardcoverRating;
+use leptos::prelude::*;
+
+#[component]
+pub fn HardcoverRatingTag(maybe_rating: Option<HardcoverRating>) -> impl IntoView {
+    maybe_rating.map(|rating| {
+        view! {
+            <span class="tag is-warning is-light" title="Rating on Hardcover">
+                <span class="icon is-small">
+                    <i class="fas fa-star"></i>
+                </span>
+                <span>{format!("{:.2} ({} ratings)", rating.rating, rating.ratings_count)}</span>
+            </span>
+        }
+    })
+}
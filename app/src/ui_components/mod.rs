@@ -3,6 +3,8 @@ pub mod audiobook;
 pub mod footer;
 pub mod navbar;
 pub mod paginator;
+pub mod score_breakdowns;
+pub mod search_facets;
 pub mod sidebar;
 pub mod subscriptions;
 pub mod user_login_component;
@@ -61,6 +61,7 @@ pub fn Navbar() -> impl IntoView {
             <div id="top-navbar" class="navbar-menu" class:is-active=is_active>
                 <div class="navbar-start">
                     <A attr:class="navbar-item" href="/most-recent/1">New arrivals</A>
+                    <A attr:class="navbar-item" href="/top-rated/1">Top rated</A>
                     <A attr:class="navbar-item" href="/about">About</A>
                     <A attr:class="navbar-item" href="/roadmap">Roadmap</A>
                     // Navigate the subscriptions.
@@ -0,0 +1,59 @@
+use entities_lib::ScoreBreakdown;
+use leptos::prelude::*;
+use leptos_router::components::A;
+
+fn format_rank(rank: Option<u32>, score: Option<f64>) -> String {
+    match (rank, score) {
+        (Some(rank), Some(score)) => format!("#{rank} ({score:.3})"),
+        _ => String::from("-"),
+    }
+}
+
+/// Why each result of the search ranked where it did, shown with the `debug` query parameter.
+#[component]
+pub fn ScoreBreakdownsTable(score_breakdowns: Vec<ScoreBreakdown>) -> impl IntoView {
+    view! {
+        <div class="box table-container">
+            <table class="table is-fullwidth is-striped is-hoverable is-narrow">
+                <thead>
+                    <tr>
+                        <th>"Audiobook"</th>
+                        <th>"Vector"</th>
+                        <th>"BM25"</th>
+                        <th>"Fusion"</th>
+                        <th>"Recency"</th>
+                        <th>"Rating"</th>
+                        <th>"Score"</th>
+                    </tr>
+                </thead>
+                <tbody>
+                    {score_breakdowns
+                        .into_iter()
+                        .map(|breakdown| {
+                            view! {
+                                <tr>
+                                    <td>
+                                        <A href=format!(
+                                            "/audiobook/{}",
+                                            breakdown.audiobook_id,
+                                        )>{breakdown.audiobook_id}</A>
+                                    </td>
+                                    <td>
+                                        {format_rank(breakdown.vector_rank, breakdown.vector_score)}
+                                    </td>
+                                    <td>{format_rank(breakdown.bm25_rank, breakdown.bm25_score)}</td>
+                                    <td>{format!("{:.3}", breakdown.fusion_score)}</td>
+                                    <td>{format!("{:+.3}", breakdown.recency_boost)}</td>
+                                    <td>{format!("{:+.3}", breakdown.rating_boost)}</td>
+                                    <td>
+                                        <strong>{format!("{:.3}", breakdown.score)}</strong>
+                                    </td>
+                                </tr>
+                            }
+                        })
+                        .collect_view()}
+                </tbody>
+            </table>
+        </div>
+    }
+}
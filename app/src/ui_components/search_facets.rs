@@ -0,0 +1,183 @@
+use entities_lib::{FacetValue, Language, SearchFacets, SearchQuery};
+use leptos::prelude::*;
+use leptos_router::components::A;
+use leptos_router::params::ParamsMap;
+
+/// Minimum bitrates offered as filters, in kbps.
+const MIN_BITRATES: [i32; 2] = [64, 128];
+
+/// Upload periods offered as filters, in days.
+const UPLOAD_PERIODS: [(u32, &str); 3] = [(7, "Last week"), (30, "Last month"), (365, "Last year")];
+
+/// The URL of the search page for the query.
+pub fn search_href(search_query: &SearchQuery) -> String {
+    format!(
+        "/search/{}{}",
+        search_query.search_string,
+        ParamsMap::from_iter(search_query.url_params()).to_query_string()
+    )
+}
+
+/// The URL of the pages of the search results for the paginator, `{}` standing for the page.
+pub fn search_pages_path(search_query: &SearchQuery) -> String {
+    format!(
+        "/search/{}/{{}}{}",
+        search_query.search_string,
+        ParamsMap::from_iter(search_query.url_params()).to_query_string()
+    )
+}
+
+/// Adds the value if it's missing, removes it otherwise.
+fn toggle<T: PartialEq>(values: &mut Vec<T>, value: T) {
+    if let Some(position) = values.iter().position(|v| *v == value) {
+        values.remove(position);
+    } else {
+        values.push(value);
+    }
+}
+
+/// Adds the value if it isn't the current one, removes it otherwise.
+fn toggle_option<T: PartialEq>(current: &mut Option<T>, value: T) {
+    if current.as_ref() == Some(&value) {
+        *current = None;
+    } else {
+        *current = Some(value);
+    }
+}
+
+#[component]
+fn FacetChip(label: String, active: bool, href: String) -> impl IntoView {
+    view! {
+        <A href=href class:tag=true class:is-link=active class:is-light=!active>
+            {label}
+        </A>
+    }
+}
+
+#[component]
+fn FacetGroup(name: &'static str, children: Children) -> impl IntoView {
+    view! {
+        <div class="tags are-medium mb-2">
+            <span class="mr-2 has-text-weight-semibold">{name}</span>
+            {children()}
+        </div>
+    }
+}
+
+/// Chips for the facet values, each toggling its value in the filters selected by `values`.
+fn facet_chips<T, F>(
+    search_query: &SearchQuery,
+    facet_values: Vec<FacetValue>,
+    parse: fn(&str) -> Option<T>,
+    values: F,
+) -> impl IntoView + use<T, F>
+where
+    T: PartialEq,
+    F: Fn(&mut SearchQuery) -> &mut Vec<T>, {
+    facet_values
+        .into_iter()
+        .filter_map(|facet_value| {
+            let value = parse(&facet_value.value)?;
+            let mut toggled = search_query.clone();
+            let active = values(&mut toggled).contains(&value);
+            toggle(values(&mut toggled), value);
+            Some(view! {
+                <FacetChip
+                    label=format!("{} ({})", facet_value.label, facet_value.count)
+                    active=active
+                    href=search_href(&toggled)
+                />
+            })
+        })
+        .collect_view()
+}
+
+/// The facets of the search results, as chips toggling the filters of the search.
+#[component]
+pub fn SearchFacetsPanel(search_query: SearchQuery, facets: SearchFacets) -> impl IntoView {
+    let unabridged_href = {
+        let mut toggled = search_query.clone();
+        toggle_option(&mut toggled.unabridged, true);
+        search_href(&toggled)
+    };
+    let bitrate_chips = MIN_BITRATES
+        .into_iter()
+        .map(|min_bitrate| {
+            let mut toggled = search_query.clone();
+            toggle_option(&mut toggled.min_bitrate, min_bitrate);
+            view! {
+                <FacetChip
+                    label=format!("≥ {min_bitrate} kbps")
+                    active=search_query.min_bitrate == Some(min_bitrate)
+                    href=search_href(&toggled)
+                />
+            }
+        })
+        .collect_view();
+    let upload_chips = UPLOAD_PERIODS
+        .into_iter()
+        .map(|(days, label)| {
+            let mut toggled = search_query.clone();
+            toggle_option(&mut toggled.uploaded_within_days, days);
+            view! {
+                <FacetChip
+                    label=label.to_string()
+                    active=search_query.uploaded_within_days == Some(days)
+                    href=search_href(&toggled)
+                />
+            }
+        })
+        .collect_view();
+    let language_chips = facet_chips(
+        &search_query,
+        facets.languages,
+        |code| Some(Language::from_code(code)),
+        |query| &mut query.languages,
+    );
+    let category_chips = facet_chips(
+        &search_query,
+        facets.categories,
+        |id| id.parse().ok(),
+        |query| &mut query.categories,
+    );
+    let author_chips = facet_chips(
+        &search_query,
+        facets.authors,
+        |id| id.parse().ok(),
+        |query| &mut query.authors,
+    );
+    let format_chips = facet_chips(
+        &search_query,
+        facets.formats,
+        |format| Some(format.to_string()),
+        |query| &mut query.formats,
+    );
+    // Clearing the filters keeps the debug mode.
+    let cleared = SearchQuery {
+        debug: search_query.debug,
+        ..SearchQuery::new(search_query.search_string.clone())
+    };
+    let has_filters = search_query != cleared;
+    let clear_href = search_href(&cleared);
+
+    view! {
+        <div class="box">
+            <FacetGroup name="Languages">{language_chips}</FacetGroup>
+            <FacetGroup name="Categories">{category_chips}</FacetGroup>
+            <FacetGroup name="Authors">{author_chips}</FacetGroup>
+            <FacetGroup name="Formats">{format_chips}</FacetGroup>
+            <FacetGroup name="Quality">
+                <FacetChip
+                    label="Unabridged".to_string()
+                    active=search_query.unabridged == Some(true)
+                    href=unabridged_href
+                />
+                {bitrate_chips}
+            </FacetGroup>
+            <FacetGroup name="Uploaded">{upload_chips}</FacetGroup>
+            <Show when=move || has_filters>
+                <A href=clear_href.clone()>"Clear the filters"</A>
+            </Show>
+        </div>
+    }
+}
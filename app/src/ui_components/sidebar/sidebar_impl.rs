@@ -11,11 +11,14 @@ pub async fn get_metadata(request: MetaRequest) -> Result<MetaResponse, ServerFn
     use shared::db_ops::parade::meta_ops::get_meta_cached;
     use shared::state::AppState;
 
+    use crate::utils::languages::current_user_languages;
+
     let state = AppState::get_app_state()?;
     let pgpool = state.database_connection_pool;
     let cache = state.meta_requests_cache;
+    let languages = current_user_languages(&pgpool).await?;
 
-    get_meta_cached(&pgpool, &cache, request)
+    get_meta_cached(&pgpool, &cache, request, &languages)
         .await
         .map_err(|e| ServerFnError::new(format!("{e:?}")))
 }
@@ -76,6 +76,7 @@ pub fn SubscriptionPanel(sub_type: Option<Signal<SubscriptionType>>) -> impl Int
             let sub = Subscription {
                 user_id: user_signal().unwrap().id,
                 subscription_type: sub_type.unwrap().get(),
+                languages: Vec::new(),
             };
             return Some(sub);
         }
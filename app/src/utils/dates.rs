@@ -4,3 +4,14 @@ use chrono::{DateTime, Utc};
 pub fn print_date(date: DateTime<Utc>) -> String {
     format!("{}", date.format("%d/%m/%Y %H:%M"))
 }
+
+/// Formats a runtime in seconds as e.g. `12h 34m`.
+pub fn print_runtime(runtime_seconds: i32) -> String {
+    let hours = runtime_seconds / 3600;
+    let minutes = (runtime_seconds % 3600) / 60;
+    if hours == 0 {
+        format!("{minutes}m")
+    } else {
+        format!("{hours}h {minutes}m")
+    }
+}
@@ -0,0 +1,24 @@
+use entities_lib::Language;
+use leptos::prelude::*;
+use sqlx::PgPool;
+
+/// The languages the current user wants to see, all of them for the guests.
+///
+/// # Errors
+///   - If the auth session can't be found.
+///   - If the preferences of the user can't be read.
+pub(crate) async fn current_user_languages(
+    pgpool: &PgPool,
+) -> Result<Vec<Language>, ServerFnError> {
+    use shared::auth_user::AuthSession;
+    use shared::db_ops::parade::subscription_ops::get_preferred_languages;
+
+    let auth_session =
+        use_context::<AuthSession>().ok_or(ServerFnError::new("Couldn't find auth session"))?;
+    let Some(user) = auth_session.current_user else {
+        return Ok(Vec::new());
+    };
+    get_preferred_languages(pgpool, user.id)
+        .await
+        .map_err(|e| ServerFnError::new(format!("{e:?}")))
+}
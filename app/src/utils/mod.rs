@@ -1,3 +1,5 @@
 pub(crate) mod dates;
+#[cfg(feature = "ssr")]
+pub(crate) mod languages;
 pub(crate) mod local_storage;
 pub mod sidebar;
@@ -1,6 +1,9 @@
 use std::fmt::Display;
+use std::path::PathBuf;
 
-use clap::{Parser, Subcommand};
+use chrono::NaiveDate;
+use clap::{Parser, Subcommand, ValueEnum};
+use shared::db_ops::parade::entity_ops::EntityKind;
 use shared::private_args::Args;
 
 #[derive(Subcommand, Debug, Clone)]
@@ -15,6 +18,21 @@ pub enum ScrapeSubCommand {
         /// Highest page for the backfilling.
         #[arg(long, default_value_t = 50)]
         page_end: u16,
+
+        /// Resume the backfill run with this id instead of starting a new one.
+        #[arg(long, conflicts_with_all = ["page_start", "page_end"])]
+        resume: Option<i64>,
+
+        /// Don't send notifications for audiobooks created more than this many days ago.
+        #[arg(long)]
+        skip_notifications_older_than_days: Option<u32>,
+    },
+
+    /// List the most recent backfill runs.
+    Runs {
+        /// Maximum number of runs to show.
+        #[arg(long, default_value_t = 20)]
+        limit: i64,
     },
 }
 
@@ -22,10 +40,325 @@ impl Display for ScrapeSubCommand {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             ScrapeSubCommand::Latest => write!(f, "latest"),
+            ScrapeSubCommand::Backfill {
+                resume: Some(run_id),
+                ..
+            } => write!(f, "backfilling (resuming run {run_id})"),
             ScrapeSubCommand::Backfill {
                 page_start,
                 page_end,
+                resume: None,
+                ..
             } => write!(f, "backfilling ({page_start}-{page_end})"),
+            ScrapeSubCommand::Runs { limit } => write!(f, "runs ({limit})"),
+        }
+    }
+}
+
+/// The PGMQ queues the cli works with.
+#[derive(ValueEnum, Debug, Clone, Copy)]
+pub enum QueueKind {
+    Latest,
+    Backfill,
+    Notifications,
+}
+
+impl QueueKind {
+    #[must_use]
+    pub fn queue_name(self, args: &Args) -> &str {
+        match self {
+            QueueKind::Latest => &args.pgmq_latest_queue_name,
+            QueueKind::Backfill => &args.pgmq_backfill_queue_name,
+            QueueKind::Notifications => &args.pgmq_notifications_queue_name,
+        }
+    }
+}
+
+impl Display for QueueKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            QueueKind::Latest => write!(f, "latest"),
+            QueueKind::Backfill => write!(f, "backfill"),
+            QueueKind::Notifications => write!(f, "notifications"),
+        }
+    }
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum QueueSubCommand {
+    /// List the dead letters of a queue.
+    Inspect {
+        #[arg(value_enum)]
+        queue: QueueKind,
+
+        /// Maximum number of dead letters to show.
+        #[arg(long, default_value_t = 20)]
+        limit: i64,
+    },
+
+    /// Move dead letters back to their source queue.
+    Requeue {
+        #[arg(value_enum)]
+        queue: QueueKind,
+
+        /// Only requeue the dead letter with this message id.
+        #[arg(long)]
+        msg_id: Option<i64>,
+    },
+
+    /// Delete all the dead letters of a queue.
+    Purge {
+        #[arg(value_enum)]
+        queue: QueueKind,
+    },
+}
+
+impl Display for QueueSubCommand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            QueueSubCommand::Inspect { queue, limit } => write!(f, "inspect {queue} ({limit})"),
+            QueueSubCommand::Requeue { queue, msg_id } => match msg_id {
+                Some(msg_id) => write!(f, "requeue {queue} ({msg_id})"),
+                None => write!(f, "requeue {queue}"),
+            },
+            QueueSubCommand::Purge { queue } => write!(f, "purge {queue}"),
+        }
+    }
+}
+
+/// The entities enriched with Hardcover metadata.
+#[derive(ValueEnum, Debug, Clone, Copy)]
+pub enum EnrichTarget {
+    Authors,
+    Series,
+    All,
+}
+
+impl Display for EnrichTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EnrichTarget::Authors => write!(f, "authors"),
+            EnrichTarget::Series => write!(f, "series"),
+            EnrichTarget::All => write!(f, "all"),
+        }
+    }
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum HardcoverSubCommand {
+    /// List the Hardcover matches with a low confidence score.
+    Review {
+        /// Only list the matches scoring below this.
+        #[arg(long, default_value_t = 0.8)]
+        below: f64,
+
+        /// Maximum number of matches to show.
+        #[arg(long, default_value_t = 20)]
+        limit: i64,
+    },
+    /// List the audiobooks that gained the most Hardcover ratings recently.
+    Trending {
+        /// Compare the ratings recorded in the last `days` days.
+        #[arg(long, default_value_t = 30)]
+        days: i64,
+
+        /// Maximum number of audiobooks to show.
+        #[arg(long, default_value_t = 20)]
+        limit: i64,
+    },
+}
+
+impl Display for HardcoverSubCommand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HardcoverSubCommand::Review { below, limit } => {
+                write!(f, "review below {below} ({limit})")
+            }
+            HardcoverSubCommand::Trending { days, limit } => {
+                write!(f, "trending in the last {days} days ({limit})")
+            }
+        }
+    }
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum EntitiesSubCommand {
+    /// Propose the entities to merge, grouping the names with a similar spelling.
+    Dedupe {
+        #[arg(long, value_enum)]
+        kind: EntityKind,
+
+        /// Minimum trigram similarity, in `[0, 1]`, of two names to be considered duplicates.
+        #[arg(long, default_value_t = 0.6)]
+        min_similarity: f32,
+
+        /// Maximum number of similar pairs to consider.
+        #[arg(long, default_value_t = 50)]
+        limit: i64,
+
+        /// Merge every proposed cluster instead of only listing them.
+        #[arg(long)]
+        apply: bool,
+    },
+
+    /// Merge entities into another one.
+    Merge {
+        #[arg(long, value_enum)]
+        kind: EntityKind,
+
+        /// Id of the entity to keep.
+        #[arg(long)]
+        into: i64,
+
+        /// Ids of the entities merged into `into` and deleted.
+        #[arg(required = true)]
+        duplicates: Vec<i64>,
+    },
+}
+
+impl Display for EntitiesSubCommand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EntitiesSubCommand::Dedupe {
+                kind,
+                min_similarity,
+                limit,
+                apply,
+            } => {
+                let mode = if *apply { "applying" } else { "dry run" };
+                write!(f, "dedupe {kind} above {min_similarity} ({limit}, {mode})")
+            }
+            EntitiesSubCommand::Merge {
+                kind,
+                into,
+                duplicates,
+            } => write!(f, "merge {kind} {duplicates:?} into {into}"),
+        }
+    }
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum TaxonomySubCommand {
+    /// Print the category taxonomy.
+    Show,
+
+    /// Add a category to the taxonomy, or move it under another parent.
+    Add {
+        name: String,
+
+        /// Name of the parent category, the category is a root if missing.
+        #[arg(long)]
+        parent: Option<String>,
+    },
+
+    /// Map a category string onto a category of the taxonomy, overriding the LLM.
+    Alias {
+        alias: String,
+
+        /// Name of the category of the taxonomy, the string is ignored if missing.
+        #[arg(long)]
+        to: Option<String>,
+    },
+
+    /// Map the categories that are not part of the taxonomy onto it, merging them into the
+    /// categories they map to.
+    Remap {
+        /// Maximum number of categories to remap, the most used first.
+        #[arg(long, default_value_t = 100)]
+        limit: i64,
+    },
+}
+
+impl Display for TaxonomySubCommand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TaxonomySubCommand::Show => write!(f, "show"),
+            TaxonomySubCommand::Add { name, parent } => match parent {
+                Some(parent) => write!(f, "add {name} under {parent}"),
+                None => write!(f, "add {name}"),
+            },
+            TaxonomySubCommand::Alias { alias, to } => match to {
+                Some(to) => write!(f, "alias {alias} to {to}"),
+                None => write!(f, "alias {alias} to nothing"),
+            },
+            TaxonomySubCommand::Remap { limit } => write!(f, "remap ({limit})"),
+        }
+    }
+}
+
+#[derive(Subcommand, Debug)]
+pub enum KeywordsSubCommand {
+    /// Print the stop-list, the aliases and the groups of synonyms.
+    Show,
+
+    /// Rewrite a keyword to another one when the audiobooks are inserted.
+    Alias {
+        alias: String,
+
+        /// The keyword to rewrite the alias to.
+        #[arg(long)]
+        to: String,
+    },
+
+    /// Group keywords as synonyms, so that subscribing to any of them matches all of them.
+    Synonyms {
+        #[arg(num_args = 2.., required = true)]
+        keywords: Vec<String>,
+    },
+
+    /// Remove a keyword from its group of synonyms.
+    Unsynonym { keyword: String },
+
+    /// Add a keyword to the stop-list.
+    Stop {
+        keyword: String,
+
+        /// Remove the keyword from the stop-list instead.
+        #[arg(long, default_value_t = false)]
+        remove: bool,
+    },
+
+    /// Apply the stop-list and the aliases to the keywords already stored.
+    Backfill,
+}
+
+impl Display for KeywordsSubCommand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            KeywordsSubCommand::Show => write!(f, "show"),
+            KeywordsSubCommand::Alias { alias, to } => write!(f, "alias {alias} to {to}"),
+            KeywordsSubCommand::Synonyms { keywords } => write!(f, "synonyms {keywords:?}"),
+            KeywordsSubCommand::Unsynonym { keyword } => write!(f, "unsynonym {keyword}"),
+            KeywordsSubCommand::Stop { keyword, remove } => {
+                if *remove {
+                    write!(f, "stop {keyword} (remove)")
+                } else {
+                    write!(f, "stop {keyword}")
+                }
+            }
+            KeywordsSubCommand::Backfill => write!(f, "backfill"),
+        }
+    }
+}
+
+#[derive(Subcommand, Debug)]
+pub enum WorksSubCommand {
+    /// Group the audiobooks that don't belong to a work yet with their other editions.
+    Group {
+        /// Maximum number of audiobooks to group, the oldest first.
+        #[arg(long, default_value_t = 1000)]
+        limit: i64,
+    },
+
+    /// Move an audiobook wrongly grouped with other editions into a work of its own.
+    Split { audiobook_id: i64 },
+}
+
+impl Display for WorksSubCommand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WorksSubCommand::Group { limit } => write!(f, "group ({limit})"),
+            WorksSubCommand::Split { audiobook_id } => write!(f, "split {audiobook_id}"),
         }
     }
 }
@@ -51,14 +384,110 @@ pub enum Commands {
         every_n_seconds: u32,
     },
 
-    TestRetrieval,
+    /// Periodically refresh the ratings of the books matched on Hardcover.
+    RefreshHardcover {
+        #[arg(long, default_value_t = 86400)] // Every day
+        every_n_seconds: u32,
+
+        /// Maximum number of Hardcover requests in every round.
+        #[arg(long, default_value_t = 500)]
+        max_requests: i64,
+
+        /// Ratings refreshed less than this many days ago are left alone.
+        #[arg(long, default_value_t = 7)]
+        stale_after_days: i64,
+    },
+
+    /// Evaluate the search against a file of relevance judgments, comparing the retrievers alone
+    /// with their fusion.
+    TestRetrieval {
+        /// JSON Lines file, each line a query with the grades of its relevant audiobooks, e.g.
+        /// `{"query": "space opera", "relevant": [{"audiobook_id": 42, "grade": 2}]}`.
+        judgments: PathBuf,
+
+        /// Number of top results evaluated by nDCG and recall.
+        #[arg(long, default_value_t = 10)]
+        k: usize,
+
+        /// Maximum number of results of each retriever.
+        #[arg(long, default_value_t = 100)]
+        depth: i32,
+
+        /// Constants of the Reciprocal Rank Fusion to compare.
+        #[arg(long, value_delimiter = ',', default_value = "10,30,60,100")]
+        rrf_k: Vec<f64>,
+    },
+
+    /// Inspect, requeue or purge the dead letters of a queue.
+    Queue {
+        #[command(subcommand)]
+        queue_sub_command: QueueSubCommand,
+    },
+
+    /// Re-run the extraction, descriptions and embeddings of ingested audiobooks from the archived
+    /// pages.
+    Reprocess {
+        /// Comma separated ids of the audiobooks to reprocess.
+        #[arg(long, value_delimiter = ',', conflicts_with_all = ["from", "to"])]
+        audiobook_ids: Vec<i64>,
+
+        /// Reprocess the audiobooks ingested from this date (YYYY-MM-DD, inclusive).
+        #[arg(long, requires = "to")]
+        from: Option<NaiveDate>,
+
+        /// Reprocess the audiobooks ingested up to this date (YYYY-MM-DD, inclusive).
+        #[arg(long, requires = "from")]
+        to: Option<NaiveDate>,
+    },
+
+    /// Review the metadata matched from Hardcover.
+    Hardcover {
+        #[command(subcommand)]
+        hardcover_sub_command: HardcoverSubCommand,
+    },
+
+    /// Store the Hardcover metadata of the authors and series that don't have it yet.
+    Enrich {
+        #[arg(long, value_enum, default_value_t = EnrichTarget::All)]
+        target: EnrichTarget,
+
+        /// Maximum number of authors and of series to enrich.
+        #[arg(long, default_value_t = 100)]
+        limit: i64,
+    },
+
+    /// Find and merge the duplicated authors, readers, series, categories and keywords.
+    Entities {
+        #[command(subcommand)]
+        entities_sub_command: EntitiesSubCommand,
+    },
+
+    /// Manage the category taxonomy and the mapping of the categories onto it.
+    Taxonomy {
+        #[command(subcommand)]
+        taxonomy_sub_command: TaxonomySubCommand,
+    },
+
+    /// Manage the keyword stop-list, aliases and synonyms.
+    Keywords {
+        #[command(subcommand)]
+        keywords_sub_command: KeywordsSubCommand,
+    },
+
+    /// Group the editions of the same book into works.
+    Works {
+        #[command(subcommand)]
+        works_sub_command: WorksSubCommand,
+    },
 }
 
 impl Display for Commands {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Commands::Scrape { scrape_sub_command } => write!(f, "scrape {scrape_sub_command}"),
-            Commands::TestRetrieval => write!(f, "test-retrieval"),
+            Commands::TestRetrieval { judgments, k, .. } => {
+                write!(f, "test-retrieval {} (k = {k})", judgments.display())
+            }
             Commands::SendNotifications => write!(f, "send-notifications"),
             Commands::SendTestNotification { audiobook_id } => {
                 write!(f, "send-test-notifications {audiobook_id}")
@@ -66,6 +495,37 @@ impl Display for Commands {
             Commands::RefreshSearchIndex { every_n_seconds } => {
                 write!(f, "refresh-search-indexes every {every_n_seconds} seconds")
             }
+            Commands::RefreshHardcover {
+                every_n_seconds,
+                max_requests,
+                stale_after_days,
+            } => write!(
+                f,
+                "refresh-hardcover every {every_n_seconds} seconds ({max_requests} requests, stale after {stale_after_days} days)"
+            ),
+            Commands::Queue { queue_sub_command } => write!(f, "queue {queue_sub_command}"),
+            Commands::Reprocess {
+                audiobook_ids,
+                from,
+                to,
+            } => match (from, to) {
+                (Some(from), Some(to)) => write!(f, "reprocess ({from} - {to})"),
+                _ => write!(f, "reprocess {audiobook_ids:?}"),
+            },
+            Commands::Hardcover {
+                hardcover_sub_command,
+            } => write!(f, "hardcover {hardcover_sub_command}"),
+            Commands::Enrich { target, limit } => write!(f, "enrich {target} ({limit})"),
+            Commands::Entities {
+                entities_sub_command,
+            } => write!(f, "entities {entities_sub_command}"),
+            Commands::Taxonomy {
+                taxonomy_sub_command,
+            } => write!(f, "taxonomy {taxonomy_sub_command}"),
+            Commands::Keywords {
+                keywords_sub_command,
+            } => write!(f, "keywords {keywords_sub_command}"),
+            Commands::Works { works_sub_command } => write!(f, "works {works_sub_command}"),
         }
     }
 }
@@ -0,0 +1,234 @@
+//! Enrichment of the authors and series with metadata from Hardcover.
+
+use entities_lib::{Author, Series};
+use reqwest::Client;
+use shared::db_ops::parade::get_postgres_connection;
+use shared::db_ops::parade::hardcover_ops::{
+    get_authors_without_hardcover_metadata, get_series_without_hardcover_metadata,
+    record_hardcover_author_attempt, record_hardcover_series_attempt,
+    upsert_hardcover_author_metadata, upsert_hardcover_series_metadata,
+};
+use shared::private_args::Args;
+use sqlx::PgPool;
+use tracing::{info, warn};
+
+use crate::cli_args::EnrichTarget;
+use crate::scraping::hardcover::{
+    HARDCOVER_HOST, HARDCOVER_REQUESTS_PER_MINUTE, HardcoverAuthorHit, HardcoverSeriesHit,
+    get_author, get_series, search_authors, search_series,
+};
+use crate::scraping::utils::hardcover_matching::is_similar_name;
+use crate::scraping::utils::http::build_robust_client;
+use crate::scraping::utils::rate_limit::HostRateLimiter;
+
+const CANDIDATES_PER_SEARCH: u32 = 5;
+
+struct HardcoverClient<'a> {
+    client: Client,
+    api_key: &'a str,
+    rate_limiter: HostRateLimiter,
+}
+
+/// Resolves up to `limit` authors and/or series without Hardcover metadata and stores their
+/// metadata.
+///
+/// Entities that can't be resolved are logged and retried after the others, on a later run.
+///
+/// # Errors
+///   - If no Hardcover API key is configured.
+///   - If the entities to enrich can't be listed.
+pub async fn handle_enrich(
+    target: EnrichTarget,
+    limit: i64,
+    args: Args,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let api_key = args
+        .hardcover_api_key
+        .as_deref()
+        .ok_or("--hardcover-api-key is required to enrich from Hardcover")?;
+    let pgpool = get_postgres_connection(&args).await;
+    let hardcover = HardcoverClient {
+        client: build_robust_client(),
+        api_key,
+        rate_limiter: HostRateLimiter::new(HARDCOVER_REQUESTS_PER_MINUTE, 1),
+    };
+
+    if matches!(target, EnrichTarget::Authors | EnrichTarget::All) {
+        enrich_authors(&pgpool, &hardcover, limit).await?;
+    }
+    if matches!(target, EnrichTarget::Series | EnrichTarget::All) {
+        enrich_series(&pgpool, &hardcover, limit).await?;
+    }
+    Ok(())
+}
+
+async fn enrich_authors(
+    pgpool: &PgPool,
+    hardcover: &HardcoverClient<'_>,
+    limit: i64,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let authors = get_authors_without_hardcover_metadata(pgpool, limit)
+        .await
+        .map_err(|e| e.to_string())?;
+    let mut enriched = 0;
+    for author in &authors {
+        match enrich_author(pgpool, hardcover, author).await {
+            Ok(true) => {
+                enriched += 1;
+                continue;
+            }
+            Ok(false) => info!("No hardcover match for author `{}`", author.name),
+            Err(e) => warn!("Couldn't enrich author `{}`: {}", author.name, e),
+        }
+        record_hardcover_author_attempt(pgpool, author.id)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    info!("Enriched {} of {} authors", enriched, authors.len());
+    Ok(())
+}
+
+async fn enrich_author(
+    pgpool: &PgPool,
+    hardcover: &HardcoverClient<'_>,
+    author: &Author,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    hardcover.rate_limiter.acquire(HARDCOVER_HOST).await;
+    let hits = search_authors(
+        &hardcover.client,
+        hardcover.api_key,
+        &author.name,
+        CANDIDATES_PER_SEARCH,
+    )
+    .await?;
+    let Some(hit) = best_author_hit(&author.name, hits) else {
+        return Ok(false);
+    };
+
+    hardcover.rate_limiter.acquire(HARDCOVER_HOST).await;
+    let Some(data) = get_author(&hardcover.client, hardcover.api_key, hit.id.parse()?).await?
+    else {
+        return Ok(false);
+    };
+    upsert_hardcover_author_metadata(pgpool, author.id, &data)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(true)
+}
+
+/// Among the hits with a matching name, the one with the most books is the most likely.
+fn best_author_hit(name: &str, hits: Vec<HardcoverAuthorHit>) -> Option<HardcoverAuthorHit> {
+    hits.into_iter()
+        .filter(|hit| is_similar_name(&hit.name, name))
+        .max_by_key(|hit| hit.books_count.unwrap_or_default())
+}
+
+async fn enrich_series(
+    pgpool: &PgPool,
+    hardcover: &HardcoverClient<'_>,
+    limit: i64,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let series = get_series_without_hardcover_metadata(pgpool, limit)
+        .await
+        .map_err(|e| e.to_string())?;
+    let mut enriched = 0;
+    for (s, author_name) in &series {
+        match enrich_one_series(pgpool, hardcover, s, author_name.as_deref()).await {
+            Ok(true) => {
+                enriched += 1;
+                continue;
+            }
+            Ok(false) => info!("No hardcover match for series `{}`", s.title),
+            Err(e) => warn!("Couldn't enrich series `{}`: {}", s.title, e),
+        }
+        record_hardcover_series_attempt(pgpool, s.id)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    info!("Enriched {} of {} series", enriched, series.len());
+    Ok(())
+}
+
+async fn enrich_one_series(
+    pgpool: &PgPool,
+    hardcover: &HardcoverClient<'_>,
+    series: &Series,
+    author_name: Option<&str>,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    hardcover.rate_limiter.acquire(HARDCOVER_HOST).await;
+    let hits = search_series(
+        &hardcover.client,
+        hardcover.api_key,
+        &series.title,
+        CANDIDATES_PER_SEARCH,
+    )
+    .await?;
+    let Some(hit) = best_series_hit(&series.title, author_name, hits) else {
+        return Ok(false);
+    };
+
+    hardcover.rate_limiter.acquire(HARDCOVER_HOST).await;
+    let Some(data) = get_series(&hardcover.client, hardcover.api_key, hit.id.parse()?).await?
+    else {
+        return Ok(false);
+    };
+    upsert_hardcover_series_metadata(pgpool, series.id, &data)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(true)
+}
+
+/// Among the hits with a matching name, prefers the ones by the same author, then the ones with
+/// the most books.
+fn best_series_hit(
+    title: &str,
+    author_name: Option<&str>,
+    hits: Vec<HardcoverSeriesHit>,
+) -> Option<HardcoverSeriesHit> {
+    hits.into_iter()
+        .filter(|hit| is_similar_name(&hit.name, title))
+        .max_by_key(|hit| {
+            let same_author = match (author_name, hit.author_name.as_deref()) {
+                (Some(a), Some(b)) => is_similar_name(a, b),
+                _ => false,
+            };
+            (same_author, hit.books_count.unwrap_or_default())
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn series_hit(id: &str, name: &str, author_name: &str, books_count: i64) -> HardcoverSeriesHit {
+        HardcoverSeriesHit {
+            id: id.to_string(),
+            name: name.to_string(),
+            author_name: Some(author_name.to_string()),
+            books_count: Some(books_count),
+        }
+    }
+
+    #[test]
+    fn test_best_series_hit_prefers_the_same_author() {
+        let hits = vec![
+            series_hit("1", "Mistborn", "Someone Else", 40),
+            series_hit("2", "Mistborn", "Brandon Sanderson", 7),
+            series_hit("3", "The Stormlight Archive", "Brandon Sanderson", 5),
+        ];
+        let best = best_series_hit("Mistborn", Some("Brandon Sanderson"), hits.clone()).unwrap();
+        assert_eq!(best.id, "2");
+        let best = best_series_hit("Mistborn", None, hits).unwrap();
+        assert_eq!(best.id, "1");
+    }
+
+    #[test]
+    fn test_best_author_hit_requires_a_similar_name() {
+        let hits = vec![HardcoverAuthorHit {
+            id: "1".to_string(),
+            name: "Andy Weir".to_string(),
+            books_count: Some(10),
+        }];
+        assert!(best_author_hit("Brandon Sanderson", hits).is_none());
+    }
+}
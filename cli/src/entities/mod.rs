@@ -0,0 +1,67 @@
+use shared::db_ops::parade::entity_ops::{MergeCluster, merge_entities, propose_merge_clusters};
+use shared::db_ops::parade::get_postgres_connection;
+use shared::private_args::Args;
+
+use crate::cli_args::EntitiesSubCommand;
+
+/// Proposes and merges the duplicated entities.
+///
+/// # Errors
+///   - If the clusters can't be proposed.
+///   - If a merge fails, the clusters merged before it stay merged.
+pub async fn handle_entities(
+    entities_sub_command: EntitiesSubCommand,
+    args: Args,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let pgpool = get_postgres_connection(&args).await;
+    match entities_sub_command {
+        EntitiesSubCommand::Dedupe {
+            kind,
+            min_similarity,
+            limit,
+            apply,
+        } => {
+            let clusters = propose_merge_clusters(&pgpool, kind, min_similarity, limit)
+                .await
+                .map_err(|e| e.to_string())?;
+            println!("{} clusters of {kind} to merge", clusters.len());
+            for cluster in &clusters {
+                print_cluster(cluster);
+            }
+            if !apply {
+                return Ok(());
+            }
+            for cluster in clusters {
+                let duplicates: Vec<i64> = cluster.duplicates.iter().map(|d| d.id).collect();
+                merge_entities(&pgpool, kind, cluster.canonical.id, &duplicates)
+                    .await
+                    .map_err(|e| e.to_string())?;
+            }
+            println!("Merged. Refresh the search index for the search to reflect it.");
+        }
+        EntitiesSubCommand::Merge {
+            kind,
+            into,
+            duplicates,
+        } => {
+            merge_entities(&pgpool, kind, into, &duplicates)
+                .await
+                .map_err(|e| e.to_string())?;
+            println!("Merged {kind} {duplicates:?} into {into}");
+        }
+    }
+    Ok(())
+}
+
+fn print_cluster(cluster: &MergeCluster) {
+    println!(
+        "[{}] `{}` ({} audiobooks)",
+        cluster.canonical.id, cluster.canonical.name, cluster.canonical.audiobooks_count
+    );
+    for duplicate in &cluster.duplicates {
+        println!(
+            "    <- [{}] `{}` ({} audiobooks)",
+            duplicate.id, duplicate.name, duplicate.audiobooks_count
+        );
+    }
+}
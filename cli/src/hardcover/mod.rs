@@ -0,0 +1,64 @@
+use chrono::{TimeDelta, Utc};
+use shared::db_ops::parade::get_postgres_connection;
+use shared::db_ops::parade::hardcover_ops::{
+    get_hardcover_rating_trends, list_low_confidence_hardcover_matches,
+};
+use shared::private_args::Args;
+
+use crate::cli_args::HardcoverSubCommand;
+
+/// Inspects the metadata matched from Hardcover.
+///
+/// # Errors
+///   - If the matches can't be listed.
+pub async fn handle_hardcover(
+    hardcover_sub_command: HardcoverSubCommand,
+    args: Args,
+) -> Result<(), Box<dyn std::error::Error>> {
+    match hardcover_sub_command {
+        HardcoverSubCommand::Review { below, limit } => {
+            let pgpool = get_postgres_connection(&args).await;
+            let matches = list_low_confidence_hardcover_matches(&pgpool, below, limit)
+                .await
+                .map_err(|e| e.to_string())?;
+            println!("{} hardcover matches scoring below {below}", matches.len());
+            for m in matches {
+                println!(
+                    "[{}] {:.2} `{}` matched to `{}` ({})\n    {}",
+                    m.audiobook_id,
+                    m.match_score,
+                    m.audiobook_title,
+                    m.hardcover_title.unwrap_or_default(),
+                    m.hardcover_slug
+                        .map(|slug| format!("https://hardcover.app/books/{slug}"))
+                        .unwrap_or_default(),
+                    m.path
+                );
+            }
+        }
+        HardcoverSubCommand::Trending { days, limit } => {
+            let pgpool = get_postgres_connection(&args).await;
+            let since = Utc::now() - TimeDelta::days(days);
+            let trends = get_hardcover_rating_trends(&pgpool, since, limit)
+                .await
+                .map_err(|e| e.to_string())?;
+            println!(
+                "{} audiobooks gained hardcover ratings in the last {days} days",
+                trends.len()
+            );
+            for t in trends {
+                println!(
+                    "[{}] +{} ratings ({} -> {}), rating {:.2} -> {:.2} `{}`",
+                    t.audiobook_id,
+                    t.last_ratings_count - t.first_ratings_count,
+                    t.first_ratings_count,
+                    t.last_ratings_count,
+                    t.first_rating.unwrap_or_default(),
+                    t.last_rating.unwrap_or_default(),
+                    t.audiobook_title
+                );
+            }
+        }
+    }
+    Ok(())
+}
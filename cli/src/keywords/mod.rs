@@ -0,0 +1,96 @@
+use shared::db_ops::parade::entity_ops::{EntityKind, merge_entities};
+use shared::db_ops::parade::get_postgres_connection;
+use shared::db_ops::parade::keyword_ops::{
+    add_keyword_alias, add_keyword_synonyms, delete_stoplisted_keywords, get_aliased_keywords,
+    get_keyword_aliases, get_keyword_stoplist, get_keyword_synonym_groups, remove_keyword_synonym,
+    set_keyword_stoplisted,
+};
+use shared::private_args::Args;
+use tracing::info;
+
+use crate::cli_args::KeywordsSubCommand;
+
+/// Manages the keyword stop-list, aliases and synonyms.
+///
+/// # Errors
+///   - If any of them can't be read or updated.
+///   - If a backfill merge fails, the keywords merged before it stay merged.
+pub async fn handle_keywords(
+    keywords_sub_command: KeywordsSubCommand,
+    args: Args,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let pgpool = get_postgres_connection(&args).await;
+    match keywords_sub_command {
+        KeywordsSubCommand::Show => {
+            let stoplist = get_keyword_stoplist(&pgpool)
+                .await
+                .map_err(|e| e.to_string())?;
+            println!("Stop-list: {}", stoplist.join(", "));
+            println!("Aliases:");
+            for alias in get_keyword_aliases(&pgpool)
+                .await
+                .map_err(|e| e.to_string())?
+            {
+                println!(
+                    "    {} -> [{}] {}",
+                    alias.alias, alias.keyword_id, alias.keyword_name
+                );
+            }
+            println!("Synonyms:");
+            for group in get_keyword_synonym_groups(&pgpool)
+                .await
+                .map_err(|e| e.to_string())?
+            {
+                println!("    {}", group.join(", "));
+            }
+        }
+        KeywordsSubCommand::Alias { alias, to } => {
+            let keyword_id = add_keyword_alias(&pgpool, &alias, &to)
+                .await
+                .map_err(|e| e.to_string())?;
+            println!(
+                "Aliased {alias} to keyword {keyword_id}. Run a backfill to apply it to the stored keywords."
+            );
+        }
+        KeywordsSubCommand::Synonyms { keywords } => {
+            let group_id = add_keyword_synonyms(&pgpool, &keywords)
+                .await
+                .map_err(|e| e.to_string())?;
+            println!("Grouped {keywords:?} as synonyms in group {group_id}");
+        }
+        KeywordsSubCommand::Unsynonym { keyword } => {
+            remove_keyword_synonym(&pgpool, &keyword)
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+        KeywordsSubCommand::Stop { keyword, remove } => {
+            set_keyword_stoplisted(&pgpool, &keyword, !remove)
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+        KeywordsSubCommand::Backfill => {
+            let deleted = delete_stoplisted_keywords(&pgpool)
+                .await
+                .map_err(|e| e.to_string())?;
+            info!("Deleted {deleted} stop-listed keywords");
+            let aliased = get_aliased_keywords(&pgpool)
+                .await
+                .map_err(|e| e.to_string())?;
+            for keywords in &aliased {
+                merge_entities(
+                    &pgpool,
+                    EntityKind::Keyword,
+                    keywords.keyword_id,
+                    &keywords.duplicate_ids,
+                )
+                .await
+                .map_err(|e| e.to_string())?;
+            }
+            println!(
+                "Deleted {deleted} stop-listed keywords and merged the aliases of {} keywords. Refresh the search index for the search to reflect it.",
+                aliased.len()
+            );
+        }
+    }
+    Ok(())
+}
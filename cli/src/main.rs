@@ -1,8 +1,18 @@
 pub mod cli_args;
+pub mod enrich;
+pub mod entities;
+pub mod hardcover;
+pub mod keywords;
 pub mod notifications;
+pub mod queue;
 pub mod queue_messages;
+pub mod refresh_hardcover;
 pub mod refresh_search_index;
+pub mod reprocess;
 pub mod scraping;
+pub mod taxonomy;
+pub mod test_retrieval;
+pub mod works;
 
 use shared::private_args::Args;
 use tracing::info;
@@ -10,9 +20,19 @@ use tracing::info;
 use clap::Parser;
 
 use crate::cli_args::{CliArgs, ScrapeSubCommand};
+use crate::enrich::handle_enrich;
+use crate::entities::handle_entities;
+use crate::hardcover::handle_hardcover;
+use crate::keywords::handle_keywords;
 use crate::notifications::entrypoints::{handle_notifications, handle_send_test_notifications};
+use crate::queue::handle_queue;
+use crate::refresh_hardcover::refresh_hardcover;
 use crate::refresh_search_index::refresh_search_index;
-use crate::scraping::{handle_backfill_impl, handle_scrape_impl};
+use crate::reprocess::handle_reprocess;
+use crate::scraping::{handle_backfill_impl, handle_list_backfill_runs, handle_scrape_impl};
+use crate::taxonomy::handle_taxonomy;
+use crate::test_retrieval::handle_test_retrieval;
+use crate::works::handle_works;
 
 /// Main entry point.
 ///
@@ -39,7 +59,12 @@ pub async fn main() -> Result<(), Box<dyn std::error::Error>> {
         cli_args::Commands::Scrape { scrape_sub_command } => {
             handle_scrape(scrape_sub_command, cli_args.args).await?;
         }
-        cli_args::Commands::TestRetrieval => handle_test_retrieval(cli_args.args).await?,
+        cli_args::Commands::TestRetrieval {
+            judgments,
+            k,
+            depth,
+            rrf_k,
+        } => handle_test_retrieval(&judgments, k, depth, &rrf_k, cli_args.args).await?,
         cli_args::Commands::SendNotifications => handle_notifications(cli_args.args).await?,
         cli_args::Commands::SendTestNotification { audiobook_id } => {
             handle_send_test_notifications(audiobook_id, cli_args.args).await?;
@@ -47,6 +72,45 @@ pub async fn main() -> Result<(), Box<dyn std::error::Error>> {
         cli_args::Commands::RefreshSearchIndex { every_n_seconds } => {
             refresh_search_index(cli_args.args, every_n_seconds).await?;
         }
+        cli_args::Commands::RefreshHardcover {
+            every_n_seconds,
+            max_requests,
+            stale_after_days,
+        } => {
+            refresh_hardcover(
+                cli_args.args,
+                every_n_seconds,
+                max_requests,
+                stale_after_days,
+            )
+            .await?;
+        }
+        cli_args::Commands::Queue { queue_sub_command } => {
+            handle_queue(queue_sub_command, cli_args.args).await?;
+        }
+        cli_args::Commands::Reprocess {
+            audiobook_ids,
+            from,
+            to,
+        } => handle_reprocess(audiobook_ids, from, to, cli_args.args).await?,
+        cli_args::Commands::Hardcover {
+            hardcover_sub_command,
+        } => handle_hardcover(hardcover_sub_command, cli_args.args).await?,
+        cli_args::Commands::Enrich { target, limit } => {
+            handle_enrich(target, limit, cli_args.args).await?;
+        }
+        cli_args::Commands::Entities {
+            entities_sub_command,
+        } => handle_entities(entities_sub_command, cli_args.args).await?,
+        cli_args::Commands::Taxonomy {
+            taxonomy_sub_command,
+        } => handle_taxonomy(taxonomy_sub_command, cli_args.args).await?,
+        cli_args::Commands::Keywords {
+            keywords_sub_command,
+        } => handle_keywords(keywords_sub_command, cli_args.args).await?,
+        cli_args::Commands::Works { works_sub_command } => {
+            handle_works(works_sub_command, cli_args.args).await?;
+        }
     }
 
     Ok(())
@@ -62,12 +126,18 @@ async fn handle_scrape(
         ScrapeSubCommand::Backfill {
             page_start,
             page_end,
-        } => handle_backfill_impl(args, page_start, page_end).await,
+            resume,
+            skip_notifications_older_than_days,
+        } => {
+            handle_backfill_impl(
+                args,
+                page_start,
+                page_end,
+                resume,
+                skip_notifications_older_than_days,
+            )
+            .await
+        }
+        ScrapeSubCommand::Runs { limit } => handle_list_backfill_runs(args, limit).await,
     }
 }
-
-// Function that handles the scraping of audiobook bay.
-#[allow(clippy::unused_async)]
-async fn handle_test_retrieval(_common_args: Args) -> Result<(), Box<dyn std::error::Error>> {
-    todo!()
-}
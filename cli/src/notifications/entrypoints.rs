@@ -2,14 +2,20 @@ use std::error::Error;
 use std::time::Duration;
 
 use shared::db_ops::parade::get_postgres_connection;
+use shared::db_ops::parade::keyword_ops::get_keyword_subscribers;
+use shared::db_ops::parade::subscription_ops::subscription_language_condition;
+use shared::db_ops::parade::taxonomy_ops::get_category_subscribers;
+use shared::db_ops::pgmq::dead_letter::{ack, nack};
 use shared::db_ops::pgmq::get_pgmq_queue;
 use shared::private_args::Args;
 use sqlx::{PgPool, Row};
 use tokio::time::sleep;
-use tracing::{debug, info};
+use tracing::{debug, error, info};
 
 use crate::queue_messages::IngestedAudiobookMessage;
 
+/// Notifies the users of the audiobook, unless they were already notified of another edition of
+/// its work and didn't opt into the new editions.
 async fn insert_or_update_notification(
     pgpool: &PgPool,
     user_ids: Vec<i64>,
@@ -21,7 +27,15 @@ async fn insert_or_update_notification(
         sqlx::query(
             r"
             INSERT INTO public.user_notification (user_id, audiobook_id, reasons)
-            VALUES ($1, $2, ARRAY[$3]::notification_reason[])
+            SELECT $1, $2, ARRAY[$3]::notification_reason[]
+            WHERE (SELECT notify_new_editions FROM public.users WHERE id = $1)
+                OR NOT EXISTS (
+                    SELECT 1
+                    FROM public.user_notification un
+                    JOIN public.audiobook other ON other.id = un.audiobook_id
+                    JOIN public.audiobook ab ON ab.id = $2
+                    WHERE un.user_id = $1 AND other.work_id = ab.work_id AND other.id <> ab.id
+                )
             ON CONFLICT (user_id, audiobook_id)
             DO UPDATE SET
             reasons = (
@@ -64,20 +78,25 @@ async fn get_entities_related_to_audiobook(
     Ok(res)
 }
 
+/// Returns the users subscribed to the entity in the language of the audiobook.
 async fn get_user_ids_interested_to_entity(
     pgpool: &PgPool,
     table_name: &str,
     target_column_name: &str,
     entity_id: i64,
+    audiobook_id: &i64,
 ) -> Result<Vec<i64>, Box<dyn Error>> {
+    let language_condition = subscription_language_condition("n");
     let query = format!(
         r"
-        SELECT user_id
-        FROM {table_name}
-        WHERE {target_column_name} = $1    "
+        SELECT n.user_id
+        FROM {table_name} n
+        JOIN audiobook ab ON ab.id = $2
+        WHERE n.{target_column_name} = $1 AND {language_condition}"
     );
     let res: Vec<i64> = sqlx::query_scalar(&query)
         .bind(entity_id)
+        .bind(audiobook_id)
         .fetch_all(pgpool)
         .await?;
     debug!(
@@ -109,6 +128,7 @@ async fn get_user_interested_to_entitities_and_update_notifications(
             notification_table_name,
             notification_table_column_name,
             entity_id,
+            audiobook_id,
         )
         .await?;
         insert_or_update_notification(pgpool, interested_users, audiobook_id, notification_reason)
@@ -118,11 +138,12 @@ async fn get_user_interested_to_entitities_and_update_notifications(
 }
 /// Loops over the notification queues and shows them to users.
 ///
+/// Messages are only removed from the queue once the notifications have been written, so a
+/// failure leaves the message to be retried (and eventually dead-lettered).
+///
 /// # Errors
 ///     - If the pgmq queue can't be created.
-///
-/// # Panics
-///     - It shouldn't panic despite the `unwrap` in the code.
+///     - If acknowledging or dead-lettering a message fails.
 pub async fn handle_notifications(args: Args) -> Result<(), Box<dyn std::error::Error>> {
     let pgpool = get_postgres_connection(&args).await;
     let queue = get_pgmq_queue(&args, &args.pgmq_notifications_queue_name).await?;
@@ -130,115 +151,133 @@ pub async fn handle_notifications(args: Args) -> Result<(), Box<dyn std::error::
 
     loop {
         let maybe_audiobook_message = queue
-            .pop::<IngestedAudiobookMessage>(&notifications_queue_name)
+            .read::<IngestedAudiobookMessage>(
+                &notifications_queue_name,
+                Some(args.pgmq_visibility_timeout_seconds),
+            )
             .await?;
 
-        // If the subscription is none it means the queue is currently empty and so
-        // we just wait a bit before trying to pop a message again.
-        if maybe_audiobook_message.is_none() {
+        // If the message is none it means the queue is currently empty and so
+        // we just wait a bit before trying to read a message again.
+        let Some(audiobook_message) = maybe_audiobook_message else {
             debug!("No message in queue. Looping without taking any action.");
             sleep(Duration::from_secs(60)).await;
             continue;
-        }
+        };
         debug!("Audiobook id message retrieved from queue.");
-        let audiobook_id: i64 = maybe_audiobook_message.unwrap().message.audiobook_id;
-
-        // At this point we need to define what happens here. The logic performs as follows:
-        // 1. Get the series id, keywords, ... from that id.
-        // 2. For each of those elements, check in the notification tables which users is
-        //    subscribed to that particular entity.
-        // 3. For each user found, create a notification entry if not already there.
+        let audiobook_id: i64 = audiobook_message.message.audiobook_id;
 
-        // 1.1 - Series
-        let maybe_series_id: Option<Option<i64>> =
-            sqlx::query_scalar("SELECT series_id FROM audiobook where id = $1")
-                .bind(audiobook_id)
-                .fetch_optional(&pgpool)
+        match notify_subscribers(&pgpool, audiobook_id).await {
+            Ok(()) => ack(&queue, &notifications_queue_name, &audiobook_message).await?,
+            Err(e) => {
+                error!("{e:?}");
+                nack(
+                    &queue,
+                    &notifications_queue_name,
+                    &audiobook_message,
+                    &e.to_string(),
+                    args.pgmq_max_retries,
+                )
                 .await?;
-        if let Some(Some(series_id)) = maybe_series_id {
-            let rows = sqlx::query(
-                "SELECT user_id FROM public.user_series_notification WHERE series_id = $1",
-            )
-            .bind(series_id)
-            .fetch_all(&pgpool)
-            .await?;
-
-            let user_ids: Vec<i64> = rows
-                .iter()
-                .map(|row| row.get::<i64, _>("user_id"))
-                .collect();
-            insert_or_update_notification(&pgpool, user_ids, &audiobook_id, "match_series").await?;
-        } else {
-            debug!(
-                "No series id associated with audiobook with id {}",
-                &audiobook_id
-            );
+            }
         }
+    }
+}
 
-        // Authors
-        info!(
-            "Pushing notifications of authors of audiobook {}",
-            audiobook_id
-        );
-        get_user_interested_to_entitities_and_update_notifications(
-            &pgpool,
-            "audiobook_author",
-            "author_id",
-            "user_author_notification",
-            "author_id",
-            "match_author",
-            &audiobook_id,
-        )
-        .await?;
+async fn notify_subscribers(pgpool: &PgPool, audiobook_id: i64) -> Result<(), Box<dyn Error>> {
+    // At this point we need to define what happens here. The logic performs as follows:
+    // 1. Get the series id, keywords, ... from that id.
+    // 2. For each of those elements, check in the notification tables which users is
+    //    subscribed to that particular entity.
+    // 3. For each user found, create a notification entry if not already there.
 
-        // Readers
-        info!(
-            "Pushing notifications of readers of audiobook {}",
-            audiobook_id
-        );
-        get_user_interested_to_entitities_and_update_notifications(
-            &pgpool,
-            "audiobook_reader",
-            "reader_id",
-            "user_reader_notification",
-            "reader_id",
-            "match_reader",
-            &audiobook_id,
-        )
-        .await?;
-
-        // Categories
-        info!(
-            "Pushing notifications of categories of audiobook {}",
-            audiobook_id
-        );
-        get_user_interested_to_entitities_and_update_notifications(
-            &pgpool,
-            "audiobook_category",
-            "category_id",
-            "user_category_notification",
-            "category_id",
-            "match_category",
-            &audiobook_id,
-        )
+    // 1.1 - Series
+    let maybe_series_id: Option<Option<i64>> =
+        sqlx::query_scalar("SELECT series_id FROM audiobook where id = $1")
+            .bind(audiobook_id)
+            .fetch_optional(pgpool)
+            .await?;
+    if let Some(Some(series_id)) = maybe_series_id {
+        let rows = sqlx::query(&format!(
+            r"
+            SELECT usn.user_id
+            FROM public.user_series_notification usn
+            JOIN public.audiobook ab ON ab.id = $2
+            WHERE usn.series_id = $1 AND {}
+            ",
+            subscription_language_condition("usn")
+        ))
+        .bind(series_id)
+        .bind(audiobook_id)
+        .fetch_all(pgpool)
         .await?;
 
-        // Keywords
-        info!(
-            "Pushing notifications of keywords of audiobook {}",
-            audiobook_id
+        let user_ids: Vec<i64> = rows
+            .iter()
+            .map(|row| row.get::<i64, _>("user_id"))
+            .collect();
+        insert_or_update_notification(pgpool, user_ids, &audiobook_id, "match_series").await?;
+    } else {
+        debug!(
+            "No series id associated with audiobook with id {}",
+            &audiobook_id
         );
-        get_user_interested_to_entitities_and_update_notifications(
-            &pgpool,
-            "audiobook_keyword",
-            "keyword_id",
-            "user_keyword_notification",
-            "keyword_id",
-            "match_keyword",
-            &audiobook_id,
-        )
-        .await?;
     }
+
+    // Authors
+    info!(
+        "Pushing notifications of authors of audiobook {}",
+        audiobook_id
+    );
+    get_user_interested_to_entitities_and_update_notifications(
+        pgpool,
+        "audiobook_author",
+        "author_id",
+        "user_author_notification",
+        "author_id",
+        "match_author",
+        &audiobook_id,
+    )
+    .await?;
+
+    // Readers
+    info!(
+        "Pushing notifications of readers of audiobook {}",
+        audiobook_id
+    );
+    get_user_interested_to_entitities_and_update_notifications(
+        pgpool,
+        "audiobook_reader",
+        "reader_id",
+        "user_reader_notification",
+        "reader_id",
+        "match_reader",
+        &audiobook_id,
+    )
+    .await?;
+
+    // Categories
+    info!(
+        "Pushing notifications of categories of audiobook {}",
+        audiobook_id
+    );
+    // Subscribing to a category of the taxonomy covers its descendants too.
+    let user_ids = get_category_subscribers(pgpool, audiobook_id)
+        .await
+        .map_err(|e| e.to_string())?;
+    insert_or_update_notification(pgpool, user_ids, &audiobook_id, "match_category").await?;
+
+    // Keywords
+    info!(
+        "Pushing notifications of keywords of audiobook {}",
+        audiobook_id
+    );
+    // Subscribing to a keyword covers its synonyms too.
+    let user_ids = get_keyword_subscribers(pgpool, audiobook_id)
+        .await
+        .map_err(|e| e.to_string())?;
+    insert_or_update_notification(pgpool, user_ids, &audiobook_id, "match_keyword").await?;
+    Ok(())
 }
 
 /// Sends a test notification to the notification queues.
@@ -0,0 +1,53 @@
+use shared::db_ops::pgmq::dead_letter::{
+    DeadLetter, list_dead_letters, purge_dead_letters, requeue_dead_letters,
+};
+use shared::db_ops::pgmq::get_pgmq_queue;
+use shared::private_args::Args;
+use tracing::info;
+
+use crate::cli_args::QueueSubCommand;
+
+/// Inspects, requeues or purges the dead letters of one of the queues.
+///
+/// # Errors
+///   - If the queue can't be created.
+///   - If any of the operations on the dead-letter queue fails.
+pub async fn handle_queue(
+    queue_sub_command: QueueSubCommand,
+    args: Args,
+) -> Result<(), Box<dyn std::error::Error>> {
+    match queue_sub_command {
+        QueueSubCommand::Inspect { queue, limit } => {
+            let queue_name = queue.queue_name(&args);
+            let pgmq = get_pgmq_queue(&args, queue_name).await?;
+            let dead_letters = list_dead_letters(&pgmq, queue_name, limit).await?;
+            println!("{} dead letters for queue {queue_name}", dead_letters.len());
+            for row in dead_letters {
+                let dead_letter: DeadLetter<serde_json::Value> =
+                    serde_json::from_value(row.message)?;
+                println!(
+                    "[{}] dead-lettered at {} after {} failures (first enqueued at {})\n    task: {}\n    last error: {}",
+                    row.msg_id,
+                    row.enqueued_at,
+                    dead_letter.failures,
+                    dead_letter.first_enqueued_at,
+                    dead_letter.task,
+                    dead_letter.last_error
+                );
+            }
+        }
+        QueueSubCommand::Requeue { queue, msg_id } => {
+            let queue_name = queue.queue_name(&args);
+            let pgmq = get_pgmq_queue(&args, queue_name).await?;
+            let requeued = requeue_dead_letters(&pgmq, queue_name, msg_id).await?;
+            info!("Requeued {} tasks onto {}", requeued, queue_name);
+        }
+        QueueSubCommand::Purge { queue } => {
+            let queue_name = queue.queue_name(&args);
+            let pgmq = get_pgmq_queue(&args, queue_name).await?;
+            let purged = purge_dead_letters(&pgmq, queue_name).await?;
+            info!("Purged {} dead letters of {}", purged, queue_name);
+        }
+    }
+    Ok(())
+}
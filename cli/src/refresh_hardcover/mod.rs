@@ -0,0 +1,101 @@
+//! Periodic refresh of the ratings of the books matched on Hardcover.
+//!
+//! The ratings are captured when an audiobook is ingested. This job re-queries the least recently
+//! attempted matches, within a budget of requests per round, and keeps a short history of the
+//! ratings so that trends can be computed. The matches of the books removed from Hardcover are
+//! dropped.
+
+use std::time::Duration;
+
+use chrono::{TimeDelta, Utc};
+use shared::db_ops::parade::get_postgres_connection;
+use shared::db_ops::parade::hardcover_ops::{
+    delete_hardcover_match, get_stale_hardcover_books, record_hardcover_refresh_attempt,
+    update_hardcover_ratings,
+};
+use shared::private_args::Args;
+use tokio::time::sleep;
+use tracing::{info, warn};
+
+use crate::scraping::hardcover::{HARDCOVER_HOST, HARDCOVER_REQUESTS_PER_MINUTE, get_book_ratings};
+use crate::scraping::utils::http::build_robust_client;
+use crate::scraping::utils::rate_limit::HostRateLimiter;
+
+/// Refreshes the ratings of up to `max_requests` Hardcover matches not refreshed in the last
+/// `stale_after_days` days, then sleeps for `every_n_seconds`.
+///
+/// # Errors
+/// - If no Hardcover API key is configured.
+/// - If the stale matches can't be listed.
+pub async fn refresh_hardcover(
+    args: Args,
+    every_n_seconds: u32,
+    max_requests: i64,
+    stale_after_days: i64,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let api_key = args
+        .hardcover_api_key
+        .clone()
+        .ok_or("--hardcover-api-key is required to refresh the ratings")?;
+    let pgpool = get_postgres_connection(&args).await;
+    let client = build_robust_client();
+    let rate_limiter = HostRateLimiter::new(HARDCOVER_REQUESTS_PER_MINUTE, 1);
+
+    loop {
+        let stale_before = Utc::now() - TimeDelta::days(stale_after_days);
+        let stale = get_stale_hardcover_books(&pgpool, stale_before, max_requests)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let mut refreshed = 0;
+        for book in &stale {
+            rate_limiter.acquire(HARDCOVER_HOST).await;
+            match get_book_ratings(&client, &api_key, book.hardcover_id).await {
+                Ok(Some(ratings)) => {
+                    match update_hardcover_ratings(&pgpool, book.audiobook_id, &ratings).await {
+                        Ok(()) => refreshed += 1,
+                        Err(e) => warn!(
+                            "Couldn't store the ratings of audiobook {}: {}",
+                            book.audiobook_id, e
+                        ),
+                    }
+                }
+                Ok(None) => {
+                    warn!(
+                        "Hardcover book {} of audiobook {} no longer exists, dropping the match",
+                        book.hardcover_id, book.audiobook_id
+                    );
+                    if let Err(e) = delete_hardcover_match(&pgpool, book.audiobook_id).await {
+                        warn!(
+                            "Couldn't drop the hardcover match of audiobook {}: {}",
+                            book.audiobook_id, e
+                        );
+                    }
+                }
+                Err(e) => {
+                    warn!(
+                        "Couldn't fetch the ratings of hardcover book {}: {}",
+                        book.hardcover_id, e
+                    );
+                    // Otherwise the failing matches would come first again in the next round.
+                    if let Err(e) =
+                        record_hardcover_refresh_attempt(&pgpool, book.audiobook_id).await
+                    {
+                        warn!(
+                            "Couldn't record the refresh attempt of audiobook {}: {}",
+                            book.audiobook_id, e
+                        );
+                    }
+                }
+            }
+        }
+
+        info!(
+            "Refreshed the hardcover ratings of {} of {} audiobooks. Sleeping for {} hours now.",
+            refreshed,
+            stale.len(),
+            every_n_seconds / 3600
+        );
+        sleep(Duration::from_secs(every_n_seconds.into())).await;
+    }
+}
@@ -0,0 +1,103 @@
+use chrono::NaiveDate;
+use shared::db_ops::parade::get_postgres_connection;
+use shared::db_ops::parade::raw_page_ops::{
+    get_audiobook_paths_by_ids, get_audiobook_paths_ingested_between, get_latest_archived_page,
+};
+use shared::private_args::Args;
+use shared::utils::llm::{build_content_embedder, build_content_generator};
+use tracing::{info, warn};
+
+use crate::scraping::scrape_impl::{ProcessedAudiobook, process_audiobook_page};
+use crate::scraping::utils::category_mapping::map_categories;
+use crate::scraping::utils::db::update_audiobook_transaction;
+
+/// Re-runs the extraction, the descriptions and the embeddings of already ingested audiobooks
+/// using the archived pages, without scraping the site again.
+///
+/// Audiobooks are selected either by id or by their ingestion date in `[from, to]`. No
+/// notifications are sent for reprocessed audiobooks.
+///
+/// # Errors
+///   - If neither the ids nor a date range are provided.
+///   - If the audiobooks can't be retrieved.
+///   - If the LLM clients can't be created.
+pub async fn handle_reprocess(
+    audiobook_ids: Vec<i64>,
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+    args: Args,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let pool = get_postgres_connection(&args).await;
+
+    let audiobooks = match (audiobook_ids.is_empty(), from, to) {
+        (false, _, _) => get_audiobook_paths_by_ids(&pool, &audiobook_ids)
+            .await
+            .map_err(|e| e.to_string())?,
+        (true, Some(from), Some(to)) => {
+            let from = from.and_hms_opt(0, 0, 0).unwrap().and_utc();
+            let to = to.succ_opt().ok_or("Invalid end date")?;
+            let to = to.and_hms_opt(0, 0, 0).unwrap().and_utc();
+            get_audiobook_paths_ingested_between(&pool, from, to)
+                .await
+                .map_err(|e| e.to_string())?
+        }
+        _ => return Err("Either --audiobook-ids or both --from and --to must be provided".into()),
+    };
+    info!("Reprocessing {} audiobooks", audiobooks.len());
+
+    let g = build_content_generator(&args)?;
+    let g_emb = build_content_embedder(&args)?;
+
+    let mut failures = 0;
+    for audiobook in &audiobooks {
+        let Some(page) = get_latest_archived_page(&pool, &audiobook.path)
+            .await
+            .map_err(|e| e.to_string())?
+        else {
+            warn!(
+                "No archived page for audiobook {} ({}), skipping",
+                audiobook.id, audiobook.path
+            );
+            failures += 1;
+            continue;
+        };
+
+        let processed =
+            match process_audiobook_page(&args, g.as_ref(), g_emb.as_ref(), &page.content).await {
+                Ok(processed) => processed,
+                Err(e) => {
+                    warn!("Couldn't reprocess audiobook {}: {}", audiobook.id, e);
+                    failures += 1;
+                    continue;
+                }
+            };
+        let ProcessedAudiobook {
+            mut extracted,
+            short_description,
+            embeddable_description,
+            embeddings,
+        } = processed;
+        extracted.categories = map_categories(&pool, g.as_ref(), &extracted.categories).await?;
+
+        update_audiobook_transaction(
+            &pool,
+            audiobook.id,
+            &extracted,
+            &short_description,
+            &embeddable_description,
+            embeddings,
+        )
+        .await?;
+        info!(
+            "Reprocessed audiobook {} from the page fetched at {}",
+            audiobook.id, page.fetched_at
+        );
+    }
+
+    info!(
+        "Reprocessed {} audiobooks, {} failed",
+        audiobooks.len() - failures,
+        failures
+    );
+    Ok(())
+}
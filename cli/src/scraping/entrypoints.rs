@@ -1,78 +1,168 @@
+use std::sync::atomic::{AtomicI64, AtomicUsize, Ordering};
 use std::time::Duration;
 
-use pgmq::PGMQueue;
+use chrono::TimeDelta;
+use futures_util::future::try_join_all;
+use pgmq::Message;
+use shared::db_ops::parade::backfill_run_ops::{
+    BackfillCounts, BackfillRun, complete_backfill_run, create_backfill_run, get_backfill_run,
+    list_backfill_runs, record_backfill_page,
+};
 use shared::db_ops::parade::get_postgres_connection;
+use shared::db_ops::pgmq::dead_letter::{ack, nack};
 use shared::db_ops::pgmq::get_pgmq_queue;
 use shared::private_args::Args;
-use sqlx::PgPool;
 use tokio::time::sleep;
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, instrument, warn};
 use url::Url;
 
-use crate::scraping::queue_items::QueueTask;
-use crate::scraping::scrape_impl::{handle_audiobook_page, handle_submission_page};
+use crate::scraping::queue_items::{QueueTask, parse_submission_date};
+use crate::scraping::scrape_impl::{
+    ScrapeContext, SubmissionPageOutcome, handle_audiobook_page, handle_submission_page,
+};
 
+/// How long an idle worker waits before polling the queue again while other workers are busy.
+const IDLE_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Counts of the items processed by the workers.
+#[derive(Default)]
+struct WorkCounters {
+    enqueued: AtomicI64,
+    skipped: AtomicI64,
+    failed: AtomicI64,
+}
+
+impl WorkCounters {
+    fn record_submission_page(&self, outcome: SubmissionPageOutcome) {
+        self.enqueued.fetch_add(outcome.enqueued, Ordering::Relaxed);
+        self.skipped.fetch_add(outcome.skipped, Ordering::Relaxed);
+    }
+
+    fn counts(&self) -> BackfillCounts {
+        BackfillCounts {
+            enqueued: self.enqueued.load(Ordering::Relaxed),
+            skipped: self.skipped.load(Ordering::Relaxed),
+            failed: self.failed.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Runs `args.scrape_workers` workers on `queue_name` until the queue is drained.
+///
+/// The queue is considered drained once it's empty and no worker is processing a task, since a
+/// task being processed can enqueue new ones.
 async fn dequeue_and_do_work(
-    pgpool: &PgPool,
-    queue: &PGMQueue,
+    ctx: &ScrapeContext,
+    queue_name: &str,
+) -> Result<BackfillCounts, Box<dyn std::error::Error>> {
+    let busy_workers = AtomicUsize::new(0);
+    let counters = WorkCounters::default();
+    let workers = (0..ctx.args.scrape_workers.max(1))
+        .map(|worker| work(ctx, queue_name, &busy_workers, &counters, worker));
+    try_join_all(workers).await?;
+    Ok(counters.counts())
+}
+
+#[instrument(skip(ctx, queue_name, busy_workers, counters))]
+async fn work(
+    ctx: &ScrapeContext,
     queue_name: &str,
-    args: &Args,
+    busy_workers: &AtomicUsize,
+    counters: &WorkCounters,
+    worker: usize,
 ) -> Result<(), Box<dyn std::error::Error>> {
     loop {
-        let mut has_made_http_request: bool = true;
-        let maybe_queue_item = queue.pop::<QueueTask>(queue_name).await?;
-        if maybe_queue_item.is_none() {
-            return Ok(());
-        }
-        debug!("Found element in queue.");
-        match maybe_queue_item.unwrap().message {
-            QueueTask::ParseSubmissionPage(url) => {
-                info!("Parsing submission page {}", &url);
-                handle_submission_page(
-                    args,
-                    pgpool,
-                    queue,
-                    queue_name,
-                    url,
-                    &mut has_made_http_request,
-                )
-                .await?;
+        busy_workers.fetch_add(1, Ordering::SeqCst);
+        let maybe_queue_item = ctx
+            .queue
+            .read::<QueueTask>(queue_name, Some(ctx.args.pgmq_visibility_timeout_seconds))
+            .await;
+        let Some(queue_item) = maybe_queue_item? else {
+            if busy_workers.fetch_sub(1, Ordering::SeqCst) == 1 {
                 debug!(
-                    "After parsing submission page `has_made_http_request` is {}",
-                    &has_made_http_request
+                    "Queue {} is drained, stopping worker {}",
+                    queue_name, worker
                 );
+                return Ok(());
             }
-            QueueTask::ParseAudiobookPage {
-                url,
-                submission_date: _,
-            } => {
-                if !&url
-                    .to_lowercase()
-                    .contains(&args.shared.audiobookbay_domain)
-                {
-                    error!(
-                        "URL {} doesn't contain the base path {}",
-                        &url, &args.shared.audiobookbay_domain
-                    );
-                    continue;
-                }
-                info!("Parsing audiobook page {}", &url);
-                match handle_audiobook_page(args, pgpool, queue, url, &mut has_made_http_request)
-                    .await
-                {
-                    Ok(e) => debug!("{e:?}"),
-                    Err(e) => error!("{e:?}"),
-                }
-                debug!(
-                    "After parsing audiobookbay page `has_made_http_request` is {}",
-                    &has_made_http_request
+            sleep(IDLE_POLL_INTERVAL).await;
+            continue;
+        };
+        let result = do_work(ctx, queue_name, counters, &queue_item).await;
+        busy_workers.fetch_sub(1, Ordering::SeqCst);
+        if let Err(e) = result {
+            // Only acknowledging the message can fail here. It becomes visible again after its
+            // timeout, so the other workers don't need to stop.
+            error!(
+                "Worker {} failed to acknowledge message {}: {e}",
+                worker, queue_item.msg_id
+            );
+        }
+    }
+}
+
+async fn do_work(
+    ctx: &ScrapeContext,
+    queue_name: &str,
+    counters: &WorkCounters,
+    queue_item: &Message<QueueTask>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    debug!(
+        "Found element {} in queue (read {} times).",
+        queue_item.msg_id, queue_item.read_ct
+    );
+    let result = match queue_item.message.clone() {
+        QueueTask::ParseSubmissionPage(url) => {
+            info!("Parsing submission page {}", &url);
+            handle_submission_page(ctx, queue_name, url)
+                .await
+                .map(|outcome| counters.record_submission_page(outcome))
+        }
+        QueueTask::ParseAudiobookPage {
+            url,
+            submission_date,
+        } => {
+            if !&url
+                .to_lowercase()
+                .contains(&ctx.args.shared.audiobookbay_domain)
+            {
+                error!(
+                    "URL {} doesn't contain the base path {}",
+                    &url, &ctx.args.shared.audiobookbay_domain
+                );
+                // Retrying won't fix a malformed URL, so we just drop the task.
+                ack(&ctx.queue, queue_name, queue_item).await?;
+                return Ok(());
+            }
+            info!("Parsing audiobook page {}", &url);
+            let maybe_submission_date = parse_submission_date(&submission_date);
+            if maybe_submission_date.is_none() {
+                warn!(
+                    "Invalid submission date `{}` for audiobook page {}",
+                    submission_date, &url
                 );
             }
+            handle_audiobook_page(ctx, url, maybe_submission_date).await
         }
-        if has_made_http_request {
-            sleep(Duration::from_secs(30)).await;
+    };
+    match result {
+        Ok(()) => ack(&ctx.queue, queue_name, queue_item).await?,
+        Err(e) => {
+            error!("{e:?}");
+            let dead_lettered = nack(
+                &ctx.queue,
+                queue_name,
+                queue_item,
+                &e.to_string(),
+                ctx.args.pgmq_max_retries,
+            )
+            .await?;
+            if dead_lettered {
+                counters.failed.fetch_add(1, Ordering::Relaxed);
+            }
         }
     }
+    Ok(())
 }
 
 /// Function that continues scraping the latest page of audiobooks.
@@ -98,19 +188,26 @@ pub async fn handle_scrape_impl(args: Args) -> Result<(), Box<dyn std::error::Er
     let base = Url::parse(&base_str)?;
     let url: String = base.join("member/index?pid=1")?.to_string();
     let pgpool = get_postgres_connection(&args).await;
+    let ctx = ScrapeContext::new(args, pgpool, queue)?;
 
     let task = QueueTask::ParseSubmissionPage(url);
     loop {
-        _ = queue.send(&queue_name, &task).await?;
-        dequeue_and_do_work(&pgpool, &queue, &queue_name, &args).await?;
+        _ = ctx.queue.send(&queue_name, &task).await?;
+        let counts = dequeue_and_do_work(&ctx, &queue_name).await?;
+        info!("Processed the latest submissions: {:?}", counts);
         info!("Queue is empty, so sleeping for 1800 seconds");
         sleep(Duration::from_secs(1800)).await;
     }
 }
 /// Function that backfills the data from audiobookbay.
 ///
+/// The progress is checkpointed after every page in a backfill run, and passing the id of a run
+/// as `resume` continues it from the first page it didn't complete. No notifications are sent for
+/// the audiobooks created more than `skip_notifications_older_than_days` days ago.
+///
 /// # Errors
 ///   - If it is not possible to create a queue with the given configuration.
+///   - If the backfill run can't be created, found or updated.
 ///   - If adding an item to the queue fails.
 ///   - If the `dequeue_and_do_work` fails.
 /// # Panics
@@ -119,6 +216,8 @@ pub async fn handle_backfill_impl(
     args: Args,
     page_start: u16,
     page_end: u16,
+    resume: Option<i64>,
+    skip_notifications_older_than_days: Option<u32>,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let queue = get_pgmq_queue(&args, &args.pgmq_backfill_queue_name).await?;
     let queue_name = args.pgmq_backfill_queue_name.clone();
@@ -132,7 +231,21 @@ pub async fn handle_backfill_impl(
     );
     let base_url = Url::parse(&base_str)?;
     let pgpool = get_postgres_connection(&args).await;
-    for page in (page_start..page_end).rev() {
+
+    let run = match resume {
+        Some(run_id) => get_backfill_run(&pgpool, run_id).await,
+        None => create_backfill_run(&pgpool, page_start.into(), page_end.into()).await,
+    }
+    .map_err(|e| e.to_string())?;
+    info!(
+        "Backfill run {} of pages [{}, {}), resuming before page {:?}",
+        run.id, run.page_start, run.page_end, run.last_completed_page
+    );
+
+    let mut ctx = ScrapeContext::new(args, pgpool, queue)?;
+    ctx.notification_max_age =
+        skip_notifications_older_than_days.map(|days| TimeDelta::days(days.into()));
+    for page in run.remaining_pages().rev() {
         let url: String = base_url
             .join(&format!("member/index?pid={page}"))?
             .to_string();
@@ -142,9 +255,56 @@ pub async fn handle_backfill_impl(
         );
 
         let task = QueueTask::ParseSubmissionPage(url);
-        _ = queue.send(&queue_name, &task).await?;
+        _ = ctx.queue.send(&queue_name, &task).await?;
+
+        let counts = dequeue_and_do_work(&ctx, &queue_name).await?;
+        record_backfill_page(&ctx.pgpool, run.id, page, counts)
+            .await
+            .map_err(|e| e.to_string())?;
+        info!(
+            "Backfill run {} completed page {}: {:?}",
+            run.id, page, counts
+        );
+    }
+    complete_backfill_run(&ctx.pgpool, run.id)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
 
-        dequeue_and_do_work(&pgpool, &queue, &queue_name, &args).await?;
+/// Prints the most recent backfill runs.
+///
+/// # Errors
+///   - If the runs can't be listed.
+pub async fn handle_list_backfill_runs(
+    args: Args,
+    limit: i64,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let pgpool = get_postgres_connection(&args).await;
+    let runs = list_backfill_runs(&pgpool, limit)
+        .await
+        .map_err(|e| e.to_string())?;
+    for run in runs {
+        println!("{}", format_backfill_run(&run));
     }
     Ok(())
 }
+
+fn format_backfill_run(run: &BackfillRun) -> String {
+    let status = match (run.completed_at, run.last_completed_page) {
+        (Some(completed_at), _) => format!("completed at {completed_at}"),
+        (None, Some(page)) => format!("last completed page {page}"),
+        (None, None) => "not started".to_string(),
+    };
+    format!(
+        "[{}] pages [{}, {}) started at {}, {}: {} enqueued, {} skipped, {} failed",
+        run.id,
+        run.page_start,
+        run.page_end,
+        run.started_at,
+        status,
+        run.enqueued_count,
+        run.skipped_count,
+        run.failed_count
+    )
+}
@@ -1,9 +1,22 @@
 use reqwest::Client;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
-use serde_json::json;
+use serde_json::{Value, json};
+use shared::db_ops::parade::hardcover_ops::{
+    HardcoverAuthorData, HardcoverRatings, HardcoverSeriesBookData, HardcoverSeriesData,
+};
 use std::error::Error;
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+const HARDCOVER_GRAPHQL_URL: &str = "https://api.hardcover.app/v1/graphql";
+pub const HARDCOVER_HOST: &str = "api.hardcover.app";
+
+/// Hardcover allows 60 requests per minute, leave some room for the scraper.
+pub const HARDCOVER_REQUESTS_PER_MINUTE: f64 = 40.0;
+
+/// Maximum number of books listed for a series.
+const MAX_SERIES_BOOKS: i64 = 50;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct HardcoverBook {
     pub id: i64,
     pub title: String,
@@ -12,31 +25,60 @@ pub struct HardcoverBook {
     pub ratings_distribution: Option<serde_json::Value>,
     pub slug: Option<String>,
     pub reviews_count: Option<i64>,
+    #[serde(default)]
+    pub author_names: Vec<String>,
+    #[serde(default)]
+    pub series_names: Vec<String>,
+    #[serde(default)]
+    pub isbns: Vec<String>,
+    #[serde(default)]
+    pub release_year: Option<i32>,
+    #[serde(default)]
+    pub pages: Option<i32>,
+    #[serde(default)]
+    pub genres: Vec<String>,
+}
+
+/// An author returned by the Hardcover search.
+#[derive(Deserialize, Debug, Clone)]
+pub struct HardcoverAuthorHit {
+    pub id: String,
+    pub name: String,
+    pub books_count: Option<i64>,
+}
+
+/// A series returned by the Hardcover search.
+#[derive(Deserialize, Debug, Clone)]
+pub struct HardcoverSeriesHit {
+    pub id: String,
+    pub name: String,
+    pub author_name: Option<String>,
+    pub books_count: Option<i64>,
 }
 
 #[derive(Deserialize, Debug)]
-struct HardcoverSearchResponse {
-    data: HardcoverSearchData,
+struct GraphqlResponse<T> {
+    data: T,
 }
 
 #[derive(Deserialize, Debug)]
-struct HardcoverSearchData {
-    search: HardcoverSearchRoot,
+struct HardcoverSearchData<D> {
+    search: HardcoverSearchRoot<D>,
 }
 
 #[derive(Deserialize, Debug)]
-struct HardcoverSearchRoot {
-    results: HardcoverSearchResults,
+struct HardcoverSearchRoot<D> {
+    results: HardcoverSearchResults<D>,
 }
 
 #[derive(Deserialize, Debug)]
-struct HardcoverSearchResults {
-    hits: Vec<HardcoverSearchHit>,
+struct HardcoverSearchResults<D> {
+    hits: Vec<HardcoverSearchHit<D>>,
 }
 
 #[derive(Deserialize, Debug)]
-struct HardcoverSearchHit {
-    document: HardcoverSearchDocument,
+struct HardcoverSearchHit<D> {
+    document: D,
 }
 
 #[derive(Deserialize, Debug)]
@@ -47,39 +89,79 @@ struct HardcoverSearchDocument {
     ratings_count: Option<i64>,
     slug: Option<String>,
     reviews_count: Option<i64>,
+    #[serde(default)]
+    author_names: Vec<String>,
+    #[serde(default)]
+    series_names: Vec<String>,
+    #[serde(default)]
+    isbns: Vec<String>,
+    release_year: Option<i32>,
+    pages: Option<i32>,
+    #[serde(default)]
+    genres: Vec<String>,
 }
 
-/// # Errors
-/// If there are errors with the graphql request.
-pub async fn search_book(
+#[derive(Deserialize, Debug)]
+struct AuthorByPkData {
+    authors_by_pk: Option<AuthorByPk>,
+}
+
+#[derive(Deserialize, Debug)]
+struct AuthorByPk {
+    id: i64,
+    name: String,
+    slug: Option<String>,
+    bio: Option<String>,
+    books_count: Option<i64>,
+    cached_image: Option<Value>,
+}
+
+#[derive(Deserialize, Debug)]
+struct BookByPkData {
+    books_by_pk: Option<HardcoverRatings>,
+}
+
+#[derive(Deserialize, Debug)]
+struct SeriesByPkData {
+    series_by_pk: Option<SeriesByPk>,
+}
+
+#[derive(Deserialize, Debug)]
+struct SeriesByPk {
+    id: i64,
+    name: String,
+    slug: Option<String>,
+    description: Option<String>,
+    books_count: Option<i64>,
+    primary_books_count: Option<i64>,
+    book_series: Vec<BookSeries>,
+}
+
+#[derive(Deserialize, Debug)]
+struct BookSeries {
+    position: Option<f64>,
+    book: BookSeriesBook,
+}
+
+#[derive(Deserialize, Debug)]
+struct BookSeriesBook {
+    title: String,
+    slug: Option<String>,
+}
+
+async fn graphql<T: DeserializeOwned>(
     client: &Client,
     api_key: &str,
-    title: &str,
-    author: &str,
-) -> Result<Option<HardcoverBook>, Box<dyn Error>> {
-    let query = r#"
-        query SearchBooks($query: String!) {
-          search(
-            query: $query
-            query_type: "Audiobook"
-            per_page: 1
-            page: 1
-          ) {
-            results
-          }
-        }
-    "#;
-
-    let search_query = format!("{title} {author}");
+    query: &str,
+    variables: Value,
+) -> Result<T, Box<dyn Error>> {
     let body = json!({
         "query": query,
-        "variables": {
-            "query": search_query
-        }
+        "variables": variables
     });
 
     let response = client
-        .post("https://api.hardcover.app/v1/graphql")
+        .post(HARDCOVER_GRAPHQL_URL)
         .header("content-type", "application/json")
         .header("Authorization", api_key)
         .json(&body)
@@ -90,11 +172,68 @@ pub async fn search_book(
         return Err(format!("Hardcover API error: {}", response.status()).into());
     }
 
-    let response_body: HardcoverSearchResponse = response.json().await?;
+    let response_body: GraphqlResponse<T> = response.json().await?;
+    Ok(response_body.data)
+}
+
+async fn search<D: DeserializeOwned>(
+    client: &Client,
+    api_key: &str,
+    query_type: &str,
+    search_query: &str,
+    per_page: u32,
+) -> Result<Vec<D>, Box<dyn Error>> {
+    let query = r"
+        query Search($query: String!, $query_type: String!, $per_page: Int!) {
+          search(
+            query: $query
+            query_type: $query_type
+            per_page: $per_page
+            page: 1
+          ) {
+            results
+          }
+        }
+    ";
+    let data: HardcoverSearchData<D> = graphql(
+        client,
+        api_key,
+        query,
+        json!({
+            "query": search_query,
+            "query_type": query_type,
+            "per_page": per_page
+        }),
+    )
+    .await?;
+    Ok(data
+        .search
+        .results
+        .hits
+        .into_iter()
+        .map(|hit| hit.document)
+        .collect())
+}
+
+/// Returns up to `per_page` candidate books matching `title` and `author`, best first according to
+/// Hardcover.
+///
+/// # Errors
+/// If there are errors with the graphql request.
+pub async fn search_books(
+    client: &Client,
+    api_key: &str,
+    title: &str,
+    author: &str,
+    per_page: u32,
+) -> Result<Vec<HardcoverBook>, Box<dyn Error>> {
+    let search_query = format!("{title} {author}");
+    let documents: Vec<HardcoverSearchDocument> =
+        search(client, api_key, "Audiobook", &search_query, per_page).await?;
 
-    if let Some(hit) = response_body.data.search.results.hits.into_iter().next() {
-        let doc = hit.document;
-        Ok(Some(HardcoverBook {
+    let mut books = Vec::new();
+    for doc in documents {
+        books.push(HardcoverBook {
             id: doc.id.parse()?,
             title: doc.title,
             rating: doc.rating,
@@ -102,8 +241,153 @@ pub async fn search_book(
             ratings_distribution: None,
             slug: doc.slug,
             reviews_count: doc.reviews_count,
-        }))
-    } else {
-        Ok(None)
+            author_names: doc.author_names,
+            series_names: doc.series_names,
+            isbns: doc.isbns,
+            release_year: doc.release_year,
+            pages: doc.pages,
+            genres: doc.genres,
+        });
     }
+    Ok(books)
+}
+
+/// Returns up to `per_page` authors matching `name`.
+///
+/// # Errors
+/// If there are errors with the graphql request.
+pub async fn search_authors(
+    client: &Client,
+    api_key: &str,
+    name: &str,
+    per_page: u32,
+) -> Result<Vec<HardcoverAuthorHit>, Box<dyn Error>> {
+    search(client, api_key, "Author", name, per_page).await
+}
+
+/// Returns up to `per_page` series matching `name`.
+///
+/// # Errors
+/// If there are errors with the graphql request.
+pub async fn search_series(
+    client: &Client,
+    api_key: &str,
+    name: &str,
+    per_page: u32,
+) -> Result<Vec<HardcoverSeriesHit>, Box<dyn Error>> {
+    search(client, api_key, "Series", name, per_page).await
+}
+
+/// Fetches the details of the author with the given Hardcover id.
+///
+/// # Errors
+/// If there are errors with the graphql request.
+pub async fn get_author(
+    client: &Client,
+    api_key: &str,
+    id: i64,
+) -> Result<Option<HardcoverAuthorData>, Box<dyn Error>> {
+    let query = r"
+        query GetAuthor($id: Int!) {
+          authors_by_pk(id: $id) {
+            id
+            name
+            slug
+            bio
+            books_count
+            cached_image
+          }
+        }
+    ";
+    let data: AuthorByPkData = graphql(client, api_key, query, json!({ "id": id })).await?;
+    Ok(data.authors_by_pk.map(|author| HardcoverAuthorData {
+        id: author.id,
+        name: author.name,
+        slug: author.slug,
+        bio: author.bio,
+        books_count: author.books_count,
+        image_url: author
+            .cached_image
+            .as_ref()
+            .and_then(|image| image["url"].as_str())
+            .map(ToString::to_string),
+    }))
+}
+
+/// Fetches the details of the series with the given Hardcover id, including its books ordered by
+/// position.
+///
+/// # Errors
+/// If there are errors with the graphql request.
+pub async fn get_series(
+    client: &Client,
+    api_key: &str,
+    id: i64,
+) -> Result<Option<HardcoverSeriesData>, Box<dyn Error>> {
+    let query = r"
+        query GetSeries($id: Int!, $limit: Int!) {
+          series_by_pk(id: $id) {
+            id
+            name
+            slug
+            description
+            books_count
+            primary_books_count
+            book_series(order_by: {position: asc}, limit: $limit) {
+              position
+              book {
+                title
+                slug
+              }
+            }
+          }
+        }
+    ";
+    let data: SeriesByPkData = graphql(
+        client,
+        api_key,
+        query,
+        json!({ "id": id, "limit": MAX_SERIES_BOOKS }),
+    )
+    .await?;
+    Ok(data.series_by_pk.map(|series| HardcoverSeriesData {
+        id: series.id,
+        name: series.name,
+        slug: series.slug,
+        description: series.description,
+        books_count: series.books_count,
+        primary_books_count: series.primary_books_count,
+        books: series
+            .book_series
+            .into_iter()
+            .map(|book_series| HardcoverSeriesBookData {
+                title: book_series.book.title,
+                slug: book_series.book.slug,
+                position: book_series.position,
+            })
+            .collect(),
+    }))
+}
+
+/// Fetches the current ratings of the book with the given Hardcover id.
+///
+/// # Errors
+/// If there are errors with the graphql request.
+pub async fn get_book_ratings(
+    client: &Client,
+    api_key: &str,
+    id: i64,
+) -> Result<Option<HardcoverRatings>, Box<dyn Error>> {
+    let query = r"
+        query GetBookRatings($id: Int!) {
+          books_by_pk(id: $id) {
+            rating
+            ratings_count
+            reviews_count
+            ratings_distribution
+          }
+        }
+    ";
+    let data: BookByPkData = graphql(client, api_key, query, json!({ "id": id })).await?;
+    Ok(data.books_by_pk)
 }
@@ -1,8 +1,9 @@
 mod entrypoints;
 pub mod hardcover;
+pub mod open_library;
 mod prompts;
 mod queue_items;
-mod scrape_impl;
+pub mod scrape_impl;
 pub mod utils;
 
-pub use entrypoints::{handle_backfill_impl, handle_scrape_impl};
+pub use entrypoints::{handle_backfill_impl, handle_list_backfill_runs, handle_scrape_impl};
@@ -0,0 +1,169 @@
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+
+/// Fields requested from the search API, the subjects come from the works API.
+const SEARCH_FIELDS: &str = "key,title,author_name,isbn,first_publish_year,number_of_pages_median";
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OpenLibraryBook {
+    /// Key of the work, e.g. "/works/OL45804W".
+    pub key: String,
+    pub title: String,
+    #[serde(default)]
+    pub author_names: Vec<String>,
+    #[serde(default)]
+    pub isbns: Vec<String>,
+    pub first_publish_year: Option<i32>,
+    pub number_of_pages_median: Option<i32>,
+    /// Only filled once the work has been fetched.
+    #[serde(default)]
+    pub subjects: Vec<String>,
+}
+
+/// The parts of an Open Library work that the search doesn't return.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct OpenLibraryWork {
+    #[serde(default)]
+    pub subjects: Vec<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct SearchResponse {
+    docs: Vec<SearchDocument>,
+}
+
+#[derive(Deserialize, Debug)]
+struct SearchDocument {
+    key: String,
+    title: String,
+    #[serde(default)]
+    author_name: Vec<String>,
+    #[serde(default)]
+    isbn: Vec<String>,
+    first_publish_year: Option<i32>,
+    number_of_pages_median: Option<i32>,
+}
+
+/// Returns up to `limit` works matching `title` and `author`, best first according to Open
+/// Library.
+///
+/// # Errors
+/// If the request fails or the response can't be parsed.
+pub async fn search_books(
+    client: &Client,
+    base_url: &str,
+    title: &str,
+    author: &str,
+    limit: u32,
+) -> Result<Vec<OpenLibraryBook>, Box<dyn Error>> {
+    let response = client
+        .get(format!("{}/search.json", base_url.trim_end_matches('/')))
+        .query(&[
+            ("title", title),
+            ("author", author),
+            ("fields", SEARCH_FIELDS),
+            ("limit", &limit.to_string()),
+        ])
+        .send()
+        .await?;
+
+    if !response.status().is_success() {
+        return Err(format!("Open Library API error: {}", response.status()).into());
+    }
+
+    let response_body: SearchResponse = response.json().await?;
+    Ok(response_body
+        .docs
+        .into_iter()
+        .map(|doc| OpenLibraryBook {
+            key: doc.key,
+            title: doc.title,
+            author_names: doc.author_name,
+            isbns: doc.isbn,
+            first_publish_year: doc.first_publish_year,
+            number_of_pages_median: doc.number_of_pages_median,
+            subjects: Vec::new(),
+        })
+        .collect())
+}
+
+/// Fetches the work with the given key, e.g. "/works/OL45804W".
+///
+/// # Errors
+/// If the request fails or the response can't be parsed.
+pub async fn get_work(
+    client: &Client,
+    base_url: &str,
+    key: &str,
+) -> Result<OpenLibraryWork, Box<dyn Error>> {
+    let response = client
+        .get(format!("{}{key}.json", base_url.trim_end_matches('/')))
+        .send()
+        .await?;
+
+    if !response.status().is_success() {
+        return Err(format!("Open Library API error: {}", response.status()).into());
+    }
+
+    Ok(response.json().await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use wiremock::matchers::{method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_search_books_and_get_work() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/search.json"))
+            .and(query_param("title", "The Well of Ascension"))
+            .and(query_param("author", "Brandon Sanderson"))
+            .and(query_param("limit", "5"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "numFound": 1,
+                "docs": [{
+                    "key": "/works/OL5738148W",
+                    "title": "The Well of Ascension",
+                    "author_name": ["Brandon Sanderson"],
+                    "isbn": ["0765316889", "9780765316882"],
+                    "first_publish_year": 2007
+                }]
+            })))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/works/OL5738148W.json"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "key": "/works/OL5738148W",
+                "title": "The Well of Ascension",
+                "subjects": ["Fantasy", "Magic"]
+            })))
+            .mount(&mock_server)
+            .await;
+
+        let client = Client::new();
+        let books = search_books(
+            &client,
+            &mock_server.uri(),
+            "The Well of Ascension",
+            "Brandon Sanderson",
+            5,
+        )
+        .await
+        .unwrap();
+        assert_eq!(books.len(), 1);
+        assert_eq!(books[0].author_names, vec!["Brandon Sanderson"]);
+        assert_eq!(books[0].first_publish_year, Some(2007));
+        assert_eq!(books[0].number_of_pages_median, None);
+
+        let work = get_work(&client, &mock_server.uri(), &books[0].key)
+            .await
+            .unwrap();
+        assert_eq!(work.subjects, vec!["Fantasy", "Magic"]);
+    }
+}
@@ -76,103 +76,46 @@ pub fn get_submission_list_schema(base_path: &String) -> serde_json::Value {
     })
 }
 
-pub fn get_audiobook_schema() -> serde_json::Value {
+pub const MAP_CATEGORIES_PROMPT: &str = r"
+You are a librarian. Map each of the audiobook categories below onto the
+genre taxonomy that follows. Pick the most specific genre that fits the
+category, and leave the genre empty if none fits, e.g. for formats such as
+'Unabridged' or for categories that are not genres.
+
+Taxonomy, one genre per line from the root down:
+{taxonomy}
+
+Categories:
+{categories}
+";
+
+pub fn get_category_mapping_schema() -> serde_json::Value {
     json!({
-            "type": "object",
-            "properties": {
-                "title": {
-                    "type": "string",
-                    "description": "The full title of the audiobook."
-                },
-                "categories": {
-                    "type": "array",
-                    "description": "A list of categories or genres associated with the audiobook. e.g., 'Fiction', 'Science Fiction'.",
-                    "items": {
-                        "type": "string"
-                    }
-                },
-                "language": {
-                    "type": "string",
-                    "description": "The language the audiobook is narrated in."
-                },
-                "keywords": {
-                    "type": "array",
-                    "description": "A list of keywords or tags related to the audiobook's content or themes.",
-                    "items": {
-                        "type": "string"
-                    }
-                },
-                "cover_url": {
-                    "type": "string",
-                    "description": "The direct, fully qualified URL to the audiobook's cover image."
-                },
-                "authors": {
-                    "type": "array",
-                    "description": "The names of the authors who wrote the book.",
-                    "items": {
-                        "type": "string"
-                    }
-                },
-                "read_by": {
-                    "type": "array",
-                    "description": "The names of the narrators or voice actors.",
-                    "items": {
-                        "type": "string"
-                    }
-                },
-                "format": {
-                    "type": "string",
-                    "description": "The audio file format, e.g., 'MP3', 'M4B'."
-                },
-                "bitrate": {
-                    "type": "string",
-                    "description": "The bitrate of the audio file, if available. e.g., '128kbps'."
-                },
-                "unabridged": {
-                    "type": "boolean",
-                    "description": "Set to true if the audiobook is an unabridged version."
-                },
-                "description": {
-                    "type": "string",
-                    "description": "The full HTML content of the audiobook's description section. Remove any other metadata that was already parsed as part by other fields from the description."
-                },
-                "file_size": {
-                    "type": "string",
-                    "description": "The total file size of the audiobook, if specified. e.g., '1.2 GB'."
-                },
-                "runtime": {
-                    "type": "string",
-                    "description": "The total runtime of the audiobook, preferably in HH:MM:SS format."
-                },
-                "is_part_of_series": {
-                    "type": "boolean",
-                    "description": "Set to true if the audiobook is part of a series."
-                },
-                "series": {
-                    "type": "string",
-                    "description": "The title of the series the audiobook belongs to. Extract this even if it's part of the main title."
-                },
-                "series_volume": {
-                    "type": "string",
-                    "description": "The volume or book number within the series. e.g., 'Book 1', 'Volume 3'."
-                },
-                "upload_date": {
-                    "type": "string",
-                    "description": "The date of the upload in the format YYYY-MM-DD. E.g. 2025-09-18."
+        "type": "object",
+        "properties": {
+            "mappings": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "category": {
+                            "type": "string",
+                            "description": "The category exactly as it was provided."
+                        },
+                        "taxonomy_path": {
+                            "type": "string",
+                            "description": "The full line of the taxonomy the category maps to, or an empty string if none fits."
+                        }
+                    },
+                    "required": [
+                        "category",
+                        "taxonomy_path"
+                    ]
                 }
-            },
-            "required": [
-                "title",
-                "categories",
-                "language",
-                "keywords",
-                "cover_url",
-                "authors",
-                "read_by",
-                "format",
-                "unabridged", // Even with a default, it's not nullable, so it's required.
-                "description"
-            ]
-
+            }
+        },
+        "required": [
+            "mappings"
+        ]
     })
 }
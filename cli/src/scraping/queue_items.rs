@@ -1,5 +1,9 @@
+use chrono::NaiveDate;
 use serde::{Deserialize, Serialize};
 
+/// Format of the `submission_date` of the audiobook pages.
+const SUBMISSION_DATE_FORMAT: &str = "%Y-%m-%d";
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum QueueTask {
     ParseSubmissionPage(String),
@@ -8,3 +12,24 @@ pub enum QueueTask {
         submission_date: String,
     },
 }
+
+/// Parses the `submission_date` of a `QueueTask::ParseAudiobookPage`.
+#[must_use]
+pub fn parse_submission_date(submission_date: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(submission_date.trim(), SUBMISSION_DATE_FORMAT).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_submission_date() {
+        assert_eq!(
+            parse_submission_date("2025-09-18"),
+            NaiveDate::from_ymd_opt(2025, 9, 18)
+        );
+        assert_eq!(parse_submission_date(""), None);
+        assert_eq!(parse_submission_date("18 Sep 2025"), None);
+    }
+}
@@ -1,49 +1,102 @@
-use gemini_rust::Gemini;
+use std::sync::Arc;
+
+use chrono::{NaiveDate, TimeDelta, Utc};
 use pgmq::PGMQueue;
+use reqwest::Client;
+use shared::db_ops::parade::quarantine_ops::{
+    quarantine_extraction, release_quarantined_extraction,
+};
+use shared::db_ops::parade::raw_page_ops::{PageKind, archive_page};
 use shared::db_ops::parade::search_ops::does_audiobook_exists;
-use shared::private_args::Args;
-use shared::utils::gemini::GeminiContentGeneratorLike;
+use shared::extracted_audiobook::{ExtractedAudiobook, InvalidExtraction};
+use shared::private_args::{Args, Extractor};
+use shared::utils::llm::{
+    ConcurrencyLimitedContentEmbedder, ConcurrencyLimitedContentGenerator, ContentEmbedder,
+    ContentGenerator, build_content_embedder, build_content_generator,
+};
 use sqlx::PgPool;
-use tracing::{debug, info, instrument};
+use tokio::sync::Semaphore;
+use tracing::{debug, info, instrument, warn};
 use url::Url;
 
 use crate::queue_messages::IngestedAudiobookMessage;
 use crate::scraping::prompts::{PARSE_HTML_INSTRUCTIONS, get_submission_list_schema};
 use crate::scraping::queue_items::QueueTask;
+use crate::scraping::utils::category_mapping::map_categories;
 use crate::scraping::utils::db::save_audiobook_transaction;
 use crate::scraping::utils::gemini_extraction::{
     create_embeddable_description, create_embeddings, create_short_description,
     extract_data_from_html,
 };
-use crate::scraping::utils::hardcover_ops::resolve_hardcover_metadata;
-use crate::scraping::utils::html::extract_only_new_submissions_table;
+use crate::scraping::utils::html::{
+    extract_only_new_submissions_table, extract_submissions_with_selectors,
+};
 use crate::scraping::utils::http::{build_robust_client, get_with_retries};
+use crate::scraping::utils::metadata_provider::resolve_book_metadata;
+use crate::scraping::utils::rate_limit::HostRateLimiter;
+
+/// State shared by all the scraping workers.
+pub struct ScrapeContext {
+    pub args: Args,
+    pub pgpool: PgPool,
+    pub queue: PGMQueue,
+    pub client: Client,
+    pub rate_limiter: HostRateLimiter,
+    pub generator: Arc<dyn ContentGenerator>,
+    pub embedder: Arc<dyn ContentEmbedder>,
+    /// Audiobooks created longer than this ago are ingested without sending notifications.
+    pub notification_max_age: Option<TimeDelta>,
+}
+
+impl ScrapeContext {
+    /// Builds the http client, the rate limiter and the LLM clients described by `args`.
+    ///
+    /// # Errors
+    ///   - If the LLM clients can't be built.
+    pub fn new(
+        args: Args,
+        pgpool: PgPool,
+        queue: PGMQueue,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let llm_permits = Arc::new(Semaphore::new(args.llm_max_concurrent_requests.max(1)));
+        let generator = Arc::new(ConcurrencyLimitedContentGenerator::new(
+            build_content_generator(&args)?,
+            llm_permits.clone(),
+        ));
+        let embedder = Arc::new(ConcurrencyLimitedContentEmbedder::new(
+            build_content_embedder(&args)?,
+            llm_permits,
+        ));
+        let rate_limiter = HostRateLimiter::new(args.scrape_requests_per_minute, args.scrape_burst);
+        Ok(ScrapeContext {
+            args,
+            pgpool,
+            queue,
+            client: build_robust_client(),
+            rate_limiter,
+            generator,
+            embedder,
+            notification_max_age: None,
+        })
+    }
+}
+
+/// What happened to the audiobooks listed on a submission page.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SubmissionPageOutcome {
+    pub enqueued: i64,
+    pub skipped: i64,
+}
 
 #[instrument(skip_all)]
 pub async fn handle_submission_page(
-    args: &Args,
-    pgpool: &PgPool,
-    queue: &PGMQueue,
+    ctx: &ScrapeContext,
     queue_name: &str,
     url: String,
-    has_made_http_request: &mut bool,
-) -> Result<(), Box<dyn std::error::Error>> {
-    let g = Gemini::with_model(
-        &args.gemini_api_key,
-        args.shared.gemini_extract_html_model_name.clone(),
-    )
-    .unwrap();
-    let client = build_robust_client();
-    let res = get_with_retries(
-        &client,
-        &url,
-        &args.shared.audiobookbay_domain,
-        &args.shared.audiobookbay_extensions,
-    )
-    .await?
-    .text()
-    .await
-    .unwrap();
+) -> Result<SubmissionPageOutcome, Box<dyn std::error::Error>> {
+    let args = &ctx.args;
+    let pgpool = &ctx.pgpool;
+    let res = fetch_and_archive_page(ctx, &url, PageKind::Submission).await?;
     debug!("Res body: {res}");
     let submission_table =
         extract_only_new_submissions_table(&res).ok_or("Couldn't get the new items table")?;
@@ -60,11 +113,31 @@ pub async fn handle_submission_page(
             .expect("At least one extension must be provided")
     );
 
-    let res = g
-        .generate_structured_content(&fomatted_prompt, get_submission_list_schema(&base_str))
-        .await?;
+    let selectors_result = if args.extractor == Extractor::Llm {
+        None
+    } else {
+        extract_submissions_with_selectors(&submission_table)
+    };
+    let res = match (selectors_result, args.extractor) {
+        (Some(res), _) => res,
+        (None, Extractor::Selectors) => {
+            return Err("Couldn't extract the submissions with selectors".into());
+        }
+        (None, _) => {
+            if args.extractor == Extractor::SelectorsThenLlm {
+                warn!("Couldn't extract the submissions with selectors, falling back to the LLM");
+            }
+            ctx.generator
+                .generate_structured_content(
+                    &fomatted_prompt,
+                    get_submission_list_schema(&base_str),
+                )
+                .await?
+        }
+    };
     let base_url = Url::parse(&base_str)?;
 
+    let mut outcome = SubmissionPageOutcome::default();
     if let Some(submissions) = res["submissions"].as_array() {
         let rev_subs: Vec<_> = submissions.iter().rev().collect();
         for submission in rev_subs {
@@ -76,7 +149,7 @@ pub async fn handle_submission_page(
                 .map_err(|e| e.to_string())?;
             if audiobook_exists {
                 info!("Skipping enqueueing audiobook as it already exists");
-                *has_made_http_request = true;
+                outcome.skipped += 1;
                 continue;
             }
             let submission_date = submission["submission_date"]
@@ -88,75 +161,103 @@ pub async fn handle_submission_page(
                 url,
                 submission_date,
             };
-            _ = queue.send(queue_name, &queue_task).await?;
+            _ = ctx.queue.send(queue_name, &queue_task).await?;
+            outcome.enqueued += 1;
         }
     }
-    *has_made_http_request = true;
 
-    Ok(())
+    Ok(outcome)
 }
 
 #[instrument(skip_all)]
 pub async fn handle_audiobook_page(
-    args: &Args,
-    pool: &PgPool,
-    queue: &PGMQueue,
+    ctx: &ScrapeContext,
     url: String,
-    has_made_http_request: &mut bool,
+    submission_date: Option<NaiveDate>,
 ) -> Result<(), Box<dyn std::error::Error>> {
+    let args = &ctx.args;
+    let pool = &ctx.pgpool;
     let notification_queue_name = &args.pgmq_notifications_queue_name;
     let audiobook_exists = does_audiobook_exists(pool, &url)
         .await
         .map_err(|e| e.to_string())?;
     if audiobook_exists {
         info!("Audiobook at url ({})exists, skipping...", &url);
-        *has_made_http_request = false;
         return Ok(());
     }
-    let g = Gemini::with_model(
-        &args.gemini_api_key,
-        args.shared.gemini_extract_html_model_name.clone(),
-    )
-    .unwrap();
-    let g_emb = Gemini::with_model(
-        &args.gemini_api_key,
-        args.shared.gemini_embedding_model_name.clone(),
-    )
-    .unwrap();
 
-    let extracted_values = extract_data_from_html(&g, &url, args).await?;
-    info!(
-        "Extracted values length {}",
-        extracted_values.to_string().len()
-    );
-    debug!("Raw values: {:?}", extracted_values);
-
-    let maybe_description = extracted_values["description"].as_str();
-    let short_description = create_short_description(&g, maybe_description).await?;
-    info!("Short description: {}", &short_description);
-
-    let embeddable_description = create_embeddable_description(&g, maybe_description).await?;
-    debug!("Embeddable description: {}", &embeddable_description);
-
-    let embeddings = create_embeddings(&g_emb, &embeddable_description, args).await?;
+    let res = fetch_and_archive_page(ctx, &url, PageKind::Audiobook).await?;
+    let extracted = match extract_audiobook_page(args, ctx.generator.as_ref(), &res)
+        .await
+        .and_then(|extracted| {
+            // Checked before generating the descriptions and the embeddings, which would be
+            // thrown away.
+            extracted.creation_date(submission_date)?;
+            Ok(extracted)
+        }) {
+        Ok(extracted) => extracted,
+        Err(e) => {
+            // Invalid extractions won't get better by retrying, so they are set aside instead.
+            if let Some(invalid) = e.downcast_ref::<InvalidExtraction>() {
+                quarantine_extraction(pool, &url, invalid)
+                    .await
+                    .map_err(|e| e.to_string())?;
+                return Ok(());
+            }
+            return Err(e);
+        }
+    };
+    let processed = describe_audiobook(
+        args,
+        ctx.generator.as_ref(),
+        ctx.embedder.as_ref(),
+        extracted,
+    )
+    .await?;
+    let ProcessedAudiobook {
+        mut extracted,
+        short_description,
+        embeddable_description,
+        embeddings,
+    } = processed;
+    extracted.categories =
+        map_categories(pool, ctx.generator.as_ref(), &extracted.categories).await?;
 
-    let hardcover_book = resolve_hardcover_metadata(args, &extracted_values).await;
+    let metadata = resolve_book_metadata(args, &extracted).await;
 
     let audiobook_id = save_audiobook_transaction(
         pool,
         &url,
-        &extracted_values,
+        &extracted,
+        submission_date,
         &short_description,
         &embeddable_description,
         embeddings,
-        hardcover_book,
+        metadata,
     )
     .await?;
 
     info!("Successfully inserted audiobook: {}", &url);
+    if let Err(e) = release_quarantined_extraction(pool, &url).await {
+        warn!(
+            "Couldn't release the quarantined extraction of {}: {}",
+            url, e
+        );
+    }
+
+    let created = extracted.upload_date().or(submission_date);
+    if let (Some(max_age), Some(created)) = (ctx.notification_max_age, created)
+        && created < Utc::now().date_naive() - max_age
+    {
+        info!(
+            "Not sending notifications for audiobook {} created on {}",
+            audiobook_id, created
+        );
+        return Ok(());
+    }
 
     let message = IngestedAudiobookMessage::new(audiobook_id);
-    queue.send(notification_queue_name, &message).await?;
+    ctx.queue.send(notification_queue_name, &message).await?;
     info!(
         "Successfully propagated audiobook_id to queue {}",
         notification_queue_name
@@ -164,3 +265,212 @@ pub async fn handle_audiobook_page(
 
     Ok(())
 }
+
+/// The data derived from an audiobook page, ready to be stored.
+pub struct ProcessedAudiobook {
+    pub extracted: ExtractedAudiobook,
+    pub short_description: String,
+    pub embeddable_description: String,
+    pub embeddings: Vec<f32>,
+}
+
+/// Runs the extraction, the description generation and the embedding on the html of an audiobook
+/// page.
+///
+/// # Errors
+///   - `InvalidExtraction` if the extraction fails validation.
+///   - If any of the other steps fails.
+#[instrument(skip_all)]
+pub async fn process_audiobook_page(
+    args: &Args,
+    g: &dyn ContentGenerator,
+    g_emb: &dyn ContentEmbedder,
+    res: &str,
+) -> Result<ProcessedAudiobook, Box<dyn std::error::Error>> {
+    let extracted = extract_audiobook_page(args, g, res).await?;
+    describe_audiobook(args, g, g_emb, extracted).await
+}
+
+/// Runs the extraction on the html of an audiobook page.
+///
+/// # Errors
+///   - `InvalidExtraction` if the extraction fails validation.
+///   - If the extraction fails.
+async fn extract_audiobook_page(
+    args: &Args,
+    g: &dyn ContentGenerator,
+    res: &str,
+) -> Result<ExtractedAudiobook, Box<dyn std::error::Error>> {
+    let extracted_values = extract_data_from_html(g, res, args).await?;
+    info!(
+        "Extracted values length {}",
+        extracted_values.to_string().len()
+    );
+    debug!("Raw values: {:?}", extracted_values);
+    Ok(ExtractedAudiobook::from_value(extracted_values)?)
+}
+
+/// Generates the descriptions and the embeddings of an extracted audiobook.
+///
+/// # Errors
+/// If the generation or the embedding fails.
+async fn describe_audiobook(
+    args: &Args,
+    g: &dyn ContentGenerator,
+    g_emb: &dyn ContentEmbedder,
+    extracted: ExtractedAudiobook,
+) -> Result<ProcessedAudiobook, Box<dyn std::error::Error>> {
+    let maybe_description = Some(extracted.description.as_str()).filter(|d| !d.is_empty());
+    let short_description = create_short_description(g, maybe_description).await?;
+    info!("Short description: {}", &short_description);
+
+    let embeddable_description = create_embeddable_description(g, maybe_description).await?;
+    debug!("Embeddable description: {}", &embeddable_description);
+
+    let embeddings = create_embeddings(g_emb, &embeddable_description, args).await?;
+
+    Ok(ProcessedAudiobook {
+        extracted,
+        short_description,
+        embeddable_description,
+        embeddings,
+    })
+}
+
+/// Fetches a page, respecting the rate limits, and stores it in the raw page archive.
+///
+/// Failing to archive the page is logged but doesn't fail the fetch.
+///
+/// # Errors
+///   - If `get_with_retries` fails.
+async fn fetch_and_archive_page(
+    ctx: &ScrapeContext,
+    url: &str,
+    page_kind: PageKind,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let res = get_with_retries(
+        &ctx.client,
+        url,
+        &ctx.args.shared.audiobookbay_domain,
+        &ctx.args.shared.audiobookbay_extensions,
+        &ctx.rate_limiter,
+    )
+    .await?
+    .text()
+    .await?;
+
+    if let Err(e) = archive_page(&ctx.pgpool, url, page_kind, &res).await {
+        warn!("Couldn't archive page {}: {}", url, e);
+    }
+    Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const AUDIOBOOK_PAGE: &str = include_str!("utils/fixtures/audiobook_page.html");
+    const CASSETTE_DIR: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/src/scraping/utils/fixtures/cassettes"
+    );
+
+    fn replay_args() -> Args {
+        Args::parse_from([
+            "cli",
+            "--hardcover-api-key",
+            "unused",
+            "--extractor",
+            "selectors",
+            "--cassette-mode",
+            "replay",
+            "--cassette-dir",
+            CASSETTE_DIR,
+        ])
+    }
+
+    #[sqlx::test(migrations = "../model/migrations")]
+    async fn test_handle_audiobook_page_from_fixtures(
+        pool: PgPool,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/audiobook"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(AUDIOBOOK_PAGE))
+            .mount(&mock_server)
+            .await;
+
+        let args = replay_args();
+        let queue = PGMQueue::new_with_pool(pool.clone()).await;
+        queue.create(&args.pgmq_notifications_queue_name).await?;
+
+        let ctx = ScrapeContext::new(args, pool.clone(), queue)?;
+
+        let url = format!("{}/audiobook", mock_server.uri());
+        handle_audiobook_page(&ctx, url.clone(), None).await?;
+
+        let (audiobook_id, title, very_short_description): (i64, String, String) = sqlx::query_as(
+            "SELECT id, title, very_short_description FROM audiobook WHERE path = $1",
+        )
+        .bind(&url)
+        .fetch_one(&pool)
+        .await?;
+        assert_eq!(title, "The Well of Ascension (Mistborn, Book 2)");
+        assert_eq!(
+            very_short_description,
+            "Vin and Elend must defend Luthadel from three armies after the fall of the Lord Ruler."
+        );
+
+        let hardcover_rows: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM hardcover_audiobook_metadata WHERE audiobook_id = $1",
+        )
+        .bind(audiobook_id)
+        .fetch_one(&pool)
+        .await?;
+        assert_eq!(hardcover_rows, 1);
+
+        let book_metadata =
+            shared::db_ops::parade::book_metadata_ops::get_book_metadata(&pool, audiobook_id)
+                .await
+                .map_err(|e| e.to_string())?
+                .expect("The merged book metadata should be stored");
+        assert_eq!(book_metadata.metadata.publication_year, Some(2007));
+        assert_eq!(
+            book_metadata.metadata.subjects,
+            vec!["Fantasy", "Magic", "Fiction"]
+        );
+
+        let archived = shared::db_ops::parade::raw_page_ops::get_latest_archived_page(&pool, &url)
+            .await
+            .map_err(|e| e.to_string())?;
+        assert_eq!(
+            archived.map(|page| page.content).as_deref(),
+            Some(AUDIOBOOK_PAGE)
+        );
+
+        let notification = ctx
+            .queue
+            .read::<IngestedAudiobookMessage>(&ctx.args.pgmq_notifications_queue_name, None)
+            .await?;
+        assert!(notification.is_some());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_replay_fails_on_unmatched_requests() {
+        let args = replay_args();
+        let g = build_content_generator(&args).unwrap();
+        let g_emb = build_content_embedder(&args).unwrap();
+        let result = process_audiobook_page(
+            &args,
+            g.as_ref(),
+            g_emb.as_ref(),
+            "<div class=\"post\"></div>",
+        )
+        .await;
+        assert!(result.is_err());
+    }
+}
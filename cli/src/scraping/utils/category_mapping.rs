@@ -0,0 +1,226 @@
+//! Mapping of the free-form categories of the audiobooks onto the curated taxonomy.
+//!
+//! The categories are looked up in `category_alias` first. The ones never seen before are mapped
+//! by the LLM, and its answers are stored as aliases so that every string is only sent once.
+
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+
+use serde::Deserialize;
+use shared::db_ops::parade::taxonomy_ops::{
+    AliasSource, TAXONOMY_PATH_SEPARATOR, TaxonomyNode, category_alias_key, get_category_aliases,
+    get_taxonomy, upsert_category_alias,
+};
+use shared::utils::llm::ContentGenerator;
+use sqlx::PgPool;
+use tracing::{info, instrument};
+
+use crate::scraping::prompts::{MAP_CATEGORIES_PROMPT, get_category_mapping_schema};
+
+#[derive(Deserialize, Debug)]
+struct CategoryMappings {
+    mappings: Vec<CategoryMapping>,
+}
+
+#[derive(Deserialize, Debug)]
+struct CategoryMapping {
+    category: String,
+    taxonomy_path: String,
+}
+
+/// Replaces the categories with the names of the taxonomy nodes they map to, dropping the ones
+/// that don't fit the taxonomy.
+///
+/// # Errors
+/// - If the aliases or the taxonomy can't be read or stored.
+/// - If the LLM can't map the categories never seen before, so that they aren't lost: the
+///   audiobook is saved with them once the task is retried.
+#[instrument(skip(pool, generator))]
+pub async fn map_categories(
+    pool: &PgPool,
+    generator: &dyn ContentGenerator,
+    categories: &[String],
+) -> Result<Vec<String>, Box<dyn Error>> {
+    let mut keys: Vec<String> = Vec::new();
+    for key in categories
+        .iter()
+        .map(|category| category_alias_key(category))
+    {
+        if !key.is_empty() && !keys.contains(&key) {
+            keys.push(key);
+        }
+    }
+    let mut mapped: HashMap<String, Option<String>> = get_category_aliases(pool, &keys)
+        .await
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|(alias, known)| (alias, known.category_name))
+        .collect();
+
+    let unknown: Vec<String> = keys
+        .iter()
+        .filter(|key| !mapped.contains_key(*key))
+        .cloned()
+        .collect();
+    if !unknown.is_empty() {
+        let llm_mapped = map_with_llm(pool, generator, &unknown)
+            .await
+            .map_err(|e| format!("Couldn't map the categories {unknown:?}: {e}"))?;
+        mapped.extend(llm_mapped);
+    }
+
+    let mut names: Vec<String> = Vec::new();
+    for name in keys
+        .iter()
+        .filter_map(|key| mapped.get(key).cloned().flatten())
+    {
+        if !names.contains(&name) {
+            names.push(name);
+        }
+    }
+    Ok(names)
+}
+
+/// Asks the LLM where the `unknown` categories fit in the taxonomy, and stores its answers as
+/// aliases.
+async fn map_with_llm(
+    pool: &PgPool,
+    generator: &dyn ContentGenerator,
+    unknown: &[String],
+) -> Result<HashMap<String, Option<String>>, Box<dyn Error>> {
+    let taxonomy = get_taxonomy(pool).await.map_err(|e| e.to_string())?;
+    let prompt = MAP_CATEGORIES_PROMPT
+        .replace(
+            "{taxonomy}",
+            &taxonomy
+                .iter()
+                .map(|node| node.path.as_str())
+                .collect::<Vec<_>>()
+                .join("\n"),
+        )
+        .replace("{categories}", &unknown.join("\n"));
+    let response = generator
+        .generate_structured_content(&prompt, get_category_mapping_schema())
+        .await
+        .map_err(|e| e.to_string())?;
+    let response: CategoryMappings = serde_json::from_value(response)?;
+
+    let unknown: HashSet<&String> = unknown.iter().collect();
+    let mut mapped = HashMap::new();
+    for mapping in response.mappings {
+        let key = category_alias_key(&mapping.category);
+        if !unknown.contains(&key) || mapped.contains_key(&key) {
+            continue;
+        }
+        let node = resolve_taxonomy_path(&taxonomy, &mapping.taxonomy_path);
+        info!(
+            "Mapped the category `{}` to {:?}",
+            key,
+            node.map(|node| &node.path)
+        );
+        upsert_category_alias(
+            pool,
+            &key,
+            node.map(|node| node.category_id),
+            AliasSource::Llm,
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+        mapped.insert(key, node.map(|node| node.name.clone()));
+    }
+    Ok(mapped)
+}
+
+/// Finds the node designated by `path`, either a full path of the taxonomy or only the name of a
+/// node, ignoring case.
+fn resolve_taxonomy_path<'a>(taxonomy: &'a [TaxonomyNode], path: &str) -> Option<&'a TaxonomyNode> {
+    let path = path.trim();
+    if path.is_empty() {
+        return None;
+    }
+    let name = path
+        .rsplit(TAXONOMY_PATH_SEPARATOR.trim())
+        .next()
+        .unwrap_or(path)
+        .trim();
+    taxonomy
+        .iter()
+        .find(|node| node.path.eq_ignore_ascii_case(path))
+        .or_else(|| {
+            taxonomy
+                .iter()
+                .find(|node| node.name.eq_ignore_ascii_case(name))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+
+    use super::*;
+
+    /// A generator whose provider is down.
+    struct UnreachableGenerator;
+
+    #[async_trait]
+    impl ContentGenerator for UnreachableGenerator {
+        async fn generate_content(&self, _prompt: &str) -> Result<String, anyhow::Error> {
+            Err(anyhow::anyhow!("unreachable"))
+        }
+
+        async fn generate_structured_content(
+            &self,
+            _prompt: &str,
+            _schema: serde_json::Value,
+        ) -> Result<serde_json::Value, anyhow::Error> {
+            Err(anyhow::anyhow!("unreachable"))
+        }
+    }
+
+    fn node(category_id: i64, path: &str) -> TaxonomyNode {
+        TaxonomyNode {
+            category_id,
+            name: path.rsplit(" > ").next().unwrap().to_string(),
+            parent_id: None,
+            path: path.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_resolve_taxonomy_path() {
+        let taxonomy = vec![
+            node(1, "Fiction"),
+            node(2, "Fiction > Science Fiction"),
+            node(3, "Fiction > Science Fiction > Space Opera"),
+        ];
+        let resolve = |path| resolve_taxonomy_path(&taxonomy, path).map(|node| node.category_id);
+        assert_eq!(resolve("Fiction > Science Fiction > Space Opera"), Some(3));
+        assert_eq!(resolve("fiction > science fiction"), Some(2));
+        assert_eq!(resolve("Space Opera"), Some(3));
+        assert_eq!(resolve("Fiction>Space Opera"), Some(3));
+        assert_eq!(resolve(""), None);
+        assert_eq!(resolve("Cooking"), None);
+    }
+
+    #[sqlx::test(migrations = "../model/migrations")]
+    async fn test_unknown_categories_fail_when_the_llm_is_unreachable(pool: PgPool) {
+        let known = vec![String::from("Sci-Fi"), String::from("Mysteries")];
+        assert_eq!(
+            map_categories(&pool, &UnreachableGenerator, &known)
+                .await
+                .unwrap(),
+            vec!["Science Fiction", "Mystery"]
+        );
+
+        let with_unknown = vec![String::from("Sci-Fi"), String::from("Space Westerns")];
+        assert!(
+            map_categories(&pool, &UnreachableGenerator, &with_unknown)
+                .await
+                .is_err()
+        );
+        let aliases = get_category_aliases(&pool, &[String::from("space westerns")])
+            .await
+            .unwrap();
+        assert!(aliases.is_empty());
+    }
+}
@@ -1,47 +1,97 @@
-use serde_json::Value;
-use shared::db_ops::parade::save_ops::insert_audiobook_data;
+use chrono::NaiveDate;
+use shared::db_ops::parade::book_metadata_ops::upsert_book_metadata;
+use shared::db_ops::parade::hardcover_ops::{HardcoverRatings, record_hardcover_ratings};
+use shared::db_ops::parade::save_ops::{insert_audiobook_data, update_audiobook_data};
+use shared::extracted_audiobook::ExtractedAudiobook;
 use sqlx::PgPool;
 use tracing::info;
 
-use crate::scraping::hardcover::HardcoverBook;
+use crate::scraping::utils::hardcover_matching::ScoredMatch;
+use crate::scraping::utils::metadata_provider::ResolvedMetadata;
 
 /// # Errors
 ///   - If the storing of the audiobook on the db fails.
-///   - If the storing of hardcover information on the db fails.
+///   - If the storing of hardcover information or of the merged book metadata on the db fails.
 ///
 /// # Panics
 ///   - If the serialization of the hardcover data fails.
+#[allow(clippy::too_many_arguments)]
 pub async fn save_audiobook_transaction(
     pool: &PgPool,
     url: &str,
-    extracted_values: &Value,
+    extracted: &ExtractedAudiobook,
+    submission_date: Option<NaiveDate>,
     short_description: &str,
     embeddable_description: &str,
     embeddings: Vec<f32>,
-    hardcover_book: Option<HardcoverBook>,
+    metadata: ResolvedMetadata,
 ) -> Result<i64, Box<dyn std::error::Error>> {
     let mut tx = pool.begin().await?;
     let audiobook_id = insert_audiobook_data(
         &mut tx,
         url,
-        extracted_values,
+        extracted,
+        submission_date,
         short_description,
         embeddable_description,
         embeddings,
     )
     .await?;
 
-    if let Some(book) = hardcover_book {
+    if let Some(ScoredMatch { book, score }) = metadata.hardcover {
         sqlx::query!(
-            "INSERT INTO hardcover_audiobook_metadata (audiobook_id, metadata) VALUES ($1, $2)",
+            "INSERT INTO hardcover_audiobook_metadata (audiobook_id, metadata, match_score) VALUES ($1, $2, $3)",
             audiobook_id,
-            serde_json::to_value(&book).unwrap()
+            serde_json::to_value(&book).unwrap(),
+            score
         )
         .execute(&mut *tx)
         .await?;
-        info!("Inserted hardcover metadata for audiobook {}", audiobook_id);
+        let ratings = HardcoverRatings {
+            rating: book.rating,
+            ratings_count: book.ratings_count,
+            reviews_count: book.reviews_count,
+            ratings_distribution: book.ratings_distribution,
+        };
+        record_hardcover_ratings(&mut tx, audiobook_id, &ratings)
+            .await
+            .map_err(|e| e.to_string())?;
+        info!(
+            "Inserted hardcover metadata for audiobook {} with score {:.2}",
+            audiobook_id, score
+        );
+    }
+
+    if let Some(book_metadata) = metadata.book_metadata {
+        upsert_book_metadata(&mut tx, audiobook_id, &book_metadata)
+            .await
+            .map_err(|e| e.to_string())?;
     }
 
     tx.commit().await?;
     Ok(audiobook_id)
 }
+
+/// # Errors
+///   - If updating the audiobook on the db fails.
+pub async fn update_audiobook_transaction(
+    pool: &PgPool,
+    audiobook_id: i64,
+    extracted: &ExtractedAudiobook,
+    short_description: &str,
+    embeddable_description: &str,
+    embeddings: Vec<f32>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut tx = pool.begin().await?;
+    update_audiobook_data(
+        &mut tx,
+        audiobook_id,
+        extracted,
+        short_description,
+        embeddable_description,
+        embeddings,
+    )
+    .await?;
+    tx.commit().await?;
+    Ok(())
+}
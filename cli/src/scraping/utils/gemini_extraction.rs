@@ -1,47 +1,46 @@
 use serde_json::Value;
-use shared::private_args::Args;
-use shared::utils::gemini::{GeminiContentEmbedderLike, GeminiContentGeneratorLike};
-use tracing::{debug, instrument};
+use shared::extracted_audiobook::ExtractedAudiobook;
+use shared::private_args::{Args, Extractor};
+use shared::utils::llm::{ContentEmbedder, ContentGenerator, EmbeddingTask};
+use tracing::{debug, instrument, warn};
 
 use crate::scraping::prompts::{
-    CREATE_DESCRIPTION_FOR_EMBEDDING, CREATE_VERY_SHORT_DESCRIPTION_PROMPT,
-    PARSE_HTML_INSTRUCTIONS, get_audiobook_schema,
+    CREATE_DESCRIPTION_FOR_EMBEDDING, CREATE_VERY_SHORT_DESCRIPTION_PROMPT, PARSE_HTML_INSTRUCTIONS,
 };
-use crate::scraping::utils::html::extract_only_post_info;
-use crate::scraping::utils::http::{build_robust_client, get_with_retries};
+use crate::scraping::utils::html::{extract_audiobook_with_selectors, extract_only_post_info};
 
-/// # Errors
-/// - If `get_with_retries` fails.
+/// Extracts the raw audiobook data from the html of an audiobook page. The result still needs to
+/// be validated with `ExtractedAudiobook::from_value`.
+///
+/// Extraction is done with CSS selectors, the LLM or both depending on `args.extractor`.
 ///
-/// # Panics
-/// - If the data extracted can't be converted to string.
+/// # Errors
+/// - If the extraction fails.
 #[instrument(skip_all)]
-pub async fn extract_data_from_html<T: GeminiContentGeneratorLike>(
-    gemini: &T,
-    url: &str,
+pub async fn extract_data_from_html<T: ContentGenerator + ?Sized>(
+    generator: &T,
+    res: &str,
     args: &Args,
 ) -> Result<Value, Box<dyn std::error::Error>> {
-    let client = build_robust_client();
+    debug!("Res body when getting the page: {}", &res);
+    let post_information = extract_only_post_info(res).ok_or("Couldn't get the post info")?;
 
-    let res = get_with_retries(
-        &client,
-        url,
-        &args.shared.audiobookbay_domain,
-        &args.shared.audiobookbay_extensions,
-    )
-    .await?
-    .text()
-    .await
-    .unwrap();
+    if args.extractor != Extractor::Llm {
+        if let Some(extracted_values) = extract_audiobook_with_selectors(&post_information) {
+            return Ok(extracted_values);
+        }
+        if args.extractor == Extractor::Selectors {
+            return Err("Couldn't extract the audiobook data with selectors".into());
+        }
+        warn!("Couldn't extract the audiobook data with selectors, falling back to the LLM");
+    }
 
-    debug!("Res body when getting the page: {}", &res);
-    let post_information = extract_only_post_info(&res).ok_or("Couldn't get the post info")?;
     let fomatted_prompt = PARSE_HTML_INSTRUCTIONS
         .to_string()
         .replace("{html}", &post_information);
 
-    gemini
-        .generate_structured_content(&fomatted_prompt, get_audiobook_schema())
+    generator
+        .generate_structured_content(&fomatted_prompt, ExtractedAudiobook::schema())
         .await
         .map_err(|e| e.to_string().into())
 }
@@ -49,15 +48,15 @@ pub async fn extract_data_from_html<T: GeminiContentGeneratorLike>(
 /// # Errors
 ///   - If the content generation fails.
 #[instrument(skip_all)]
-pub async fn create_short_description<T: GeminiContentGeneratorLike>(
-    gemini: &T,
+pub async fn create_short_description<T: ContentGenerator + ?Sized>(
+    generator: &T,
     maybe_description: Option<&str>,
 ) -> Result<String, Box<dyn std::error::Error>> {
     if let Some(description) = maybe_description {
         let formatted_prompt = CREATE_VERY_SHORT_DESCRIPTION_PROMPT
             .to_string()
             .replace("{description}", description);
-        return gemini
+        return generator
             .generate_content(&formatted_prompt)
             .await
             .map_err(|e| e.to_string().into());
@@ -69,15 +68,15 @@ pub async fn create_short_description<T: GeminiContentGeneratorLike>(
 /// # Errors
 ///   - If the content generation fails.
 #[instrument(skip_all)]
-pub async fn create_embeddable_description<T: GeminiContentGeneratorLike>(
-    gemini: &T,
+pub async fn create_embeddable_description<T: ContentGenerator + ?Sized>(
+    generator: &T,
     maybe_description: Option<&str>,
 ) -> Result<String, Box<dyn std::error::Error>> {
     if let Some(description) = maybe_description {
         let formatted_prompt = CREATE_DESCRIPTION_FOR_EMBEDDING
             .to_string()
             .replace("{description}", description);
-        return gemini
+        return generator
             .generate_content(&formatted_prompt)
             .await
             .map_err(|e| e.to_string().into());
@@ -87,21 +86,21 @@ pub async fn create_embeddable_description<T: GeminiContentGeneratorLike>(
 }
 
 /// # Errors
-///   - If the embedding creation fails either due to hitting the Google API fails, or because
+///   - If the embedding creation fails either due to hitting the provider API fails, or because
 ///     we the normalization fails.
 #[instrument(skip_all)]
-pub async fn create_embeddings<T: GeminiContentEmbedderLike>(
-    gemini: &T,
+pub async fn create_embeddings<T: ContentEmbedder + ?Sized>(
+    embedder: &T,
     description: &str,
     args: &Args,
 ) -> Result<Vec<f32>, Box<dyn std::error::Error>> {
     let formatted_prompt = CREATE_DESCRIPTION_FOR_EMBEDDING
         .to_string()
         .replace("{description}", description);
-    return gemini
+    return embedder
         .embed_content(
             &formatted_prompt,
-            &gemini_rust::TaskType::RetrievalDocument,
+            EmbeddingTask::RetrievalDocument,
             args.shared.gemini_embeddings_size,
         )
         .await
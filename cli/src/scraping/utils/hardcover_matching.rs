@@ -0,0 +1,183 @@
+//! Scoring of the candidates returned by the metadata providers against an extracted audiobook.
+//!
+//! The score combines the similarity of the titles, the overlap of the authors and, when the
+//! audiobook is part of a series, whether the candidate belongs to the same series.
+
+use shared::extracted_audiobook::ExtractedAudiobook;
+use strsim::{jaro_winkler, normalized_levenshtein};
+
+use crate::scraping::utils::metadata_provider::BookCandidate;
+
+const TITLE_WEIGHT: f64 = 0.6;
+const AUTHOR_WEIGHT: f64 = 0.3;
+const SERIES_WEIGHT: f64 = 0.1;
+
+/// Minimum Jaro-Winkler similarity for two names to be considered the same person or series.
+const NAME_SIMILARITY_THRESHOLD: f64 = 0.9;
+
+/// A candidate together with how well it matches the audiobook, in `[0, 1]`.
+#[derive(Debug, Clone)]
+pub struct ScoredMatch<B> {
+    pub book: B,
+    pub score: f64,
+}
+
+/// Returns the best scoring candidate, if any.
+#[must_use]
+pub fn best_match<B: BookCandidate>(
+    extracted: &ExtractedAudiobook,
+    candidates: Vec<B>,
+) -> Option<ScoredMatch<B>> {
+    candidates
+        .into_iter()
+        .map(|book| ScoredMatch {
+            score: score_candidate(extracted, &book),
+            book,
+        })
+        .max_by(|a, b| a.score.total_cmp(&b.score))
+}
+
+/// Scores how likely `book` is the same book as the extracted audiobook.
+#[must_use]
+pub fn score_candidate<B: BookCandidate>(extracted: &ExtractedAudiobook, book: &B) -> f64 {
+    let title = title_similarity(&extracted.title, book.title());
+    let authors = author_overlap(&extracted.authors, book.author_names());
+    match extracted.series_title() {
+        Some(series) => {
+            let series = if contains_similar_name(book.series_names(), series) {
+                1.0
+            } else {
+                0.0
+            };
+            TITLE_WEIGHT * title + AUTHOR_WEIGHT * authors + SERIES_WEIGHT * series
+        }
+        None => (TITLE_WEIGHT * title + AUTHOR_WEIGHT * authors) / (TITLE_WEIGHT + AUTHOR_WEIGHT),
+    }
+}
+
+/// Similarity of the titles, ignoring case, punctuation and the trailing series information that
+/// the uploaders often add, e.g. "(Mistborn, Book 2)".
+fn title_similarity(extracted_title: &str, candidate_title: &str) -> f64 {
+    let candidate = normalize(candidate_title);
+    let full = normalized_levenshtein(&normalize(extracted_title), &candidate);
+    let stripped =
+        normalized_levenshtein(&normalize(strip_title_suffix(extracted_title)), &candidate);
+    full.max(stripped)
+}
+
+/// Fraction of the extracted authors found among the candidate authors.
+#[allow(clippy::cast_precision_loss)]
+fn author_overlap(extracted_authors: &[String], candidate_authors: &[String]) -> f64 {
+    let authors: Vec<&String> = extracted_authors
+        .iter()
+        .filter(|author| !author.trim().is_empty())
+        .collect();
+    if authors.is_empty() {
+        return 0.0;
+    }
+    let found = authors
+        .iter()
+        .filter(|author| contains_similar_name(candidate_authors, author))
+        .count();
+    found as f64 / authors.len() as f64
+}
+
+/// Whether two author or series names likely refer to the same entity.
+#[must_use]
+pub fn is_similar_name(a: &str, b: &str) -> bool {
+    jaro_winkler(&normalize(a), &normalize(b)) >= NAME_SIMILARITY_THRESHOLD
+}
+
+fn contains_similar_name(names: &[String], name: &str) -> bool {
+    names
+        .iter()
+        .any(|candidate| is_similar_name(candidate, name))
+}
+
+fn strip_title_suffix(title: &str) -> &str {
+    title.split(['(', '[', ':']).next().unwrap_or(title).trim()
+}
+
+fn normalize(s: &str) -> String {
+    s.chars()
+        .map(|c| {
+            if c.is_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                ' '
+            }
+        })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scraping::hardcover::HardcoverBook;
+
+    fn book(title: &str, authors: &[&str], series: &[&str]) -> HardcoverBook {
+        HardcoverBook {
+            id: 1,
+            title: title.to_string(),
+            author_names: authors.iter().map(ToString::to_string).collect(),
+            series_names: series.iter().map(ToString::to_string).collect(),
+            ..Default::default()
+        }
+    }
+
+    fn extracted() -> ExtractedAudiobook {
+        ExtractedAudiobook {
+            title: "The Well of Ascension (Mistborn, Book 2)".to_string(),
+            authors: vec!["Brandon Sanderson".to_string()],
+            series: Some("Mistborn".to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_exact_match_scores_high() {
+        let score = score_candidate(
+            &extracted(),
+            &book(
+                "The Well of Ascension",
+                &["Brandon Sanderson"],
+                &["Mistborn"],
+            ),
+        );
+        assert!(score > 0.99, "{score}");
+    }
+
+    #[test]
+    fn test_unrelated_book_scores_low() {
+        let score = score_candidate(
+            &extracted(),
+            &book("Project Hail Mary", &["Andy Weir"], &[]),
+        );
+        assert!(score < 0.3, "{score}");
+    }
+
+    #[test]
+    fn test_best_match_picks_the_highest_score() {
+        let candidates = vec![
+            book("The Final Empire", &["Brandon Sanderson"], &["Mistborn"]),
+            book(
+                "The Well of Ascension",
+                &["Brandon Sanderson"],
+                &["Mistborn"],
+            ),
+        ];
+        let best = best_match(&extracted(), candidates).unwrap();
+        assert_eq!(best.book.title, "The Well of Ascension");
+        assert!(best_match(&extracted(), Vec::<HardcoverBook>::new()).is_none());
+    }
+
+    #[test]
+    fn test_is_similar_name() {
+        assert!(is_similar_name("Brandon Sanderson", "brandon sanderson"));
+        assert!(is_similar_name("J.R.R. Tolkien", "J. R. R. Tolkien"));
+        assert!(!is_similar_name("Brandon Sanderson", "Brandon Mull"));
+    }
+}
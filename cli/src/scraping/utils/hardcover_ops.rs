@@ -1,42 +1,108 @@
-use serde_json::Value;
-use shared::private_args::Args;
-use tracing::error;
+use std::error::Error;
 
-use crate::scraping::hardcover::{search_book, HardcoverBook};
+use anyhow::anyhow;
+use async_trait::async_trait;
+use reqwest::Client;
+use serde_json::json;
+use shared::db_ops::parade::book_metadata_ops::BookMetadata;
+use shared::private_args::{Args, CassetteMode};
+use shared::utils::cassette::Cassette;
+
+use crate::scraping::hardcover::{HardcoverBook, search_books};
 use crate::scraping::utils::http::build_robust_client;
+use crate::scraping::utils::metadata_provider::{
+    BookCandidate, HARDCOVER, MetadataProvider, preferred_isbn,
+};
+
+/// Number of Hardcover candidates scored for every audiobook.
+const CANDIDATES_PER_SEARCH: u32 = 5;
+
+impl BookCandidate for HardcoverBook {
+    fn title(&self) -> &str {
+        &self.title
+    }
+
+    fn author_names(&self) -> &[String] {
+        &self.author_names
+    }
+
+    fn series_names(&self) -> &[String] {
+        &self.series_names
+    }
 
-pub async fn resolve_hardcover_metadata(
-    args: &Args,
-    extracted_values: &Value,
-) -> Option<HardcoverBook> {
-    let title = extracted_values["title"].as_str().unwrap_or("").to_string();
-    let authors_val = extracted_values["authors"].as_array();
-    let author = if let Some(authors) = authors_val {
-        if let Some(first_author) = authors.first() {
-            first_author.as_str().unwrap_or("").to_string()
-        } else {
-            String::new()
+    fn book_metadata(&self) -> BookMetadata {
+        BookMetadata {
+            isbn: preferred_isbn(&self.isbns),
+            publication_year: self.release_year,
+            subjects: self.genres.clone(),
+            page_count: self.pages,
         }
-    } else {
-        String::new()
-    };
-
-    if !title.is_empty() && !author.is_empty() {
-        match search_book(
-            &build_robust_client(),
-            &args.hardcover_api_key,
-            &title,
-            &author,
-        )
-        .await
-        {
-            Ok(book) => book,
-            Err(e) => {
-                error!("Failed to search hardcover: {}", e);
-                None
-            }
+    }
+}
+
+/// Searches the books on Hardcover, going through the cassette if `args.cassette_mode` is not
+/// `Off`.
+pub struct HardcoverProvider {
+    client: Client,
+    api_key: String,
+    cassette_mode: CassetteMode,
+    cassette_dir: String,
+}
+
+impl HardcoverProvider {
+    /// Returns `None` when no Hardcover API key is configured.
+    #[must_use]
+    pub fn from_args(args: &Args) -> Option<Self> {
+        Some(HardcoverProvider {
+            client: build_robust_client(),
+            api_key: args.hardcover_api_key.clone()?,
+            cassette_mode: args.cassette_mode,
+            cassette_dir: args.cassette_dir.clone(),
+        })
+    }
+}
+
+#[async_trait]
+impl MetadataProvider for HardcoverProvider {
+    type Book = HardcoverBook;
+
+    fn name(&self) -> &'static str {
+        HARDCOVER
+    }
+
+    async fn search_book(
+        &self,
+        title: &str,
+        author: &str,
+    ) -> Result<Vec<HardcoverBook>, Box<dyn Error>> {
+        if self.cassette_mode == CassetteMode::Off {
+            return search_books(
+                &self.client,
+                &self.api_key,
+                title,
+                author,
+                CANDIDATES_PER_SEARCH,
+            )
+            .await;
         }
-    } else {
-        None
+
+        let cassette = Cassette::open(&self.cassette_dir, HARDCOVER, self.cassette_mode)?;
+        let request =
+            json!({ "title": title, "author": author, "per_page": CANDIDATES_PER_SEARCH });
+        let response = cassette
+            .call("search_books", request, || async {
+                let books = search_books(
+                    &self.client,
+                    &self.api_key,
+                    title,
+                    author,
+                    CANDIDATES_PER_SEARCH,
+                )
+                .await
+                .map_err(|e| anyhow!(e.to_string()))?;
+                Ok(serde_json::to_value(books)?)
+            })
+            .await?;
+        Ok(serde_json::from_value(response)?)
     }
 }
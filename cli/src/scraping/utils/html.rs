@@ -1,4 +1,6 @@
-use scraper::{Html, Selector};
+use chrono::NaiveDate;
+use scraper::{ElementRef, Html, Selector};
+use serde_json::{Value, json};
 
 /// Returns the outer HTML of the first element matching ".post".
 /// Returns `None` if no such element is found.
@@ -40,3 +42,282 @@ pub fn extract_only_new_submissions_table(body: &str) -> Option<String> {
         .next()
         .map(|element| element.html())
 }
+
+/// Extracts the audiobook information from an audiobook page (or its `.post` section) using CSS
+/// selectors only.
+///
+/// The returned value has the same shape as the one produced by the LLM with
+/// `get_audiobook_schema()`. Returns `None` if the page doesn't contain at least a title and an
+/// author, since in that case the markup is not the one we expect.
+///
+/// # Panics
+///   - If one of the hardcoded selectors is invalid.
+#[must_use]
+pub fn extract_audiobook_with_selectors(body: &str) -> Option<Value> {
+    let document = Html::parse_document(body);
+
+    let authors = select_texts(&document, ".postContent .author");
+    let read_by = select_texts(&document, ".postContent .narrator");
+    let raw_title = select_first_text(&document, ".postTitle h1")?;
+    let first_author = authors.first()?;
+    let title = raw_title
+        .strip_suffix(first_author.as_str())
+        .and_then(|t| t.trim_end().strip_suffix('-'))
+        .map_or(raw_title.clone(), |t| t.trim().to_string());
+    let (series, series_volume) = parse_series_from_title(&title).unzip();
+
+    let info_text = select_first_text(&document, ".postInfo").unwrap_or_default();
+    let content_text = select_first_text(&document, ".postContent").unwrap_or_default();
+    let language = text_after_label(&info_text, "Language:")
+        .and_then(|l| l.split_whitespace().next().map(str::to_string));
+    let upload_date = table_value(&document, "Posted:").and_then(|d| normalize_date(&d));
+
+    let cover_selector = Selector::parse(".postContent img").expect("Invalid CSS selector for img");
+    let cover_url = document
+        .select(&cover_selector)
+        .find_map(|img| img.value().attr("src"))
+        .map(str::to_string);
+
+    let description_selector =
+        Selector::parse(".postContent .desc").expect("Invalid CSS selector for .desc");
+    let description = document
+        .select(&description_selector)
+        .next()
+        .map(|element| element.inner_html().trim().to_string());
+
+    let mut extracted = json!({
+        "title": title,
+        "categories": select_texts(&document, ".postInfo a[rel~=\"category\"]"),
+        "language": language.unwrap_or_default(),
+        "keywords": select_texts(&document, ".postInfo a[rel=\"tag\"]"),
+        "cover_url": cover_url.unwrap_or_default(),
+        "authors": authors,
+        "read_by": read_by,
+        "format": select_first_text(&document, ".postContent .format").unwrap_or_default(),
+        "unabridged": content_text.to_lowercase().contains("unabridged"),
+        "description": description.unwrap_or_default(),
+        "is_part_of_series": series.is_some(),
+    });
+    let optional_fields = [
+        (
+            "bitrate",
+            select_first_text(&document, ".postContent .bitrate"),
+        ),
+        (
+            "file_size",
+            table_value(&document, "Combined File Size:")
+                .map(|s| s.trim_end_matches(['s', 'S']).to_string()),
+        ),
+        ("series", series),
+        ("series_volume", series_volume),
+        ("upload_date", upload_date),
+    ];
+    for (key, value) in optional_fields {
+        if let Some(value) = value {
+            extracted[key] = Value::String(value);
+        }
+    }
+    Some(extracted)
+}
+
+/// Extracts the list of submissions from a submission page (or its `.main_table`) using CSS
+/// selectors only.
+///
+/// The returned value has the same shape as the one produced by the LLM with
+/// `get_submission_list_schema()`. Rows without a link are skipped. Returns `None` if no
+/// submission could be found.
+///
+/// # Panics
+///   - If one of the hardcoded selectors is invalid.
+#[must_use]
+pub fn extract_submissions_with_selectors(body: &str) -> Option<Value> {
+    let document = Html::parse_document(body);
+    let row_selector = Selector::parse(".main_table tr").expect("Invalid CSS selector for rows");
+    let cell_selector = Selector::parse("td").expect("Invalid CSS selector for td");
+    let link_selector = Selector::parse("a[href]").expect("Invalid CSS selector for links");
+
+    let submissions: Vec<Value> = document
+        .select(&row_selector)
+        .filter_map(|row| {
+            let url = row
+                .select(&link_selector)
+                .next()?
+                .value()
+                .attr("href")?
+                .to_string();
+            let submission_date = row
+                .select(&cell_selector)
+                .find_map(|cell| normalize_date(&element_text(cell)))
+                .unwrap_or_default();
+            Some(json!({ "submission_date": submission_date, "url": url }))
+        })
+        .collect();
+
+    if submissions.is_empty() {
+        return None;
+    }
+    Some(json!({ "submissions": submissions }))
+}
+
+/// Joins the text nodes of an element collapsing all the whitespace.
+fn element_text(element: ElementRef<'_>) -> String {
+    element
+        .text()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn select_texts(document: &Html, selector: &str) -> Vec<String> {
+    let selector = Selector::parse(selector).expect("Invalid CSS selector");
+    document
+        .select(&selector)
+        .map(element_text)
+        .filter(|text| !text.is_empty())
+        .collect()
+}
+
+fn select_first_text(document: &Html, selector: &str) -> Option<String> {
+    select_texts(document, selector).into_iter().next()
+}
+
+/// Returns the text following `label` in `text`, if any.
+fn text_after_label<'a>(text: &'a str, label: &str) -> Option<&'a str> {
+    text.find(label).map(|idx| text[idx + label.len()..].trim())
+}
+
+/// Returns the content of the cell that follows the cell whose text is `label` in the torrent
+/// information table.
+fn table_value(document: &Html, label: &str) -> Option<String> {
+    let row_selector = Selector::parse(".postContent tr").expect("Invalid CSS selector for rows");
+    let cell_selector = Selector::parse("td").expect("Invalid CSS selector for td");
+    document.select(&row_selector).find_map(|row| {
+        let cells: Vec<String> = row.select(&cell_selector).map(element_text).collect();
+        match cells.as_slice() {
+            [key, value, ..] if key.eq_ignore_ascii_case(label) => Some(value.clone()),
+            _ => None,
+        }
+    })
+}
+
+/// Parses the dates formats used on audiobookbay into `YYYY-MM-DD`.
+fn normalize_date(s: &str) -> Option<String> {
+    ["%Y-%m-%d", "%d %b %Y", "%d %B %Y", "%b %d, %Y"]
+        .iter()
+        .find_map(|format| NaiveDate::parse_from_str(s.trim(), format).ok())
+        .map(|date| date.format("%Y-%m-%d").to_string())
+}
+
+/// Parses titles like `Title (Series, Book 2)` or `Title (Series #2)` into the series title and
+/// volume.
+fn parse_series_from_title(title: &str) -> Option<(String, String)> {
+    let inner = title.trim().strip_suffix(')')?;
+    let inner = &inner[inner.rfind('(')? + 1..];
+    let (series, volume) = inner.rsplit_once(',').or_else(|| inner.rsplit_once('#'))?;
+    let series = series.trim();
+    let volume = volume.trim();
+    if series.is_empty() || !volume.chars().any(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    Some((series.to_string(), volume.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AUDIOBOOK_PAGE: &str = include_str!("fixtures/audiobook_page.html");
+    const SUBMISSION_PAGE: &str = include_str!("fixtures/submission_page.html");
+
+    #[test]
+    fn test_extract_audiobook_with_selectors() {
+        let extracted = extract_audiobook_with_selectors(AUDIOBOOK_PAGE).unwrap();
+
+        assert_eq!(
+            extracted["title"],
+            "The Well of Ascension (Mistborn, Book 2)"
+        );
+        assert_eq!(extracted["authors"], json!(["Brandon Sanderson"]));
+        assert_eq!(extracted["read_by"], json!(["Michael Kramer"]));
+        assert_eq!(extracted["categories"], json!(["Fantasy", "Adventure"]));
+        assert_eq!(
+            extracted["keywords"],
+            json!(["Allomancy", "Epic Fantasy", "Magic System"])
+        );
+        assert_eq!(extracted["language"], "English");
+        assert_eq!(
+            extracted["cover_url"],
+            "https://example.com/covers/well-of-ascension.jpg"
+        );
+        assert_eq!(extracted["format"], "M4B");
+        assert_eq!(extracted["bitrate"], "64 Kbps");
+        assert_eq!(extracted["file_size"], "1.03 GB");
+        assert_eq!(extracted["unabridged"], true);
+        assert_eq!(extracted["is_part_of_series"], true);
+        assert_eq!(extracted["series"], "Mistborn");
+        assert_eq!(extracted["series_volume"], "Book 2");
+        assert_eq!(extracted["upload_date"], "2025-09-18");
+        assert!(
+            extracted["description"]
+                .as_str()
+                .unwrap()
+                .starts_with("<p>Evil has been defeated.")
+        );
+    }
+
+    #[test]
+    fn test_extract_audiobook_with_selectors_from_post_fragment() {
+        let post = extract_only_post_info(AUDIOBOOK_PAGE).unwrap();
+        assert_eq!(
+            extract_audiobook_with_selectors(&post),
+            extract_audiobook_with_selectors(AUDIOBOOK_PAGE)
+        );
+    }
+
+    #[test]
+    fn test_extract_audiobook_with_selectors_unexpected_markup() {
+        assert!(extract_audiobook_with_selectors("<div><h1>Not a post</h1></div>").is_none());
+    }
+
+    #[test]
+    fn test_extract_submissions_with_selectors() {
+        let extracted = extract_submissions_with_selectors(SUBMISSION_PAGE).unwrap();
+
+        assert_eq!(
+            extracted,
+            json!({
+                "submissions": [
+                    {
+                        "submission_date": "2025-09-18",
+                        "url": "/abss/the-well-of-ascension-mistborn-book-2-brandon-sanderson/"
+                    },
+                    {
+                        "submission_date": "2025-09-17",
+                        "url": "https://audiobookbay.is/abss/project-hail-mary-andy-weir/"
+                    }
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn test_extract_submissions_with_selectors_no_table() {
+        assert!(extract_submissions_with_selectors("<p>Nothing here</p>").is_none());
+    }
+
+    #[test]
+    fn test_parse_series_from_title() {
+        assert_eq!(
+            parse_series_from_title("The Well of Ascension (Mistborn, Book 2)"),
+            Some(("Mistborn".to_string(), "Book 2".to_string()))
+        );
+        assert_eq!(
+            parse_series_from_title("Leviathan Wakes (The Expanse #1)"),
+            Some(("The Expanse".to_string(), "1".to_string()))
+        );
+        assert_eq!(parse_series_from_title("Project Hail Mary"), None);
+        assert_eq!(parse_series_from_title("Dune (Unabridged)"), None);
+    }
+}
@@ -4,6 +4,8 @@ use std::time::Duration;
 use tracing::{debug, warn};
 use url::Url;
 
+use crate::scraping::utils::rate_limit::HostRateLimiter;
+
 /// Builds an http client that is a bit more resilient to be able to connect through VPNs.
 ///
 /// # Panics
@@ -29,6 +31,8 @@ pub fn build_robust_client() -> Client {
 }
 
 /// Tries to get the content with retries and trying different domains.
+///
+/// Every request waits for a token of its host in `rate_limiter`.
 /// # Errors
 ///   - If the request is not successful despite our best efforts.
 pub async fn get_with_retries(
@@ -36,12 +40,14 @@ pub async fn get_with_retries(
     original_url: &str,
     domain_keyword: &str,
     extensions: &[String],
+    rate_limiter: &HostRateLimiter,
 ) -> Result<Response, Box<dyn std::error::Error>> {
     let parsed_url = Url::parse(original_url)?;
     let host_str = parsed_url.host_str().ok_or("No host in URL")?;
 
     if !host_str.contains(domain_keyword) {
         // Not the target domain, just request it directly
+        rate_limiter.acquire(host_str).await;
         let res = client.get(original_url).send().await?;
         if res.status().is_success() {
             return Ok(res);
@@ -63,6 +69,7 @@ pub async fn get_with_retries(
         let url_str = new_url.to_string();
         debug!("Trying URL: {}", url_str);
 
+        rate_limiter.acquire(&new_host).await;
         match client.get(&url_str).send().await {
             Ok(res) => {
                 if res.status().is_success() || res.status().is_client_error() {
@@ -107,7 +114,14 @@ mod tests {
         let domain_keyword = "audiobookbay";
         let extensions = vec!["is".to_string(), "lu".to_string()];
 
-        let result = get_with_retries(&client, &url, domain_keyword, &extensions).await;
+        let result = get_with_retries(
+            &client,
+            &url,
+            domain_keyword,
+            &extensions,
+            &HostRateLimiter::new(60.0, 1),
+        )
+        .await;
         assert!(result.is_ok());
         assert!(result.unwrap().status().is_success());
     }
@@ -131,7 +145,14 @@ mod tests {
 
         let extensions = vec!["is".to_string(), "lu".to_string()];
 
-        let result = get_with_retries(&client, &url, domain_keyword, &extensions).await;
+        let result = get_with_retries(
+            &client,
+            &url,
+            domain_keyword,
+            &extensions,
+            &HostRateLimiter::new(60.0, 1),
+        )
+        .await;
         assert!(result.is_err());
     }
 }
@@ -0,0 +1,285 @@
+//! Common interface of the services the audiobooks are enriched from, Hardcover and Open Library,
+//! and the rules deciding which of them wins for every field.
+
+use std::collections::BTreeMap;
+use std::error::Error;
+
+use async_trait::async_trait;
+use shared::db_ops::parade::book_metadata_ops::{BookMetadata, MergedBookMetadata};
+use shared::extracted_audiobook::ExtractedAudiobook;
+use shared::private_args::Args;
+use tracing::{error, info, warn};
+
+use crate::scraping::hardcover::HardcoverBook;
+use crate::scraping::utils::hardcover_matching::{ScoredMatch, best_match};
+use crate::scraping::utils::hardcover_ops::HardcoverProvider;
+use crate::scraping::utils::open_library_ops::OpenLibraryProvider;
+
+pub const HARDCOVER: &str = "hardcover";
+pub const OPEN_LIBRARY: &str = "open_library";
+
+/// Maximum number of subjects kept for a book.
+const MAX_SUBJECTS: usize = 10;
+
+/// A book returned by a `MetadataProvider`.
+pub trait BookCandidate {
+    fn title(&self) -> &str;
+    fn author_names(&self) -> &[String];
+    fn series_names(&self) -> &[String];
+    fn book_metadata(&self) -> BookMetadata;
+}
+
+#[async_trait]
+pub trait MetadataProvider: Send + Sync {
+    type Book: BookCandidate + Clone + Send;
+
+    /// Name of the provider, as recorded in the sources of the merged metadata.
+    fn name(&self) -> &'static str;
+
+    /// Returns the candidate books matching `title` and `author`, best first according to the
+    /// provider.
+    async fn search_book(
+        &self,
+        title: &str,
+        author: &str,
+    ) -> Result<Vec<Self::Book>, Box<dyn Error>>;
+
+    /// Completes the matched book with the details the search doesn't return.
+    async fn book_details(&self, book: Self::Book) -> Result<Self::Book, Box<dyn Error>> {
+        Ok(book)
+    }
+}
+
+/// The metadata found for an audiobook.
+#[derive(Debug, Clone, Default)]
+pub struct ResolvedMetadata {
+    pub hardcover: Option<ScoredMatch<HardcoverBook>>,
+    pub book_metadata: Option<MergedBookMetadata>,
+}
+
+/// Looks the audiobook up on every enabled provider and merges what they return.
+pub async fn resolve_book_metadata(
+    args: &Args,
+    extracted: &ExtractedAudiobook,
+) -> ResolvedMetadata {
+    let hardcover = match HardcoverProvider::from_args(args) {
+        Some(provider) => find_book(&provider, extracted, args.metadata_min_match_score).await,
+        None => None,
+    };
+    let open_library = if args.disable_open_library {
+        None
+    } else {
+        let provider = OpenLibraryProvider::from_args(args);
+        find_book(&provider, extracted, args.metadata_min_match_score).await
+    };
+
+    let book_metadata = merge_book_metadata(
+        hardcover.as_ref().map(|m| m.book.book_metadata()),
+        open_library.map(|m| m.book.book_metadata()),
+    );
+    ResolvedMetadata {
+        hardcover,
+        book_metadata,
+    }
+}
+
+/// Searches the provider for the audiobook and returns the best scoring candidate, with its
+/// details, unless its score is below `min_score`.
+pub async fn find_book<P: MetadataProvider>(
+    provider: &P,
+    extracted: &ExtractedAudiobook,
+    min_score: f64,
+) -> Option<ScoredMatch<P::Book>> {
+    let title = extracted.title.as_str();
+    let author = extracted.authors.first().map_or("", String::as_str);
+
+    if title.is_empty() || author.is_empty() {
+        return None;
+    }
+    let candidates = match provider.search_book(title, author).await {
+        Ok(candidates) => candidates,
+        Err(e) => {
+            error!("Failed to search {}: {}", provider.name(), e);
+            return None;
+        }
+    };
+    let scored = best_match(extracted, candidates)?;
+    if scored.score < min_score {
+        info!(
+            "Rejected {} match `{}` for `{}` with score {:.2}",
+            provider.name(),
+            scored.book.title(),
+            title,
+            scored.score
+        );
+        return None;
+    }
+    match provider.book_details(scored.book.clone()).await {
+        Ok(book) => Some(ScoredMatch {
+            book,
+            score: scored.score,
+        }),
+        Err(e) => {
+            warn!(
+                "Couldn't fetch the {} details of `{}`: {}",
+                provider.name(),
+                scored.book.title(),
+                e
+            );
+            Some(scored)
+        }
+    }
+}
+
+/// Merges the metadata found on Hardcover and Open Library.
+///
+/// - ISBN: Open Library indexes the ISBNs of every edition, so it wins over Hardcover.
+/// - Publication year: Open Library's is the year of the first edition, so it wins too.
+/// - Subjects: Hardcover's genres are curated while Open Library's subjects are free-form, so
+///   Hardcover wins whenever it has any.
+/// - Page count: Hardcover's is the one of its default edition, Open Library's is the median of
+///   all the editions, so Hardcover wins.
+#[must_use]
+pub fn merge_book_metadata(
+    hardcover: Option<BookMetadata>,
+    open_library: Option<BookMetadata>,
+) -> Option<MergedBookMetadata> {
+    if hardcover.is_none() && open_library.is_none() {
+        return None;
+    }
+    let hardcover = hardcover.unwrap_or_default();
+    let open_library = open_library.unwrap_or_default();
+    let mut sources = BTreeMap::new();
+
+    let isbn = pick(
+        &mut sources,
+        "isbn",
+        [
+            (OPEN_LIBRARY, open_library.isbn),
+            (HARDCOVER, hardcover.isbn),
+        ],
+    );
+    let publication_year = pick(
+        &mut sources,
+        "publication_year",
+        [
+            (OPEN_LIBRARY, open_library.publication_year),
+            (HARDCOVER, hardcover.publication_year),
+        ],
+    );
+    let subjects = pick(
+        &mut sources,
+        "subjects",
+        [
+            (
+                HARDCOVER,
+                Some(hardcover.subjects).filter(|s| !s.is_empty()),
+            ),
+            (
+                OPEN_LIBRARY,
+                Some(open_library.subjects).filter(|s| !s.is_empty()),
+            ),
+        ],
+    )
+    .map(|subjects| subjects.into_iter().take(MAX_SUBJECTS).collect())
+    .unwrap_or_default();
+    let page_count = pick(
+        &mut sources,
+        "page_count",
+        [
+            (HARDCOVER, hardcover.page_count),
+            (OPEN_LIBRARY, open_library.page_count),
+        ],
+    );
+
+    Some(MergedBookMetadata {
+        metadata: BookMetadata {
+            isbn,
+            publication_year,
+            subjects,
+            page_count,
+        },
+        sources,
+    })
+}
+
+/// Returns the first value, in order of preference, recording the provider it comes from.
+fn pick<T, const N: usize>(
+    sources: &mut BTreeMap<String, String>,
+    field: &str,
+    values: [(&str, Option<T>); N],
+) -> Option<T> {
+    let (provider, value) = values
+        .into_iter()
+        .find_map(|(provider, value)| value.map(|value| (provider, value)))?;
+    sources.insert(field.to_string(), provider.to_string());
+    Some(value)
+}
+
+/// The first ISBN-13 in `isbns`, or the first ISBN if there's none.
+#[must_use]
+pub fn preferred_isbn(isbns: &[String]) -> Option<String> {
+    isbns
+        .iter()
+        .find(|isbn| isbn.len() == 13)
+        .or_else(|| isbns.first())
+        .cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metadata(isbn: Option<&str>, year: Option<i32>, subjects: &[&str]) -> BookMetadata {
+        BookMetadata {
+            isbn: isbn.map(ToString::to_string),
+            publication_year: year,
+            subjects: subjects.iter().map(ToString::to_string).collect(),
+            page_count: None,
+        }
+    }
+
+    #[test]
+    fn test_merge_book_metadata() {
+        let hardcover = BookMetadata {
+            page_count: Some(590),
+            ..metadata(Some("9780765316882"), Some(2008), &["Fantasy"])
+        };
+        let open_library = BookMetadata {
+            page_count: Some(781),
+            ..metadata(Some("9780765356130"), Some(2007), &["Magic", "Fiction"])
+        };
+
+        let merged = merge_book_metadata(Some(hardcover), Some(open_library)).unwrap();
+        assert_eq!(merged.metadata.isbn.as_deref(), Some("9780765356130"));
+        assert_eq!(merged.metadata.publication_year, Some(2007));
+        assert_eq!(merged.metadata.subjects, vec!["Fantasy"]);
+        assert_eq!(merged.metadata.page_count, Some(590));
+        assert_eq!(merged.sources["isbn"], OPEN_LIBRARY);
+        assert_eq!(merged.sources["subjects"], HARDCOVER);
+        assert_eq!(merged.sources["page_count"], HARDCOVER);
+    }
+
+    #[test]
+    fn test_merge_book_metadata_falls_back() {
+        let hardcover = metadata(Some("9780765316882"), None, &[]);
+        let open_library = metadata(None, Some(2007), &["Magic"]);
+
+        let merged = merge_book_metadata(Some(hardcover), Some(open_library)).unwrap();
+        assert_eq!(merged.metadata.isbn.as_deref(), Some("9780765316882"));
+        assert_eq!(merged.metadata.subjects, vec!["Magic"]);
+        assert_eq!(merged.sources["isbn"], HARDCOVER);
+        assert_eq!(merged.sources["subjects"], OPEN_LIBRARY);
+        assert!(!merged.sources.contains_key("page_count"));
+
+        assert!(merge_book_metadata(None, None).is_none());
+    }
+
+    #[test]
+    fn test_preferred_isbn() {
+        let isbns = vec!["0765316889".to_string(), "9780765316882".to_string()];
+        assert_eq!(preferred_isbn(&isbns).as_deref(), Some("9780765316882"));
+        assert_eq!(preferred_isbn(&isbns[..1]).as_deref(), Some("0765316889"));
+        assert_eq!(preferred_isbn(&[]), None);
+    }
+}
@@ -1,5 +1,10 @@
+pub mod category_mapping;
 pub mod db;
 pub mod gemini_extraction;
+pub mod hardcover_matching;
 pub mod hardcover_ops;
 pub mod html;
-pub mod http;
\ No newline at end of file
+pub mod http;
+pub mod metadata_provider;
+pub mod open_library_ops;
+pub mod rate_limit;
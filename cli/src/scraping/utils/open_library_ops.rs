@@ -0,0 +1,128 @@
+use std::error::Error;
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use reqwest::Client;
+use serde_json::json;
+use shared::db_ops::parade::book_metadata_ops::BookMetadata;
+use shared::private_args::{Args, CassetteMode};
+use shared::utils::cassette::Cassette;
+
+use crate::scraping::open_library::{OpenLibraryBook, OpenLibraryWork, get_work, search_books};
+use crate::scraping::utils::http::build_robust_client;
+use crate::scraping::utils::metadata_provider::{
+    BookCandidate, MetadataProvider, OPEN_LIBRARY, preferred_isbn,
+};
+
+/// Number of Open Library candidates scored for every audiobook.
+const CANDIDATES_PER_SEARCH: u32 = 5;
+
+impl BookCandidate for OpenLibraryBook {
+    fn title(&self) -> &str {
+        &self.title
+    }
+
+    fn author_names(&self) -> &[String] {
+        &self.author_names
+    }
+
+    fn series_names(&self) -> &[String] {
+        // Open Library doesn't index the series.
+        &[]
+    }
+
+    fn book_metadata(&self) -> BookMetadata {
+        BookMetadata {
+            isbn: preferred_isbn(&self.isbns),
+            publication_year: self.first_publish_year,
+            subjects: self.subjects.clone(),
+            page_count: self.number_of_pages_median,
+        }
+    }
+}
+
+/// Searches the books on Open Library and fetches the subjects from their work, going through the
+/// cassette if `args.cassette_mode` is not `Off`.
+pub struct OpenLibraryProvider {
+    client: Client,
+    base_url: String,
+    cassette_mode: CassetteMode,
+    cassette_dir: String,
+}
+
+impl OpenLibraryProvider {
+    #[must_use]
+    pub fn from_args(args: &Args) -> Self {
+        OpenLibraryProvider {
+            client: build_robust_client(),
+            base_url: args.open_library_base_url.clone(),
+            cassette_mode: args.cassette_mode,
+            cassette_dir: args.cassette_dir.clone(),
+        }
+    }
+}
+
+#[async_trait]
+impl MetadataProvider for OpenLibraryProvider {
+    type Book = OpenLibraryBook;
+
+    fn name(&self) -> &'static str {
+        OPEN_LIBRARY
+    }
+
+    async fn search_book(
+        &self,
+        title: &str,
+        author: &str,
+    ) -> Result<Vec<OpenLibraryBook>, Box<dyn Error>> {
+        if self.cassette_mode == CassetteMode::Off {
+            return search_books(
+                &self.client,
+                &self.base_url,
+                title,
+                author,
+                CANDIDATES_PER_SEARCH,
+            )
+            .await;
+        }
+
+        let cassette = Cassette::open(&self.cassette_dir, OPEN_LIBRARY, self.cassette_mode)?;
+        let request = json!({ "title": title, "author": author, "limit": CANDIDATES_PER_SEARCH });
+        let response = cassette
+            .call("search_books", request, || async {
+                let books = search_books(
+                    &self.client,
+                    &self.base_url,
+                    title,
+                    author,
+                    CANDIDATES_PER_SEARCH,
+                )
+                .await
+                .map_err(|e| anyhow!(e.to_string()))?;
+                Ok(serde_json::to_value(books)?)
+            })
+            .await?;
+        Ok(serde_json::from_value(response)?)
+    }
+
+    async fn book_details(&self, book: OpenLibraryBook) -> Result<OpenLibraryBook, Box<dyn Error>> {
+        let work: OpenLibraryWork = if self.cassette_mode == CassetteMode::Off {
+            get_work(&self.client, &self.base_url, &book.key).await?
+        } else {
+            let cassette = Cassette::open(&self.cassette_dir, OPEN_LIBRARY, self.cassette_mode)?;
+            let response = cassette
+                .call("get_work", json!({ "key": book.key }), || async {
+                    let work = get_work(&self.client, &self.base_url, &book.key)
+                        .await
+                        .map_err(|e| anyhow!(e.to_string()))?;
+                    Ok(serde_json::to_value(work)?)
+                })
+                .await?;
+            serde_json::from_value(response)?
+        };
+        Ok(OpenLibraryBook {
+            subjects: work.subjects,
+            ..book
+        })
+    }
+}
@@ -0,0 +1,146 @@
+//! Token-bucket rate limiting of the requests made to each host.
+//!
+//! A single `HostRateLimiter` is shared by all the scraping workers, so the configured rate is
+//! respected no matter how many workers are running.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::time::{Instant, sleep};
+use tracing::debug;
+
+/// A token bucket holding at most `capacity` tokens and refilled at `refill_per_second`.
+#[derive(Debug)]
+pub struct TokenBucket {
+    capacity: f64,
+    refill_per_second: f64,
+    state: Mutex<BucketState>,
+}
+
+#[derive(Debug)]
+struct BucketState {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    /// Creates a full bucket.
+    ///
+    /// # Panics
+    /// If `capacity` is zero or `refill_per_second` is not positive.
+    #[must_use]
+    pub fn new(capacity: u32, refill_per_second: f64) -> Self {
+        assert!(
+            capacity > 0,
+            "The capacity of a token bucket must be positive"
+        );
+        assert!(
+            refill_per_second > 0.0,
+            "The refill rate of a token bucket must be positive"
+        );
+        TokenBucket {
+            capacity: f64::from(capacity),
+            refill_per_second,
+            state: Mutex::new(BucketState {
+                tokens: f64::from(capacity),
+                last_refill: Instant::now(),
+            }),
+        }
+    }
+
+    /// Waits until a token is available and takes it.
+    ///
+    /// # Panics
+    /// If the state lock is poisoned.
+    pub async fn acquire(&self) {
+        loop {
+            let wait = {
+                let mut state = self.state.lock().expect("Token bucket lock poisoned");
+                let now = Instant::now();
+                let elapsed = now.duration_since(state.last_refill).as_secs_f64();
+                state.tokens = (state.tokens + elapsed * self.refill_per_second).min(self.capacity);
+                state.last_refill = now;
+                if state.tokens >= 1.0 {
+                    state.tokens -= 1.0;
+                    return;
+                }
+                Duration::from_secs_f64((1.0 - state.tokens) / self.refill_per_second)
+            };
+            sleep(wait).await;
+        }
+    }
+}
+
+/// Keeps one `TokenBucket` per host, created on first use.
+#[derive(Debug)]
+pub struct HostRateLimiter {
+    burst: u32,
+    requests_per_minute: f64,
+    buckets: Mutex<HashMap<String, Arc<TokenBucket>>>,
+}
+
+impl HostRateLimiter {
+    /// Every host is allowed `requests_per_minute` requests, with bursts of up to `burst`.
+    #[must_use]
+    pub fn new(requests_per_minute: f64, burst: u32) -> Self {
+        HostRateLimiter {
+            burst,
+            requests_per_minute,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Waits until a request to `host` is allowed.
+    ///
+    /// # Panics
+    /// If the buckets lock is poisoned.
+    pub async fn acquire(&self, host: &str) {
+        let bucket = {
+            let mut buckets = self.buckets.lock().expect("Rate limiter lock poisoned");
+            buckets
+                .entry(host.to_string())
+                .or_insert_with(|| {
+                    Arc::new(TokenBucket::new(
+                        self.burst,
+                        self.requests_per_minute / 60.0,
+                    ))
+                })
+                .clone()
+        };
+        bucket.acquire().await;
+        debug!("Acquired a request token for {}", host);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_token_bucket_waits_for_refill() {
+        let bucket = TokenBucket::new(2, 0.5);
+        let start = Instant::now();
+
+        bucket.acquire().await;
+        bucket.acquire().await;
+        assert_eq!(start.elapsed(), Duration::ZERO);
+
+        bucket.acquire().await;
+        assert!(start.elapsed() >= Duration::from_secs(2));
+        assert!(start.elapsed() < Duration::from_secs(3));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_host_rate_limiter_is_per_host() {
+        let limiter = HostRateLimiter::new(1.0, 1);
+        let start = Instant::now();
+
+        limiter.acquire("audiobookbay.is").await;
+        limiter.acquire("audiobookbay.lu").await;
+        assert_eq!(start.elapsed(), Duration::ZERO);
+
+        limiter.acquire("audiobookbay.is").await;
+        assert!(start.elapsed() >= Duration::from_secs(60));
+    }
+}
@@ -0,0 +1,110 @@
+use shared::db_ops::parade::entity_ops::{EntityKind, merge_entities};
+use shared::db_ops::parade::get_postgres_connection;
+use shared::db_ops::parade::taxonomy_ops::{
+    AliasSource, TaxonomyNode, add_taxonomy_node, category_alias_key, get_taxonomy,
+    get_unmapped_categories, upsert_category_alias,
+};
+use shared::private_args::Args;
+use shared::utils::llm::build_content_generator;
+use tracing::info;
+
+use crate::cli_args::TaxonomySubCommand;
+use crate::scraping::utils::category_mapping::map_categories;
+
+/// Inspects and edits the category taxonomy.
+///
+/// # Errors
+///   - If the taxonomy can't be read or updated.
+///   - If a category to alias or to remap onto isn't part of the taxonomy.
+pub async fn handle_taxonomy(
+    taxonomy_sub_command: TaxonomySubCommand,
+    args: Args,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let pgpool = get_postgres_connection(&args).await;
+    match taxonomy_sub_command {
+        TaxonomySubCommand::Show => {
+            let taxonomy = get_taxonomy(&pgpool).await.map_err(|e| e.to_string())?;
+            for node in taxonomy {
+                let depth = node.path.matches(" > ").count();
+                println!(
+                    "{}[{}] {}",
+                    "    ".repeat(depth),
+                    node.category_id,
+                    node.name
+                );
+            }
+        }
+        TaxonomySubCommand::Add { name, parent } => {
+            let category_id = add_taxonomy_node(&pgpool, &name, parent.as_deref())
+                .await
+                .map_err(|e| e.to_string())?;
+            println!("Added {name} to the taxonomy as category {category_id}");
+        }
+        TaxonomySubCommand::Alias { alias, to } => {
+            let category_id = match to {
+                Some(to) => {
+                    let taxonomy = get_taxonomy(&pgpool).await.map_err(|e| e.to_string())?;
+                    Some(find_node(&taxonomy, &to)?.category_id)
+                }
+                None => None,
+            };
+            upsert_category_alias(
+                &pgpool,
+                &category_alias_key(&alias),
+                category_id,
+                AliasSource::Manual,
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+        }
+        TaxonomySubCommand::Remap { limit } => {
+            let generator = build_content_generator(&args)?;
+            let taxonomy = get_taxonomy(&pgpool).await.map_err(|e| e.to_string())?;
+            let unmapped = get_unmapped_categories(&pgpool, limit)
+                .await
+                .map_err(|e| e.to_string())?;
+            let mut remapped = 0;
+            for category in &unmapped {
+                let mapped = map_categories(
+                    &pgpool,
+                    generator.as_ref(),
+                    std::slice::from_ref(&category.name),
+                )
+                .await?;
+                let Some(name) = mapped.first() else {
+                    info!("The category `{}` doesn't fit the taxonomy", category.name);
+                    continue;
+                };
+                let node = find_node(&taxonomy, name)?;
+                merge_entities(
+                    &pgpool,
+                    EntityKind::Category,
+                    node.category_id,
+                    &[category.id],
+                )
+                .await
+                .map_err(|e| e.to_string())?;
+                info!(
+                    "Merged the category `{}` ({} audiobooks) into {}",
+                    category.name, category.audiobooks_count, node.path
+                );
+                remapped += 1;
+            }
+            println!(
+                "Remapped {remapped} of {} categories. Refresh the search index for the search to reflect it.",
+                unmapped.len()
+            );
+        }
+    }
+    Ok(())
+}
+
+fn find_node<'a>(
+    taxonomy: &'a [TaxonomyNode],
+    name: &str,
+) -> Result<&'a TaxonomyNode, Box<dyn std::error::Error>> {
+    taxonomy
+        .iter()
+        .find(|node| node.name.eq_ignore_ascii_case(name))
+        .ok_or_else(|| format!("No category named {name} in the taxonomy").into())
+}
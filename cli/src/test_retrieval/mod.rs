@@ -0,0 +1,262 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use entities_lib::{RankingConfig, ScoreBreakdown, SearchQuery};
+use serde::Deserialize;
+use shared::db_ops::parade::get_postgres_connection;
+use shared::db_ops::parade::search_ops::{
+    combine_results_hybrid, rank_audiobooks, retrieve_audiobooks,
+};
+use shared::private_args::Args;
+use shared::utils::llm::build_content_embedder;
+use tracing::info;
+
+/// A query with the audiobooks relevant to it, a line of the judgments file.
+#[derive(Debug, Deserialize)]
+struct Judgment {
+    query: String,
+    relevant: Vec<RelevantAudiobook>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RelevantAudiobook {
+    audiobook_id: i64,
+    /// How relevant the audiobook is, the higher the better. 0 counts as not relevant.
+    grade: u32,
+}
+
+/// Metrics of a ranking, summed over the queries until `mean` is called.
+#[derive(Debug, Default, Clone, Copy)]
+struct Metrics {
+    ndcg: f64,
+    reciprocal_rank: f64,
+    recall: f64,
+}
+
+impl Metrics {
+    fn of(ranking: &[i64], grades: &HashMap<i64, u32>, k: usize) -> Self {
+        Self {
+            ndcg: ndcg_at_k(ranking, grades, k),
+            reciprocal_rank: reciprocal_rank(ranking, grades),
+            recall: recall_at_k(ranking, grades, k),
+        }
+    }
+
+    fn add(&mut self, other: Self) {
+        self.ndcg += other.ndcg;
+        self.reciprocal_rank += other.reciprocal_rank;
+        self.recall += other.recall;
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn mean(self, queries: usize) -> Self {
+        let queries = queries.max(1) as f64;
+        Self {
+            ndcg: self.ndcg / queries,
+            reciprocal_rank: self.reciprocal_rank / queries,
+            recall: self.recall / queries,
+        }
+    }
+}
+
+/// Discounted cumulative gain of the first `k` grades, with gains `2^grade - 1`.
+#[allow(clippy::cast_precision_loss)]
+fn dcg_at_k(grades: impl Iterator<Item = u32>, k: usize) -> f64 {
+    grades
+        .take(k)
+        .enumerate()
+        .map(|(rank, grade)| (f64::from(grade).exp2() - 1.0) / (rank as f64 + 2.0).log2())
+        .sum()
+}
+
+/// nDCG of the first `k` results, 0 if no audiobook is relevant.
+fn ndcg_at_k(ranking: &[i64], grades: &HashMap<i64, u32>, k: usize) -> f64 {
+    let mut ideal: Vec<u32> = grades.values().copied().collect();
+    ideal.sort_unstable_by(|a, b| b.cmp(a));
+    let ideal_dcg = dcg_at_k(ideal.into_iter(), k);
+    if ideal_dcg == 0.0 {
+        return 0.0;
+    }
+    let dcg = dcg_at_k(
+        ranking
+            .iter()
+            .map(|id| grades.get(id).copied().unwrap_or_default()),
+        k,
+    );
+    dcg / ideal_dcg
+}
+
+/// Inverse of the rank of the first relevant result, 0 if none is found.
+#[allow(clippy::cast_precision_loss)]
+fn reciprocal_rank(ranking: &[i64], grades: &HashMap<i64, u32>) -> f64 {
+    ranking
+        .iter()
+        .position(|id| grades.get(id).is_some_and(|grade| *grade > 0))
+        .map_or(0.0, |rank| 1.0 / (rank as f64 + 1.0))
+}
+
+/// Share of the relevant audiobooks found in the first `k` results, 0 if none is relevant.
+#[allow(clippy::cast_precision_loss)]
+fn recall_at_k(ranking: &[i64], grades: &HashMap<i64, u32>, k: usize) -> f64 {
+    let relevant = grades.values().filter(|grade| **grade > 0).count();
+    if relevant == 0 {
+        return 0.0;
+    }
+    let found = ranking
+        .iter()
+        .take(k)
+        .filter(|id| grades.get(id).is_some_and(|grade| *grade > 0))
+        .count();
+    found as f64 / relevant as f64
+}
+
+fn ids(breakdowns: Vec<ScoreBreakdown>) -> Vec<i64> {
+    breakdowns
+        .into_iter()
+        .map(|breakdown| breakdown.audiobook_id)
+        .collect()
+}
+
+fn read_judgments(path: &Path) -> Result<Vec<Judgment>, Box<dyn std::error::Error>> {
+    std::fs::read_to_string(path)?
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            serde_json::from_str(line).map_err(|e| {
+                format!("Invalid judgment at {}:{}: {e}", path.display(), index + 1).into()
+            })
+        })
+        .collect()
+}
+
+/// Runs the queries of the judgments file and prints the mean nDCG@k, MRR and recall@k of the
+/// vector retriever, of the BM25 retriever, of their fusion for each of the `rrf_ks` and of the
+/// ranking configured by the arguments.
+///
+/// # Errors
+///   - If the judgments file can't be read or parsed.
+///   - If the embedder can't be built or a search fails.
+pub async fn handle_test_retrieval(
+    judgments_path: &Path,
+    k: usize,
+    depth: i32,
+    rrf_ks: &[f64],
+    args: Args,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let judgments = read_judgments(judgments_path)?;
+    if judgments.is_empty() {
+        return Err(format!("No judgments in {}", judgments_path.display()).into());
+    }
+    let pgpool = get_postgres_connection(&args).await;
+    let embedder = build_content_embedder(&args)?;
+    let shareable_args = &args.shared;
+
+    let mut rankings: Vec<(String, Metrics)> = vec![
+        (String::from("vector"), Metrics::default()),
+        (String::from("bm25"), Metrics::default()),
+    ];
+    rankings.extend(
+        rrf_ks
+            .iter()
+            .map(|rrf_k| (format!("rrf k={rrf_k}"), Metrics::default())),
+    );
+    rankings.push((String::from("configured"), Metrics::default()));
+
+    for judgment in &judgments {
+        let results = retrieve_audiobooks(
+            &pgpool,
+            &SearchQuery::new(judgment.query.clone()),
+            depth,
+            shareable_args,
+            embedder.clone(),
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+        let grades: HashMap<i64, u32> = judgment
+            .relevant
+            .iter()
+            .map(|relevant| (relevant.audiobook_id, relevant.grade))
+            .collect();
+
+        let vector_ranking: Vec<i64> = results.vector.iter().map(|(id, _)| *id).collect();
+        let bm25_ranking: Vec<i64> = results.bm25.iter().map(|(id, _)| *id).collect();
+        let fused_rankings = rrf_ks.iter().map(|rrf_k| {
+            let config = RankingConfig {
+                ranking_rrf_k: *rrf_k,
+                ..RankingConfig::default()
+            };
+            ids(combine_results_hybrid(&results, &config, &HashMap::new()))
+        });
+        let configured_ranking = ids(rank_audiobooks(
+            &pgpool,
+            &results,
+            &args.ranking,
+            shareable_args.top_rated_min_ratings,
+        )
+        .await
+        .map_err(|e| e.to_string())?);
+        for ((_, metrics), ranking) in rankings.iter_mut().zip(
+            [vector_ranking, bm25_ranking]
+                .into_iter()
+                .chain(fused_rankings)
+                .chain([configured_ranking]),
+        ) {
+            metrics.add(Metrics::of(&ranking, &grades, k));
+        }
+        info!(
+            "Evaluated `{}`: {} vector and {} BM25 results",
+            judgment.query,
+            results.vector.len(),
+            results.bm25.len()
+        );
+    }
+
+    println!(
+        "{:<12} {:>8} {:>8} {:>10}",
+        "ranking",
+        format!("nDCG@{k}"),
+        "MRR",
+        format!("recall@{k}")
+    );
+    for (name, metrics) in rankings {
+        let metrics = metrics.mean(judgments.len());
+        println!(
+            "{name:<12} {:>8.4} {:>8.4} {:>10.4}",
+            metrics.ndcg, metrics.reciprocal_rank, metrics.recall
+        );
+    }
+    println!("{} queries, {depth} results per retriever", judgments.len());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grades(grades: &[(i64, u32)]) -> HashMap<i64, u32> {
+        grades.iter().copied().collect()
+    }
+
+    #[test]
+    fn test_ndcg_at_k_is_one_for_the_ideal_ranking() {
+        let grades = grades(&[(1, 3), (2, 1), (3, 2)]);
+        assert!((ndcg_at_k(&[1, 3, 2, 4], &grades, 3) - 1.0).abs() < 1e-9);
+        assert!(ndcg_at_k(&[2, 3, 1], &grades, 3) < 1.0);
+        assert!(ndcg_at_k(&[4, 5, 6], &grades, 3).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_reciprocal_rank_ignores_grade_zero() {
+        let grades = grades(&[(1, 0), (3, 2)]);
+        assert!((reciprocal_rank(&[1, 2, 3], &grades) - 1.0 / 3.0).abs() < 1e-9);
+        assert!(reciprocal_rank(&[1, 2], &grades).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_recall_at_k_counts_the_first_k_results() {
+        let grades = grades(&[(1, 1), (2, 1), (3, 1), (4, 1)]);
+        assert!((recall_at_k(&[1, 5, 2, 3], &grades, 3) - 0.5).abs() < 1e-9);
+        assert!(recall_at_k(&[1], &HashMap::new(), 3).abs() < 1e-9);
+    }
+}
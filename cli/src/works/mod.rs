@@ -0,0 +1,45 @@
+use shared::db_ops::parade::get_postgres_connection;
+use shared::db_ops::parade::work_ops::{assign_work, get_ungrouped_audiobooks, split_edition};
+use shared::private_args::Args;
+use tracing::info;
+
+use crate::cli_args::WorksSubCommand;
+
+/// Groups the editions of the same book into works.
+///
+/// # Errors
+///   - If the audiobooks to group can't be read.
+///   - If grouping an audiobook fails, the audiobooks grouped before it stay grouped.
+///   - If the audiobook to split doesn't exist.
+pub async fn handle_works(
+    works_sub_command: WorksSubCommand,
+    args: Args,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let pgpool = get_postgres_connection(&args).await;
+    match works_sub_command {
+        WorksSubCommand::Group { limit } => {
+            let audiobooks = get_ungrouped_audiobooks(&pgpool, limit)
+                .await
+                .map_err(|e| e.to_string())?;
+            for audiobook in &audiobooks {
+                let mut tx = pgpool.begin().await?;
+                let work_id = assign_work(&mut tx, audiobook.id, &audiobook.title)
+                    .await
+                    .map_err(|e| e.to_string())?;
+                tx.commit().await?;
+                info!(
+                    "Grouped the audiobook {} `{}` in the work {work_id}",
+                    audiobook.id, audiobook.title
+                );
+            }
+            println!("Grouped {} audiobooks", audiobooks.len());
+        }
+        WorksSubCommand::Split { audiobook_id } => {
+            let work_id = split_edition(&pgpool, audiobook_id)
+                .await
+                .map_err(|e| e.to_string())?;
+            println!("Moved the audiobook {audiobook_id} to the work {work_id}");
+        }
+    }
+    Ok(())
+}
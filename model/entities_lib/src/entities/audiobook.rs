@@ -5,6 +5,8 @@
 // use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
 
+use crate::Edition;
+
 #[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct AudioBook {
     pub id: i64,
@@ -40,5 +42,15 @@ pub struct AudioBook {
     /// Title
     pub unabriged: bool,
 
+    /// Position of the audiobook in its series, e.g. `3` or `2.5`.
     pub series_volume: Option<String>,
+
+    /// Total runtime in seconds.
+    pub runtime_seconds: Option<i32>,
+
+    /// The work this audiobook is an edition of.
+    pub work_id: Option<i64>,
+
+    /// All the editions of the work, this one included, the most recent first.
+    pub editions: Vec<Edition>,
 }
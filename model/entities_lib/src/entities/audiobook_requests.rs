@@ -2,10 +2,14 @@ use std::hash::Hash;
 
 use serde::{Deserialize, Serialize};
 
-use crate::{Author, Category, Keyword, Reader, Series};
+use crate::{Author, Category, Keyword, Language, Reader, Series};
 
 pub type Page = u32;
 pub type Limit = u32;
+/// Minimum number of Hardcover ratings for an audiobook to be listed as top rated.
+pub type MinRatings = u32;
+/// The languages of the audiobooks to list, all of them if it's empty.
+pub type LanguageFilter = Vec<Language>;
 
 #[derive(Clone, Debug, Serialize, Deserialize, Hash, PartialEq, Eq)]
 pub enum GetAudioBookRequestType {
@@ -13,8 +17,14 @@ pub enum GetAudioBookRequestType {
     ByAuthor(Author, Page),
     ByReader(Reader, Page),
     ByCategory(Category, Page),
+    /// The audiobooks of the category and of its descendants in the taxonomy.
+    ByCategoryTree(Category, Page),
     ByKeyword(Keyword, Page),
     BySeries(Series, Page),
+    TopRated(MinRatings, Page),
+    /// The audiobooks with the closest description to the one with this id, except the other
+    /// editions of the same book.
+    SimilarTo(i64, Page),
     ById(String),
     ByIdList(Vec<i64>),
     AllExcept(Vec<Category>, Vec<Keyword>),
@@ -0,0 +1,45 @@
+use serde::{Deserialize, Serialize};
+
+use crate::Language;
+
+/// One of the uploads of a work, as listed in the edition picker.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Edition {
+    pub audiobook_id: i64,
+
+    pub readers: Vec<String>,
+
+    pub format: Option<String>,
+
+    /// Bitrate in kbps.
+    pub bitrate: Option<String>,
+
+    /// ISO 639-1 code.
+    pub language: String,
+
+    pub last_upload: i64,
+}
+
+impl Edition {
+    /// Short description telling the edition apart from the others, e.g. "Read by Michael
+    /// Kramer, English, MP3, 64 kbps".
+    pub fn label(&self) -> String {
+        let mut parts = Vec::new();
+        if self.readers.is_empty() {
+            parts.push(String::from("Unknown reader"));
+        } else {
+            parts.push(format!("Read by {}", self.readers.join(", ")));
+        }
+        let language = Language::from_code(&self.language);
+        if language != Language::Unknown {
+            parts.push(String::from(language.name()));
+        }
+        if let Some(format) = self.format.as_ref().filter(|format| !format.is_empty()) {
+            parts.push(format.clone());
+        }
+        if let Some(bitrate) = &self.bitrate {
+            parts.push(format!("{bitrate} kbps"));
+        }
+        parts.join(", ")
+    }
+}
@@ -1,16 +1,154 @@
 use serde::{Deserialize, Serialize};
 use strum_macros::EnumString;
 
-#[derive(Clone, Copy, Serialize, Deserialize, Eq, PartialEq, Debug)]
+/// Language of an audiobook, stored as its ISO 639-1 code.
+#[derive(Clone, Copy, Serialize, Deserialize, Eq, PartialEq, Hash, Debug, Default)]
 pub enum Language {
+    #[default]
     Unknown = 0,
 
     English = 1,
 
     Italian = 2,
+
+    Spanish = 3,
+
+    French = 4,
+
+    German = 5,
+
+    Portuguese = 6,
+
+    Dutch = 7,
+
+    Russian = 8,
+
+    Polish = 9,
+
+    Swedish = 10,
+
+    Japanese = 11,
+
+    Chinese = 12,
+}
+
+impl Language {
+    /// All the known languages, in the order they are offered to the users.
+    pub const ALL: [Language; 12] = [
+        Language::English,
+        Language::Italian,
+        Language::Spanish,
+        Language::French,
+        Language::German,
+        Language::Portuguese,
+        Language::Dutch,
+        Language::Russian,
+        Language::Polish,
+        Language::Swedish,
+        Language::Japanese,
+        Language::Chinese,
+    ];
+
+    /// The ISO 639-1 code of the language, or "und" (undetermined) if it's unknown.
+    pub fn code(self) -> &'static str {
+        match self {
+            Language::Unknown => "und",
+            Language::English => "en",
+            Language::Italian => "it",
+            Language::Spanish => "es",
+            Language::French => "fr",
+            Language::German => "de",
+            Language::Portuguese => "pt",
+            Language::Dutch => "nl",
+            Language::Russian => "ru",
+            Language::Polish => "pl",
+            Language::Swedish => "sv",
+            Language::Japanese => "ja",
+            Language::Chinese => "zh",
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Language::Unknown => "Unknown",
+            Language::English => "English",
+            Language::Italian => "Italian",
+            Language::Spanish => "Spanish",
+            Language::French => "French",
+            Language::German => "German",
+            Language::Portuguese => "Portuguese",
+            Language::Dutch => "Dutch",
+            Language::Russian => "Russian",
+            Language::Polish => "Polish",
+            Language::Swedish => "Swedish",
+            Language::Japanese => "Japanese",
+            Language::Chinese => "Chinese",
+        }
+    }
+
+    /// The language of an ISO 639-1 code, as returned by `code`.
+    pub fn from_code(code: &str) -> Language {
+        Language::ALL
+            .into_iter()
+            .find(|language| language.code() == code)
+            .unwrap_or(Language::Unknown)
+    }
+
+    /// Parses the free-form language of an upload: an ISO 639-1 or 639-2 code, or a name in
+    /// English or in the language itself, e.g. "English (US)", "eng" or "Italiano". Only the
+    /// first language is kept when several are listed.
+    pub fn parse(text: &str) -> Language {
+        let text = text.trim().to_lowercase();
+        let first_word = text
+            .split(|c: char| !c.is_alphabetic())
+            .find(|word| !word.is_empty())
+            .unwrap_or_default();
+        match first_word {
+            "en" | "eng" | "english" | "inglese" => Language::English,
+            "it" | "ita" | "italian" | "italiano" => Language::Italian,
+            "es" | "spa" | "spanish" | "español" | "espanol" | "castellano" => Language::Spanish,
+            "fr" | "fre" | "fra" | "french" | "français" | "francais" => Language::French,
+            "de" | "ger" | "deu" | "german" | "deutsch" => Language::German,
+            "pt" | "por" | "portuguese" | "português" | "portugues" => Language::Portuguese,
+            "nl" | "dut" | "nld" | "dutch" | "nederlands" | "flemish" => Language::Dutch,
+            "ru" | "rus" | "russian" | "русский" => Language::Russian,
+            "pl" | "pol" | "polish" | "polski" => Language::Polish,
+            "sv" | "swe" | "swedish" | "svenska" => Language::Swedish,
+            "ja" | "jpn" | "japanese" | "日本語" => Language::Japanese,
+            "zh" | "chi" | "zho" | "chinese" | "mandarin" | "中文" => Language::Chinese,
+            _ => Language::Unknown,
+        }
+    }
 }
 
 #[derive(Clone, Copy, Deserialize, EnumString)]
 pub enum RelationshipNames {
     Written,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_language() {
+        assert_eq!(Language::parse("English"), Language::English);
+        assert_eq!(Language::parse(" english (US) "), Language::English);
+        assert_eq!(Language::parse("ENG"), Language::English);
+        assert_eq!(Language::parse("Italiano"), Language::Italian);
+        assert_eq!(Language::parse("Español"), Language::Spanish);
+        assert_eq!(Language::parse("German, English"), Language::German);
+        assert_eq!(Language::parse(""), Language::Unknown);
+        assert_eq!(Language::parse("Klingon"), Language::Unknown);
+    }
+
+    #[test]
+    fn test_language_codes() {
+        for language in Language::ALL {
+            assert_eq!(Language::from_code(language.code()), language);
+            assert_eq!(Language::parse(language.code()), language);
+            assert_eq!(Language::parse(language.name()), language);
+        }
+        assert_eq!(Language::from_code("und"), Language::Unknown);
+    }
+}
@@ -20,3 +20,43 @@ pub struct HardcoverSeriesMetadata {
     pub series_id: i64,
     pub metadata: serde_json::Value,
 }
+
+/// Rating of an audiobook on Hardcover.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct HardcoverRating {
+    /// Average rating, from 0 to 5.
+    pub rating: f64,
+    pub ratings_count: i64,
+}
+
+/// Hardcover information shown on the page of an author.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct HardcoverAuthorProfile {
+    pub bio: Option<String>,
+    pub image_url: Option<String>,
+    pub books_count: Option<i64>,
+    /// Link to the author on Hardcover.
+    pub url: Option<String>,
+}
+
+/// A book of a series, as listed by Hardcover.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct HardcoverSeriesBook {
+    pub title: String,
+    /// Position of the book in the series, e.g. "2" or "2.5".
+    pub position: Option<String>,
+    /// Link to the book on Hardcover.
+    pub url: Option<String>,
+}
+
+/// Hardcover information shown on the page of a series.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct HardcoverSeriesProfile {
+    pub description: Option<String>,
+    pub books_count: Option<i64>,
+    pub primary_books_count: Option<i64>,
+    /// Link to the series on Hardcover.
+    pub url: Option<String>,
+    /// The books of the series, ordered by position.
+    pub books: Vec<HardcoverSeriesBook>,
+}
@@ -1,4 +1,5 @@
-use crate::entities::audiobook_requests::{Limit, Page};
+use crate::entities::audiobook_requests::{Limit, MinRatings, Page};
+use crate::entities::hardcover::{HardcoverAuthorProfile, HardcoverSeriesProfile};
 use crate::{Author, Category, Keyword, Reader, Series};
 use serde::{Deserialize, Serialize};
 use std::hash::Hash;
@@ -23,10 +24,17 @@ pub enum MetaRequest {
     SeriesAlphabetically(Page, Limit),
     CountAllAudiobooks,
     CountAudiobooksForCategory(Category),
+    /// Counts the audiobooks of the category and of its descendants in the taxonomy.
+    CountAudiobooksForCategoryTree(Category),
+    /// The children of the category in the taxonomy.
+    SubcategoriesOf(Category),
     CountAudiobooksForKeyword(Keyword),
     CountAudiobooksForAuthor(Author),
     CountAudiobooksForReader(Reader),
     CountAudiobooksInSeries(Series),
+    CountTopRatedAudiobooks(MinRatings),
+    HardcoverAuthorProfile(Author),
+    HardcoverSeriesProfile(Series),
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, Hash, PartialEq, Eq)]
@@ -37,4 +45,6 @@ pub enum MetaResponse {
     Readers(Vec<Reader>),
     Series(Vec<Series>),
     Count(u32),
+    HardcoverAuthorProfile(Option<HardcoverAuthorProfile>),
+    HardcoverSeriesProfile(Option<HardcoverSeriesProfile>),
 }
@@ -3,11 +3,13 @@ pub mod audiobook_display;
 pub mod audiobook_requests;
 pub mod author;
 pub mod category;
+pub mod edition;
 pub mod enums;
 pub mod hardcover;
 pub mod keyword;
 pub mod meta_request;
 pub mod notifications;
+pub mod ranking;
 pub mod reader;
 pub mod search_query;
 pub mod series;
@@ -0,0 +1,108 @@
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "ssr")]
+use clap;
+
+pub const DEFAULT_RRF_K: f64 = 60.0;
+pub const DEFAULT_RETRIEVER_WEIGHT: f64 = 1.0;
+pub const DEFAULT_RECENCY_HALF_LIFE_DAYS: f64 = 30.0;
+
+/// How the results of the vector and the BM25 retrievers are merged.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(clap::ValueEnum))]
+pub enum FusionMethod {
+    /// Reciprocal Rank Fusion: only the ranks of the results count, `weight / (rrf_k + rank)`.
+    Rrf,
+    /// The scores of each retriever scaled to [0, 1] by their minimum and maximum, then summed
+    /// with the weights.
+    Score,
+}
+
+/// How the results of the hybrid search are ranked.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(clap::Args))]
+// Otherwise the doc comment becomes the about text of the commands flattening it.
+#[cfg_attr(feature = "ssr", command(about = None, long_about = None))]
+pub struct RankingConfig {
+    #[cfg_attr(feature = "ssr", clap(value_enum))]
+    #[cfg_attr(feature = "ssr", arg(long, default_value_t = FusionMethod::Rrf))]
+    pub ranking_fusion: FusionMethod,
+
+    /// Constant of the Reciprocal Rank Fusion, the higher the less the top ranks weigh.
+    #[cfg_attr(feature = "ssr", arg(long, default_value_t = DEFAULT_RRF_K))]
+    pub ranking_rrf_k: f64,
+
+    #[cfg_attr(feature = "ssr", arg(long, default_value_t = DEFAULT_RETRIEVER_WEIGHT))]
+    pub ranking_vector_weight: f64,
+
+    #[cfg_attr(feature = "ssr", arg(long, default_value_t = DEFAULT_RETRIEVER_WEIGHT))]
+    pub ranking_bm25_weight: f64,
+
+    /// Boost of the audiobooks ingested right now, halving every `ranking_recency_half_life_days`.
+    /// The fused score of the best result is 1.
+    #[cfg_attr(feature = "ssr", arg(long, default_value_t = 0.0))]
+    pub ranking_recency_boost: f64,
+
+    #[cfg_attr(feature = "ssr", arg(long, default_value_t = DEFAULT_RECENCY_HALF_LIFE_DAYS))]
+    pub ranking_recency_half_life_days: f64,
+
+    /// Boost of the audiobooks rated 5 on Hardcover, proportional to the rating. Only the
+    /// audiobooks with at least `top_rated_min_ratings` ratings are boosted.
+    #[cfg_attr(feature = "ssr", arg(long, default_value_t = 0.0))]
+    pub ranking_rating_boost: f64,
+}
+
+impl Default for RankingConfig {
+    fn default() -> Self {
+        Self {
+            ranking_fusion: FusionMethod::Rrf,
+            ranking_rrf_k: DEFAULT_RRF_K,
+            ranking_vector_weight: DEFAULT_RETRIEVER_WEIGHT,
+            ranking_bm25_weight: DEFAULT_RETRIEVER_WEIGHT,
+            ranking_recency_boost: 0.0,
+            ranking_recency_half_life_days: DEFAULT_RECENCY_HALF_LIFE_DAYS,
+            ranking_rating_boost: 0.0,
+        }
+    }
+}
+
+impl RankingConfig {
+    pub fn has_boosts(&self) -> bool {
+        self.ranking_recency_boost != 0.0 || self.ranking_rating_boost != 0.0
+    }
+}
+
+/// Why a result of the search ranked where it did.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ScoreBreakdown {
+    pub audiobook_id: i64,
+    /// Rank among the results of the vector retriever, from 1.
+    pub vector_rank: Option<u32>,
+    pub vector_score: Option<f64>,
+    /// Rank among the results of the BM25 retriever, from 1.
+    pub bm25_rank: Option<u32>,
+    pub bm25_score: Option<f64>,
+    /// The weighted fusion of the retrievers, relative to the best result.
+    pub fusion_score: f64,
+    pub recency_boost: f64,
+    pub rating_boost: f64,
+    /// The sum of the fusion score and of the boosts, the results are sorted by it.
+    pub score: f64,
+}
+
+impl ScoreBreakdown {
+    /// The breakdown of an audiobook not found by any retriever yet.
+    pub fn new(audiobook_id: i64) -> Self {
+        Self {
+            audiobook_id,
+            vector_rank: None,
+            vector_score: None,
+            bm25_rank: None,
+            bm25_score: None,
+            fusion_score: 0.0,
+            recency_boost: 0.0,
+            rating_boost: 0.0,
+            score: 0.0,
+        }
+    }
+}
@@ -1,8 +1,124 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+use crate::{Language, ScoreBreakdown};
+
+/// Names of the query parameters of the search page URL holding the filters.
+pub const LANGUAGE_PARAM: &str = "language";
+pub const CATEGORY_PARAM: &str = "category";
+pub const AUTHOR_PARAM: &str = "author";
+pub const FORMAT_PARAM: &str = "format";
+pub const UNABRIDGED_PARAM: &str = "unabridged";
+pub const MIN_BITRATE_PARAM: &str = "min_bitrate";
+pub const UPLOADED_WITHIN_DAYS_PARAM: &str = "days";
+pub const DEBUG_PARAM: &str = "debug";
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
 pub struct SearchQuery {
     pub search_string: String,
+    /// The languages of the results, the preferred languages of the user if it's empty.
+    #[serde(default)]
+    pub languages: Vec<Language>,
+    /// Ids of the categories of the results, including their descendants in the taxonomy. Any
+    /// category if it's empty.
+    #[serde(default)]
+    pub categories: Vec<i64>,
+    /// Ids of the authors of the results, any author if it's empty.
+    #[serde(default)]
+    pub authors: Vec<i64>,
+    /// Formats of the results, e.g. "MP3", any format if it's empty.
+    #[serde(default)]
+    pub formats: Vec<String>,
+    #[serde(default)]
+    pub unabridged: Option<bool>,
+    /// Minimum bitrate in kbps.
+    #[serde(default)]
+    pub min_bitrate: Option<i32>,
+    /// Only the audiobooks uploaded in the last days.
+    #[serde(default)]
+    pub uploaded_within_days: Option<u32>,
+    /// Returns the breakdown of the score of each result, ignored outside of development.
+    #[serde(default)]
+    pub debug: bool,
+}
+
+impl SearchQuery {
+    pub fn new(search_string: String) -> Self {
+        Self {
+            search_string,
+            ..Self::default()
+        }
+    }
+
+    /// Reads the filters from the query parameters of the search page URL, `get_all` returning the
+    /// values of a parameter. The values that can't be parsed are ignored.
+    pub fn with_url_params<F>(search_string: String, get_all: F) -> Self
+    where
+        F: Fn(&str) -> Vec<String>, {
+        let last = |param: &str| get_all(param).pop();
+        Self {
+            search_string,
+            languages: get_all(LANGUAGE_PARAM)
+                .iter()
+                .filter_map(|code| {
+                    // Only "und" selects the unknown language, the other unknown codes are invalid.
+                    let language = Language::from_code(code);
+                    (language != Language::Unknown || code == Language::Unknown.code())
+                        .then_some(language)
+                })
+                .collect(),
+            categories: get_all(CATEGORY_PARAM)
+                .iter()
+                .filter_map(|id| id.parse().ok())
+                .collect(),
+            authors: get_all(AUTHOR_PARAM)
+                .iter()
+                .filter_map(|id| id.parse().ok())
+                .collect(),
+            formats: get_all(FORMAT_PARAM)
+                .into_iter()
+                .filter(|format| !format.is_empty())
+                .collect(),
+            unabridged: last(UNABRIDGED_PARAM).and_then(|value| value.parse().ok()),
+            min_bitrate: last(MIN_BITRATE_PARAM).and_then(|value| value.parse().ok()),
+            uploaded_within_days: last(UPLOADED_WITHIN_DAYS_PARAM)
+                .and_then(|value| value.parse().ok()),
+            debug: last(DEBUG_PARAM).is_some_and(|value| value == "true"),
+        }
+    }
+
+    /// The query parameters of the filters, as read by `with_url_params`.
+    pub fn url_params(&self) -> Vec<(&'static str, String)> {
+        let mut params: Vec<(&'static str, String)> = Vec::new();
+        params.extend(
+            self.languages
+                .iter()
+                .map(|language| (LANGUAGE_PARAM, language.code().to_string())),
+        );
+        params.extend(
+            self.categories
+                .iter()
+                .map(|id| (CATEGORY_PARAM, id.to_string())),
+        );
+        params.extend(self.authors.iter().map(|id| (AUTHOR_PARAM, id.to_string())));
+        params.extend(
+            self.formats
+                .iter()
+                .map(|format| (FORMAT_PARAM, format.clone())),
+        );
+        if let Some(unabridged) = self.unabridged {
+            params.push((UNABRIDGED_PARAM, unabridged.to_string()));
+        }
+        if let Some(min_bitrate) = self.min_bitrate {
+            params.push((MIN_BITRATE_PARAM, min_bitrate.to_string()));
+        }
+        if let Some(days) = self.uploaded_within_days {
+            params.push((UPLOADED_WITHIN_DAYS_PARAM, days.to_string()));
+        }
+        if self.debug {
+            params.push((DEBUG_PARAM, String::from("true")));
+        }
+        params
+    }
 }
 
 // #[derive(Debug, Hash, Serialize, Deserialize)]
@@ -10,3 +126,79 @@ pub struct SearchQuery {
 //     pub search_string: String,
 //     pub embeddings: [f32; 256],
 // }
+
+/// A value of a facet, with the number of results having it.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct FacetValue {
+    /// The value of the filter in the URL, e.g. the id of a category.
+    pub value: String,
+    pub label: String,
+    pub count: u32,
+}
+
+/// The values found in the results, the most frequent first.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
+pub struct SearchFacets {
+    pub categories: Vec<FacetValue>,
+    pub languages: Vec<FacetValue>,
+    pub formats: Vec<FacetValue>,
+    pub authors: Vec<FacetValue>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+pub struct SearchResults {
+    /// The ids of the audiobooks in the page, the most relevant first.
+    pub audiobook_ids: Vec<i64>,
+    /// Estimated number of results in all the pages, the retrievers stopping at the last page.
+    #[serde(default)]
+    pub total_hits: u32,
+    /// The facets of the results in all the pages.
+    pub facets: SearchFacets,
+    /// The breakdown of the score of each result in the page, in the same order, if the query
+    /// asked for it.
+    #[serde(default)]
+    pub score_breakdowns: Vec<ScoreBreakdown>,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    #[test]
+    fn test_url_params_round_trip() {
+        let search_query = SearchQuery {
+            search_string: String::from("dragons"),
+            languages: vec![Language::English, Language::Italian],
+            categories: vec![3, 14],
+            authors: vec![15],
+            formats: vec![String::from("MP3")],
+            unabridged: Some(true),
+            min_bitrate: Some(64),
+            uploaded_within_days: Some(30),
+            debug: true,
+        };
+        let mut params: HashMap<&str, Vec<String>> = HashMap::new();
+        for (key, value) in search_query.url_params() {
+            params.entry(key).or_default().push(value);
+        }
+        let parsed = SearchQuery::with_url_params(String::from("dragons"), |key| {
+            params.get(key).cloned().unwrap_or_default()
+        });
+        assert_eq!(parsed, search_query);
+    }
+
+    #[test]
+    fn test_with_url_params_ignores_invalid_values() {
+        let parsed = SearchQuery::with_url_params(String::from("dragons"), |key| match key {
+            LANGUAGE_PARAM => vec![String::from("xx"), String::from("fr"), String::from("und")],
+            CATEGORY_PARAM => vec![String::from("abc")],
+            MIN_BITRATE_PARAM => vec![String::from("fast")],
+            _ => Vec::new(),
+        });
+        assert_eq!(parsed.languages, vec![Language::French, Language::Unknown]);
+        assert!(parsed.categories.is_empty());
+        assert_eq!(parsed.min_bitrate, None);
+    }
+}
@@ -23,9 +23,18 @@ pub struct ShareableArgsValues {
     #[cfg_attr(feature = "ssr", arg(long, default_value_t = 8))]
     pub user_audiobooks_per_homepage_section: u32,
 
+    /// Number of search results in a page.
     #[cfg_attr(feature = "ssr", arg(long, default_value_t = 24))]
     pub max_search_results: i32,
 
+    /// Number of pages of search results, each retriever returning enough results to fill them.
+    #[cfg_attr(feature = "ssr", arg(long, default_value_t = 10))]
+    pub max_search_pages: i32,
+
+    /// Minimum number of Hardcover ratings for an audiobook to be listed as top rated.
+    #[cfg_attr(feature = "ssr", arg(long, default_value_t = 25))]
+    pub top_rated_min_ratings: u32,
+
     #[cfg_attr(feature = "ssr", arg(long, default_value_t = String::from("models/gemini-2.5-flash-lite")))]
     pub gemini_extract_html_model_name: String,
 
@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 
-use crate::{Author, Category, Keyword, Reader, Series};
+use crate::{Author, Category, Keyword, Language, Reader, Series};
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum SubscriptionType {
@@ -22,6 +22,10 @@ pub enum SubscriptionExists {
 pub struct Subscription {
     pub user_id: i64,
     pub subscription_type: SubscriptionType,
+    /// The languages of the audiobooks to notify, all of them if it's empty. A new subscription
+    /// without languages takes the preferred languages of the user.
+    #[serde(default)]
+    pub languages: Vec<Language>,
 }
 
 impl Subscription {
@@ -52,4 +56,15 @@ impl Subscription {
             SubscriptionType::ToKeyword(_) => String::from("Keyword"),
         }
     }
+
+    pub fn render_languages(&self) -> String {
+        if self.languages.is_empty() {
+            return String::from("All");
+        }
+        self.languages
+            .iter()
+            .map(|language| language.name())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
 }
@@ -4,15 +4,18 @@ pub mod error;
 pub use self::error::{Error, Result};
 
 pub use entities::audiobook::AudioBook;
-pub use entities::audiobook_requests::GetAudioBookRequestType;
+pub use entities::audiobook_requests::{GetAudioBookRequestType, LanguageFilter};
 pub use entities::author::Author;
 pub use entities::category::Category;
+pub use entities::edition::Edition;
 pub use entities::enums::Language;
+pub use entities::hardcover::HardcoverRating;
 pub use entities::keyword::Keyword;
 pub use entities::meta_request::{MetaRequest, MetaResponse};
 pub use entities::notifications::{NotificationReason, UserNotification};
+pub use entities::ranking::{FusionMethod, RankingConfig, ScoreBreakdown};
 pub use entities::reader::Reader;
-pub use entities::search_query::SearchQuery;
+pub use entities::search_query::{FacetValue, SearchFacets, SearchQuery, SearchResults};
 pub use entities::series::Series;
 pub use entities::shareable_args::{Environment, ShareableArgsValues};
 pub use entities::subscription::{SubscriptionExists, SubscriptionType};
@@ -25,4 +28,5 @@ pub type AudiobookWithData = (
     Vec<Keyword>,
     Vec<Reader>,
     Option<Series>,
+    Option<HardcoverRating>,
 );
@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS public.raw_page_fetch;
+DROP TABLE IF EXISTS public.raw_page_blob;
@@ -0,0 +1,19 @@
+-- Content-addressed archive of the raw pages fetched by the scraper, so that audiobooks can be
+-- re-extracted without scraping the site again.
+CREATE TABLE IF NOT EXISTS public.raw_page_blob (
+content_hash TEXT PRIMARY KEY,
+compressed_content BYTEA NOT NULL
+) ;
+
+CREATE TABLE IF NOT EXISTS public.raw_page_fetch (
+id BIGINT PRIMARY KEY GENERATED ALWAYS AS IDENTITY,
+url TEXT NOT NULL,
+page_kind TEXT NOT NULL,
+fetched_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+content_hash TEXT NOT NULL,
+CONSTRAINT fk_raw_page_blob FOREIGN KEY (content_hash)
+REFERENCES public.raw_page_blob (content_hash)
+) ;
+
+CREATE INDEX IF NOT EXISTS idx_raw_page_fetch_url_fetched_at ON
+public.raw_page_fetch (url, fetched_at DESC) ;
@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS public.idx_audiobook_series_volume;
+ALTER TABLE public.audiobook DROP COLUMN IF EXISTS series_volume;
+ALTER TABLE public.audiobook DROP COLUMN IF EXISTS runtime_seconds;
@@ -0,0 +1,7 @@
+-- Normalized runtime and numeric position in the series, both parsed from the extracted values.
+ALTER TABLE public.audiobook
+ADD COLUMN IF NOT EXISTS runtime_seconds INTEGER,
+ADD COLUMN IF NOT EXISTS series_volume DOUBLE PRECISION ;
+
+CREATE INDEX IF NOT EXISTS idx_audiobook_series_volume ON
+public.audiobook (series_id, series_volume) ;
@@ -0,0 +1,8 @@
+-- Extractions that failed validation, kept for inspection instead of being inserted.
+CREATE TABLE IF NOT EXISTS public.extraction_quarantine (
+id BIGINT PRIMARY KEY GENERATED ALWAYS AS IDENTITY,
+url TEXT NOT NULL UNIQUE,
+extracted_values JSONB NOT NULL,
+errors TEXT [] NOT NULL,
+quarantined_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+) ;
@@ -0,0 +1,13 @@
+-- Progress of the backfill runs, so an interrupted run can be resumed.
+CREATE TABLE IF NOT EXISTS public.backfill_run (
+id BIGINT PRIMARY KEY GENERATED ALWAYS AS IDENTITY,
+page_start INTEGER NOT NULL,
+page_end INTEGER NOT NULL,
+last_completed_page INTEGER,
+enqueued_count BIGINT NOT NULL DEFAULT 0,
+skipped_count BIGINT NOT NULL DEFAULT 0,
+failed_count BIGINT NOT NULL DEFAULT 0,
+started_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+completed_at TIMESTAMP WITH TIME ZONE
+) ;
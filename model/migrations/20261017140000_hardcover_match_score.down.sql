@@ -0,0 +1,2 @@
+DROP INDEX IF EXISTS public.idx_hardcover_audiobook_metadata_match_score;
+ALTER TABLE public.hardcover_audiobook_metadata DROP COLUMN IF EXISTS match_score;
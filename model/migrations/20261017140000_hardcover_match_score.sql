@@ -0,0 +1,7 @@
+-- How confident the match between the audiobook and the Hardcover book is, in [0, 1].
+-- NULL for the matches made before the scoring was introduced.
+ALTER TABLE public.hardcover_audiobook_metadata
+ADD COLUMN IF NOT EXISTS match_score DOUBLE PRECISION ;
+
+CREATE INDEX IF NOT EXISTS idx_hardcover_audiobook_metadata_match_score
+ON public.hardcover_audiobook_metadata (match_score) ;
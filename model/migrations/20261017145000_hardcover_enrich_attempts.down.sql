@@ -0,0 +1,2 @@
+ALTER TABLE public.series DROP COLUMN IF EXISTS hardcover_attempted_at;
+ALTER TABLE public.author DROP COLUMN IF EXISTS hardcover_attempted_at;
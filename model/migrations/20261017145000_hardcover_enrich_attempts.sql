@@ -0,0 +1,7 @@
+-- When the metadata of the entity was last searched on Hardcover without finding it. The entities
+-- are enriched in this order, so that the ones without a match don't starve the others.
+ALTER TABLE public.author
+ADD COLUMN IF NOT EXISTS hardcover_attempted_at TIMESTAMP WITH TIME ZONE NULL ;
+
+ALTER TABLE public.series
+ADD COLUMN IF NOT EXISTS hardcover_attempted_at TIMESTAMP WITH TIME ZONE NULL ;
@@ -0,0 +1,4 @@
+DROP TABLE IF EXISTS public.hardcover_rating_history;
+DROP INDEX IF EXISTS public.idx_hardcover_audiobook_metadata_last_attempted_at;
+ALTER TABLE public.hardcover_audiobook_metadata DROP COLUMN IF EXISTS last_attempted_at;
+ALTER TABLE public.hardcover_audiobook_metadata DROP COLUMN IF EXISTS refreshed_at;
@@ -0,0 +1,34 @@
+-- When the ratings of the Hardcover metadata were last refreshed. NULL for the rows that were never
+-- refreshed since their ingestion.
+ALTER TABLE public.hardcover_audiobook_metadata
+ADD COLUMN IF NOT EXISTS refreshed_at TIMESTAMP WITH TIME ZONE ;
+
+ALTER TABLE public.hardcover_audiobook_metadata
+ALTER COLUMN refreshed_at SET DEFAULT CURRENT_TIMESTAMP ;
+
+-- When the ratings were last requested from Hardcover, whether the request succeeded or not. The
+-- stale matches are refreshed in this order, never attempted first, so that the failing ones don't
+-- starve the others.
+ALTER TABLE public.hardcover_audiobook_metadata
+ADD COLUMN IF NOT EXISTS last_attempted_at TIMESTAMP WITH TIME ZONE ;
+
+ALTER TABLE public.hardcover_audiobook_metadata
+ALTER COLUMN last_attempted_at SET DEFAULT CURRENT_TIMESTAMP ;
+
+CREATE INDEX IF NOT EXISTS idx_hardcover_audiobook_metadata_last_attempted_at
+ON public.hardcover_audiobook_metadata (last_attempted_at ASC NULLS FIRST) ;
+
+-- Snapshots of the Hardcover ratings, used to show trends.
+CREATE TABLE IF NOT EXISTS public.hardcover_rating_history (
+id BIGINT PRIMARY KEY GENERATED ALWAYS AS IDENTITY,
+audiobook_id BIGINT NOT NULL,
+rating DOUBLE PRECISION,
+ratings_count BIGINT,
+reviews_count BIGINT,
+recorded_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+CONSTRAINT fk_hardcover_rating_history_audiobook FOREIGN KEY (audiobook_id)
+REFERENCES public.audiobook (id) ON DELETE CASCADE
+) ;
+
+CREATE INDEX IF NOT EXISTS idx_hardcover_rating_history_audiobook
+ON public.hardcover_rating_history (audiobook_id, recorded_at DESC) ;
@@ -0,0 +1 @@
+DROP TABLE IF EXISTS public.audiobook_book_metadata;
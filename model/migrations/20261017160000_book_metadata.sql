@@ -0,0 +1,17 @@
+-- Bibliographic metadata of the audiobooks, merged from the metadata providers (Hardcover, Open
+-- Library). `sources` maps every field to the provider it was taken from.
+CREATE TABLE IF NOT EXISTS public.audiobook_book_metadata (
+id BIGINT PRIMARY KEY GENERATED ALWAYS AS IDENTITY,
+audiobook_id BIGINT NOT NULL UNIQUE,
+isbn TEXT,
+publication_year INTEGER,
+subjects TEXT [] NOT NULL DEFAULT '{}',
+page_count INTEGER,
+sources JSONB NOT NULL DEFAULT '{}',
+updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+CONSTRAINT fk_audiobook_book_metadata_audiobook FOREIGN KEY (audiobook_id)
+REFERENCES public.audiobook (id) ON DELETE CASCADE
+) ;
+
+CREATE INDEX IF NOT EXISTS idx_audiobook_book_metadata_isbn
+ON public.audiobook_book_metadata (isbn) ;
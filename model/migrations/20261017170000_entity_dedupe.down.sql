@@ -0,0 +1,10 @@
+DROP INDEX IF EXISTS public.idx_series_title_trgm;
+DROP INDEX IF EXISTS public.idx_keyword_name_trgm;
+DROP INDEX IF EXISTS public.idx_category_name_trgm;
+DROP INDEX IF EXISTS public.idx_reader_name_trgm;
+DROP INDEX IF EXISTS public.idx_author_name_trgm;
+DROP INDEX IF EXISTS public.idx_series_lower_title;
+DROP INDEX IF EXISTS public.idx_keyword_lower_name;
+DROP INDEX IF EXISTS public.idx_category_lower_name;
+DROP INDEX IF EXISTS public.idx_reader_lower_name;
+DROP INDEX IF EXISTS public.idx_author_lower_name;
@@ -0,0 +1,20 @@
+-- Case-insensitive lookups of the entities when they are stored, and trigram similarity to
+-- propose the duplicates to merge, see `cli entities dedupe`.
+CREATE EXTENSION IF NOT EXISTS pg_trgm ;
+
+CREATE INDEX IF NOT EXISTS idx_author_lower_name ON public.author (lower(name)) ;
+CREATE INDEX IF NOT EXISTS idx_reader_lower_name ON public.reader (lower(name)) ;
+CREATE INDEX IF NOT EXISTS idx_category_lower_name ON public.category (lower(name)) ;
+CREATE INDEX IF NOT EXISTS idx_keyword_lower_name ON public.keyword (lower(name)) ;
+CREATE INDEX IF NOT EXISTS idx_series_lower_title ON public.series (lower(title)) ;
+
+CREATE INDEX IF NOT EXISTS idx_author_name_trgm
+ON public.author USING gin (lower(name) gin_trgm_ops) ;
+CREATE INDEX IF NOT EXISTS idx_reader_name_trgm
+ON public.reader USING gin (lower(name) gin_trgm_ops) ;
+CREATE INDEX IF NOT EXISTS idx_category_name_trgm
+ON public.category USING gin (lower(name) gin_trgm_ops) ;
+CREATE INDEX IF NOT EXISTS idx_keyword_name_trgm
+ON public.keyword USING gin (lower(name) gin_trgm_ops) ;
+CREATE INDEX IF NOT EXISTS idx_series_title_trgm
+ON public.series USING gin (lower(title) gin_trgm_ops) ;
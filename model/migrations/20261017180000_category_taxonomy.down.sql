@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS public.category_alias;
+DROP TABLE IF EXISTS public.category_taxonomy;
@@ -0,0 +1,163 @@
+-- Curated hierarchy of genres. Its nodes are rows of `category`, so that the category pages, the
+-- links and the subscriptions keep working on them, and the free-form categories are mapped onto
+-- them through `category_alias`.
+CREATE TABLE IF NOT EXISTS public.category_taxonomy (
+category_id BIGINT PRIMARY KEY,
+parent_id BIGINT NULL,
+CONSTRAINT fk_category_taxonomy_category FOREIGN KEY (category_id)
+REFERENCES public.category (id) ON DELETE CASCADE,
+CONSTRAINT fk_category_taxonomy_parent FOREIGN KEY (parent_id)
+REFERENCES public.category_taxonomy (category_id) ON DELETE CASCADE,
+CONSTRAINT chk_category_taxonomy_parent CHECK (parent_id <> category_id)
+) ;
+
+CREATE INDEX IF NOT EXISTS idx_category_taxonomy_parent_id
+ON public.category_taxonomy (parent_id) ;
+
+-- Lowercased, normalized category strings and the taxonomy node they map to. A NULL
+-- `category_id` records that the string doesn't fit the taxonomy, so it's not mapped again.
+-- `source` is one of 'curated', 'llm' or 'manual'.
+CREATE TABLE IF NOT EXISTS public.category_alias (
+alias TEXT PRIMARY KEY,
+category_id BIGINT NULL,
+source TEXT NOT NULL,
+created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+CONSTRAINT fk_category_alias_category FOREIGN KEY (category_id)
+REFERENCES public.category_taxonomy (category_id) ON DELETE CASCADE
+) ;
+
+CREATE INDEX IF NOT EXISTS idx_category_alias_category_id
+ON public.category_alias (category_id) ;
+
+-- =================================================================
+-- Initial taxonomy
+-- =================================================================
+
+CREATE TEMPORARY TABLE seed_taxonomy (name TEXT NOT NULL, parent TEXT NULL) ;
+
+INSERT INTO seed_taxonomy (name, parent) VALUES
+('Fiction', NULL),
+('Fantasy', 'Fiction'),
+('Epic Fantasy', 'Fantasy'),
+('Urban Fantasy', 'Fantasy'),
+('Dark Fantasy', 'Fantasy'),
+('LitRPG', 'Fantasy'),
+('Science Fiction', 'Fiction'),
+('Space Opera', 'Science Fiction'),
+('Cyberpunk', 'Science Fiction'),
+('Dystopian', 'Science Fiction'),
+('Post-Apocalyptic', 'Science Fiction'),
+('Military Science Fiction', 'Science Fiction'),
+('Mystery', 'Fiction'),
+('Crime', 'Mystery'),
+('Detective', 'Mystery'),
+('Cozy Mystery', 'Mystery'),
+('Thriller', 'Fiction'),
+('Horror', 'Fiction'),
+('Romance', 'Fiction'),
+('Historical Fiction', 'Fiction'),
+('Literary Fiction', 'Fiction'),
+('Classics', 'Fiction'),
+('Adventure', 'Fiction'),
+('Action', 'Fiction'),
+('Humor', 'Fiction'),
+('Western', 'Fiction'),
+('Young Adult', 'Fiction'),
+('Children''s', 'Fiction'),
+('Non-Fiction', NULL),
+('Biography & Memoir', 'Non-Fiction'),
+('History', 'Non-Fiction'),
+('Science', 'Non-Fiction'),
+('Technology', 'Non-Fiction'),
+('Business', 'Non-Fiction'),
+('Self-Help', 'Non-Fiction'),
+('Health & Fitness', 'Non-Fiction'),
+('Philosophy', 'Non-Fiction'),
+('Religion & Spirituality', 'Non-Fiction'),
+('Politics', 'Non-Fiction'),
+('True Crime', 'Non-Fiction'),
+('Education', 'Non-Fiction'),
+('Poetry', NULL),
+('Drama', NULL) ;
+
+INSERT INTO public.category (name)
+SELECT name FROM seed_taxonomy
+ON CONFLICT (name) DO NOTHING ;
+
+INSERT INTO public.category_taxonomy (category_id, parent_id)
+SELECT c.id, NULL
+FROM seed_taxonomy t
+JOIN public.category c ON c.name = t.name
+WHERE t.parent IS NULL
+ON CONFLICT (category_id) DO NOTHING ;
+
+INSERT INTO public.category_taxonomy (category_id, parent_id)
+SELECT c.id, p.id
+FROM seed_taxonomy t
+JOIN public.category c ON c.name = t.name
+JOIN public.category p ON p.name = t.parent
+WHERE t.parent IN (SELECT name FROM seed_taxonomy WHERE parent IS NULL)
+ON CONFLICT (category_id) DO NOTHING ;
+
+INSERT INTO public.category_taxonomy (category_id, parent_id)
+SELECT c.id, p.id
+FROM seed_taxonomy t
+JOIN public.category c ON c.name = t.name
+JOIN public.category p ON p.name = t.parent
+ON CONFLICT (category_id) DO NOTHING ;
+
+INSERT INTO public.category_alias (alias, category_id, source)
+SELECT lower(c.name), c.id, 'curated'
+FROM public.category_taxonomy ct
+JOIN public.category c ON c.id = ct.category_id
+ON CONFLICT (alias) DO NOTHING ;
+
+INSERT INTO public.category_alias (alias, category_id, source)
+SELECT a.alias, c.id, 'curated'
+FROM (VALUES
+('general fiction', 'Fiction'),
+('sci-fi', 'Science Fiction'),
+('scifi', 'Science Fiction'),
+('sci fi', 'Science Fiction'),
+('sf', 'Science Fiction'),
+('science fiction & fantasy', 'Science Fiction'),
+('apocalyptic', 'Post-Apocalyptic'),
+('post apocalyptic', 'Post-Apocalyptic'),
+('mystery & thriller', 'Mystery'),
+('mysteries', 'Mystery'),
+('crime fiction', 'Crime'),
+('suspense', 'Thriller'),
+('thrillers', 'Thriller'),
+('historical', 'Historical Fiction'),
+('literature', 'Literary Fiction'),
+('classic', 'Classics'),
+('comedy', 'Humor'),
+('teen', 'Young Adult'),
+('teen & young adult', 'Young Adult'),
+('ya', 'Young Adult'),
+('children', 'Children''s'),
+('kids', 'Children''s'),
+('nonfiction', 'Non-Fiction'),
+('non fiction', 'Non-Fiction'),
+('biography', 'Biography & Memoir'),
+('biographies', 'Biography & Memoir'),
+('biographies & memoirs', 'Biography & Memoir'),
+('memoir', 'Biography & Memoir'),
+('autobiography', 'Biography & Memoir'),
+('self help', 'Self-Help'),
+('self-development', 'Self-Help'),
+('business & money', 'Business'),
+('health', 'Health & Fitness'),
+('religion', 'Religion & Spirituality'),
+('spirituality', 'Religion & Spirituality'),
+('politics & social sciences', 'Politics'),
+('computers & technology', 'Technology'),
+('art, design & music', NULL),
+('action & adventure', 'Adventure'),
+('poems', 'Poetry'),
+('plays', 'Drama')
+) AS a (alias, name)
+LEFT JOIN public.category c ON c.name = a.name
+ON CONFLICT (alias) DO NOTHING ;
+
+DROP TABLE seed_taxonomy ;
@@ -0,0 +1,4 @@
+DROP TABLE IF EXISTS public.keyword_stoplist;
+DROP TABLE IF EXISTS public.keyword_synonym;
+DROP TABLE IF EXISTS public.keyword_synonym_group;
+DROP TABLE IF EXISTS public.keyword_alias;
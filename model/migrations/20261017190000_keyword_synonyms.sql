@@ -0,0 +1,56 @@
+-- Lowercased, normalized keyword strings rewritten to another keyword when the audiobooks are
+-- inserted, e.g. "magical" to "magic".
+CREATE TABLE IF NOT EXISTS public.keyword_alias (
+alias TEXT PRIMARY KEY,
+keyword_id BIGINT NOT NULL,
+created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+CONSTRAINT fk_keyword_alias_keyword FOREIGN KEY (keyword_id)
+REFERENCES public.keyword (id) ON DELETE CASCADE
+) ;
+
+CREATE INDEX IF NOT EXISTS idx_keyword_alias_keyword_id
+ON public.keyword_alias (keyword_id) ;
+
+-- Groups of distinct keywords meaning the same thing, e.g. "magic" and "magic system". A
+-- subscription to any keyword of a group matches the audiobooks of all of them.
+CREATE TABLE IF NOT EXISTS public.keyword_synonym_group (
+id BIGSERIAL PRIMARY KEY,
+created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+) ;
+
+CREATE TABLE IF NOT EXISTS public.keyword_synonym (
+keyword_id BIGINT PRIMARY KEY,
+group_id BIGINT NOT NULL,
+CONSTRAINT fk_keyword_synonym_keyword FOREIGN KEY (keyword_id)
+REFERENCES public.keyword (id) ON DELETE CASCADE,
+CONSTRAINT fk_keyword_synonym_group FOREIGN KEY (group_id)
+REFERENCES public.keyword_synonym_group (id) ON DELETE CASCADE
+) ;
+
+CREATE INDEX IF NOT EXISTS idx_keyword_synonym_group_id
+ON public.keyword_synonym (group_id) ;
+
+-- Lowercased, normalized keyword strings that say nothing about the audiobook and are dropped.
+CREATE TABLE IF NOT EXISTS public.keyword_stoplist (
+keyword TEXT PRIMARY KEY,
+created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+) ;
+
+INSERT INTO public.keyword_stoplist (keyword) VALUES
+('audiobook'),
+('audiobooks'),
+('audio book'),
+('audio'),
+('unabridged'),
+('abridged'),
+('narrated'),
+('narration'),
+('audible'),
+('mp3'),
+('m4b'),
+('book'),
+('books'),
+('novel'),
+('fiction'),
+('english')
+ON CONFLICT (keyword) DO NOTHING ;
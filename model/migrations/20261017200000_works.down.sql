@@ -0,0 +1,5 @@
+ALTER TABLE public.users DROP COLUMN IF EXISTS notify_new_editions;
+ALTER TABLE public.audiobook DROP COLUMN IF EXISTS work_pinned;
+DROP INDEX IF EXISTS public.idx_audiobook_work_id;
+ALTER TABLE public.audiobook DROP COLUMN IF EXISTS work_id;
+DROP TABLE IF EXISTS public.work;
@@ -0,0 +1,25 @@
+-- A work groups the editions of the same book: uploads with a different narrator, bitrate or
+-- format. The editions share the normalized title, an author and the series volume.
+CREATE TABLE IF NOT EXISTS public.work (
+id BIGINT PRIMARY KEY GENERATED ALWAYS AS IDENTITY,
+title_key TEXT NOT NULL,
+series_volume DOUBLE PRECISION NULL,
+created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+) ;
+
+CREATE INDEX IF NOT EXISTS idx_work_title_key
+ON public.work (title_key) ;
+
+ALTER TABLE public.audiobook
+ADD COLUMN IF NOT EXISTS work_id BIGINT NULL REFERENCES public.work (id) ON DELETE SET NULL ;
+
+CREATE INDEX IF NOT EXISTS idx_audiobook_work_id
+ON public.audiobook (work_id) ;
+
+-- Set when an edition was split out of its work by hand, so that it isn't grouped again.
+ALTER TABLE public.audiobook
+ADD COLUMN IF NOT EXISTS work_pinned BOOLEAN NOT NULL DEFAULT FALSE ;
+
+-- By default a user is notified once per work. With this set, every new edition is notified.
+ALTER TABLE public.users
+ADD COLUMN IF NOT EXISTS notify_new_editions BOOLEAN NOT NULL DEFAULT FALSE ;
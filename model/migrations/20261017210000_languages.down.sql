@@ -0,0 +1,9 @@
+ALTER TABLE public.user_reader_notification DROP COLUMN IF EXISTS languages;
+ALTER TABLE public.user_author_notification DROP COLUMN IF EXISTS languages;
+ALTER TABLE public.user_category_notification DROP COLUMN IF EXISTS languages;
+ALTER TABLE public.user_keyword_notification DROP COLUMN IF EXISTS languages;
+ALTER TABLE public.user_series_notification DROP COLUMN IF EXISTS languages;
+ALTER TABLE public.users DROP COLUMN IF EXISTS preferred_languages;
+DROP INDEX IF EXISTS public.idx_audiobook_language;
+UPDATE public.audiobook SET language = language_raw WHERE language_raw IS NOT NULL;
+ALTER TABLE public.audiobook DROP COLUMN IF EXISTS language_raw;
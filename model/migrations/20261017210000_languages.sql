@@ -0,0 +1,55 @@
+-- The languages are stored as ISO 639-1 codes, "und" if undetermined. The names below mirror
+-- `entities_lib::Language::parse`, which normalizes the language of the new uploads.
+CREATE TEMPORARY TABLE language_names (name TEXT PRIMARY KEY, code TEXT NOT NULL) ;
+
+INSERT INTO language_names (name, code) VALUES
+('en', 'en'), ('eng', 'en'), ('english', 'en'), ('inglese', 'en'),
+('it', 'it'), ('ita', 'it'), ('italian', 'it'), ('italiano', 'it'),
+('es', 'es'), ('spa', 'es'), ('spanish', 'es'), ('español', 'es'), ('espanol', 'es'),
+('castellano', 'es'),
+('fr', 'fr'), ('fre', 'fr'), ('fra', 'fr'), ('french', 'fr'), ('français', 'fr'),
+('francais', 'fr'),
+('de', 'de'), ('ger', 'de'), ('deu', 'de'), ('german', 'de'), ('deutsch', 'de'),
+('pt', 'pt'), ('por', 'pt'), ('portuguese', 'pt'), ('português', 'pt'), ('portugues', 'pt'),
+('nl', 'nl'), ('dut', 'nl'), ('nld', 'nl'), ('dutch', 'nl'), ('nederlands', 'nl'),
+('flemish', 'nl'),
+('ru', 'ru'), ('rus', 'ru'), ('russian', 'ru'), ('русский', 'ru'),
+('pl', 'pl'), ('pol', 'pl'), ('polish', 'pl'), ('polski', 'pl'),
+('sv', 'sv'), ('swe', 'sv'), ('swedish', 'sv'), ('svenska', 'sv'),
+('ja', 'ja'), ('jpn', 'ja'), ('japanese', 'ja'), ('日本語', 'ja'),
+('zh', 'zh'), ('chi', 'zh'), ('zho', 'zh'), ('chinese', 'zh'), ('mandarin', 'zh'), ('中文', 'zh') ;
+
+-- The language as it was scraped, kept since the languages outside of the mapping become "und".
+ALTER TABLE public.audiobook
+ADD COLUMN IF NOT EXISTS language_raw TEXT NULL ;
+
+UPDATE public.audiobook SET language_raw = language ;
+
+UPDATE public.audiobook ab SET language = COALESCE(
+    (
+        SELECT ln.code FROM language_names ln
+        WHERE ln.name = lower(substring(btrim(ab.language) FROM '[[:alpha:]]+'))
+    ),
+    'und'
+) ;
+
+DROP TABLE language_names ;
+
+CREATE INDEX IF NOT EXISTS idx_audiobook_language
+ON public.audiobook (language) ;
+
+-- The languages a user wants to see, all of them if empty.
+ALTER TABLE public.users
+ADD COLUMN IF NOT EXISTS preferred_languages TEXT [] NOT NULL DEFAULT '{}' ;
+
+-- The languages of the audiobooks a subscription notifies, all of them if empty.
+ALTER TABLE public.user_series_notification
+ADD COLUMN IF NOT EXISTS languages TEXT [] NOT NULL DEFAULT '{}' ;
+ALTER TABLE public.user_keyword_notification
+ADD COLUMN IF NOT EXISTS languages TEXT [] NOT NULL DEFAULT '{}' ;
+ALTER TABLE public.user_category_notification
+ADD COLUMN IF NOT EXISTS languages TEXT [] NOT NULL DEFAULT '{}' ;
+ALTER TABLE public.user_author_notification
+ADD COLUMN IF NOT EXISTS languages TEXT [] NOT NULL DEFAULT '{}' ;
+ALTER TABLE public.user_reader_notification
+ADD COLUMN IF NOT EXISTS languages TEXT [] NOT NULL DEFAULT '{}' ;
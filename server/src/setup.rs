@@ -12,17 +12,22 @@ use axum_session_auth::{AuthConfig, AuthSessionLayer};
 use axum_session_sqlx::SessionPgPool;
 use chrono::Duration as ChronoDuration;
 use entities_lib::entities::meta_request::{MetaRequest, MetaResponse};
-use entities_lib::{AudiobookWithData, Environment, GetAudioBookRequestType};
-use gemini_rust::Gemini;
+use entities_lib::{
+    AudiobookWithData, Environment, GetAudioBookRequestType, LanguageFilter, SearchQuery,
+};
 use leptos::prelude::*;
 use leptos_axum::{LeptosRoutes, generate_route_list};
 use moka::future::Cache;
 use reqwest::{Client, ClientBuilder};
 use shared::db_ops::AppError;
 use shared::db_ops::parade::get_postgres_connection;
+use shared::db_ops::parade::search_ops::RankedSearch;
 use shared::private_args::Args;
 use shared::sql_user::SqlUser;
 use shared::state::AppState;
+use shared::utils::llm::{
+    ContentEmbedder, ContentGenerator, build_content_embedder, build_content_generator,
+};
 use sqlx::PgPool;
 use tower_http::compression::CompressionLayer;
 use tower_http::services::ServeDir;
@@ -56,10 +61,10 @@ async fn init_app_state(args: &Args) -> (AppState, SessionStore<SessionPgPool>)
     let leptos_options = conf.leptos_options;
     let routes = generate_route_list(App);
 
-    let (g, g_emb) = init_gemini_models(args);
+    let (content_generator, embedder) = init_llm_models(args);
     let pg_pool = get_postgres_connection(args).await;
     let session_store = init_session_store(&pg_pool, args).await;
-    let (audiobook_cache, meta_cache) = init_caches(args);
+    let (audiobook_cache, meta_cache, search_cache) = init_caches(args);
     let http_client: Client = ClientBuilder::new().build().unwrap();
 
     let app_state = AppState {
@@ -69,10 +74,12 @@ async fn init_app_state(args: &Args) -> (AppState, SessionStore<SessionPgPool>)
         password_handler: Argon2::default(),
         audiobooks_cache: audiobook_cache,
         meta_requests_cache: meta_cache,
+        search_cache,
         shareable_args: args.shared.clone(),
+        ranking: args.ranking.clone(),
         http_client,
-        embedder: Arc::new(g_emb),
-        content_generator: Arc::new(g),
+        embedder,
+        content_generator,
     };
 
     (app_state, session_store)
@@ -135,25 +142,17 @@ fn create_router(app_state: &AppState, session_store: SessionStore<SessionPgPool
     router
 }
 
-/// Initializes the Gemini models (content generator and embedder).
+/// Initializes the models (content generator and embedder) of the configured LLM provider.
 ///
 /// # Arguments
-/// * `args` - The command line arguments containing API keys and model names.
+/// * `args` - The command line arguments containing the provider, API keys and model names.
 ///
 /// # Returns
-/// A tuple containing the content generator `Gemini` instance and the embedder `Gemini` instance.
-fn init_gemini_models(args: &Args) -> (Gemini, Gemini) {
-    let g = Gemini::with_model(
-        &args.gemini_api_key,
-        args.shared.gemini_extract_html_model_name.clone(),
-    )
-    .unwrap();
-    let g_emb = Gemini::with_model(
-        &args.gemini_api_key,
-        args.shared.gemini_embedding_model_name.clone(),
-    )
-    .unwrap();
-    (g, g_emb)
+/// A tuple containing the content generator and the embedder.
+fn init_llm_models(args: &Args) -> (Arc<dyn ContentGenerator>, Arc<dyn ContentEmbedder>) {
+    let content_generator = build_content_generator(args).unwrap();
+    let embedder = build_content_embedder(args).unwrap();
+    (content_generator, embedder)
 }
 
 /// Initializes the session store using `PostgreSQL`.
@@ -183,13 +182,14 @@ async fn init_session_store(pg_pool: &PgPool, args: &Args) -> SessionStore<Sessi
 /// * `args` - The command line arguments containing cache configuration.
 ///
 /// # Returns
-/// A tuple containing the audiobook cache and the meta requests cache.
+/// A tuple containing the audiobook cache, the meta requests cache and the search cache.
 #[allow(clippy::type_complexity)]
 fn init_caches(
     args: &Args,
 ) -> (
-    Cache<GetAudioBookRequestType, Result<Vec<AudiobookWithData>, AppError>>,
-    Cache<MetaRequest, Result<MetaResponse, AppError>>,
+    Cache<(GetAudioBookRequestType, LanguageFilter), Result<Vec<AudiobookWithData>, AppError>>,
+    Cache<(MetaRequest, LanguageFilter), Result<MetaResponse, AppError>>,
+    Cache<SearchQuery, Arc<RankedSearch>>,
 ) {
     let audiobook_cache = Cache::builder()
         .max_capacity(args.audiobook_cache_max_capacity)
@@ -201,5 +201,10 @@ fn init_caches(
         .time_to_live(Duration::from_secs(args.meta_cache_ttl))
         .build();
 
-    (audiobook_cache, meta_cache)
+    let search_cache = Cache::builder()
+        .max_capacity(args.search_cache_max_capacity)
+        .time_to_live(Duration::from_secs(args.search_cache_ttl))
+        .build();
+
+    (audiobook_cache, meta_cache, search_cache)
 }
@@ -1,7 +1,9 @@
 use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+
 use entities_lib::{
-    AudioBook, AudiobookWithData, Author, Category, GetAudioBookRequestType, Keyword, Reader,
-    Series,
+    AudioBook, AudiobookWithData, Author, Category, Edition, GetAudioBookRequestType,
+    HardcoverRating, Keyword, Language, LanguageFilter, Reader, Series,
 };
 use moka::future::Cache;
 use sqlx::postgres::PgArguments;
@@ -9,61 +11,100 @@ use sqlx::{Arguments, FromRow, PgPool};
 use tracing::instrument;
 
 use crate::db_ops::AppError;
+use crate::db_ops::parade::taxonomy_ops::CATEGORY_TREE_CTE;
 
 /// .
 ///
+/// The listings only contain the audiobooks in `languages`, or in any language if it's empty.
+/// The audiobooks requested by id are returned whatever their language.
+///
 /// # Errors
 ///
 /// This function will return an error if.
 #[instrument(skip_all)]
 pub async fn get_audiobooks_cached(
     db_pool: &PgPool,
-    cache: &Cache<GetAudioBookRequestType, Result<Vec<AudiobookWithData>, AppError>>,
+    cache: &Cache<
+        (GetAudioBookRequestType, LanguageFilter),
+        Result<Vec<AudiobookWithData>, AppError>,
+    >,
     request_type: GetAudioBookRequestType,
+    languages: &[Language],
     limit: u32,
 ) -> Result<Vec<AudiobookWithData>, AppError> {
-    let cache_key = request_type.clone();
+    let cache_key = (request_type.clone(), languages.to_vec());
 
     match request_type {
         GetAudioBookRequestType::MostRecent(page) => {
             cache
                 .get_with(cache_key, async {
-                    get_most_recent_audiobooks_with_data(db_pool, limit, page).await
+                    get_most_recent_audiobooks_with_data(db_pool, languages, limit, page).await
                 })
                 .await
         }
         GetAudioBookRequestType::ByAuthor(author, page) => {
             cache
                 .get_with(cache_key, async {
-                    get_audiobooks_with_data_by_author(db_pool, author, limit, page).await
+                    get_audiobooks_with_data_by_author(db_pool, author, languages, limit, page)
+                        .await
                 })
                 .await
         }
         GetAudioBookRequestType::ByReader(reader, page) => {
             cache
                 .get_with(cache_key, async {
-                    get_audiobooks_with_data_by_reader(db_pool, reader, limit, page).await
+                    get_audiobooks_with_data_by_reader(db_pool, reader, languages, limit, page)
+                        .await
                 })
                 .await
         }
         GetAudioBookRequestType::ByCategory(category, page) => {
             cache
                 .get_with(cache_key, async {
-                    get_audiobooks_with_data_by_category(db_pool, category, limit, page).await
+                    get_audiobooks_with_data_by_category(db_pool, category, languages, limit, page)
+                        .await
+                })
+                .await
+        }
+        GetAudioBookRequestType::ByCategoryTree(category, page) => {
+            cache
+                .get_with(cache_key, async {
+                    get_audiobooks_with_data_by_category_tree(
+                        db_pool, category, languages, limit, page,
+                    )
+                    .await
                 })
                 .await
         }
         GetAudioBookRequestType::ByKeyword(keyword, page) => {
             cache
                 .get_with(cache_key, async {
-                    get_audiobooks_with_data_by_keyword(db_pool, keyword, limit, page).await
+                    get_audiobooks_with_data_by_keyword(db_pool, keyword, languages, limit, page)
+                        .await
                 })
                 .await
         }
         GetAudioBookRequestType::BySeries(series, page) => {
             cache
                 .get_with(cache_key, async {
-                    get_audiobooks_with_data_by_series(db_pool, series, limit, page).await
+                    get_audiobooks_with_data_by_series(db_pool, series, languages, limit, page)
+                        .await
+                })
+                .await
+        }
+        GetAudioBookRequestType::TopRated(min_ratings, page) => {
+            cache
+                .get_with(cache_key, async {
+                    get_top_rated_audiobooks_with_data(db_pool, min_ratings, languages, limit, page)
+                        .await
+                })
+                .await
+        }
+        GetAudioBookRequestType::SimilarTo(audiobook_id, page) => {
+            cache
+                .get_with(cache_key, async {
+                    get_similar_audiobooks_with_data(db_pool, audiobook_id, languages, limit, page)
+                        .await
                 })
                 .await
         }
@@ -94,7 +135,7 @@ const BASE_AUDIOBOOK_QUERY: &str = "
 SELECT
     ab.id, ab.title, ab.bitrate, ab.cover_url, ab.description, ab.very_short_description,
     ab.description_for_embeddings, ab.file_size, ab.format, ab.language, ab.path,
-    ab.timestamp_ingested, ab.unabridged,
+    ab.timestamp_ingested, ab.unabridged, ab.runtime_seconds, ab.series_volume, ab.work_id,
     COALESCE(array_agg(DISTINCT aut.id) FILTER (WHERE aut.name IS NOT NULL), '{}') AS authors_ids,
     COALESCE(array_agg(DISTINCT aut.name) FILTER (WHERE aut.name IS NOT NULL), '{}') AS authors,
     COALESCE(array_agg(DISTINCT cat.id) FILTER (WHERE cat.name IS NOT NULL), '{}') AS categories_ids,
@@ -104,7 +145,9 @@ SELECT
     COALESCE(array_agg(DISTINCT rdr.id) FILTER (WHERE rdr.name IS NOT NULL), '{}') AS readers_ids,
     COALESCE(array_agg(DISTINCT rdr.name) FILTER (WHERE rdr.name IS NOT NULL), '{}') AS readers,
     ser.id AS series_id,
-    ser.title as series_title
+    ser.title as series_title,
+    (hc.metadata ->> 'rating')::DOUBLE PRECISION AS hardcover_rating,
+    (hc.metadata ->> 'ratings_count')::BIGINT AS hardcover_ratings_count
 FROM audiobook ab
 LEFT JOIN audiobook_author aba ON ab.id = aba.audiobook_id
 LEFT JOIN author aut ON aba.author_id = aut.id
@@ -115,9 +158,40 @@ LEFT JOIN keyword key ON abk.keyword_id = key.id
 LEFT JOIN audiobook_reader abr ON ab.id = abr.audiobook_id
 LEFT JOIN reader rdr ON abr.reader_id = rdr.id
 LEFT JOIN series ser ON ab.series_id = ser.id
+LEFT JOIN hardcover_audiobook_metadata hc ON ab.id = hc.audiobook_id
 ";
 
-const GROUP_BY_AUDIOBOOK: &str = "GROUP BY ab.id, ser.title, ser.id";
+const GROUP_BY_AUDIOBOOK: &str = "GROUP BY ab.id, ser.title, ser.id, hc.id";
+
+/// Identifies the work of the audiobook `ab`, or the audiobook itself if it isn't grouped in a
+/// work yet. The listings show a single edition for each value.
+pub(crate) const WORK_OF_AUDIOBOOK: &str = "COALESCE(ab.work_id, -ab.id)";
+
+/// Condition on `hardcover_audiobook_metadata` selecting the audiobooks that can be listed as top
+/// rated, i.e. rated at least `$1` times.
+pub(crate) const TOP_RATED_CONDITION: &str = "(metadata ->> 'rating') IS NOT NULL
+    AND COALESCE((metadata ->> 'ratings_count')::BIGINT, 0) >= $1";
+
+/// Condition on the audiobook `ab` keeping the languages whose codes are bound to the parameter
+/// `$param`, or all the languages if there are none.
+pub(crate) fn language_condition(param: usize) -> String {
+    format!("(cardinality(${param}::TEXT[]) = 0 OR ab.language = ANY(${param}::TEXT[]))")
+}
+
+/// The codes of the languages, to be bound to a `language_condition`.
+pub(crate) fn language_codes(languages: &[Language]) -> Vec<&'static str> {
+    languages.iter().map(|language| language.code()).collect()
+}
+
+/// Subtracted from the cosine distance of the similar audiobooks sharing an author with the book.
+const SIMILAR_SHARED_AUTHOR_BOOST: f64 = 0.05;
+
+/// Subtracted from the cosine distance of the similar audiobooks in the same series as the book.
+const SIMILAR_SHARED_SERIES_BOOST: f64 = 0.05;
+
+/// Minimum number of nearest neighbors fetched through the HNSW index before the editions of the
+/// book are excluded and the boosts applied.
+const MIN_SIMILAR_CANDIDATES: i64 = 100;
 
 #[derive(FromRow)]
 struct FullAudiobookRow {
@@ -134,6 +208,9 @@ struct FullAudiobookRow {
     path: String,
     timestamp_ingested: DateTime<Utc>,
     unabridged: Option<bool>,
+    runtime_seconds: Option<i32>,
+    series_volume: Option<f64>,
+    work_id: Option<i64>,
     authors_ids: Vec<i64>,
     authors: Vec<String>,
     categories_ids: Vec<i64>,
@@ -144,6 +221,8 @@ struct FullAudiobookRow {
     readers: Vec<String>,
     series_id: Option<i64>,
     series_title: Option<String>,
+    hardcover_rating: Option<f64>,
+    hardcover_ratings_count: Option<i64>,
 }
 
 fn map_row_to_audiobook_with_data(row: FullAudiobookRow) -> AudiobookWithData {
@@ -163,7 +242,10 @@ fn map_row_to_audiobook_with_data(row: FullAudiobookRow) -> AudiobookWithData {
         path: row.path,
         last_upload: row.timestamp_ingested.timestamp(),
         unabriged: row.unabridged.unwrap_or(false),
-        series_volume: None,
+        series_volume: row.series_volume.map(|volume| volume.to_string()),
+        runtime_seconds: row.runtime_seconds,
+        work_id: row.work_id,
+        editions: Vec::new(),
     };
 
     let authors = row
@@ -209,7 +291,20 @@ fn map_row_to_audiobook_with_data(row: FullAudiobookRow) -> AudiobookWithData {
         _ => None,
     };
 
-    (audiobook, authors, categories, keywords, readers, series)
+    let hardcover_rating = row.hardcover_rating.map(|rating| HardcoverRating {
+        rating,
+        ratings_count: row.hardcover_ratings_count.unwrap_or(0),
+    });
+
+    (
+        audiobook,
+        authors,
+        categories,
+        keywords,
+        readers,
+        series,
+        hardcover_rating,
+    )
 }
 
 async fn execute_query(
@@ -223,10 +318,79 @@ async fn execute_query(
         .await
         .map_err(|e| AppError::DeserializationError(format!("Database query failed: {e}")))?;
 
-    Ok(rows
+    let mut audiobooks: Vec<AudiobookWithData> = rows
         .into_iter()
         .map(map_row_to_audiobook_with_data)
-        .collect())
+        .collect();
+    attach_editions(db_pool, &mut audiobooks).await?;
+    Ok(audiobooks)
+}
+
+#[derive(FromRow)]
+struct EditionRow {
+    audiobook_id: i64,
+    work_id: i64,
+    readers: Vec<String>,
+    format: String,
+    bitrate: Option<i32>,
+    language: String,
+    timestamp_ingested: DateTime<Utc>,
+}
+
+/// Fills the editions of the works of the audiobooks, for the edition picker.
+async fn attach_editions(
+    db_pool: &PgPool,
+    audiobooks: &mut [AudiobookWithData],
+) -> Result<(), AppError> {
+    let work_ids: Vec<i64> = audiobooks
+        .iter()
+        .filter_map(|(audiobook, ..)| audiobook.work_id)
+        .collect();
+    if work_ids.is_empty() {
+        return Ok(());
+    }
+    let rows = sqlx::query_as::<_, EditionRow>(
+        r"
+        SELECT
+            ab.id AS audiobook_id, ab.work_id, ab.format, ab.bitrate, ab.language,
+            ab.timestamp_ingested,
+            COALESCE(array_agg(rdr.name ORDER BY rdr.name) FILTER (WHERE rdr.name IS NOT NULL), '{}') AS readers
+        FROM audiobook ab
+        LEFT JOIN audiobook_reader abr ON ab.id = abr.audiobook_id
+        LEFT JOIN reader rdr ON abr.reader_id = rdr.id
+        WHERE ab.work_id = ANY($1)
+        GROUP BY ab.id
+        ORDER BY ab.timestamp_ingested DESC, ab.id DESC
+        ",
+    )
+    .bind(&work_ids)
+    .fetch_all(db_pool)
+    .await
+    .map_err(|e| AppError::DeserializationError(format!("Database query failed: {e}")))?;
+
+    let mut editions_by_work: HashMap<i64, Vec<Edition>> = HashMap::new();
+    for row in rows {
+        editions_by_work
+            .entry(row.work_id)
+            .or_default()
+            .push(Edition {
+                audiobook_id: row.audiobook_id,
+                readers: row.readers,
+                format: Some(row.format),
+                bitrate: row.bitrate.map(|b| b.to_string()),
+                language: row.language,
+                last_upload: row.timestamp_ingested.timestamp(),
+            });
+    }
+    for (audiobook, ..) in audiobooks.iter_mut() {
+        if let Some(editions) = audiobook
+            .work_id
+            .and_then(|work_id| editions_by_work.get(&work_id))
+        {
+            audiobook.editions.clone_from(editions);
+        }
+    }
+    Ok(())
 }
 
 #[instrument(skip_all)]
@@ -270,22 +434,29 @@ async fn get_audiobook_by_id(
     get_audiobooks_by_ids(db_pool, vec![id]).await
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn get_paginated_audiobooks_by_relation_using_ids(
     db_pool: &PgPool,
     relation_table: &str,
     relation_column: &str,
     relation_value: i64,
     join_table: &str,
+    languages: &[Language],
     limit: i64,
     offset: i64,
 ) -> Result<Vec<AudiobookWithData>, AppError> {
+    let language_condition = language_condition(4);
     let query_str = format!(
         "WITH filtered_ab AS (
-            SELECT ab.id FROM audiobook ab
-            JOIN {join_table} j ON ab.id = j.audiobook_id
-            JOIN {relation_table} r ON j.{relation_table}_id = r.id
-            WHERE r.{relation_column} = $1
-            ORDER BY ab.timestamp_ingested DESC
+            SELECT id FROM (
+                SELECT DISTINCT ON ({WORK_OF_AUDIOBOOK}) ab.id, ab.timestamp_ingested
+                FROM audiobook ab
+                JOIN {join_table} j ON ab.id = j.audiobook_id
+                JOIN {relation_table} r ON j.{relation_table}_id = r.id
+                WHERE r.{relation_column} = $1 AND {language_condition}
+                ORDER BY {WORK_OF_AUDIOBOOK}, ab.timestamp_ingested DESC
+            ) latest_editions
+            ORDER BY timestamp_ingested DESC
             LIMIT $2 OFFSET $3
         )
         {BASE_AUDIOBOOK_QUERY}
@@ -298,6 +469,7 @@ async fn get_paginated_audiobooks_by_relation_using_ids(
     let _ = args.add(relation_value);
     let _ = args.add(limit);
     let _ = args.add(offset);
+    let _ = args.add(language_codes(languages));
     execute_query(db_pool, &query_str, args).await
 }
 
@@ -305,6 +477,7 @@ async fn get_paginated_audiobooks_by_relation_using_ids(
 async fn get_audiobooks_with_data_by_category(
     db_pool: &PgPool,
     category: Category,
+    languages: &[Language],
     limit: u32,
     page: u32,
 ) -> Result<Vec<AudiobookWithData>, AppError> {
@@ -316,16 +489,61 @@ async fn get_audiobooks_with_data_by_category(
         "id",
         category.id,
         "audiobook_category",
+        languages,
         limit,
         offset,
     )
     .await
 }
 
+#[instrument(skip_all)]
+async fn get_audiobooks_with_data_by_category_tree(
+    db_pool: &PgPool,
+    category: Category,
+    languages: &[Language],
+    limit: u32,
+    page: u32,
+) -> Result<Vec<AudiobookWithData>, AppError> {
+    let limit = i64::from(limit);
+    let offset = i64::from(page.saturating_sub(1)) * limit;
+    let language_condition = language_condition(4);
+    let query_str = format!(
+        "WITH {CATEGORY_TREE_CTE}, filtered_ab AS (
+            SELECT id FROM (
+                SELECT DISTINCT ON ({WORK_OF_AUDIOBOOK}) ab.id, ab.timestamp_ingested
+                FROM audiobook ab
+                WHERE EXISTS (
+                    SELECT 1 FROM audiobook_category abc
+                    WHERE abc.audiobook_id = ab.id AND (
+                        abc.category_id = $1
+                        OR abc.category_id IN (SELECT category_id FROM category_tree WHERE root_id = $1)
+                    )
+                )
+                AND {language_condition}
+                ORDER BY {WORK_OF_AUDIOBOOK}, ab.timestamp_ingested DESC
+            ) latest_editions
+            ORDER BY timestamp_ingested DESC
+            LIMIT $2 OFFSET $3
+        )
+        {BASE_AUDIOBOOK_QUERY}
+        JOIN filtered_ab ON ab.id = filtered_ab.id
+        {GROUP_BY_AUDIOBOOK}
+        ORDER BY ab.timestamp_ingested DESC"
+    );
+
+    let mut args = PgArguments::default();
+    let _ = args.add(category.id);
+    let _ = args.add(limit);
+    let _ = args.add(offset);
+    let _ = args.add(language_codes(languages));
+    execute_query(db_pool, &query_str, args).await
+}
+
 #[instrument(skip_all)]
 async fn get_audiobooks_with_data_by_keyword(
     db_pool: &PgPool,
     keyword: Keyword,
+    languages: &[Language],
     limit: u32,
     page: u32,
 ) -> Result<Vec<AudiobookWithData>, AppError> {
@@ -337,6 +555,7 @@ async fn get_audiobooks_with_data_by_keyword(
         "id",
         keyword.id,
         "audiobook_keyword",
+        languages,
         limit,
         offset,
     )
@@ -347,29 +566,35 @@ async fn get_audiobooks_with_data_by_keyword(
 async fn get_audiobooks_with_data_by_series(
     db_pool: &PgPool,
     series: Series,
+    languages: &[Language],
     limit: u32,
     page: u32,
 ) -> Result<Vec<AudiobookWithData>, AppError> {
     let limit = i64::from(limit);
     let offset = i64::from(page.saturating_sub(1)) * limit;
+    let language_condition = language_condition(4);
     let query_str = format!(
         "WITH filtered_ab AS (
-            SELECT ab.id FROM audiobook ab
-            JOIN series s ON ab.series_id = s.id
-            WHERE s.id = $1
-            ORDER BY ab.timestamp_ingested DESC
+            SELECT id FROM (
+                SELECT DISTINCT ON ({WORK_OF_AUDIOBOOK}) ab.id, ab.series_volume, ab.timestamp_ingested
+                FROM audiobook ab
+                WHERE ab.series_id = $1 AND {language_condition}
+                ORDER BY {WORK_OF_AUDIOBOOK}, ab.timestamp_ingested DESC
+            ) latest_editions
+            ORDER BY series_volume ASC NULLS LAST, timestamp_ingested ASC
             LIMIT $2 OFFSET $3
         )
         {BASE_AUDIOBOOK_QUERY}
         JOIN filtered_ab ON ab.id = filtered_ab.id
         {GROUP_BY_AUDIOBOOK}
-        ORDER BY ab.timestamp_ingested DESC"
+        ORDER BY ab.series_volume ASC NULLS LAST, ab.timestamp_ingested ASC"
     );
 
     let mut args = PgArguments::default();
     let _ = args.add(series.id);
     let _ = args.add(limit);
     let _ = args.add(offset);
+    let _ = args.add(language_codes(languages));
     execute_query(db_pool, &query_str, args).await
 }
 
@@ -377,6 +602,7 @@ async fn get_audiobooks_with_data_by_series(
 async fn get_audiobooks_with_data_by_reader(
     db_pool: &PgPool,
     reader: Reader,
+    languages: &[Language],
     limit: u32,
     page: u32,
 ) -> Result<Vec<AudiobookWithData>, AppError> {
@@ -388,6 +614,7 @@ async fn get_audiobooks_with_data_by_reader(
         "id",
         reader.id,
         "audiobook_reader",
+        languages,
         limit,
         offset,
     )
@@ -398,6 +625,7 @@ async fn get_audiobooks_with_data_by_reader(
 async fn get_audiobooks_with_data_by_author(
     db_pool: &PgPool,
     author: Author,
+    languages: &[Language],
     limit: u32,
     page: u32,
 ) -> Result<Vec<AudiobookWithData>, AppError> {
@@ -409,6 +637,7 @@ async fn get_audiobooks_with_data_by_author(
         "id",
         author.id,
         "audiobook_author",
+        languages,
         limit,
         offset,
     )
@@ -418,20 +647,257 @@ async fn get_audiobooks_with_data_by_author(
 #[instrument(skip_all)]
 async fn get_most_recent_audiobooks_with_data(
     db_pool: &PgPool,
+    languages: &[Language],
+    limit: u32,
+    page: u32,
+) -> Result<Vec<AudiobookWithData>, AppError> {
+    let limit = i64::from(limit);
+    let offset = i64::from(page.saturating_sub(1)) * limit;
+    let language_condition = language_condition(3);
+    let query_str = format!(
+        "WITH filtered_ab AS (
+            SELECT id FROM (
+                SELECT DISTINCT ON ({WORK_OF_AUDIOBOOK}) ab.id, ab.timestamp_ingested
+                FROM audiobook ab
+                WHERE {language_condition}
+                ORDER BY {WORK_OF_AUDIOBOOK}, ab.timestamp_ingested DESC
+            ) latest_editions
+            ORDER BY timestamp_ingested DESC
+            LIMIT $1 OFFSET $2
+        )
+        {BASE_AUDIOBOOK_QUERY}
+        JOIN filtered_ab ON ab.id = filtered_ab.id
+        {GROUP_BY_AUDIOBOOK}
+        ORDER BY ab.timestamp_ingested DESC"
+    );
+
+    let mut args = PgArguments::default();
+    let _ = args.add(limit);
+    let _ = args.add(offset);
+    let _ = args.add(language_codes(languages));
+    execute_query(db_pool, &query_str, args).await
+}
+
+#[instrument(skip_all)]
+async fn get_top_rated_audiobooks_with_data(
+    db_pool: &PgPool,
+    min_ratings: u32,
+    languages: &[Language],
     limit: u32,
     page: u32,
 ) -> Result<Vec<AudiobookWithData>, AppError> {
     let limit = i64::from(limit);
     let offset = i64::from(page.saturating_sub(1)) * limit;
+    let language_condition = language_condition(4);
     let query_str = format!(
-        "{BASE_AUDIOBOOK_QUERY}
+        "WITH filtered_ab AS (
+            SELECT id FROM (
+                SELECT DISTINCT ON ({WORK_OF_AUDIOBOOK}) ab.id,
+                    (hc.metadata ->> 'rating')::DOUBLE PRECISION AS rating,
+                    (hc.metadata ->> 'ratings_count')::BIGINT AS ratings_count
+                FROM hardcover_audiobook_metadata hc
+                JOIN audiobook ab ON ab.id = hc.audiobook_id
+                WHERE {TOP_RATED_CONDITION} AND {language_condition}
+                ORDER BY {WORK_OF_AUDIOBOOK}, ratings_count DESC, ab.id DESC
+            ) rated_editions
+            ORDER BY rating DESC, ratings_count DESC, id DESC
+            LIMIT $2 OFFSET $3
+        )
+        {BASE_AUDIOBOOK_QUERY}
+        JOIN filtered_ab ON ab.id = filtered_ab.id
         {GROUP_BY_AUDIOBOOK}
-        ORDER BY ab.timestamp_ingested DESC
-        LIMIT $1 OFFSET $2"
+        ORDER BY hardcover_rating DESC, hardcover_ratings_count DESC, ab.id DESC"
     );
 
     let mut args = PgArguments::default();
+    let _ = args.add(i64::from(min_ratings));
     let _ = args.add(limit);
     let _ = args.add(offset);
+    let _ = args.add(language_codes(languages));
     execute_query(db_pool, &query_str, args).await
 }
+
+/// Returns the audiobooks closest to `audiobook_id` by description embedding, one edition per
+/// work, skipping the editions of the book itself. Sharing an author or the series with the book
+/// brings an audiobook closer.
+#[instrument(skip_all)]
+async fn get_similar_audiobooks_with_data(
+    db_pool: &PgPool,
+    audiobook_id: i64,
+    languages: &[Language],
+    limit: u32,
+    page: u32,
+) -> Result<Vec<AudiobookWithData>, AppError> {
+    let limit = i64::from(limit);
+    let offset = i64::from(page.saturating_sub(1)) * limit;
+    let candidates = ((offset + limit) * 4).max(MIN_SIMILAR_CANDIDATES);
+    let language_condition = language_condition(4);
+    // The nearest neighbors are found first, so that the ordering can use the HNSW index.
+    let query_str = format!(
+        "WITH candidates AS (
+            SELECT ab.id
+            FROM audiobook ab
+            WHERE ab.optimized_description_embedding IS NOT NULL
+            ORDER BY ab.optimized_description_embedding <=> (
+                SELECT optimized_description_embedding FROM audiobook WHERE id = $1
+            )
+            LIMIT $5
+        )
+        SELECT id FROM (
+            SELECT DISTINCT ON ({WORK_OF_AUDIOBOOK}) ab.id,
+                (ab.optimized_description_embedding <=> book.optimized_description_embedding)
+                - CASE WHEN EXISTS (
+                    SELECT 1
+                    FROM audiobook_author a
+                    JOIN audiobook_author b ON b.author_id = a.author_id
+                    WHERE a.audiobook_id = ab.id AND b.audiobook_id = book.id
+                ) THEN {SIMILAR_SHARED_AUTHOR_BOOST} ELSE 0 END
+                - CASE WHEN ab.series_id = book.series_id
+                    THEN {SIMILAR_SHARED_SERIES_BOOST} ELSE 0 END AS distance
+            FROM candidates c
+            JOIN audiobook ab ON ab.id = c.id
+            JOIN audiobook book ON book.id = $1
+            WHERE ab.id <> book.id
+                AND (book.work_id IS NULL OR ab.work_id IS DISTINCT FROM book.work_id)
+                AND lower(ab.title) <> lower(book.title)
+                AND {language_condition}
+            ORDER BY {WORK_OF_AUDIOBOOK}, distance ASC
+        ) similar_works
+        ORDER BY distance ASC, id ASC
+        LIMIT $2 OFFSET $3"
+    );
+
+    let ids: Vec<i64> = sqlx::query_scalar(&query_str)
+        .bind(audiobook_id)
+        .bind(limit)
+        .bind(offset)
+        .bind(language_codes(languages))
+        .bind(candidates)
+        .fetch_all(db_pool)
+        .await
+        .map_err(|e| AppError::DeserializationError(format!("Database query failed: {e}")))?;
+    get_audiobooks_by_ids(db_pool, ids.iter().map(ToString::to_string).collect()).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An embedding at the cosine distance `distance` from the first axis.
+    fn embedding(distance: f32) -> Vec<f32> {
+        let mut embedding = vec![0.0; 768];
+        embedding[0] = 1.0 - distance;
+        embedding[1] = (1.0 - (1.0 - distance).powi(2)).sqrt();
+        embedding
+    }
+
+    async fn insert_audiobook(
+        pool: &PgPool,
+        title: &str,
+        distance: f32,
+        work_id: Option<i64>,
+        series_id: Option<i64>,
+    ) -> i64 {
+        sqlx::query_scalar(
+            r"
+            INSERT INTO audiobook (
+                title, cover_url, description, description_for_embeddings, format, language, path,
+                timestamp_created, timestamp_ingested, very_short_description,
+                optimized_description_embedding, work_id, series_id
+            )
+            VALUES (
+                $1, '', '', '', 'MP3', 'en', gen_random_uuid()::TEXT, NOW(), NOW(), '', $2::REAL[],
+                $3, $4
+            )
+            RETURNING id
+            ",
+        )
+        .bind(title)
+        .bind(embedding(distance))
+        .bind(work_id)
+        .bind(series_id)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+    }
+
+    async fn insert_returning_id(pool: &PgPool, query: &str, value: &str) -> i64 {
+        sqlx::query_scalar(query)
+            .bind(value)
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    async fn add_author(pool: &PgPool, audiobook_id: i64, author_id: i64) {
+        sqlx::query("INSERT INTO audiobook_author (audiobook_id, author_id) VALUES ($1, $2)")
+            .bind(audiobook_id)
+            .bind(author_id)
+            .execute(pool)
+            .await
+            .unwrap();
+    }
+
+    async fn similar_titles(pool: &PgPool, audiobook_id: i64) -> Vec<String> {
+        get_similar_audiobooks_with_data(pool, audiobook_id, &[], 10, 1)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|(audiobook, ..)| audiobook.title)
+            .collect()
+    }
+
+    #[sqlx::test(migrations = "../model/migrations")]
+    async fn test_similar_audiobooks_skip_the_editions_of_the_book(pool: PgPool) {
+        let work_id = insert_returning_id(
+            &pool,
+            "INSERT INTO work (title_key) VALUES ($1) RETURNING id",
+            "the way of kings",
+        )
+        .await;
+        let book = insert_audiobook(&pool, "The Way of Kings", 0.0, Some(work_id), None).await;
+        insert_audiobook(
+            &pool,
+            "The Way of Kings (Unabridged)",
+            0.01,
+            Some(work_id),
+            None,
+        )
+        .await;
+        insert_audiobook(&pool, "THE WAY OF KINGS", 0.02, None, None).await;
+        insert_audiobook(&pool, "Elantris", 0.3, None, None).await;
+        insert_audiobook(&pool, "Warbreaker", 0.2, None, None).await;
+
+        assert_eq!(
+            similar_titles(&pool, book).await,
+            ["Warbreaker", "Elantris"]
+        );
+    }
+
+    #[sqlx::test(migrations = "../model/migrations")]
+    async fn test_similar_audiobooks_sharing_an_author_or_the_series_come_first(pool: PgPool) {
+        let series_id = insert_returning_id(
+            &pool,
+            "INSERT INTO series (title) VALUES ($1) RETURNING id",
+            "The Stormlight Archive",
+        )
+        .await;
+        let author_id = insert_returning_id(
+            &pool,
+            "INSERT INTO author (name) VALUES ($1) RETURNING id",
+            "Brandon Sanderson",
+        )
+        .await;
+        let book = insert_audiobook(&pool, "The Way of Kings", 0.0, None, Some(series_id)).await;
+        add_author(&pool, book, author_id).await;
+        insert_audiobook(&pool, "The Name of the Wind", 0.03, None, None).await;
+        let by_the_author = insert_audiobook(&pool, "Elantris", 0.06, None, None).await;
+        add_author(&pool, by_the_author, author_id).await;
+        insert_audiobook(&pool, "Words of Radiance", 0.07, None, Some(series_id)).await;
+
+        assert_eq!(
+            similar_titles(&pool, book).await,
+            ["Elantris", "Words of Radiance", "The Name of the Wind"]
+        );
+    }
+}
@@ -0,0 +1,171 @@
+//! Persisted progress of the backfill runs.
+//!
+//! Submission pages are backfilled from the highest to the lowest, and a run is checkpointed after
+//! every page, so an interrupted run can be resumed from the first page it didn't complete.
+
+use std::ops::Range;
+
+use chrono::{DateTime, Utc};
+use sqlx::{FromRow, PgPool};
+use tracing::instrument;
+
+use crate::db_ops::AppError;
+
+/// A backfill of the submission pages in `[page_start, page_end)`.
+#[derive(Debug, Clone, FromRow)]
+pub struct BackfillRun {
+    pub id: i64,
+    pub page_start: i32,
+    pub page_end: i32,
+    /// The lowest page completed so far, `None` until the first page is completed.
+    pub last_completed_page: Option<i32>,
+    pub enqueued_count: i64,
+    pub skipped_count: i64,
+    pub failed_count: i64,
+    pub started_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+impl BackfillRun {
+    /// The pages that still have to be backfilled. They are meant to be processed in reverse.
+    #[must_use]
+    pub fn remaining_pages(&self) -> Range<i32> {
+        self.page_start..self.last_completed_page.unwrap_or(self.page_end)
+    }
+}
+
+/// Items processed while backfilling one page.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BackfillCounts {
+    /// Audiobook pages added to the queue.
+    pub enqueued: i64,
+    /// Audiobooks that were already ingested.
+    pub skipped: i64,
+    /// Tasks that were moved to the dead-letter queue.
+    pub failed: i64,
+}
+
+/// Creates a new backfill run of the pages in `[page_start, page_end)`.
+///
+/// # Errors
+/// If the insert fails.
+#[instrument(skip(pool))]
+pub async fn create_backfill_run(
+    pool: &PgPool,
+    page_start: i32,
+    page_end: i32,
+) -> Result<BackfillRun, AppError> {
+    sqlx::query_as::<_, BackfillRun>(
+        "INSERT INTO backfill_run (page_start, page_end) VALUES ($1, $2) RETURNING *",
+    )
+    .bind(page_start)
+    .bind(page_end)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| AppError::GenericError(e.to_string()))
+}
+
+/// Returns the backfill run with the given id.
+///
+/// # Errors
+/// - `AppError::NotFound` if there is no such run.
+/// - If the query fails.
+#[instrument(skip(pool))]
+pub async fn get_backfill_run(pool: &PgPool, run_id: i64) -> Result<BackfillRun, AppError> {
+    sqlx::query_as::<_, BackfillRun>("SELECT * FROM backfill_run WHERE id = $1")
+        .bind(run_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| AppError::GenericError(e.to_string()))?
+        .ok_or_else(|| AppError::NotFound(format!("Backfill run {run_id}")))
+}
+
+/// Returns the most recent backfill runs.
+///
+/// # Errors
+/// If the query fails.
+#[instrument(skip(pool))]
+pub async fn list_backfill_runs(pool: &PgPool, limit: i64) -> Result<Vec<BackfillRun>, AppError> {
+    sqlx::query_as::<_, BackfillRun>("SELECT * FROM backfill_run ORDER BY id DESC LIMIT $1")
+        .bind(limit)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| AppError::GenericError(e.to_string()))
+}
+
+/// Checkpoints `page` as completed and adds `counts` to the totals of the run.
+///
+/// # Errors
+/// If the update fails.
+#[instrument(skip(pool))]
+pub async fn record_backfill_page(
+    pool: &PgPool,
+    run_id: i64,
+    page: i32,
+    counts: BackfillCounts,
+) -> Result<(), AppError> {
+    sqlx::query(
+        r"
+        UPDATE backfill_run SET
+            last_completed_page = $2,
+            enqueued_count = enqueued_count + $3,
+            skipped_count = skipped_count + $4,
+            failed_count = failed_count + $5,
+            updated_at = CURRENT_TIMESTAMP
+        WHERE id = $1
+        ",
+    )
+    .bind(run_id)
+    .bind(page)
+    .bind(counts.enqueued)
+    .bind(counts.skipped)
+    .bind(counts.failed)
+    .execute(pool)
+    .await
+    .map_err(|e| AppError::GenericError(e.to_string()))?;
+    Ok(())
+}
+
+/// Marks the run as completed.
+///
+/// # Errors
+/// If the update fails.
+#[instrument(skip(pool))]
+pub async fn complete_backfill_run(pool: &PgPool, run_id: i64) -> Result<(), AppError> {
+    sqlx::query(
+        "UPDATE backfill_run SET completed_at = CURRENT_TIMESTAMP, updated_at = CURRENT_TIMESTAMP WHERE id = $1",
+    )
+    .bind(run_id)
+    .execute(pool)
+    .await
+    .map_err(|e| AppError::GenericError(e.to_string()))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(last_completed_page: Option<i32>) -> BackfillRun {
+        BackfillRun {
+            id: 1,
+            page_start: 1,
+            page_end: 50,
+            last_completed_page,
+            enqueued_count: 0,
+            skipped_count: 0,
+            failed_count: 0,
+            started_at: Utc::now(),
+            updated_at: Utc::now(),
+            completed_at: None,
+        }
+    }
+
+    #[test]
+    fn test_remaining_pages() {
+        assert_eq!(run(None).remaining_pages(), 1..50);
+        assert_eq!(run(Some(27)).remaining_pages(), 1..27);
+        assert!(run(Some(1)).remaining_pages().is_empty());
+    }
+}
@@ -0,0 +1,103 @@
+//! Bibliographic metadata of the audiobooks, merged from the metadata providers.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
+use sqlx::{FromRow, PgPool, Postgres, Transaction};
+use tracing::instrument;
+
+use crate::db_ops::AppError;
+
+/// Bibliographic metadata of a book, as described by one provider or merged from several.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BookMetadata {
+    pub isbn: Option<String>,
+    pub publication_year: Option<i32>,
+    pub subjects: Vec<String>,
+    pub page_count: Option<i32>,
+}
+
+/// Metadata merged from the providers, with the provider every field was taken from.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MergedBookMetadata {
+    pub metadata: BookMetadata,
+    /// Field name to provider name, only for the fields that have a value.
+    pub sources: BTreeMap<String, String>,
+}
+
+#[derive(FromRow)]
+struct BookMetadataRow {
+    isbn: Option<String>,
+    publication_year: Option<i32>,
+    subjects: Vec<String>,
+    page_count: Option<i32>,
+    sources: Json<BTreeMap<String, String>>,
+}
+
+/// Stores the merged metadata of the audiobook, replacing the existing one.
+///
+/// # Errors
+/// If the upsert fails.
+pub async fn upsert_book_metadata(
+    tx: &mut Transaction<'_, Postgres>,
+    audiobook_id: i64,
+    merged: &MergedBookMetadata,
+) -> Result<(), AppError> {
+    let metadata = &merged.metadata;
+    sqlx::query(
+        r"
+        INSERT INTO audiobook_book_metadata
+            (audiobook_id, isbn, publication_year, subjects, page_count, sources)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        ON CONFLICT (audiobook_id) DO UPDATE SET
+            isbn = EXCLUDED.isbn,
+            publication_year = EXCLUDED.publication_year,
+            subjects = EXCLUDED.subjects,
+            page_count = EXCLUDED.page_count,
+            sources = EXCLUDED.sources,
+            updated_at = CURRENT_TIMESTAMP
+        ",
+    )
+    .bind(audiobook_id)
+    .bind(&metadata.isbn)
+    .bind(metadata.publication_year)
+    .bind(&metadata.subjects)
+    .bind(metadata.page_count)
+    .bind(Json(&merged.sources))
+    .execute(&mut **tx)
+    .await
+    .map_err(|e| AppError::GenericError(e.to_string()))?;
+    Ok(())
+}
+
+/// Returns the merged metadata of the audiobook, if any.
+///
+/// # Errors
+/// If the query fails.
+#[instrument(skip(pool))]
+pub async fn get_book_metadata(
+    pool: &PgPool,
+    audiobook_id: i64,
+) -> Result<Option<MergedBookMetadata>, AppError> {
+    let row = sqlx::query_as::<_, BookMetadataRow>(
+        r"
+        SELECT isbn, publication_year, subjects, page_count, sources
+        FROM audiobook_book_metadata
+        WHERE audiobook_id = $1
+        ",
+    )
+    .bind(audiobook_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| AppError::GenericError(e.to_string()))?;
+    Ok(row.map(|row| MergedBookMetadata {
+        metadata: BookMetadata {
+            isbn: row.isbn,
+            publication_year: row.publication_year,
+            subjects: row.subjects,
+            page_count: row.page_count,
+        },
+        sources: row.sources.0,
+    }))
+}
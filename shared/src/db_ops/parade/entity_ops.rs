@@ -0,0 +1,629 @@
+//! Lookup, deduplication and merging of the entities linked to the audiobooks: authors, readers,
+//! series, categories and keywords.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Display;
+
+use clap::ValueEnum;
+use sqlx::{FromRow, PgPool, Postgres, Transaction};
+use tracing::{info, instrument};
+
+use crate::db_ops::AppError;
+use crate::entity_names::{normalize_entity_name, normalize_person_name};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum EntityKind {
+    Author,
+    Reader,
+    Series,
+    Category,
+    Keyword,
+}
+
+impl EntityKind {
+    /// Name of the table of the entities, which is also the prefix of their foreign keys.
+    #[must_use]
+    pub fn table(self) -> &'static str {
+        match self {
+            EntityKind::Author => "author",
+            EntityKind::Reader => "reader",
+            EntityKind::Series => "series",
+            EntityKind::Category => "category",
+            EntityKind::Keyword => "keyword",
+        }
+    }
+
+    #[must_use]
+    pub fn name_column(self) -> &'static str {
+        match self {
+            EntityKind::Series => "title",
+            _ => "name",
+        }
+    }
+
+    /// The table of the Hardcover metadata of the entities, if they are enriched from Hardcover.
+    #[must_use]
+    pub fn hardcover_table(self) -> Option<&'static str> {
+        match self {
+            EntityKind::Author => Some("hardcover_author_metadata"),
+            EntityKind::Series => Some("hardcover_series_metadata"),
+            _ => None,
+        }
+    }
+
+    /// Normalizes a name of this kind of entity before it is stored.
+    #[must_use]
+    pub fn normalize(self, name: &str) -> String {
+        match self {
+            EntityKind::Author | EntityKind::Reader => normalize_person_name(name),
+            _ => normalize_entity_name(name),
+        }
+    }
+}
+
+impl Display for EntityKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.table())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, FromRow)]
+pub struct EntityRecord {
+    pub id: i64,
+    pub name: String,
+    pub audiobooks_count: i64,
+    /// Whether the entity is a node of the curated category taxonomy.
+    pub in_taxonomy: bool,
+}
+
+/// Entities that likely are the same, to be merged into `canonical`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MergeCluster {
+    pub canonical: EntityRecord,
+    pub duplicates: Vec<EntityRecord>,
+}
+
+#[derive(FromRow)]
+struct SimilarPair {
+    a_id: i64,
+    a_name: String,
+    a_count: i64,
+    a_taxonomy: bool,
+    b_id: i64,
+    b_name: String,
+    b_count: i64,
+    b_taxonomy: bool,
+}
+
+/// Returns the id of the entity named `name` ignoring case, creating it if it doesn't exist yet.
+/// The name is expected to be normalized already.
+///
+/// # Errors
+/// If the query fails.
+pub async fn upsert_entity(
+    tx: &mut Transaction<'_, Postgres>,
+    kind: EntityKind,
+    name: &str,
+) -> Result<i64, AppError> {
+    let table = kind.table();
+    let column = kind.name_column();
+    sqlx::query_scalar(&format!(
+        r"
+        WITH existing AS (
+            SELECT id FROM {table} WHERE lower({column}) = lower($1) ORDER BY id LIMIT 1
+        ), inserted AS (
+            INSERT INTO {table} ({column})
+            SELECT $1 WHERE NOT EXISTS (SELECT 1 FROM existing)
+            ON CONFLICT ({column}) DO UPDATE SET {column} = EXCLUDED.{column}
+            RETURNING id
+        )
+        SELECT id FROM existing UNION ALL SELECT id FROM inserted
+        "
+    ))
+    .bind(name)
+    .fetch_one(&mut **tx)
+    .await
+    .map_err(|e| AppError::GenericError(e.to_string()))
+}
+
+/// Proposes the entities to merge: the pairs whose names have a trigram similarity of at least
+/// `min_similarity` are grouped into clusters, and the member with the best name of each cluster
+/// is picked as the canonical one. Since the clusters are transitive, only the members similar
+/// to the canonical one are kept as its duplicates, the others are left for a later run. Two nodes
+/// of the category taxonomy are never paired.
+///
+/// # Errors
+/// If the query fails.
+#[instrument(skip(pool))]
+pub async fn propose_merge_clusters(
+    pool: &PgPool,
+    kind: EntityKind,
+    min_similarity: f32,
+    limit: i64,
+) -> Result<Vec<MergeCluster>, AppError> {
+    let table = kind.table();
+    let column = kind.name_column();
+    let in_taxonomy = if kind == EntityKind::Category {
+        "EXISTS (SELECT 1 FROM category_taxonomy t WHERE t.category_id = e.id)"
+    } else {
+        "FALSE"
+    };
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| AppError::GenericError(e.to_string()))?;
+    // The threshold of the `%` operator, which unlike `similarity()` can use the trigram index.
+    sqlx::query("SELECT set_config('pg_trgm.similarity_threshold', $1::text, true)")
+        .bind(min_similarity)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| AppError::GenericError(e.to_string()))?;
+    let pairs = sqlx::query_as::<_, SimilarPair>(&format!(
+        r"
+        WITH counted AS (
+            SELECT
+                e.id, e.{column} AS name, COUNT(j.{table}_id) AS audiobooks_count,
+                {in_taxonomy} AS in_taxonomy
+            FROM {table} e
+            LEFT JOIN {link_table} j ON j.{table}_id = e.id
+            GROUP BY e.id
+        )
+        SELECT
+            a.id AS a_id, a.name AS a_name, a.audiobooks_count AS a_count,
+            a.in_taxonomy AS a_taxonomy,
+            b.id AS b_id, b.name AS b_name, b.audiobooks_count AS b_count,
+            b.in_taxonomy AS b_taxonomy
+        FROM counted a
+        JOIN counted b ON a.id < b.id AND lower(a.name) % lower(b.name)
+        WHERE NOT (a.in_taxonomy AND b.in_taxonomy)
+        ORDER BY similarity(lower(a.name), lower(b.name)) DESC, a.id, b.id
+        LIMIT $1
+        ",
+        link_table = audiobook_link_table(kind),
+    ))
+    .bind(limit)
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(|e| AppError::GenericError(e.to_string()))?;
+    tx.commit()
+        .await
+        .map_err(|e| AppError::GenericError(e.to_string()))?;
+
+    let mut records = BTreeMap::new();
+    let mut edges = Vec::with_capacity(pairs.len());
+    for pair in pairs {
+        records.entry(pair.a_id).or_insert(EntityRecord {
+            id: pair.a_id,
+            name: pair.a_name,
+            audiobooks_count: pair.a_count,
+            in_taxonomy: pair.a_taxonomy,
+        });
+        records.entry(pair.b_id).or_insert(EntityRecord {
+            id: pair.b_id,
+            name: pair.b_name,
+            audiobooks_count: pair.b_count,
+            in_taxonomy: pair.b_taxonomy,
+        });
+        edges.push((pair.a_id, pair.b_id));
+    }
+    let similar: BTreeSet<(i64, i64)> = edges.iter().copied().collect();
+    Ok(cluster_pairs(&edges)
+        .into_iter()
+        .filter_map(|ids| {
+            let members = ids.iter().filter_map(|id| records.remove(id)).collect();
+            retain_similar_to_canonical(into_merge_cluster(kind, members), &similar)
+        })
+        .collect())
+}
+
+/// Merges the `duplicates` into the entity `into`, in a single transaction: the audiobooks and
+/// the notification subscriptions of the duplicates are moved to `into`, and so is their Hardcover
+/// metadata unless `into` has its own. The aliases of merged keywords are moved too, and `into`
+/// joins their group of synonyms if it has none. A merged node of the category taxonomy hands its
+/// place in the taxonomy, its children and its aliases over to `into`. The duplicates are then
+/// deleted.
+///
+/// # Errors
+/// - If `into` doesn't exist or is among the duplicates.
+/// - If more than one of the merged categories is a node of the taxonomy.
+/// - If any of the queries fails, in which case nothing is merged.
+#[instrument(skip(pool))]
+pub async fn merge_entities(
+    pool: &PgPool,
+    kind: EntityKind,
+    into: i64,
+    duplicates: &[i64],
+) -> Result<(), AppError> {
+    if duplicates.contains(&into) {
+        return Err(AppError::GenericError(format!(
+            "Can't merge {kind} {into} into itself"
+        )));
+    }
+    let table = kind.table();
+    let foreign_key = format!("{table}_id");
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| AppError::GenericError(e.to_string()))?;
+
+    let exists: bool = sqlx::query_scalar(&format!(
+        "SELECT EXISTS (SELECT 1 FROM {table} WHERE id = $1)"
+    ))
+    .bind(into)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| AppError::GenericError(e.to_string()))?;
+    if !exists {
+        return Err(AppError::NotFound(format!("No {kind} with id {into}")));
+    }
+    if kind == EntityKind::Category {
+        // Deleting a node would delete its subtree and its aliases, so it must be kept or hand
+        // them over, which only one node can do.
+        let nodes: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM category_taxonomy WHERE category_id = $1 OR category_id = ANY($2)",
+        )
+        .bind(into)
+        .bind(duplicates)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| AppError::GenericError(e.to_string()))?;
+        if nodes > 1 {
+            return Err(AppError::GenericError(format!(
+                "Can't merge {nodes} nodes of the category taxonomy into one"
+            )));
+        }
+    }
+
+    let mut statements = Vec::new();
+    if kind == EntityKind::Series {
+        statements
+            .push("UPDATE audiobook SET series_id = $1 WHERE series_id = ANY($2)".to_string());
+    } else {
+        statements.extend(move_links(
+            audiobook_link_table(kind),
+            "audiobook_id",
+            &foreign_key,
+            &[],
+        ));
+    }
+    statements.extend(move_links(
+        &format!("user_{table}_notification"),
+        "user_id",
+        &foreign_key,
+        &["languages"],
+    ));
+    if let Some(hardcover_table) = kind.hardcover_table() {
+        statements.push(format!(
+            r"
+            UPDATE {hardcover_table} SET {foreign_key} = $1
+            WHERE id = (
+                SELECT id FROM {hardcover_table} WHERE {foreign_key} = ANY($2) ORDER BY id LIMIT 1
+            ) AND NOT EXISTS (SELECT 1 FROM {hardcover_table} WHERE {foreign_key} = $1)
+            "
+        ));
+    }
+    if kind == EntityKind::Keyword {
+        statements.push(
+            "UPDATE keyword_alias SET keyword_id = $1 WHERE keyword_id = ANY($2)".to_string(),
+        );
+        statements.push(
+            r"
+            INSERT INTO keyword_synonym (keyword_id, group_id)
+            SELECT $1, group_id FROM keyword_synonym WHERE keyword_id = ANY($2)
+            ORDER BY group_id LIMIT 1
+            ON CONFLICT (keyword_id) DO NOTHING
+            "
+            .to_string(),
+        );
+    }
+    if kind == EntityKind::Category {
+        statements.push(
+            r"
+            INSERT INTO category_taxonomy (category_id, parent_id)
+            SELECT $1, parent_id FROM category_taxonomy WHERE category_id = ANY($2)
+            "
+            .to_string(),
+        );
+        statements.push(
+            "UPDATE category_taxonomy SET parent_id = $1 WHERE parent_id = ANY($2)".to_string(),
+        );
+        statements.push(
+            "UPDATE category_alias SET category_id = $1 WHERE category_id = ANY($2)".to_string(),
+        );
+    }
+    statements.push(format!("DELETE FROM {table} WHERE id = ANY($2)"));
+
+    for statement in statements {
+        sqlx::query(&statement)
+            .bind(into)
+            .bind(duplicates)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| AppError::GenericError(e.to_string()))?;
+    }
+    tx.commit()
+        .await
+        .map_err(|e| AppError::GenericError(e.to_string()))?;
+    info!("Merged {} {kind} into {into}", duplicates.len());
+    Ok(())
+}
+
+/// The table linking the audiobooks to the entities: the `audiobook_*` join table, or the
+/// audiobooks themselves for the series.
+fn audiobook_link_table(kind: EntityKind) -> &'static str {
+    match kind {
+        EntityKind::Author => "audiobook_author",
+        EntityKind::Reader => "audiobook_reader",
+        EntityKind::Series => "audiobook",
+        EntityKind::Category => "audiobook_category",
+        EntityKind::Keyword => "audiobook_keyword",
+    }
+}
+
+/// Statements pointing the rows of `table` from the duplicates (`$2`) to the merged entity
+/// (`$1`), along with their other `columns`, skipping the rows that already exist for it.
+fn move_links(table: &str, owner_column: &str, foreign_key: &str, columns: &[&str]) -> [String; 2] {
+    let columns: String = columns.iter().map(|column| format!(", {column}")).collect();
+    [
+        format!(
+            r"
+            INSERT INTO {table} ({owner_column}, {foreign_key}{columns})
+            SELECT DISTINCT ON ({owner_column}) {owner_column}, $1{columns}
+            FROM {table} WHERE {foreign_key} = ANY($2)
+            ORDER BY {owner_column}, {foreign_key}
+            ON CONFLICT DO NOTHING
+            "
+        ),
+        format!("DELETE FROM {table} WHERE {foreign_key} = ANY($2)"),
+    ]
+}
+
+/// Groups the ids connected by `pairs`, each group sorted, in order of their smallest id.
+fn cluster_pairs(pairs: &[(i64, i64)]) -> Vec<Vec<i64>> {
+    fn find(parents: &mut BTreeMap<i64, i64>, id: i64) -> i64 {
+        let parent = *parents.entry(id).or_insert(id);
+        if parent == id {
+            return id;
+        }
+        let root = find(parents, parent);
+        parents.insert(id, root);
+        root
+    }
+
+    let mut parents = BTreeMap::new();
+    for &(a, b) in pairs {
+        let (root_a, root_b) = (find(&mut parents, a), find(&mut parents, b));
+        parents.insert(root_a.max(root_b), root_a.min(root_b));
+    }
+    let ids: Vec<i64> = parents.keys().copied().collect();
+    let mut clusters: BTreeMap<i64, Vec<i64>> = BTreeMap::new();
+    for id in ids {
+        let root = find(&mut parents, id);
+        clusters.entry(root).or_default().push(id);
+    }
+    clusters.into_values().collect()
+}
+
+/// Picks the canonical member of a cluster: the node of the category taxonomy, then the one whose
+/// name is already normalized, then the one with the most audiobooks, then the oldest.
+fn into_merge_cluster(kind: EntityKind, mut members: Vec<EntityRecord>) -> MergeCluster {
+    members.sort_by_key(|member| {
+        (
+            !member.in_taxonomy,
+            kind.normalize(&member.name) != member.name,
+            -member.audiobooks_count,
+            member.id,
+        )
+    });
+    let canonical = members.remove(0);
+    MergeCluster {
+        canonical,
+        duplicates: members,
+    }
+}
+
+/// Drops the duplicates that aren't similar to the canonical member themselves but only through
+/// other members, `None` if no duplicate is left. `similar` holds the similar pairs of ids, the
+/// smallest first.
+fn retain_similar_to_canonical(
+    mut cluster: MergeCluster,
+    similar: &BTreeSet<(i64, i64)>,
+) -> Option<MergeCluster> {
+    let canonical = cluster.canonical.id;
+    cluster.duplicates.retain(|duplicate| {
+        similar.contains(&(canonical.min(duplicate.id), canonical.max(duplicate.id)))
+    });
+    (!cluster.duplicates.is_empty()).then_some(cluster)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(id: i64, name: &str, audiobooks_count: i64) -> EntityRecord {
+        EntityRecord {
+            id,
+            name: name.to_string(),
+            audiobooks_count,
+            in_taxonomy: false,
+        }
+    }
+
+    async fn category_id(pool: &PgPool, name: &str) -> i64 {
+        sqlx::query_scalar("SELECT id FROM category WHERE name = $1")
+            .bind(name)
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    #[test]
+    fn test_cluster_pairs() {
+        let clusters = cluster_pairs(&[(5, 9), (1, 2), (2, 3), (9, 7), (4, 6), (3, 6)]);
+        assert_eq!(clusters, vec![vec![1, 2, 3, 4, 6], vec![5, 7, 9]]);
+        assert!(cluster_pairs(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_canonical_member() {
+        let cluster = into_merge_cluster(
+            EntityKind::Author,
+            vec![
+                record(1, "Tolkien, J.R.R.", 12),
+                record(2, "J. R. R. Tolkien", 3),
+                record(3, "J.R.R. Tolkien", 1),
+            ],
+        );
+        assert_eq!(cluster.canonical.id, 3);
+        assert_eq!(
+            cluster.duplicates.iter().map(|d| d.id).collect::<Vec<_>>(),
+            vec![1, 2]
+        );
+
+        let cluster = into_merge_cluster(
+            EntityKind::Keyword,
+            vec![record(4, "fantasy", 2), record(7, "Fantasy", 9)],
+        );
+        assert_eq!(cluster.canonical.id, 7);
+
+        let cluster = into_merge_cluster(
+            EntityKind::Category,
+            vec![
+                record(2, "Sci-Fi", 40),
+                EntityRecord {
+                    in_taxonomy: true,
+                    ..record(5, "science fiction", 1)
+                },
+            ],
+        );
+        assert_eq!(cluster.canonical.id, 5);
+    }
+
+    #[test]
+    fn test_duplicates_must_be_similar_to_the_canonical_member() {
+        // "Bob Smith" ~ "Rob Smith" ~ "Rob Smyth", but "Bob Smith" and "Rob Smyth" aren't similar.
+        let similar = BTreeSet::from([(1, 2), (2, 3)]);
+        let cluster = MergeCluster {
+            canonical: record(1, "Bob Smith", 5),
+            duplicates: vec![record(2, "Rob Smith", 1), record(3, "Rob Smyth", 1)],
+        };
+        let cluster = retain_similar_to_canonical(cluster, &similar).unwrap();
+        assert_eq!(
+            cluster.duplicates.iter().map(|d| d.id).collect::<Vec<_>>(),
+            vec![2]
+        );
+
+        let cluster = MergeCluster {
+            canonical: record(3, "Rob Smyth", 5),
+            duplicates: vec![record(1, "Bob Smith", 1)],
+        };
+        assert!(retain_similar_to_canonical(cluster, &similar).is_none());
+    }
+
+    #[sqlx::test(migrations = "../model/migrations")]
+    async fn test_merged_subscriptions_keep_their_languages(pool: PgPool) {
+        let user_id: i64 = sqlx::query_scalar(
+            r"
+            INSERT INTO users (username, anonymous, password_mcf, last_access)
+            VALUES ('reader', FALSE, '', NOW())
+            RETURNING id
+            ",
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        let authors: Vec<i64> = sqlx::query_scalar(
+            "INSERT INTO author (name) VALUES ('Andrea Camilleri'), ('A. Camilleri') RETURNING id",
+        )
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        sqlx::query(
+            "INSERT INTO user_author_notification (user_id, author_id, languages) VALUES ($1, $2, $3)",
+        )
+        .bind(user_id)
+        .bind(authors[1])
+        .bind(vec!["it"])
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        merge_entities(&pool, EntityKind::Author, authors[0], &authors[1..])
+            .await
+            .unwrap();
+
+        let languages: Vec<String> = sqlx::query_scalar(
+            "SELECT languages FROM user_author_notification WHERE user_id = $1 AND author_id = $2",
+        )
+        .bind(user_id)
+        .bind(authors[0])
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(languages, vec!["it"]);
+    }
+
+    #[sqlx::test(migrations = "../model/migrations")]
+    async fn test_merging_a_taxonomy_node_keeps_its_subtree_and_aliases(pool: PgPool) {
+        let fantasy = category_id(&pool, "Fantasy").await;
+        let fiction = category_id(&pool, "Fiction").await;
+        let into: i64 = sqlx::query_scalar(
+            "INSERT INTO category (name) VALUES ('Fantasy Fiction') RETURNING id",
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+
+        merge_entities(&pool, EntityKind::Category, into, &[fantasy])
+            .await
+            .unwrap();
+
+        let parent: Option<i64> =
+            sqlx::query_scalar("SELECT parent_id FROM category_taxonomy WHERE category_id = $1")
+                .bind(into)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(parent, Some(fiction));
+        let children: Vec<String> = sqlx::query_scalar(
+            r"
+            SELECT c.name FROM category_taxonomy t JOIN category c ON c.id = t.category_id
+            WHERE t.parent_id = $1 ORDER BY c.name
+            ",
+        )
+        .bind(into)
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        assert_eq!(
+            children,
+            vec!["Dark Fantasy", "Epic Fantasy", "LitRPG", "Urban Fantasy"]
+        );
+        let alias: Option<i64> =
+            sqlx::query_scalar("SELECT category_id FROM category_alias WHERE alias = 'fantasy'")
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(alias, Some(into));
+    }
+
+    #[sqlx::test(migrations = "../model/migrations")]
+    async fn test_two_taxonomy_nodes_are_not_merged(pool: PgPool) {
+        let fantasy = category_id(&pool, "Fantasy").await;
+        let epic_fantasy = category_id(&pool, "Epic Fantasy").await;
+
+        assert!(
+            merge_entities(&pool, EntityKind::Category, fantasy, &[epic_fantasy])
+                .await
+                .is_err()
+        );
+        let nodes: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM category_taxonomy WHERE category_id = ANY($1)",
+        )
+        .bind([fantasy, epic_fantasy])
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(nodes, 2);
+    }
+}
@@ -0,0 +1,717 @@
+//! Queries on the metadata matched from Hardcover.
+
+use chrono::{DateTime, Utc};
+use entities_lib::entities::hardcover::{
+    HardcoverAuthorProfile, HardcoverSeriesBook, HardcoverSeriesProfile,
+};
+use entities_lib::{Author, Series};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
+use sqlx::{FromRow, PgPool, Postgres, Transaction};
+use tracing::instrument;
+
+use crate::db_ops::AppError;
+
+const HARDCOVER_URL: &str = "https://hardcover.app";
+
+/// Author metadata fetched from Hardcover, as stored in `hardcover_author_metadata`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HardcoverAuthorData {
+    pub id: i64,
+    pub name: String,
+    pub slug: Option<String>,
+    pub bio: Option<String>,
+    pub books_count: Option<i64>,
+    pub image_url: Option<String>,
+}
+
+/// A book of a series fetched from Hardcover.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HardcoverSeriesBookData {
+    pub title: String,
+    pub slug: Option<String>,
+    pub position: Option<f64>,
+}
+
+/// Series metadata fetched from Hardcover, as stored in `hardcover_series_metadata`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HardcoverSeriesData {
+    pub id: i64,
+    pub name: String,
+    pub slug: Option<String>,
+    pub description: Option<String>,
+    pub books_count: Option<i64>,
+    pub primary_books_count: Option<i64>,
+    pub books: Vec<HardcoverSeriesBookData>,
+}
+
+fn hardcover_url(kind: &str, slug: Option<String>) -> Option<String> {
+    slug.map(|slug| format!("{HARDCOVER_URL}/{kind}/{slug}"))
+}
+
+impl From<HardcoverAuthorData> for HardcoverAuthorProfile {
+    fn from(data: HardcoverAuthorData) -> Self {
+        HardcoverAuthorProfile {
+            bio: data.bio.filter(|bio| !bio.trim().is_empty()),
+            image_url: data.image_url,
+            books_count: data.books_count,
+            url: hardcover_url("authors", data.slug),
+        }
+    }
+}
+
+impl From<HardcoverSeriesData> for HardcoverSeriesProfile {
+    fn from(data: HardcoverSeriesData) -> Self {
+        HardcoverSeriesProfile {
+            description: data
+                .description
+                .filter(|description| !description.trim().is_empty()),
+            books_count: data.books_count,
+            primary_books_count: data.primary_books_count,
+            url: hardcover_url("series", data.slug),
+            books: data
+                .books
+                .into_iter()
+                .map(|book| HardcoverSeriesBook {
+                    title: book.title,
+                    position: book.position.map(|position| position.to_string()),
+                    url: hardcover_url("books", book.slug),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// A Hardcover match to be reviewed.
+#[derive(Debug, Clone, FromRow)]
+pub struct HardcoverMatchReview {
+    pub audiobook_id: i64,
+    pub audiobook_title: String,
+    pub path: String,
+    pub hardcover_title: Option<String>,
+    pub hardcover_slug: Option<String>,
+    pub match_score: f64,
+}
+
+/// Returns the scored Hardcover matches with a score below `below`, worst first.
+///
+/// # Errors
+/// If the query fails.
+#[instrument(skip(pool))]
+pub async fn list_low_confidence_hardcover_matches(
+    pool: &PgPool,
+    below: f64,
+    limit: i64,
+) -> Result<Vec<HardcoverMatchReview>, AppError> {
+    sqlx::query_as::<_, HardcoverMatchReview>(
+        r"
+        SELECT
+            a.id AS audiobook_id,
+            a.title AS audiobook_title,
+            a.path,
+            h.metadata ->> 'title' AS hardcover_title,
+            h.metadata ->> 'slug' AS hardcover_slug,
+            h.match_score
+        FROM hardcover_audiobook_metadata h
+        JOIN audiobook a ON a.id = h.audiobook_id
+        WHERE h.match_score < $1
+        ORDER BY h.match_score ASC, a.id ASC
+        LIMIT $2
+        ",
+    )
+    .bind(below)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| AppError::GenericError(e.to_string()))
+}
+
+/// Stores the Hardcover metadata of an author, replacing any previous one.
+///
+/// # Errors
+/// If the upsert fails.
+#[instrument(skip(pool, data))]
+pub async fn upsert_hardcover_author_metadata(
+    pool: &PgPool,
+    author_id: i64,
+    data: &HardcoverAuthorData,
+) -> Result<(), AppError> {
+    sqlx::query(
+        r"
+        INSERT INTO hardcover_author_metadata (author_id, metadata) VALUES ($1, $2)
+        ON CONFLICT (author_id) DO UPDATE SET metadata = EXCLUDED.metadata
+        ",
+    )
+    .bind(author_id)
+    .bind(Json(data))
+    .execute(pool)
+    .await
+    .map_err(|e| AppError::GenericError(e.to_string()))?;
+    Ok(())
+}
+
+/// Stores the Hardcover metadata of a series, replacing any previous one.
+///
+/// # Errors
+/// If the upsert fails.
+#[instrument(skip(pool, data))]
+pub async fn upsert_hardcover_series_metadata(
+    pool: &PgPool,
+    series_id: i64,
+    data: &HardcoverSeriesData,
+) -> Result<(), AppError> {
+    sqlx::query(
+        r"
+        INSERT INTO hardcover_series_metadata (series_id, metadata) VALUES ($1, $2)
+        ON CONFLICT (series_id) DO UPDATE SET metadata = EXCLUDED.metadata
+        ",
+    )
+    .bind(series_id)
+    .bind(Json(data))
+    .execute(pool)
+    .await
+    .map_err(|e| AppError::GenericError(e.to_string()))?;
+    Ok(())
+}
+
+/// Returns the Hardcover profile of the author, if it was enriched.
+///
+/// # Errors
+/// If the query fails or the stored metadata is malformed.
+#[instrument(skip(pool))]
+pub async fn get_hardcover_author_profile(
+    pool: &PgPool,
+    author_id: i64,
+) -> Result<Option<HardcoverAuthorProfile>, AppError> {
+    let maybe_data: Option<Json<HardcoverAuthorData>> =
+        sqlx::query_scalar("SELECT metadata FROM hardcover_author_metadata WHERE author_id = $1")
+            .bind(author_id)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| AppError::DeserializationError(e.to_string()))?;
+    Ok(maybe_data.map(|Json(data)| data.into()))
+}
+
+/// Returns the Hardcover profile of the series, if it was enriched.
+///
+/// # Errors
+/// If the query fails or the stored metadata is malformed.
+#[instrument(skip(pool))]
+pub async fn get_hardcover_series_profile(
+    pool: &PgPool,
+    series_id: i64,
+) -> Result<Option<HardcoverSeriesProfile>, AppError> {
+    let maybe_data: Option<Json<HardcoverSeriesData>> =
+        sqlx::query_scalar("SELECT metadata FROM hardcover_series_metadata WHERE series_id = $1")
+            .bind(series_id)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| AppError::DeserializationError(e.to_string()))?;
+    Ok(maybe_data.map(|Json(data)| data.into()))
+}
+
+/// Returns the authors that don't have Hardcover metadata yet: the ones never searched first, most
+/// published first, then the ones searched the longest ago.
+///
+/// # Errors
+/// If the query fails.
+#[instrument(skip(pool))]
+pub async fn get_authors_without_hardcover_metadata(
+    pool: &PgPool,
+    limit: i64,
+) -> Result<Vec<Author>, AppError> {
+    let rows: Vec<(i64, String)> = sqlx::query_as(
+        r"
+        SELECT a.id, a.name
+        FROM author a
+        LEFT JOIN hardcover_author_metadata h ON h.author_id = a.id
+        LEFT JOIN audiobook_author aa ON aa.author_id = a.id
+        WHERE h.id IS NULL
+        GROUP BY a.id, a.name
+        ORDER BY a.hardcover_attempted_at ASC NULLS FIRST, COUNT(aa.audiobook_id) DESC, a.id ASC
+        LIMIT $1
+        ",
+    )
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| AppError::GenericError(e.to_string()))?;
+    Ok(rows
+        .into_iter()
+        .map(|(id, name)| Author { id, name })
+        .collect())
+}
+
+/// Returns the series that don't have Hardcover metadata yet, in the same order as
+/// [`get_authors_without_hardcover_metadata`], with the name of one of their authors to
+/// disambiguate the search.
+///
+/// # Errors
+/// If the query fails.
+#[instrument(skip(pool))]
+pub async fn get_series_without_hardcover_metadata(
+    pool: &PgPool,
+    limit: i64,
+) -> Result<Vec<(Series, Option<String>)>, AppError> {
+    let rows: Vec<(i64, String, Option<String>)> = sqlx::query_as(
+        r"
+        SELECT s.id, s.title, MIN(au.name) AS author_name
+        FROM series s
+        LEFT JOIN hardcover_series_metadata h ON h.series_id = s.id
+        LEFT JOIN audiobook ab ON ab.series_id = s.id
+        LEFT JOIN audiobook_author aa ON aa.audiobook_id = ab.id
+        LEFT JOIN author au ON au.id = aa.author_id
+        WHERE h.id IS NULL
+        GROUP BY s.id, s.title
+        ORDER BY s.hardcover_attempted_at ASC NULLS FIRST, COUNT(DISTINCT ab.id) DESC, s.id ASC
+        LIMIT $1
+        ",
+    )
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| AppError::GenericError(e.to_string()))?;
+    Ok(rows
+        .into_iter()
+        .map(|(id, title, author_name)| (Series { id, title }, author_name))
+        .collect())
+}
+
+/// Records that the author couldn't be enriched from Hardcover, so that it's retried after the
+/// others.
+///
+/// # Errors
+/// If the update fails.
+#[instrument(skip(pool))]
+pub async fn record_hardcover_author_attempt(
+    pool: &PgPool,
+    author_id: i64,
+) -> Result<(), AppError> {
+    sqlx::query("UPDATE author SET hardcover_attempted_at = CURRENT_TIMESTAMP WHERE id = $1")
+        .bind(author_id)
+        .execute(pool)
+        .await
+        .map_err(|e| AppError::GenericError(e.to_string()))?;
+    Ok(())
+}
+
+/// Records that the series couldn't be enriched from Hardcover, so that it's retried after the
+/// others.
+///
+/// # Errors
+/// If the update fails.
+#[instrument(skip(pool))]
+pub async fn record_hardcover_series_attempt(
+    pool: &PgPool,
+    series_id: i64,
+) -> Result<(), AppError> {
+    sqlx::query("UPDATE series SET hardcover_attempted_at = CURRENT_TIMESTAMP WHERE id = $1")
+        .bind(series_id)
+        .execute(pool)
+        .await
+        .map_err(|e| AppError::GenericError(e.to_string()))?;
+    Ok(())
+}
+
+/// Number of rating snapshots kept for every audiobook.
+const RATING_HISTORY_LENGTH: i64 = 24;
+
+/// The ratings of a Hardcover book, refreshed periodically.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HardcoverRatings {
+    pub rating: Option<f64>,
+    pub ratings_count: Option<i64>,
+    pub reviews_count: Option<i64>,
+    pub ratings_distribution: Option<serde_json::Value>,
+}
+
+/// A Hardcover match whose ratings should be refreshed.
+#[derive(Debug, Clone, FromRow)]
+pub struct StaleHardcoverBook {
+    pub audiobook_id: i64,
+    pub hardcover_id: i64,
+}
+
+/// An audiobook whose number of Hardcover ratings grew recently.
+#[derive(Debug, Clone, FromRow)]
+pub struct HardcoverRatingTrend {
+    pub audiobook_id: i64,
+    pub audiobook_title: String,
+    pub first_ratings_count: i64,
+    pub last_ratings_count: i64,
+    pub first_rating: Option<f64>,
+    pub last_rating: Option<f64>,
+}
+
+/// Returns the Hardcover matches neither refreshed nor attempted since `refreshed_before`, least
+/// recently attempted first.
+///
+/// # Errors
+/// If the query fails.
+#[instrument(skip(pool))]
+pub async fn get_stale_hardcover_books(
+    pool: &PgPool,
+    refreshed_before: DateTime<Utc>,
+    limit: i64,
+) -> Result<Vec<StaleHardcoverBook>, AppError> {
+    sqlx::query_as::<_, StaleHardcoverBook>(
+        r"
+        SELECT audiobook_id, (metadata ->> 'id')::BIGINT AS hardcover_id
+        FROM hardcover_audiobook_metadata
+        WHERE (refreshed_at IS NULL OR refreshed_at < $1)
+            AND (last_attempted_at IS NULL OR last_attempted_at < $1)
+        ORDER BY last_attempted_at ASC NULLS FIRST, audiobook_id ASC
+        LIMIT $2
+        ",
+    )
+    .bind(refreshed_before)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| AppError::GenericError(e.to_string()))
+}
+
+/// Overwrites the ratings in the Hardcover metadata of the audiobook and records them in the
+/// rating history, dropping the oldest snapshots.
+///
+/// # Errors
+/// If any of the queries fails.
+#[instrument(skip(pool, ratings))]
+pub async fn update_hardcover_ratings(
+    pool: &PgPool,
+    audiobook_id: i64,
+    ratings: &HardcoverRatings,
+) -> Result<(), AppError> {
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| AppError::GenericError(e.to_string()))?;
+    sqlx::query(
+        r"
+        UPDATE hardcover_audiobook_metadata
+        SET metadata = metadata || $2,
+            refreshed_at = CURRENT_TIMESTAMP,
+            last_attempted_at = CURRENT_TIMESTAMP
+        WHERE audiobook_id = $1
+        ",
+    )
+    .bind(audiobook_id)
+    .bind(Json(ratings))
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| AppError::GenericError(e.to_string()))?;
+    record_hardcover_ratings(&mut tx, audiobook_id, ratings).await?;
+    tx.commit()
+        .await
+        .map_err(|e| AppError::GenericError(e.to_string()))?;
+    Ok(())
+}
+
+/// Records a failed refresh of the ratings of the audiobook, so that it's retried after the other
+/// stale matches.
+///
+/// # Errors
+/// If the update fails.
+#[instrument(skip(pool))]
+pub async fn record_hardcover_refresh_attempt(
+    pool: &PgPool,
+    audiobook_id: i64,
+) -> Result<(), AppError> {
+    sqlx::query(
+        "UPDATE hardcover_audiobook_metadata SET last_attempted_at = CURRENT_TIMESTAMP
+        WHERE audiobook_id = $1",
+    )
+    .bind(audiobook_id)
+    .execute(pool)
+    .await
+    .map_err(|e| AppError::GenericError(e.to_string()))?;
+    Ok(())
+}
+
+/// Removes the Hardcover match of the audiobook, e.g. once the book was removed from Hardcover.
+/// The rating history is kept.
+///
+/// # Errors
+/// If the delete fails.
+#[instrument(skip(pool))]
+pub async fn delete_hardcover_match(pool: &PgPool, audiobook_id: i64) -> Result<(), AppError> {
+    sqlx::query("DELETE FROM hardcover_audiobook_metadata WHERE audiobook_id = $1")
+        .bind(audiobook_id)
+        .execute(pool)
+        .await
+        .map_err(|e| AppError::GenericError(e.to_string()))?;
+    Ok(())
+}
+
+/// Adds a snapshot of the ratings to the history of the audiobook, dropping the oldest ones.
+///
+/// # Errors
+/// If the insert or the cleanup fails.
+pub async fn record_hardcover_ratings(
+    tx: &mut Transaction<'_, Postgres>,
+    audiobook_id: i64,
+    ratings: &HardcoverRatings,
+) -> Result<(), AppError> {
+    sqlx::query(
+        r"
+        INSERT INTO hardcover_rating_history (audiobook_id, rating, ratings_count, reviews_count)
+        VALUES ($1, $2, $3, $4)
+        ",
+    )
+    .bind(audiobook_id)
+    .bind(ratings.rating)
+    .bind(ratings.ratings_count)
+    .bind(ratings.reviews_count)
+    .execute(&mut **tx)
+    .await
+    .map_err(|e| AppError::GenericError(e.to_string()))?;
+    sqlx::query(
+        r"
+        DELETE FROM hardcover_rating_history
+        WHERE audiobook_id = $1 AND id NOT IN (
+            SELECT id FROM hardcover_rating_history
+            WHERE audiobook_id = $1
+            ORDER BY recorded_at DESC, id DESC
+            LIMIT $2
+        )
+        ",
+    )
+    .bind(audiobook_id)
+    .bind(RATING_HISTORY_LENGTH)
+    .execute(&mut **tx)
+    .await
+    .map_err(|e| AppError::GenericError(e.to_string()))?;
+    Ok(())
+}
+
+/// Returns the audiobooks whose number of Hardcover ratings grew the most since `since`.
+///
+/// # Errors
+/// If the query fails.
+#[instrument(skip(pool))]
+pub async fn get_hardcover_rating_trends(
+    pool: &PgPool,
+    since: DateTime<Utc>,
+    limit: i64,
+) -> Result<Vec<HardcoverRatingTrend>, AppError> {
+    sqlx::query_as::<_, HardcoverRatingTrend>(
+        r"
+        WITH snapshots AS (
+            SELECT
+                audiobook_id,
+                FIRST_VALUE(ratings_count) OVER w AS first_ratings_count,
+                LAST_VALUE(ratings_count) OVER w AS last_ratings_count,
+                FIRST_VALUE(rating) OVER w AS first_rating,
+                LAST_VALUE(rating) OVER w AS last_rating
+            FROM hardcover_rating_history
+            WHERE recorded_at >= $1 AND ratings_count IS NOT NULL
+            WINDOW w AS (
+                PARTITION BY audiobook_id ORDER BY recorded_at, id
+                ROWS BETWEEN UNBOUNDED PRECEDING AND UNBOUNDED FOLLOWING
+            )
+        )
+        SELECT DISTINCT
+            s.audiobook_id, a.title AS audiobook_title, s.first_ratings_count,
+            s.last_ratings_count, s.first_rating, s.last_rating
+        FROM snapshots s
+        JOIN audiobook a ON a.id = s.audiobook_id
+        WHERE s.last_ratings_count > s.first_ratings_count
+        ORDER BY s.last_ratings_count - s.first_ratings_count DESC, s.audiobook_id ASC
+        LIMIT $2
+        ",
+    )
+    .bind(since)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| AppError::GenericError(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[sqlx::test(migrations = "../model/migrations")]
+    async fn test_authors_searched_without_a_match_go_last(pool: PgPool) {
+        let authors: Vec<i64> = sqlx::query_scalar(
+            "INSERT INTO author (name) VALUES ('Brandon Sanderson'), ('Andy Weir') RETURNING id",
+        )
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        let first = |authors: Vec<Author>| authors.first().map(|author| author.id);
+
+        assert_eq!(
+            first(
+                get_authors_without_hardcover_metadata(&pool, 1)
+                    .await
+                    .unwrap()
+            ),
+            Some(authors[0])
+        );
+        record_hardcover_author_attempt(&pool, authors[0])
+            .await
+            .unwrap();
+        assert_eq!(
+            first(
+                get_authors_without_hardcover_metadata(&pool, 1)
+                    .await
+                    .unwrap()
+            ),
+            Some(authors[1])
+        );
+        record_hardcover_author_attempt(&pool, authors[1])
+            .await
+            .unwrap();
+        assert_eq!(
+            first(
+                get_authors_without_hardcover_metadata(&pool, 1)
+                    .await
+                    .unwrap()
+            ),
+            Some(authors[0])
+        );
+    }
+
+    #[sqlx::test(migrations = "../model/migrations")]
+    async fn test_series_searched_without_a_match_go_last(pool: PgPool) {
+        let series: Vec<i64> = sqlx::query_scalar(
+            "INSERT INTO series (title) VALUES ('Mistborn'), ('Red Rising') RETURNING id",
+        )
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        record_hardcover_series_attempt(&pool, series[0])
+            .await
+            .unwrap();
+        let pending = get_series_without_hardcover_metadata(&pool, 2)
+            .await
+            .unwrap();
+        assert_eq!(
+            pending.iter().map(|(s, _)| s.id).collect::<Vec<_>>(),
+            vec![series[1], series[0]]
+        );
+    }
+
+    #[test]
+    fn test_series_profile_from_data() {
+        let data = HardcoverSeriesData {
+            id: 1,
+            name: "Mistborn".to_string(),
+            slug: Some("mistborn".to_string()),
+            description: Some(" ".to_string()),
+            books_count: Some(7),
+            primary_books_count: Some(7),
+            books: vec![HardcoverSeriesBookData {
+                title: "Secret History".to_string(),
+                slug: None,
+                position: Some(3.5),
+            }],
+        };
+        let profile = HardcoverSeriesProfile::from(data);
+        assert_eq!(profile.description, None);
+        assert_eq!(
+            profile.url.as_deref(),
+            Some("https://hardcover.app/series/mistborn")
+        );
+        assert_eq!(profile.books[0].position.as_deref(), Some("3.5"));
+        assert_eq!(profile.books[0].url, None);
+    }
+
+    async fn insert_hardcover_match(pool: &PgPool, refreshed_days_ago: i32) -> i64 {
+        let audiobook_id: i64 = sqlx::query_scalar(
+            r"
+            INSERT INTO audiobook (
+                title, cover_url, description, description_for_embeddings, format, language, path,
+                timestamp_created, timestamp_ingested, very_short_description
+            )
+            VALUES ('Mistborn', '', '', '', 'MP3', 'en', gen_random_uuid()::TEXT, NOW(), NOW(), '')
+            RETURNING id
+            ",
+        )
+        .fetch_one(pool)
+        .await
+        .unwrap();
+        sqlx::query(
+            r"
+            INSERT INTO hardcover_audiobook_metadata (
+                audiobook_id, metadata, refreshed_at, last_attempted_at
+            )
+            VALUES (
+                $1, $3, NOW() - make_interval(days => $2), NOW() - make_interval(days => $2)
+            )
+            ",
+        )
+        .bind(audiobook_id)
+        .bind(refreshed_days_ago)
+        .bind(serde_json::json!({"id": 7, "title": "Mistborn", "rating": 3.0}))
+        .execute(pool)
+        .await
+        .unwrap();
+        audiobook_id
+    }
+
+    #[sqlx::test(migrations = "../model/migrations")]
+    async fn test_update_hardcover_ratings(pool: PgPool) -> Result<(), AppError> {
+        let audiobook_id = insert_hardcover_match(&pool, 30).await;
+        let ratings = HardcoverRatings {
+            rating: Some(4.2),
+            ratings_count: Some(10),
+            reviews_count: None,
+            ratings_distribution: None,
+        };
+        update_hardcover_ratings(&pool, audiobook_id, &ratings).await?;
+
+        let metadata: serde_json::Value = sqlx::query_scalar(
+            "SELECT metadata FROM hardcover_audiobook_metadata WHERE audiobook_id = $1",
+        )
+        .bind(audiobook_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        // The ratings are overwritten, the rest of the match is kept.
+        assert_eq!(metadata["rating"], 4.2);
+        assert_eq!(metadata["ratings_count"], 10);
+        assert_eq!(metadata["title"], "Mistborn");
+        let history: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM hardcover_rating_history WHERE audiobook_id = $1",
+        )
+        .bind(audiobook_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(history, 1);
+
+        let stale =
+            get_stale_hardcover_books(&pool, Utc::now() - chrono::TimeDelta::days(7), 10).await?;
+        assert!(stale.is_empty());
+        Ok(())
+    }
+
+    #[sqlx::test(migrations = "../model/migrations")]
+    async fn test_failed_refreshes_go_after_the_other_stale_matches(
+        pool: PgPool,
+    ) -> Result<(), AppError> {
+        let failing = insert_hardcover_match(&pool, 30).await;
+        let other = insert_hardcover_match(&pool, 20).await;
+        let stale_before = Utc::now() - chrono::TimeDelta::days(7);
+        let stale_ids = |stale: Vec<StaleHardcoverBook>| -> Vec<i64> {
+            stale.into_iter().map(|book| book.audiobook_id).collect()
+        };
+
+        let stale = get_stale_hardcover_books(&pool, stale_before, 1).await?;
+        assert_eq!(stale_ids(stale), vec![failing]);
+
+        record_hardcover_refresh_attempt(&pool, failing).await?;
+        let stale = get_stale_hardcover_books(&pool, stale_before, 10).await?;
+        assert_eq!(stale_ids(stale), vec![other]);
+
+        delete_hardcover_match(&pool, other).await?;
+        assert!(
+            get_stale_hardcover_books(&pool, stale_before, 10)
+                .await?
+                .is_empty()
+        );
+        Ok(())
+    }
+}
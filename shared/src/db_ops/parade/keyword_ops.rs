@@ -0,0 +1,508 @@
+//! The keyword stop-list, the aliases rewriting keywords to other keywords, and the groups of
+//! synonyms.
+
+use std::collections::HashMap;
+
+use sqlx::{FromRow, PgPool, Postgres, Transaction};
+use tracing::instrument;
+
+use crate::db_ops::AppError;
+use crate::db_ops::parade::entity_ops::{EntityKind, upsert_entity};
+use crate::db_ops::parade::subscription_ops::subscription_language_condition;
+
+/// What `keyword_alias` rewrites a keyword to.
+#[derive(Debug, Clone, PartialEq, Eq, FromRow)]
+pub struct KeywordAlias {
+    pub alias: String,
+    pub keyword_id: i64,
+    pub keyword_name: String,
+}
+
+/// Keywords to merge into the keyword their names are an alias of.
+#[derive(Debug, Clone, PartialEq, Eq, FromRow)]
+pub struct AliasedKeywords {
+    pub keyword_id: i64,
+    pub duplicate_ids: Vec<i64>,
+}
+
+#[derive(FromRow)]
+struct KeywordRule {
+    key: String,
+    stoplisted: bool,
+    alias_of: Option<String>,
+}
+
+/// The key under which a keyword string is stored in `keyword_alias` and `keyword_stoplist`.
+#[must_use]
+pub fn keyword_key(name: &str) -> String {
+    EntityKind::Keyword.normalize(name).to_lowercase()
+}
+
+/// Normalizes the keywords, drops the stop-listed ones and rewrites the aliases to the keywords
+/// they stand for. The duplicates left are removed, ignoring case.
+///
+/// # Errors
+/// If the query fails.
+pub async fn resolve_keywords(
+    tx: &mut Transaction<'_, Postgres>,
+    keywords: &[String],
+) -> Result<Vec<String>, AppError> {
+    let keys: Vec<String> = keywords
+        .iter()
+        .map(|keyword| keyword_key(keyword))
+        .collect();
+    let rules: HashMap<String, KeywordRule> = sqlx::query_as::<_, KeywordRule>(
+        r"
+        SELECT
+            k.key,
+            EXISTS (SELECT 1 FROM keyword_stoplist s WHERE s.keyword = k.key) AS stoplisted,
+            kw.name AS alias_of
+        FROM (SELECT DISTINCT unnest($1::text[]) AS key) k
+        LEFT JOIN keyword_alias ka ON ka.alias = k.key
+        LEFT JOIN keyword kw ON kw.id = ka.keyword_id
+        ",
+    )
+    .bind(&keys)
+    .fetch_all(&mut **tx)
+    .await
+    .map_err(|e| AppError::GenericError(e.to_string()))?
+    .into_iter()
+    .map(|rule| (rule.key.clone(), rule))
+    .collect();
+
+    let mut resolved: Vec<String> = Vec::new();
+    for (keyword, key) in keywords.iter().zip(&keys) {
+        if key.is_empty() {
+            continue;
+        }
+        let keyword = match rules.get(key) {
+            Some(rule) if rule.stoplisted => continue,
+            Some(KeywordRule {
+                alias_of: Some(alias_of),
+                ..
+            }) => alias_of.clone(),
+            _ => EntityKind::Keyword.normalize(keyword),
+        };
+        if !resolved
+            .iter()
+            .any(|known| known.eq_ignore_ascii_case(&keyword))
+        {
+            resolved.push(keyword);
+        }
+    }
+    Ok(resolved)
+}
+
+/// Rewrites `alias` to the keyword named `to`, which is created if it doesn't exist yet.
+///
+/// # Errors
+/// - If `alias` and `to` are the same keyword.
+/// - If any of the queries fails.
+#[instrument(skip(pool))]
+pub async fn add_keyword_alias(pool: &PgPool, alias: &str, to: &str) -> Result<i64, AppError> {
+    let alias = keyword_key(alias);
+    let to = EntityKind::Keyword.normalize(to);
+    if alias.is_empty() || to.is_empty() || alias == to.to_lowercase() {
+        return Err(AppError::GenericError(format!(
+            "Can't alias `{alias}` to `{to}`"
+        )));
+    }
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| AppError::GenericError(e.to_string()))?;
+    let keyword_id = upsert_entity(&mut tx, EntityKind::Keyword, &to).await?;
+    sqlx::query(
+        r"
+        INSERT INTO keyword_alias (alias, keyword_id) VALUES ($1, $2)
+        ON CONFLICT (alias) DO UPDATE SET
+            keyword_id = EXCLUDED.keyword_id,
+            created_at = CURRENT_TIMESTAMP
+        ",
+    )
+    .bind(&alias)
+    .bind(keyword_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| AppError::GenericError(e.to_string()))?;
+    tx.commit()
+        .await
+        .map_err(|e| AppError::GenericError(e.to_string()))?;
+    Ok(keyword_id)
+}
+
+/// Returns all the aliases, ordered by the keyword they stand for.
+///
+/// # Errors
+/// If the query fails.
+#[instrument(skip(pool))]
+pub async fn get_keyword_aliases(pool: &PgPool) -> Result<Vec<KeywordAlias>, AppError> {
+    sqlx::query_as::<_, KeywordAlias>(
+        r"
+        SELECT ka.alias, ka.keyword_id, k.name AS keyword_name
+        FROM keyword_alias ka
+        JOIN keyword k ON k.id = ka.keyword_id
+        ORDER BY k.name, ka.alias
+        ",
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| AppError::GenericError(e.to_string()))
+}
+
+/// Adds `keyword` to the stop-list, or removes it from it.
+///
+/// # Errors
+/// If the query fails.
+#[instrument(skip(pool))]
+pub async fn set_keyword_stoplisted(
+    pool: &PgPool,
+    keyword: &str,
+    stoplisted: bool,
+) -> Result<(), AppError> {
+    let query = if stoplisted {
+        "INSERT INTO keyword_stoplist (keyword) VALUES ($1) ON CONFLICT (keyword) DO NOTHING"
+    } else {
+        "DELETE FROM keyword_stoplist WHERE keyword = $1"
+    };
+    sqlx::query(query)
+        .bind(keyword_key(keyword))
+        .execute(pool)
+        .await
+        .map_err(|e| AppError::GenericError(e.to_string()))?;
+    Ok(())
+}
+
+/// Returns the stop-list, in alphabetical order.
+///
+/// # Errors
+/// If the query fails.
+#[instrument(skip(pool))]
+pub async fn get_keyword_stoplist(pool: &PgPool) -> Result<Vec<String>, AppError> {
+    sqlx::query_scalar("SELECT keyword FROM keyword_stoplist ORDER BY keyword")
+        .fetch_all(pool)
+        .await
+        .map_err(|e| AppError::GenericError(e.to_string()))
+}
+
+/// Groups the keywords named `keywords` as synonyms, creating the ones that don't exist yet. The
+/// groups some of them already belong to are merged into a single one.
+///
+/// # Errors
+/// If any of the queries fails.
+#[instrument(skip(pool))]
+pub async fn add_keyword_synonyms(pool: &PgPool, keywords: &[String]) -> Result<i64, AppError> {
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| AppError::GenericError(e.to_string()))?;
+    let mut keyword_ids = Vec::new();
+    for keyword in keywords {
+        let keyword = EntityKind::Keyword.normalize(keyword);
+        if !keyword.is_empty() {
+            keyword_ids.push(upsert_entity(&mut tx, EntityKind::Keyword, &keyword).await?);
+        }
+    }
+    let existing_groups: Vec<i64> = sqlx::query_scalar(
+        "SELECT DISTINCT group_id FROM keyword_synonym WHERE keyword_id = ANY($1) ORDER BY group_id",
+    )
+    .bind(&keyword_ids)
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(|e| AppError::GenericError(e.to_string()))?;
+    let group_id = match existing_groups.split_first() {
+        Some((group_id, others)) => {
+            sqlx::query("UPDATE keyword_synonym SET group_id = $1 WHERE group_id = ANY($2)")
+                .bind(group_id)
+                .bind(others)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| AppError::GenericError(e.to_string()))?;
+            sqlx::query("DELETE FROM keyword_synonym_group WHERE id = ANY($1)")
+                .bind(others)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| AppError::GenericError(e.to_string()))?;
+            *group_id
+        }
+        None => sqlx::query_scalar("INSERT INTO keyword_synonym_group DEFAULT VALUES RETURNING id")
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(|e| AppError::GenericError(e.to_string()))?,
+    };
+    sqlx::query(
+        r"
+        INSERT INTO keyword_synonym (keyword_id, group_id)
+        SELECT unnest($1::bigint[]), $2
+        ON CONFLICT (keyword_id) DO UPDATE SET group_id = EXCLUDED.group_id
+        ",
+    )
+    .bind(&keyword_ids)
+    .bind(group_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| AppError::GenericError(e.to_string()))?;
+    tx.commit()
+        .await
+        .map_err(|e| AppError::GenericError(e.to_string()))?;
+    Ok(group_id)
+}
+
+/// Removes the keyword named `keyword` from its group of synonyms. The groups left with a single
+/// keyword are dissolved.
+///
+/// # Errors
+/// If any of the queries fails.
+#[instrument(skip(pool))]
+pub async fn remove_keyword_synonym(pool: &PgPool, keyword: &str) -> Result<(), AppError> {
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| AppError::GenericError(e.to_string()))?;
+    sqlx::query(
+        r"
+        DELETE FROM keyword_synonym ks
+        USING keyword k
+        WHERE k.id = ks.keyword_id AND lower(k.name) = $1
+        ",
+    )
+    .bind(keyword_key(keyword))
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| AppError::GenericError(e.to_string()))?;
+    sqlx::query(
+        r"
+        DELETE FROM keyword_synonym_group g
+        WHERE (SELECT COUNT(*) FROM keyword_synonym ks WHERE ks.group_id = g.id) < 2
+        ",
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| AppError::GenericError(e.to_string()))?;
+    tx.commit()
+        .await
+        .map_err(|e| AppError::GenericError(e.to_string()))?;
+    Ok(())
+}
+
+/// Returns the names of the keywords of every group of synonyms.
+///
+/// # Errors
+/// If the query fails.
+#[instrument(skip(pool))]
+pub async fn get_keyword_synonym_groups(pool: &PgPool) -> Result<Vec<Vec<String>>, AppError> {
+    sqlx::query_scalar(
+        r"
+        SELECT array_agg(k.name ORDER BY k.name)
+        FROM keyword_synonym ks
+        JOIN keyword k ON k.id = ks.keyword_id
+        GROUP BY ks.group_id
+        ORDER BY min(k.name)
+        ",
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| AppError::GenericError(e.to_string()))
+}
+
+/// Deletes the keywords in the stop-list, with their links and subscriptions.
+///
+/// # Errors
+/// If the query fails.
+#[instrument(skip(pool))]
+pub async fn delete_stoplisted_keywords(pool: &PgPool) -> Result<u64, AppError> {
+    sqlx::query(
+        r"
+        DELETE FROM keyword k
+        USING keyword_stoplist s
+        WHERE lower(k.name) = s.keyword
+        ",
+    )
+    .execute(pool)
+    .await
+    .map(|result| result.rows_affected())
+    .map_err(|e| AppError::GenericError(e.to_string()))
+}
+
+/// Returns the existing keywords whose names are an alias of another keyword, grouped by the
+/// keyword they stand for.
+///
+/// # Errors
+/// If the query fails.
+#[instrument(skip(pool))]
+pub async fn get_aliased_keywords(pool: &PgPool) -> Result<Vec<AliasedKeywords>, AppError> {
+    sqlx::query_as::<_, AliasedKeywords>(
+        r"
+        SELECT ka.keyword_id, array_agg(k.id ORDER BY k.id) AS duplicate_ids
+        FROM keyword_alias ka
+        JOIN keyword k ON lower(k.name) = ka.alias AND k.id <> ka.keyword_id
+        GROUP BY ka.keyword_id
+        ORDER BY ka.keyword_id
+        ",
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| AppError::GenericError(e.to_string()))
+}
+
+/// Returns the users subscribed to any keyword of the audiobook, or to any of their synonyms, in
+/// the language of the audiobook.
+///
+/// # Errors
+/// If the query fails.
+#[instrument(skip(pool))]
+pub async fn get_keyword_subscribers(
+    pool: &PgPool,
+    audiobook_id: i64,
+) -> Result<Vec<i64>, AppError> {
+    let language_condition = subscription_language_condition("ukn");
+    sqlx::query_scalar(&format!(
+        r"
+        SELECT DISTINCT ukn.user_id
+        FROM audiobook_keyword abk
+        JOIN audiobook ab ON ab.id = abk.audiobook_id
+        LEFT JOIN keyword_synonym ks ON ks.keyword_id = abk.keyword_id
+        JOIN user_keyword_notification ukn ON ukn.keyword_id = abk.keyword_id
+            OR ukn.keyword_id IN (
+                SELECT s.keyword_id FROM keyword_synonym s WHERE s.group_id = ks.group_id
+            )
+        WHERE abk.audiobook_id = $1 AND {language_condition}
+        "
+    ))
+    .bind(audiobook_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| AppError::GenericError(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keyword_key() {
+        assert_eq!(keyword_key("  Magic  System "), "magic system");
+        assert_eq!(keyword_key("Unabridged;"), "unabridged");
+    }
+
+    async fn insert_user(pool: &PgPool, username: &str) -> i64 {
+        sqlx::query_scalar(
+            r"
+            INSERT INTO users (username, anonymous, password_mcf, last_access)
+            VALUES ($1, FALSE, '', NOW())
+            RETURNING id
+            ",
+        )
+        .bind(username)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+    }
+
+    async fn insert_keyword(pool: &PgPool, name: &str) -> i64 {
+        let mut tx = pool.begin().await.unwrap();
+        let keyword_id = upsert_entity(&mut tx, EntityKind::Keyword, name)
+            .await
+            .unwrap();
+        tx.commit().await.unwrap();
+        keyword_id
+    }
+
+    /// Inserts an audiobook with the keywords, returning its id.
+    async fn insert_audiobook(pool: &PgPool, keyword_ids: &[i64]) -> i64 {
+        let audiobook_id: i64 = sqlx::query_scalar(
+            r"
+            INSERT INTO audiobook (
+                title, cover_url, description, description_for_embeddings, format, language, path,
+                timestamp_created, timestamp_ingested, very_short_description
+            )
+            VALUES ('Mistborn', '', '', '', 'MP3', 'en', gen_random_uuid()::TEXT, NOW(), NOW(), '')
+            RETURNING id
+            ",
+        )
+        .fetch_one(pool)
+        .await
+        .unwrap();
+        sqlx::query(
+            "INSERT INTO audiobook_keyword (audiobook_id, keyword_id) SELECT $1, unnest($2::bigint[])",
+        )
+        .bind(audiobook_id)
+        .bind(keyword_ids)
+        .execute(pool)
+        .await
+        .unwrap();
+        audiobook_id
+    }
+
+    async fn subscribe(pool: &PgPool, user_id: i64, keyword_id: i64) {
+        sqlx::query("INSERT INTO user_keyword_notification (user_id, keyword_id) VALUES ($1, $2)")
+            .bind(user_id)
+            .bind(keyword_id)
+            .execute(pool)
+            .await
+            .unwrap();
+    }
+
+    #[sqlx::test(migrations = "../model/migrations")]
+    async fn test_resolve_keywords(pool: PgPool) {
+        add_keyword_alias(&pool, "Magical", "Magic").await.unwrap();
+        set_keyword_stoplisted(&pool, "Chosen One", true)
+            .await
+            .unwrap();
+
+        let mut tx = pool.begin().await.unwrap();
+        let resolved = resolve_keywords(
+            &mut tx,
+            &[
+                String::from("magical"),
+                String::from("Audiobook"),
+                String::from("Magic"),
+                String::from(" time  travel "),
+                String::from("Time Travel"),
+                String::from("chosen one"),
+                String::from(";"),
+            ],
+        )
+        .await
+        .unwrap();
+        assert_eq!(resolved, vec!["Magic", "time travel"]);
+    }
+
+    #[sqlx::test(migrations = "../model/migrations")]
+    async fn test_keyword_subscribers_include_the_synonyms(pool: PgPool) {
+        let magic = insert_keyword(&pool, "Magic").await;
+        let magic_system = insert_keyword(&pool, "Magic System").await;
+        let dragons = insert_keyword(&pool, "Dragons").await;
+        add_keyword_synonyms(
+            &pool,
+            &[String::from("Magic"), String::from("Magic System")],
+        )
+        .await
+        .unwrap();
+        let magic_subscriber = insert_user(&pool, "magic").await;
+        let magic_system_subscriber = insert_user(&pool, "magic system").await;
+        let dragons_subscriber = insert_user(&pool, "dragons").await;
+        subscribe(&pool, magic_subscriber, magic).await;
+        subscribe(&pool, magic_system_subscriber, magic_system).await;
+        subscribe(&pool, dragons_subscriber, dragons).await;
+
+        let subscribers = |audiobook_id| {
+            let pool = pool.clone();
+            async move {
+                let mut subscribers = get_keyword_subscribers(&pool, audiobook_id).await.unwrap();
+                subscribers.sort_unstable();
+                subscribers
+            }
+        };
+        let magic_audiobook = insert_audiobook(&pool, &[magic]).await;
+        assert_eq!(
+            subscribers(magic_audiobook).await,
+            vec![magic_subscriber, magic_system_subscriber]
+        );
+        let dragons_audiobook = insert_audiobook(&pool, &[dragons]).await;
+        assert_eq!(
+            subscribers(dragons_audiobook).await,
+            vec![dragons_subscriber]
+        );
+    }
+}
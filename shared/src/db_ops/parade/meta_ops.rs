@@ -1,5 +1,14 @@
 use crate::db_ops::AppError;
-use entities_lib::{Author, Category, Keyword, MetaRequest, MetaResponse, Reader, Series};
+use crate::db_ops::parade::audiobook_ops::{
+    TOP_RATED_CONDITION, WORK_OF_AUDIOBOOK, language_codes, language_condition,
+};
+use crate::db_ops::parade::hardcover_ops::{
+    get_hardcover_author_profile, get_hardcover_series_profile,
+};
+use crate::db_ops::parade::taxonomy_ops::CATEGORY_TREE_CTE;
+use entities_lib::{
+    Author, Category, Keyword, Language, LanguageFilter, MetaRequest, MetaResponse, Reader, Series,
+};
 use moka::future::Cache;
 use sqlx::{FromRow, PgPool};
 use tracing::instrument;
@@ -24,16 +33,20 @@ fn get_limit_offset(page: u32, limit: u32) -> (i64, i64) {
 
 /// Returns the meta queries either from cache or by hitting the DB.
 ///
+/// The audiobook counts only include the audiobooks in `languages`, or in any language if it's
+/// empty, so that they match the listings.
+///
 /// # Errors
 /// - If the request is not present and so the query to hit the DB fails.
 #[allow(clippy::too_many_lines)]
 #[instrument(skip_all)]
 pub async fn get_meta_cached(
     db_pool: &PgPool,
-    cache: &Cache<MetaRequest, Result<MetaResponse, AppError>>,
+    cache: &Cache<(MetaRequest, LanguageFilter), Result<MetaResponse, AppError>>,
     request: MetaRequest,
+    languages: &[Language],
 ) -> Result<MetaResponse, AppError> {
-    let key = request.clone();
+    let key = (request.clone(), languages.to_vec());
     cache
         .get_with(key, async {
             match request {
@@ -114,32 +127,55 @@ pub async fn get_meta_cached(
                         .map(MetaResponse::Series)
                 }
                 MetaRequest::CountAudiobooksForCategory(category) => {
-                    count_audiobooks_for_category(db_pool, category.id)
+                    count_audiobooks_for_category(db_pool, category.id, languages)
+                        .await
+                        .map(MetaResponse::Count)
+                }
+                MetaRequest::CountAudiobooksForCategoryTree(category) => {
+                    count_audiobooks_for_category_tree(db_pool, category.id, languages)
                         .await
                         .map(MetaResponse::Count)
                 }
+                MetaRequest::SubcategoriesOf(category) => get_subcategories(db_pool, category.id)
+                    .await
+                    .map(MetaResponse::Categories),
                 MetaRequest::CountAudiobooksForKeyword(keyword) => {
-                    count_audiobooks_for_keyword(db_pool, keyword.id)
+                    count_audiobooks_for_keyword(db_pool, keyword.id, languages)
                         .await
                         .map(MetaResponse::Count)
                 }
                 MetaRequest::CountAudiobooksForAuthor(author) => {
-                    count_audiobooks_for_author(db_pool, author.id)
+                    count_audiobooks_for_author(db_pool, author.id, languages)
                         .await
                         .map(MetaResponse::Count)
                 }
                 MetaRequest::CountAudiobooksForReader(reader) => {
-                    count_audiobooks_for_reader(db_pool, reader.id)
+                    count_audiobooks_for_reader(db_pool, reader.id, languages)
                         .await
                         .map(MetaResponse::Count)
                 }
                 MetaRequest::CountAudiobooksInSeries(series) => {
-                    count_audiobooks_in_series(db_pool, series.id)
+                    count_audiobooks_in_series(db_pool, series.id, languages)
                         .await
                         .map(MetaResponse::Count)
                 }
-                MetaRequest::CountAllAudiobooks => {
-                    count_all_audiobooks(db_pool).await.map(MetaResponse::Count)
+                MetaRequest::CountTopRatedAudiobooks(min_ratings) => {
+                    count_top_rated_audiobooks(db_pool, min_ratings, languages)
+                        .await
+                        .map(MetaResponse::Count)
+                }
+                MetaRequest::CountAllAudiobooks => count_all_audiobooks(db_pool, languages)
+                    .await
+                    .map(MetaResponse::Count),
+                MetaRequest::HardcoverAuthorProfile(author) => {
+                    get_hardcover_author_profile(db_pool, author.id)
+                        .await
+                        .map(MetaResponse::HardcoverAuthorProfile)
+                }
+                MetaRequest::HardcoverSeriesProfile(series) => {
+                    get_hardcover_series_profile(db_pool, series.id)
+                        .await
+                        .map(MetaResponse::HardcoverSeriesProfile)
                 }
             }
         })
@@ -193,15 +229,20 @@ async fn get_categories_by_published_audiobooks(
     page: u32,
     limit: u32,
 ) -> Result<Vec<Category>, AppError> {
-    let query = "
+    // Only the taxonomy is listed, counting the audiobooks of the descendants too.
+    let query = format!(
+        "
+        WITH {CATEGORY_TREE_CTE}
         SELECT c.id, c.name
         FROM category c
-        LEFT JOIN audiobook_category abc ON c.id = abc.category_id
+        JOIN category_tree t ON t.root_id = c.id
+        LEFT JOIN audiobook_category abc ON t.category_id = abc.category_id
         GROUP BY c.id, c.name
-        ORDER BY COUNT(abc.audiobook_id) DESC, c.name ASC
+        ORDER BY COUNT(DISTINCT abc.audiobook_id) DESC, c.name ASC
         LIMIT $1 OFFSET $2
-    ";
-    execute_id_name_query(pool, query, page, limit, |r| Category {
+    "
+    );
+    execute_id_name_query(pool, &query, page, limit, |r| Category {
         id: r.id,
         value: r.name,
     })
@@ -298,6 +339,7 @@ async fn get_categories_by_n_subscribers(
     let query = "
         SELECT c.id, c.name
         FROM category c
+        JOIN category_taxonomy ct ON c.id = ct.category_id
         LEFT JOIN user_category_notification ucn ON c.id = ucn.category_id
         GROUP BY c.id, c.name
         ORDER BY COUNT(ucn.user_id) DESC, c.name ASC
@@ -396,9 +438,10 @@ async fn get_categories_alphabetically(
     limit: u32,
 ) -> Result<Vec<Category>, AppError> {
     let query = "
-        SELECT id, name
-        FROM category
-        ORDER BY name ASC
+        SELECT c.id, c.name
+        FROM category c
+        JOIN category_taxonomy ct ON c.id = ct.category_id
+        ORDER BY c.name ASC
         LIMIT $1 OFFSET $2
     ";
     execute_id_name_query(pool, query, page, limit, |r| Category {
@@ -480,65 +523,184 @@ async fn get_series_alphabetically(
     .await
 }
 
-#[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
-async fn count_audiobooks_for_category(pool: &PgPool, category_id: i64) -> Result<u32, AppError> {
-    let count: i64 =
-        sqlx::query_scalar("SELECT COUNT(*) FROM audiobook_category WHERE category_id = $1")
-            .bind(category_id)
-            .fetch_one(pool)
-            .await
-            .map_err(|e| AppError::DeserializationError(format!("Database query failed: {e}")))?;
-    Ok(count as u32)
+async fn count_audiobooks_for_category(
+    pool: &PgPool,
+    category_id: i64,
+    languages: &[Language],
+) -> Result<u32, AppError> {
+    count_works_linked_to(
+        pool,
+        "audiobook_category",
+        "category_id",
+        category_id,
+        languages,
+    )
+    .await
 }
 
 #[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
-async fn count_audiobooks_for_keyword(pool: &PgPool, keyword_id: i64) -> Result<u32, AppError> {
-    let count: i64 =
-        sqlx::query_scalar("SELECT COUNT(*) FROM audiobook_keyword WHERE keyword_id = $1")
-            .bind(keyword_id)
-            .fetch_one(pool)
-            .await
-            .map_err(|e| AppError::DeserializationError(format!("Database query failed: {e}")))?;
+async fn count_audiobooks_for_category_tree(
+    pool: &PgPool,
+    category_id: i64,
+    languages: &[Language],
+) -> Result<u32, AppError> {
+    let language_condition = language_condition(2);
+    let count: i64 = sqlx::query_scalar(&format!(
+        "
+        WITH {CATEGORY_TREE_CTE}
+        SELECT COUNT(DISTINCT {WORK_OF_AUDIOBOOK})
+        FROM audiobook_category abc
+        JOIN audiobook ab ON ab.id = abc.audiobook_id
+        WHERE (
+                abc.category_id = $1
+                OR abc.category_id IN (SELECT category_id FROM category_tree WHERE root_id = $1)
+            )
+            AND {language_condition}
+        "
+    ))
+    .bind(category_id)
+    .bind(language_codes(languages))
+    .fetch_one(pool)
+    .await
+    .map_err(|e| AppError::DeserializationError(format!("Database query failed: {e}")))?;
     Ok(count as u32)
 }
 
+async fn get_subcategories(pool: &PgPool, category_id: i64) -> Result<Vec<Category>, AppError> {
+    let rows = sqlx::query_as::<_, IdNameRow>(
+        "
+        SELECT c.id, c.name
+        FROM category_taxonomy ct
+        JOIN category c ON c.id = ct.category_id
+        WHERE ct.parent_id = $1
+        ORDER BY c.name ASC
+    ",
+    )
+    .bind(category_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| AppError::DeserializationError(format!("Database query failed: {e}")))?;
+    Ok(rows
+        .into_iter()
+        .map(|r| Category {
+            id: r.id,
+            value: r.name,
+        })
+        .collect())
+}
+
+async fn count_audiobooks_for_keyword(
+    pool: &PgPool,
+    keyword_id: i64,
+    languages: &[Language],
+) -> Result<u32, AppError> {
+    count_works_linked_to(
+        pool,
+        "audiobook_keyword",
+        "keyword_id",
+        keyword_id,
+        languages,
+    )
+    .await
+}
+
+async fn count_audiobooks_for_author(
+    pool: &PgPool,
+    author_id: i64,
+    languages: &[Language],
+) -> Result<u32, AppError> {
+    count_works_linked_to(pool, "audiobook_author", "author_id", author_id, languages).await
+}
+
+async fn count_audiobooks_for_reader(
+    pool: &PgPool,
+    reader_id: i64,
+    languages: &[Language],
+) -> Result<u32, AppError> {
+    count_works_linked_to(pool, "audiobook_reader", "reader_id", reader_id, languages).await
+}
+
 #[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
-async fn count_audiobooks_for_author(pool: &PgPool, author_id: i64) -> Result<u32, AppError> {
-    let count: i64 =
-        sqlx::query_scalar("SELECT COUNT(*) FROM audiobook_author WHERE author_id = $1")
-            .bind(author_id)
-            .fetch_one(pool)
-            .await
-            .map_err(|e| AppError::DeserializationError(format!("Database query failed: {e}")))?;
+async fn count_audiobooks_in_series(
+    pool: &PgPool,
+    series_id: i64,
+    languages: &[Language],
+) -> Result<u32, AppError> {
+    let language_condition = language_condition(2);
+    let count: i64 = sqlx::query_scalar(&format!(
+        "
+        SELECT COUNT(DISTINCT {WORK_OF_AUDIOBOOK})
+        FROM audiobook ab
+        WHERE ab.series_id = $1 AND {language_condition}
+        "
+    ))
+    .bind(series_id)
+    .bind(language_codes(languages))
+    .fetch_one(pool)
+    .await
+    .map_err(|e| AppError::DeserializationError(format!("Database query failed: {e}")))?;
     Ok(count as u32)
 }
 
 #[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
-async fn count_audiobooks_for_reader(pool: &PgPool, reader_id: i64) -> Result<u32, AppError> {
-    let count: i64 =
-        sqlx::query_scalar("SELECT COUNT(*) FROM audiobook_reader WHERE reader_id = $1")
-            .bind(reader_id)
-            .fetch_one(pool)
-            .await
-            .map_err(|e| AppError::DeserializationError(format!("Database query failed: {e}")))?;
+async fn count_top_rated_audiobooks(
+    pool: &PgPool,
+    min_ratings: u32,
+    languages: &[Language],
+) -> Result<u32, AppError> {
+    let language_condition = language_condition(2);
+    let count: i64 = sqlx::query_scalar(&format!(
+        "
+        SELECT COUNT(DISTINCT {WORK_OF_AUDIOBOOK})
+        FROM hardcover_audiobook_metadata hc
+        JOIN audiobook ab ON ab.id = hc.audiobook_id
+        WHERE {TOP_RATED_CONDITION} AND {language_condition}
+        "
+    ))
+    .bind(i64::from(min_ratings))
+    .bind(language_codes(languages))
+    .fetch_one(pool)
+    .await
+    .map_err(|e| AppError::DeserializationError(format!("Database query failed: {e}")))?;
     Ok(count as u32)
 }
 
 #[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
-async fn count_audiobooks_in_series(pool: &PgPool, series_id: i64) -> Result<u32, AppError> {
-    let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM audiobook WHERE series_id = $1")
-        .bind(series_id)
-        .fetch_one(pool)
-        .await
-        .map_err(|e| AppError::DeserializationError(format!("Database query failed: {e}")))?;
+async fn count_all_audiobooks(pool: &PgPool, languages: &[Language]) -> Result<u32, AppError> {
+    let language_condition = language_condition(1);
+    let count: i64 = sqlx::query_scalar(&format!(
+        "SELECT COUNT(DISTINCT {WORK_OF_AUDIOBOOK}) FROM audiobook ab WHERE {language_condition}"
+    ))
+    .bind(language_codes(languages))
+    .fetch_one(pool)
+    .await
+    .map_err(|e| AppError::DeserializationError(format!("Database query failed: {e}")))?;
     Ok(count as u32)
 }
 
+/// Counts the works with an edition in `languages` linked to the entity `entity_id` through
+/// `join_table`.
 #[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
-async fn count_all_audiobooks(pool: &PgPool) -> Result<u32, AppError> {
-    let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM audiobook")
-        .fetch_one(pool)
-        .await
-        .map_err(|e| AppError::DeserializationError(format!("Database query failed: {e}")))?;
+async fn count_works_linked_to(
+    pool: &PgPool,
+    join_table: &str,
+    foreign_key: &str,
+    entity_id: i64,
+    languages: &[Language],
+) -> Result<u32, AppError> {
+    let language_condition = language_condition(2);
+    let count: i64 = sqlx::query_scalar(&format!(
+        "
+        SELECT COUNT(DISTINCT {WORK_OF_AUDIOBOOK})
+        FROM {join_table} j
+        JOIN audiobook ab ON ab.id = j.audiobook_id
+        WHERE j.{foreign_key} = $1 AND {language_condition}
+        "
+    ))
+    .bind(entity_id)
+    .bind(language_codes(languages))
+    .fetch_one(pool)
+    .await
+    .map_err(|e| AppError::DeserializationError(format!("Database query failed: {e}")))?;
     Ok(count as u32)
 }
@@ -5,11 +5,20 @@ use tracing::info;
 use crate::private_args::Args;
 
 pub mod audiobook_ops;
+pub mod backfill_run_ops;
+pub mod book_metadata_ops;
+pub mod entity_ops;
+pub mod hardcover_ops;
+pub mod keyword_ops;
 pub mod meta_ops;
 pub mod notifications;
+pub mod quarantine_ops;
+pub mod raw_page_ops;
 pub mod save_ops;
 pub mod search_ops;
 pub mod subscription_ops;
+pub mod taxonomy_ops;
+pub mod work_ops;
 
 /// Creates a `PGPool` or panics.
 ///
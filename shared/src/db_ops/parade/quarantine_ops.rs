@@ -0,0 +1,54 @@
+//! Extractions that failed validation are quarantined instead of being inserted, so they can be
+//! inspected and the prompt or the selectors fixed.
+
+use sqlx::PgPool;
+use tracing::{instrument, warn};
+
+use crate::db_ops::AppError;
+use crate::extracted_audiobook::InvalidExtraction;
+
+/// Stores an invalid extraction of the page at `url`, replacing any previous one.
+///
+/// # Errors
+/// If the insert fails.
+#[instrument(skip(pool, invalid))]
+pub async fn quarantine_extraction(
+    pool: &PgPool,
+    url: &str,
+    invalid: &InvalidExtraction,
+) -> Result<(), AppError> {
+    let errors: Vec<String> = invalid.errors.iter().map(ToString::to_string).collect();
+    sqlx::query(
+        r"
+        INSERT INTO extraction_quarantine (url, extracted_values, errors)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (url) DO UPDATE SET
+            extracted_values = EXCLUDED.extracted_values,
+            errors = EXCLUDED.errors,
+            quarantined_at = CURRENT_TIMESTAMP
+        ",
+    )
+    .bind(url)
+    .bind(&invalid.raw)
+    .bind(&errors)
+    .execute(pool)
+    .await
+    .map_err(|e| AppError::GenericError(e.to_string()))?;
+
+    warn!("Quarantined extraction of {}: {}", url, errors.join(", "));
+    Ok(())
+}
+
+/// Removes the quarantined extraction of `url`, if any, e.g. once the page was ingested.
+///
+/// # Errors
+/// If the delete fails.
+#[instrument(skip(pool))]
+pub async fn release_quarantined_extraction(pool: &PgPool, url: &str) -> Result<(), AppError> {
+    sqlx::query("DELETE FROM extraction_quarantine WHERE url = $1")
+        .bind(url)
+        .execute(pool)
+        .await
+        .map_err(|e| AppError::GenericError(e.to_string()))?;
+    Ok(())
+}
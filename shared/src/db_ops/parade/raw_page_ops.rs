@@ -0,0 +1,232 @@
+//! Archive of the raw pages fetched by the scraper.
+//!
+//! Pages are stored zstd-compressed and addressed by the sha256 of their content, so fetching the
+//! same page twice only records a new fetch.
+
+use std::fmt::Display;
+
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+use sqlx::{FromRow, PgPool};
+use tracing::{debug, instrument};
+
+use crate::db_ops::AppError;
+
+const ZSTD_COMPRESSION_LEVEL: i32 = 9;
+
+/// The kind of page that was archived.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PageKind {
+    Submission,
+    Audiobook,
+}
+
+impl Display for PageKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PageKind::Submission => write!(f, "submission"),
+            PageKind::Audiobook => write!(f, "audiobook"),
+        }
+    }
+}
+
+/// A page read back from the archive.
+#[derive(Debug, Clone)]
+pub struct ArchivedPage {
+    pub url: String,
+    pub fetched_at: DateTime<Utc>,
+    pub content: String,
+}
+
+#[derive(FromRow)]
+struct ArchivedPageRow {
+    url: String,
+    fetched_at: DateTime<Utc>,
+    compressed_content: Vec<u8>,
+}
+
+impl TryFrom<ArchivedPageRow> for ArchivedPage {
+    type Error = AppError;
+
+    fn try_from(row: ArchivedPageRow) -> Result<Self, Self::Error> {
+        let decompressed = zstd::decode_all(row.compressed_content.as_slice())
+            .map_err(|e| AppError::DeserializationError(e.to_string()))?;
+        let content = String::from_utf8(decompressed)
+            .map_err(|e| AppError::DeserializationError(e.to_string()))?;
+        Ok(ArchivedPage {
+            url: row.url,
+            fetched_at: row.fetched_at,
+            content,
+        })
+    }
+}
+
+/// Stores a fetched page in the archive.
+///
+/// # Errors
+/// If the compression or any of the inserts fails.
+#[instrument(skip(pool, content))]
+pub async fn archive_page(
+    pool: &PgPool,
+    url: &str,
+    page_kind: PageKind,
+    content: &str,
+) -> Result<(), AppError> {
+    let content_hash = format!("{:x}", Sha256::digest(content.as_bytes()));
+    let compressed = zstd::encode_all(content.as_bytes(), ZSTD_COMPRESSION_LEVEL)
+        .map_err(|e| AppError::GenericError(e.to_string()))?;
+
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| AppError::GenericError(e.to_string()))?;
+    sqlx::query(
+        "INSERT INTO raw_page_blob (content_hash, compressed_content) VALUES ($1, $2) ON CONFLICT DO NOTHING",
+    )
+    .bind(&content_hash)
+    .bind(compressed)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| AppError::GenericError(e.to_string()))?;
+    sqlx::query("INSERT INTO raw_page_fetch (url, page_kind, content_hash) VALUES ($1, $2, $3)")
+        .bind(url)
+        .bind(page_kind.to_string())
+        .bind(&content_hash)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| AppError::GenericError(e.to_string()))?;
+    tx.commit()
+        .await
+        .map_err(|e| AppError::GenericError(e.to_string()))?;
+
+    debug!("Archived page {} with hash {}", url, content_hash);
+    Ok(())
+}
+
+/// Returns the most recent archived version of the page at `url`, if any.
+///
+/// # Errors
+/// If the query or the decompression fails.
+#[instrument(skip(pool))]
+pub async fn get_latest_archived_page(
+    pool: &PgPool,
+    url: &str,
+) -> Result<Option<ArchivedPage>, AppError> {
+    let maybe_row = sqlx::query_as::<_, ArchivedPageRow>(
+        r"
+        SELECT f.url, f.fetched_at, b.compressed_content
+        FROM raw_page_fetch f
+        JOIN raw_page_blob b ON f.content_hash = b.content_hash
+        WHERE f.url = $1
+        ORDER BY f.fetched_at DESC
+        LIMIT 1
+        ",
+    )
+    .bind(url)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| AppError::GenericError(e.to_string()))?;
+
+    maybe_row.map(ArchivedPage::try_from).transpose()
+}
+
+/// The id and the scraped url of an audiobook.
+#[derive(Debug, Clone, FromRow)]
+pub struct AudiobookPath {
+    pub id: i64,
+    pub path: String,
+}
+
+/// Returns the paths of the audiobooks with the given ids.
+///
+/// # Errors
+/// If the query fails.
+#[instrument(skip(pool))]
+pub async fn get_audiobook_paths_by_ids(
+    pool: &PgPool,
+    ids: &[i64],
+) -> Result<Vec<AudiobookPath>, AppError> {
+    sqlx::query_as::<_, AudiobookPath>(
+        "SELECT id, path FROM audiobook WHERE id = ANY($1) ORDER BY id ASC",
+    )
+    .bind(ids)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| AppError::GenericError(e.to_string()))
+}
+
+/// Returns the paths of the audiobooks ingested in `[from, to)`.
+///
+/// # Errors
+/// If the query fails.
+#[instrument(skip(pool))]
+pub async fn get_audiobook_paths_ingested_between(
+    pool: &PgPool,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> Result<Vec<AudiobookPath>, AppError> {
+    sqlx::query_as::<_, AudiobookPath>(
+        r"
+        SELECT id, path FROM audiobook
+        WHERE timestamp_ingested >= $1 AND timestamp_ingested < $2
+        ORDER BY id ASC
+        ",
+    )
+    .bind(from)
+    .bind(to)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| AppError::GenericError(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn count(pool: &PgPool, table: &str) -> i64 {
+        sqlx::query_scalar(&format!("SELECT COUNT(*) FROM {table}"))
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    #[sqlx::test(migrations = "../model/migrations")]
+    async fn test_archive_then_read_the_latest_page(pool: PgPool) {
+        let url = "https://example.com/audiobook";
+        assert!(
+            get_latest_archived_page(&pool, url)
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        archive_page(&pool, url, PageKind::Audiobook, "<html>first</html>")
+            .await
+            .unwrap();
+        let page = get_latest_archived_page(&pool, url).await.unwrap().unwrap();
+        assert_eq!(page.url, url);
+        assert_eq!(page.content, "<html>first</html>");
+
+        archive_page(&pool, url, PageKind::Audiobook, "<html>second ✓</html>")
+            .await
+            .unwrap();
+        let page = get_latest_archived_page(&pool, url).await.unwrap().unwrap();
+        assert_eq!(page.content, "<html>second ✓</html>");
+    }
+
+    #[sqlx::test(migrations = "../model/migrations")]
+    async fn test_identical_pages_share_a_blob(pool: PgPool) {
+        let url = "https://example.com/submission";
+        archive_page(&pool, url, PageKind::Submission, "<html>same</html>")
+            .await
+            .unwrap();
+        archive_page(&pool, url, PageKind::Submission, "<html>same</html>")
+            .await
+            .unwrap();
+
+        assert_eq!(count(&pool, "raw_page_fetch").await, 2);
+        assert_eq!(count(&pool, "raw_page_blob").await, 1);
+        let page = get_latest_archived_page(&pool, url).await.unwrap().unwrap();
+        assert_eq!(page.content, "<html>same</html>");
+    }
+}
@@ -1,9 +1,14 @@
-use chrono::{Local, NaiveDateTime, TimeZone, Utc};
-use serde_json::Value;
+use chrono::{DateTime, NaiveDate, NaiveTime, TimeZone, Utc};
+use entities_lib::Language;
 use sqlx::Transaction;
 use std::error::Error;
 use tracing::instrument;
 
+use crate::db_ops::parade::entity_ops::{EntityKind, upsert_entity};
+use crate::db_ops::parade::keyword_ops::resolve_keywords;
+use crate::db_ops::parade::work_ops::assign_work;
+use crate::extracted_audiobook::ExtractedAudiobook;
+
 fn parse_bitrate(s: &str) -> Option<i32> {
     s.trim()
         .to_lowercase()
@@ -31,120 +36,354 @@ fn parse_filesize(s: &str) -> Option<i64> {
     }
 }
 
+/// Parses a runtime such as `12:34:56`, `34:56`, `12 hrs 34 mins`, `12h 34m 56s` or `1.5 hours`
+/// into seconds, `None` if it doesn't fit in an `i32`.
+#[allow(clippy::cast_possible_truncation)]
+fn parse_runtime(s: &str) -> Option<i32> {
+    let s = s.trim().to_lowercase();
+    if s.is_empty() {
+        return None;
+    }
+
+    if s.contains(':') {
+        let parts = s
+            .split(':')
+            .map(|part| part.trim().parse::<i32>().ok())
+            .collect::<Option<Vec<_>>>()?;
+        let (h, m, sec) = match parts.as_slice() {
+            [h, m, sec] => (*h, *m, *sec),
+            [m, sec] => (0, *m, *sec),
+            _ => return None,
+        };
+        return h
+            .checked_mul(3600)?
+            .checked_add(m.checked_mul(60)?)?
+            .checked_add(sec);
+    }
+
+    let mut total = 0.0;
+    let mut found_unit = false;
+    let mut number = String::new();
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        // A decimal point only counts after the digits, not e.g. in `12 hrs. 34 mins`.
+        if c.is_ascii_digit() || (c == '.' && !number.is_empty()) {
+            number.push(c);
+            continue;
+        }
+        if !c.is_alphabetic() {
+            continue;
+        }
+        let mut unit = String::from(c);
+        while let Some(next) = chars.next_if(|next| next.is_alphabetic()) {
+            unit.push(next);
+        }
+        if number.is_empty() {
+            continue;
+        }
+        let multiplier = match unit.as_str() {
+            "h" | "hr" | "hrs" | "hour" | "hours" => 3600.0,
+            "m" | "min" | "mins" | "minute" | "minutes" => 60.0,
+            "s" | "sec" | "secs" | "second" | "seconds" => 1.0,
+            _ => continue,
+        };
+        total += number.parse::<f64>().ok()? * multiplier;
+        number.clear();
+        found_unit = true;
+    }
+    let total = total.round();
+    (found_unit && total <= f64::from(i32::MAX)).then_some(total as i32)
+}
+
+/// Parses the position in a series, e.g. `Book 3`, `Vol. 2.5`, `#4` or `Volume III`.
+fn parse_series_volume(s: &str) -> Option<f64> {
+    let tokens: Vec<&str> = s
+        .split(|c: char| c.is_whitespace() || c == ',' || c == ':' || c == '#')
+        .map(|token| token.trim_matches(|c: char| !c.is_alphanumeric()))
+        .filter(|token| !token.is_empty())
+        .collect();
+
+    tokens
+        .iter()
+        .find_map(|token| token.parse::<f64>().ok().filter(|v| v.is_finite()))
+        .or_else(|| tokens.iter().find_map(|token| parse_roman_numeral(token)))
+}
+
+fn parse_roman_numeral(s: &str) -> Option<f64> {
+    const NUMERALS: [(u32, &str); 13] = [
+        (1000, "M"),
+        (900, "CM"),
+        (500, "D"),
+        (400, "CD"),
+        (100, "C"),
+        (90, "XC"),
+        (50, "L"),
+        (40, "XL"),
+        (10, "X"),
+        (9, "IX"),
+        (5, "V"),
+        (4, "IV"),
+        (1, "I"),
+    ];
+    let upper = s.to_uppercase();
+    let mut rest = upper.as_str();
+    let mut value = 0;
+    for (numeral_value, numeral) in NUMERALS {
+        while let Some(stripped) = rest.strip_prefix(numeral) {
+            value += numeral_value;
+            rest = stripped;
+        }
+    }
+    // Series rarely go past 100 volumes, so larger values are most likely words such as "mix".
+    if !rest.is_empty() || value == 0 || value > 100 {
+        return None;
+    }
+
+    // Only accept canonical numerals, so that words such as "vim" are not parsed.
+    let mut canonical = String::new();
+    let mut remaining = value;
+    for (numeral_value, numeral) in NUMERALS {
+        while remaining >= numeral_value {
+            canonical.push_str(numeral);
+            remaining -= numeral_value;
+        }
+    }
+    (canonical == upper).then_some(f64::from(value))
+}
+
+/// The creation timestamp of an audiobook created on `date`.
+fn start_of_day(date: NaiveDate) -> DateTime<Utc> {
+    Utc.from_utc_datetime(&date.and_time(NaiveTime::MIN))
+}
+
 /// Insert the audiobook data into the DB.
 ///
+/// The creation timestamp is the extracted upload date, or `submission_date` when the upload date
+/// is missing.
+///
 /// # Errors
-/// If the insert doesn't work for some reason.
+/// - `InvalidExtraction` if neither the upload date nor `submission_date` are available.
+/// - If the insert doesn't work for some reason.
 #[instrument(skip_all)]
 pub async fn insert_audiobook_data(
     tx: &mut Transaction<'_, sqlx::Postgres>,
     url: &str,
-    extracted_values: &Value,
+    extracted: &ExtractedAudiobook,
+    submission_date: Option<NaiveDate>,
     short_description: &str,
     embeddable_description: &str,
     embeddings: Vec<f32>,
 ) -> Result<i64, Box<dyn Error>> {
-    let series_id: Option<i64> = if let Some(series_title) = extracted_values["series"]
-        .as_str()
-        .filter(|s| !s.is_empty())
-    {
-        Some(
-                sqlx::query_scalar(
-                "INSERT INTO series (title) VALUES ($1) ON CONFLICT (title) DO UPDATE SET title = EXCLUDED.title RETURNING id",
-            ).bind(series_title)
-                .fetch_one(&mut **tx)
-                .await?,
-            )
-    } else {
-        None
-    };
-    let parsed_date = extracted_values["upload_date"].as_str().unwrap_or("");
-    let naive_date =
-        NaiveDateTime::parse_from_str(parsed_date, "%Y-%m-%d").unwrap_or(Local::now().naive_utc());
-    let timestamp_created = Utc.from_utc_datetime(&naive_date);
+    let series_id = upsert_series(tx, extracted).await?;
+    let timestamp_created = start_of_day(extracted.creation_date(submission_date)?);
 
     let audiobook_id: i64 = sqlx::query_scalar(
         r"
         INSERT INTO audiobook (
             title, language, cover_url, format, unabridged, description, bitrate, file_size, series_id,
             path, timestamp_created, timestamp_ingested, very_short_description,
-            description_for_embeddings, optimized_description_embedding
+            description_for_embeddings, optimized_description_embedding, runtime_seconds,
+            series_volume, language_raw
         )
-        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, NOW(), $12, $13, $14)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, NOW(), $12, $13, $14, $15, $16, $17)
         RETURNING id
         ")
-        .bind(extracted_values["title"].as_str())
-        .bind(extracted_values["language"].as_str())
-        .bind(extracted_values["cover_url"].as_str())
-        .bind(extracted_values["format"].as_str())
-        .bind(extracted_values["unabridged"].as_bool())
-        .bind(extracted_values["description"].as_str())
-        .bind(extracted_values["bitrate"].as_str().and_then(parse_bitrate))
-        .bind(extracted_values["file_size"].as_str().and_then(parse_filesize),)
+        .bind(&extracted.title)
+        .bind(Language::parse(&extracted.language).code())
+        .bind(&extracted.cover_url)
+        .bind(&extracted.format)
+        .bind(extracted.unabridged)
+        .bind(&extracted.description)
+        .bind(extracted.bitrate.as_deref().and_then(parse_bitrate))
+        .bind(extracted.file_size.as_deref().and_then(parse_filesize))
         .bind(series_id)
         .bind(url)
         .bind(timestamp_created)
         .bind(short_description)
         .bind(embeddable_description)
         .bind(embeddings)
+        .bind(extracted.runtime.as_deref().and_then(parse_runtime))
+        .bind(extracted.series_volume.as_deref().and_then(parse_series_volume))
+        .bind(&extracted.language)
         .fetch_one(&mut **tx)
         .await?;
 
-    for author in extracted_values["authors"].as_array().unwrap_or(&vec![]) {
-        if let Some(author_name) = author.as_str() {
-            let author_id: i64 = sqlx::query_scalar(
-                "INSERT INTO author (name) VALUES ($1) ON CONFLICT (name) DO UPDATE SET name = EXCLUDED.name RETURNING id")
-                .bind(author_name
-            ).fetch_one(&mut **tx).await?;
-            sqlx::query(
-                "INSERT INTO audiobook_author (audiobook_id, author_id) VALUES ($1, $2) ON CONFLICT DO NOTHING")
-                .bind(audiobook_id)
-                .bind(author_id)
-            .execute(&mut **tx).await?;
-        }
+    link_audiobook_relations(tx, audiobook_id, extracted).await?;
+    assign_work(tx, audiobook_id, &extracted.title)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(audiobook_id)
+}
+
+/// Overwrites the extracted data of an existing audiobook, e.g. after re-running the extraction
+/// on an archived page. The path and the ingestion timestamp are left untouched, and so is the
+/// creation timestamp unless a valid upload date was extracted.
+///
+/// # Errors
+/// If any of the updates doesn't work for some reason.
+#[instrument(skip_all)]
+pub async fn update_audiobook_data(
+    tx: &mut Transaction<'_, sqlx::Postgres>,
+    audiobook_id: i64,
+    extracted: &ExtractedAudiobook,
+    short_description: &str,
+    embeddable_description: &str,
+    embeddings: Vec<f32>,
+) -> Result<(), Box<dyn Error>> {
+    let series_id = upsert_series(tx, extracted).await?;
+    let timestamp_created = extracted.creation_date(None).ok().map(start_of_day);
+
+    sqlx::query(
+        r"
+        UPDATE audiobook SET
+            title = $2, language = $3, cover_url = $4, format = $5, unabridged = $6,
+            description = $7, bitrate = $8, file_size = $9, series_id = $10,
+            timestamp_created = COALESCE($11, timestamp_created), very_short_description = $12,
+            description_for_embeddings = $13, optimized_description_embedding = $14,
+            runtime_seconds = $15, series_volume = $16, language_raw = $17
+        WHERE id = $1
+        ",
+    )
+    .bind(audiobook_id)
+    .bind(&extracted.title)
+    .bind(Language::parse(&extracted.language).code())
+    .bind(&extracted.cover_url)
+    .bind(&extracted.format)
+    .bind(extracted.unabridged)
+    .bind(&extracted.description)
+    .bind(extracted.bitrate.as_deref().and_then(parse_bitrate))
+    .bind(extracted.file_size.as_deref().and_then(parse_filesize))
+    .bind(series_id)
+    .bind(timestamp_created)
+    .bind(short_description)
+    .bind(embeddable_description)
+    .bind(embeddings)
+    .bind(extracted.runtime.as_deref().and_then(parse_runtime))
+    .bind(
+        extracted
+            .series_volume
+            .as_deref()
+            .and_then(parse_series_volume),
+    )
+    .bind(&extracted.language)
+    .execute(&mut **tx)
+    .await?;
+
+    for table in [
+        "audiobook_author",
+        "audiobook_reader",
+        "audiobook_category",
+        "audiobook_keyword",
+    ] {
+        sqlx::query(&format!("DELETE FROM {table} WHERE audiobook_id = $1"))
+            .bind(audiobook_id)
+            .execute(&mut **tx)
+            .await?;
     }
+    link_audiobook_relations(tx, audiobook_id, extracted).await?;
+    assign_work(tx, audiobook_id, &extracted.title)
+        .await
+        .map_err(|e| e.to_string())?;
 
-    for reader in extracted_values["read_by"].as_array().unwrap_or(&vec![]) {
-        if let Some(reader_name) = reader.as_str() {
-            let reader_id: i64 = sqlx::query_scalar(
-                "INSERT INTO reader (name) VALUES ($1) ON CONFLICT (name) DO UPDATE SET name = EXCLUDED.name RETURNING id")
-                .bind(reader_name)
-            .fetch_one(&mut **tx).await?;
-            sqlx::query(
-                "INSERT INTO audiobook_reader (audiobook_id, reader_id) VALUES ($1, $2) ON CONFLICT DO NOTHING")
-                .bind(audiobook_id)
-                .bind(reader_id)
-            .execute(&mut **tx).await?;
+    Ok(())
+}
+
+async fn upsert_series(
+    tx: &mut Transaction<'_, sqlx::Postgres>,
+    extracted: &ExtractedAudiobook,
+) -> Result<Option<i64>, Box<dyn Error>> {
+    let Some(series_title) = extracted
+        .series_title()
+        .map(|title| EntityKind::Series.normalize(title))
+        .filter(|title| !title.is_empty())
+    else {
+        return Ok(None);
+    };
+    let series_id = upsert_entity(tx, EntityKind::Series, &series_title)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(Some(series_id))
+}
+
+async fn link_audiobook_relations(
+    tx: &mut Transaction<'_, sqlx::Postgres>,
+    audiobook_id: i64,
+    extracted: &ExtractedAudiobook,
+) -> Result<(), Box<dyn Error>> {
+    let keywords = resolve_keywords(tx, &extracted.keywords)
+        .await
+        .map_err(|e| e.to_string())?;
+    for (kind, names) in [
+        (EntityKind::Author, &extracted.authors),
+        (EntityKind::Reader, &extracted.read_by),
+        (EntityKind::Category, &extracted.categories),
+        (EntityKind::Keyword, &keywords),
+    ] {
+        let table = kind.table();
+        for name in names {
+            let name = kind.normalize(name);
+            if name.is_empty() {
+                continue;
+            }
+            let entity_id = upsert_entity(tx, kind, &name)
+                .await
+                .map_err(|e| e.to_string())?;
+            sqlx::query(&format!(
+                "INSERT INTO audiobook_{table} (audiobook_id, {table}_id) VALUES ($1, $2) ON CONFLICT DO NOTHING"
+            ))
+            .bind(audiobook_id)
+            .bind(entity_id)
+            .execute(&mut **tx)
+            .await?;
         }
     }
 
-    for category in extracted_values["categories"].as_array().unwrap_or(&vec![]) {
-        if let Some(category_name) = category.as_str() {
-            let category_id: i64 = sqlx::query_scalar(
-                "INSERT INTO category (name) VALUES ($1) ON CONFLICT (name) DO UPDATE SET name = EXCLUDED.name RETURNING id")
-                .bind(category_name)
-            .fetch_one(&mut **tx).await?;
-            sqlx::query(
-                "INSERT INTO audiobook_category (audiobook_id, category_id) VALUES ($1, $2) ON CONFLICT DO NOTHING")
-                .bind(audiobook_id)
-                .bind(category_id)
-            .execute(&mut **tx).await?;
-        }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_runtime() {
+        assert_eq!(parse_runtime("12:34:56"), Some(12 * 3600 + 34 * 60 + 56));
+        assert_eq!(parse_runtime("34:56"), Some(34 * 60 + 56));
+        assert_eq!(parse_runtime("12 hrs 34 mins"), Some(12 * 3600 + 34 * 60));
+        assert_eq!(parse_runtime("12h 34m 56s"), Some(12 * 3600 + 34 * 60 + 56));
+        assert_eq!(
+            parse_runtime("10 hours and 5 minutes"),
+            Some(10 * 3600 + 5 * 60)
+        );
+        assert_eq!(parse_runtime("1.5 hours"), Some(5400));
+        assert_eq!(parse_runtime("2.25h"), Some(2 * 3600 + 15 * 60));
+        assert_eq!(parse_runtime("12 hrs. 34 mins."), Some(12 * 3600 + 34 * 60));
+        assert_eq!(parse_runtime("999999:00:00"), None);
+        assert_eq!(parse_runtime("2147483647:00"), None);
+        assert_eq!(parse_runtime("99999999999 hours"), None);
+        assert_eq!(parse_runtime(""), None);
+        assert_eq!(parse_runtime("unknown"), None);
     }
 
-    for keyword in extracted_values["keywords"].as_array().unwrap_or(&vec![]) {
-        if let Some(keyword_name) = keyword.as_str() {
-            let keyword_id: i64 = sqlx::query_scalar(
-                "INSERT INTO keyword (name) VALUES ($1) ON CONFLICT (name) DO UPDATE SET name = EXCLUDED.name RETURNING id")
-                .bind(keyword_name)
-        .fetch_one(&mut **tx).await?;
-            sqlx::query(
-                "INSERT INTO audiobook_keyword (audiobook_id, keyword_id) VALUES ($1, $2) ON CONFLICT DO NOTHING")
-                .bind(audiobook_id)
-                .bind(keyword_id)
-            .execute(&mut **tx).await?;
-        }
+    #[test]
+    fn test_parse_series_volume() {
+        assert_eq!(parse_series_volume("Book 3"), Some(3.0));
+        assert_eq!(parse_series_volume("Vol. 2.5"), Some(2.5));
+        assert_eq!(parse_series_volume("#4"), Some(4.0));
+        assert_eq!(parse_series_volume("Volume III"), Some(3.0));
+        assert_eq!(parse_series_volume("Book XIV"), Some(14.0));
+        assert_eq!(parse_series_volume("Book 1 of 3"), Some(1.0));
+        assert_eq!(parse_series_volume("Mix"), None);
+        assert_eq!(parse_series_volume("Vim"), None);
+        assert_eq!(parse_series_volume(""), None);
     }
 
-    Ok(audiobook_id)
+    #[test]
+    fn test_start_of_day() {
+        assert_eq!(
+            start_of_day(NaiveDate::from_ymd_opt(2021, 2, 3).unwrap()),
+            Utc.with_ymd_and_hms(2021, 2, 3, 0, 0, 0).unwrap()
+        );
+    }
 }
@@ -1,123 +1,558 @@
 //! Search operations on Parade.
 
 use std::collections::HashMap;
+use std::ops::Range;
 use std::sync::Arc;
 
-use entities_lib::SearchQuery;
-use sqlx::postgres::PgRow;
-use sqlx::{self, PgPool, Row};
+use entities_lib::{
+    FacetValue, FusionMethod, Language, RankingConfig, ScoreBreakdown, SearchFacets, SearchQuery,
+    SearchResults,
+};
+use moka::future::Cache;
+use sqlx::postgres::{PgArguments, PgRow};
+use sqlx::query::Query;
+use sqlx::{self, FromRow, PgPool, Postgres, Row};
 use tracing::{debug, instrument};
 
 use crate::db_ops::AppError;
-use crate::utils::gemini::GeminiContentEmbedderLike;
+use crate::db_ops::parade::audiobook_ops::{language_codes, language_condition};
+use crate::db_ops::parade::taxonomy_ops::CATEGORY_TREE_CTE;
+use crate::utils::llm::{ContentEmbedder, EmbeddingTask};
 use entities_lib::ShareableArgsValues;
 
-const VECTOR_QUERY: &str = r"
+/// Maximum number of values listed for the facets with many values, e.g. the authors.
+const MAX_FACET_VALUES: usize = 10;
+
+/// Longest `uploaded_within_days` filter, a hundred years, which includes every audiobook.
+const MAX_UPLOADED_WITHIN_DAYS: u32 = 36_500;
+
+/// Highest Hardcover rating.
+const MAX_RATING: f64 = 5.0;
+
+/// The ids and scores of the audiobooks found by each retriever of the hybrid search, the best
+/// first.
+#[derive(Debug, Clone, Default)]
+pub struct RetrieverResults {
+    pub vector: Vec<(i64, f64)>,
+    pub bm25: Vec<(i64, f64)>,
+}
+
+/// The ranked results of a search in all the pages, with their facets. It's cached, so that each
+/// page is sliced from it instead of running the search again.
+#[derive(Debug, Clone, Default)]
+pub struct RankedSearch {
+    pub score_breakdowns: Vec<ScoreBreakdown>,
+    pub facets: SearchFacets,
+}
+
+impl RankedSearch {
+    /// The page of the results, with their breakdowns if `debug`. The pages start from 1.
+    #[must_use]
+    pub fn page(&self, page: u32, page_size: usize, debug: bool) -> SearchResults {
+        let page = &self.score_breakdowns[page_range(self.score_breakdowns.len(), page, page_size)];
+        SearchResults {
+            audiobook_ids: page
+                .iter()
+                .map(|breakdown| breakdown.audiobook_id)
+                .collect(),
+            total_hits: u32::try_from(self.score_breakdowns.len()).unwrap_or(u32::MAX),
+            facets: self.facets.clone(),
+            score_breakdowns: if debug { page.to_vec() } else { Vec::new() },
+        }
+    }
+}
+
+/// What the boosts of the ranking are computed from.
+#[derive(Debug, Clone, Copy, FromRow)]
+pub struct RankingSignals {
+    /// Days since the audiobook was ingested.
+    pub age_days: f64,
+    /// The Hardcover rating, if the audiobook has enough ratings.
+    pub rating: Option<f64>,
+}
+
+#[derive(FromRow)]
+struct RankingSignalsRow {
+    id: i64,
+    #[sqlx(flatten)]
+    signals: RankingSignals,
+}
+
+const RANKING_SIGNALS_QUERY: &str = r"
 SELECT
-    id,
-    (optimized_description_embedding <#> ($1)::vector) * -1 AS score
-FROM audiobook
-ORDER BY (optimized_description_embedding <#> ($1)::vector) ASC
-LIMIT $2
+    ab.id,
+    EXTRACT(EPOCH FROM (now() - ab.timestamp_ingested))::DOUBLE PRECISION / 86400 AS age_days,
+    CASE WHEN COALESCE((hc.metadata ->> 'ratings_count')::BIGINT, 0) >= $2
+        THEN (hc.metadata ->> 'rating')::DOUBLE PRECISION
+    END AS rating
+FROM audiobook ab
+LEFT JOIN hardcover_audiobook_metadata hc ON hc.audiobook_id = ab.id
+WHERE ab.id = ANY($1)
 ";
 
-const BM25_QUERY: &str = r"
-SELECT
-    audiobook_id AS id,
-    paradedb.score(audiobook_id) AS score
-FROM audiobook_search_view
-WHERE search_content @@@ $1
-ORDER BY score DESC
-LIMIT $2
+/// Nearest descriptions to the embedding `$1` among the audiobooks passing the filters.
+fn vector_query() -> String {
+    format!(
+        r"
+        SELECT
+            ab.id,
+            (ab.optimized_description_embedding <#> ($1)::vector) * -1 AS score
+        FROM audiobook ab
+        WHERE {}
+        ORDER BY (ab.optimized_description_embedding <#> ($1)::vector) ASC
+        LIMIT $2
+        ",
+        search_filters_condition()
+    )
+}
+
+/// Best BM25 matches of the text `$1` among the audiobooks passing the filters.
+fn bm25_query() -> String {
+    format!(
+        r"
+        SELECT
+            v.audiobook_id AS id,
+            paradedb.score(v.audiobook_id) AS score
+        FROM audiobook_search_view v
+        JOIN audiobook ab ON ab.id = v.audiobook_id
+        WHERE v.search_content @@@ $1 AND {}
+        ORDER BY score DESC
+        LIMIT $2
+        ",
+        search_filters_condition()
+    )
+}
+
+/// Condition on the audiobook `ab` applying the filters of the search, whose values are bound
+/// from `$3` on by `bind_search_filters`.
+fn search_filters_condition() -> String {
+    format!(
+        r"
+        {language_condition}
+        AND (
+            cardinality($4::BIGINT[]) = 0
+            OR EXISTS (
+                SELECT 1 FROM audiobook_category abc
+                WHERE abc.audiobook_id = ab.id AND (
+                    abc.category_id = ANY($4::BIGINT[])
+                    OR abc.category_id IN (
+                        WITH {CATEGORY_TREE_CTE}
+                        SELECT category_id FROM category_tree WHERE root_id = ANY($4::BIGINT[])
+                    )
+                )
+            )
+        )
+        AND (
+            cardinality($5::BIGINT[]) = 0
+            OR EXISTS (
+                SELECT 1 FROM audiobook_author aba
+                WHERE aba.audiobook_id = ab.id AND aba.author_id = ANY($5::BIGINT[])
+            )
+        )
+        AND (cardinality($6::TEXT[]) = 0 OR ab.format = ANY($6::TEXT[]))
+        AND ($7::BOOLEAN IS NULL OR ab.unabridged = $7::BOOLEAN)
+        AND ($8::INTEGER IS NULL OR ab.bitrate >= $8::INTEGER)
+        AND (
+            $9::INTEGER IS NULL
+            OR ab.timestamp_ingested >= now() - make_interval(days => $9::INTEGER)
+        )
+        ",
+        language_condition = language_condition(3)
+    )
+}
+
+fn bind_search_filters<'q>(
+    query: Query<'q, Postgres, PgArguments>,
+    search_query: &'q SearchQuery,
+) -> Query<'q, Postgres, PgArguments> {
+    query
+        .bind(language_codes(&search_query.languages))
+        .bind(&search_query.categories)
+        .bind(&search_query.authors)
+        .bind(&search_query.formats)
+        .bind(search_query.unabridged)
+        .bind(search_query.min_bitrate)
+        .bind(uploaded_within_days(search_query))
+}
+
+/// The `uploaded_within_days` filter, clamped so that the start of the interval stays a valid
+/// timestamp.
+fn uploaded_within_days(search_query: &SearchQuery) -> Option<i32> {
+    search_query
+        .uploaded_within_days
+        .map(|days| i32::try_from(days.min(MAX_UPLOADED_WITHIN_DAYS)).unwrap_or(i32::MAX))
+}
+
+/// The categories, languages, formats and authors of the audiobooks with their counts.
+const FACETS_QUERY: &str = r"
+SELECT 'category' AS facet, c.id::TEXT AS value, c.name AS label, COUNT(*) AS count
+FROM audiobook_category abc
+JOIN category c ON c.id = abc.category_id
+WHERE abc.audiobook_id = ANY($1)
+GROUP BY c.id, c.name
+UNION ALL
+SELECT 'language', ab.language, ab.language, COUNT(*)
+FROM audiobook ab
+WHERE ab.id = ANY($1)
+GROUP BY ab.language
+UNION ALL
+SELECT 'format', ab.format, ab.format, COUNT(*)
+FROM audiobook ab
+WHERE ab.id = ANY($1) AND ab.format <> ''
+GROUP BY ab.format
+UNION ALL
+SELECT 'author', a.id::TEXT, a.name, COUNT(*)
+FROM audiobook_author aba
+JOIN author a ON a.id = aba.author_id
+WHERE aba.audiobook_id = ANY($1)
+GROUP BY a.id, a.name
+ORDER BY count DESC, label ASC
 ";
 
+#[derive(FromRow)]
+struct FacetRow {
+    facet: String,
+    value: String,
+    label: String,
+    count: i64,
+}
+
+/// Returns the page of the audiobooks matching the search and passing its filters, the most
+/// relevant first, with the number and the facets of the results in all the pages. The pages start
+/// from 1, and are sliced from the ranked results cached by query.
+///
 /// # Errors
 ///
-/// .
+/// If the search fails, see [`rank_search`].
+#[instrument(skip_all)]
+pub async fn search_audiobooks(
+    pool: &PgPool,
+    cache: &Cache<SearchQuery, Arc<RankedSearch>>,
+    search_query: &SearchQuery,
+    page: u32,
+    shareable_args: &ShareableArgsValues,
+    ranking: &RankingConfig,
+    embedder: Arc<dyn ContentEmbedder>,
+) -> Result<SearchResults, AppError> {
+    // The breakdowns are always computed, so the debug flag doesn't need its own entry.
+    let cache_key = SearchQuery {
+        debug: false,
+        ..search_query.clone()
+    };
+    // The failures aren't cached, they may be transient.
+    let ranked_search = cache
+        .try_get_with(cache_key, async {
+            rank_search(pool, search_query, shareable_args, ranking, embedder)
+                .await
+                .map(Arc::new)
+        })
+        .await
+        .map_err(|e| (*e).clone())?;
+    Ok(ranked_search.page(
+        page,
+        usize::try_from(shareable_args.max_search_results).unwrap_or_default(),
+        search_query.debug,
+    ))
+}
+
+/// Ranks the audiobooks matching the search and passing its filters in all the pages, and counts
+/// their facets.
+///
+/// # Errors
+///
+/// If the search string can't be embedded or one of the queries fails.
+#[instrument(skip_all)]
+pub async fn rank_search(
+    pool: &PgPool,
+    search_query: &SearchQuery,
+    shareable_args: &ShareableArgsValues,
+    ranking: &RankingConfig,
+    embedder: Arc<dyn ContentEmbedder>,
+) -> Result<RankedSearch, AppError> {
+    // The retrievers always fill all the pages so that the fused list, and therefore the content
+    // of a page, doesn't depend on the page requested.
+    let depth = shareable_args
+        .max_search_results
+        .saturating_mul(shareable_args.max_search_pages);
+    let retriever_results =
+        retrieve_audiobooks(pool, search_query, depth, shareable_args, embedder).await?;
+    let score_breakdowns = rank_audiobooks(
+        pool,
+        &retriever_results,
+        ranking,
+        shareable_args.top_rated_min_ratings,
+    )
+    .await?;
+    let audiobook_ids: Vec<i64> = score_breakdowns
+        .iter()
+        .map(|breakdown| breakdown.audiobook_id)
+        .collect();
+    let facets = get_search_facets(pool, &audiobook_ids).await?;
+    Ok(RankedSearch {
+        score_breakdowns,
+        facets,
+    })
+}
+
+/// The range of the results in the page, empty past the last page.
+fn page_range(total: usize, page: u32, page_size: usize) -> Range<usize> {
+    let page = usize::try_from(page.saturating_sub(1)).unwrap_or(usize::MAX);
+    let start = page.saturating_mul(page_size).min(total);
+    start..start.saturating_add(page_size).min(total)
+}
+
+/// Runs the vector and the BM25 retrievers of the search, each returning at most `limit`
+/// audiobooks passing the filters.
+///
+/// # Errors
+///
+/// If the search string can't be embedded or one of the queries fails.
 ///
 /// # Panics
 ///
 /// .
-#[allow(clippy::cast_sign_loss)]
 #[instrument(skip_all)]
-pub async fn search_audiobooks(
+pub async fn retrieve_audiobooks(
     pool: &PgPool,
     search_query: &SearchQuery,
+    limit: i32,
     shareable_args: &ShareableArgsValues,
-    embedder: Arc<dyn GeminiContentEmbedderLike>,
-) -> Result<Vec<i64>, AppError> {
+    embedder: Arc<dyn ContentEmbedder>,
+) -> Result<RetrieverResults, AppError> {
     let embeddings = embedder
         .embed_content(
             &search_query.search_string,
-            &gemini_rust::TaskType::RetrievalDocument,
+            EmbeddingTask::RetrievalDocument,
             shareable_args.gemini_embeddings_size,
         )
         .await
         .map_err(|e| AppError::GenericError(e.to_string()))?;
 
+    let vector_query = vector_query();
+    let bm25_query = bm25_query();
+
     // Run Vector Search
-    let vector_future = sqlx::query(VECTOR_QUERY)
-        .bind(embeddings) // Note: ensure embeddings is cloned or cheap to reference if needed
-        .bind(shareable_args.max_search_results)
-        .map(|row: PgRow| {
-            (
-                row.try_get::<i64, _>("id").unwrap(),
-                row.try_get::<f64, _>("score").unwrap(),
-            )
-        })
-        .fetch_all(pool);
+    let vector_future = bind_search_filters(
+        sqlx::query(&vector_query)
+            .bind(embeddings) // Note: ensure embeddings is cloned or cheap to reference if needed
+            .bind(limit),
+        search_query,
+    )
+    .map(|row: PgRow| {
+        (
+            row.try_get::<i64, _>("id").unwrap(),
+            row.try_get::<f64, _>("score").unwrap(),
+        )
+    })
+    .fetch_all(pool);
 
-    let bm25_future = sqlx::query(BM25_QUERY)
-        .bind(&search_query.search_string)
-        .bind(shareable_args.max_search_results)
-        .map(|row: PgRow| {
-            (
-                row.try_get::<i64, _>("id").unwrap(),
-                f64::from(row.try_get::<f32, _>("score").unwrap()),
-            )
-        })
-        .fetch_all(pool);
+    let bm25_future = bind_search_filters(
+        sqlx::query(&bm25_query)
+            .bind(&search_query.search_string)
+            .bind(limit),
+        search_query,
+    )
+    .map(|row: PgRow| {
+        (
+            row.try_get::<i64, _>("id").unwrap(),
+            f64::from(row.try_get::<f32, _>("score").unwrap()),
+        )
+    })
+    .fetch_all(pool);
 
     // 2. Run them concurrently
     let (vector_results_res, bm25_results_res) = tokio::join!(vector_future, bm25_future);
 
     // 3. Handle errors
-    let vector_results = vector_results_res.map_err(|e| AppError::GenericError(e.to_string()))?;
-    let bm25_results = bm25_results_res.map_err(|e| AppError::GenericError(e.to_string()))?;
-    debug!("Semantic results: {:?}", &vector_results);
-    debug!("BM25 results: {:?}", &bm25_results);
+    let vector = vector_results_res.map_err(|e| AppError::GenericError(e.to_string()))?;
+    let bm25 = bm25_results_res.map_err(|e| AppError::GenericError(e.to_string()))?;
+    debug!("Semantic results: {:?}", &vector);
+    debug!("BM25 results: {:?}", &bm25);
+    Ok(RetrieverResults { vector, bm25 })
+}
+
+async fn get_search_facets(pool: &PgPool, audiobook_ids: &[i64]) -> Result<SearchFacets, AppError> {
+    let rows = sqlx::query_as::<_, FacetRow>(FACETS_QUERY)
+        .bind(audiobook_ids)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| AppError::GenericError(e.to_string()))?;
+
+    let mut facets = SearchFacets::default();
+    for row in rows {
+        let (values, label) = match row.facet.as_str() {
+            "category" => (&mut facets.categories, row.label),
+            "language" => (
+                &mut facets.languages,
+                String::from(Language::from_code(&row.value).name()),
+            ),
+            "format" => (&mut facets.formats, row.label),
+            "author" => (&mut facets.authors, row.label),
+            _ => unreachable!("Unexpected facet from DB: {}", row.facet),
+        };
+        values.push(FacetValue {
+            value: row.value,
+            label,
+            count: u32::try_from(row.count).unwrap_or(u32::MAX),
+        });
+    }
+    facets.categories.truncate(MAX_FACET_VALUES);
+    facets.authors.truncate(MAX_FACET_VALUES);
+    Ok(facets)
+}
 
-    Ok(combine_results_hybrid(vector_results, bm25_results))
+/// Ranks the results of the retrievers with `config`, the best first. Only the audiobooks rated at
+/// least `top_rated_min_ratings` times get the rating boost.
+///
+/// # Errors
+///
+/// If the signals of the boosts can't be read.
+#[instrument(skip_all)]
+pub async fn rank_audiobooks(
+    pool: &PgPool,
+    retriever_results: &RetrieverResults,
+    config: &RankingConfig,
+    top_rated_min_ratings: u32,
+) -> Result<Vec<ScoreBreakdown>, AppError> {
+    let signals = if config.has_boosts() {
+        let audiobook_ids: Vec<i64> = retriever_results
+            .vector
+            .iter()
+            .chain(&retriever_results.bm25)
+            .map(|(id, _)| *id)
+            .collect();
+        get_ranking_signals(pool, &audiobook_ids, top_rated_min_ratings).await?
+    } else {
+        HashMap::new()
+    };
+    Ok(combine_results_hybrid(retriever_results, config, &signals))
 }
 
+/// Returns the signals of the boosts of the audiobooks, the ratings only counting with at least
+/// `min_ratings` ratings.
+///
+/// # Errors
+///
+/// If the query fails.
+pub async fn get_ranking_signals(
+    pool: &PgPool,
+    audiobook_ids: &[i64],
+    min_ratings: u32,
+) -> Result<HashMap<i64, RankingSignals>, AppError> {
+    let rows = sqlx::query_as::<_, RankingSignalsRow>(RANKING_SIGNALS_QUERY)
+        .bind(audiobook_ids)
+        .bind(i64::from(min_ratings))
+        .fetch_all(pool)
+        .await
+        .map_err(|e| AppError::GenericError(e.to_string()))?;
+    Ok(rows.into_iter().map(|row| (row.id, row.signals)).collect())
+}
+
+/// The contribution of each result of a retriever to the fused score.
 #[allow(clippy::cast_precision_loss)]
-fn combine_results_hybrid(
-    vector_results: Vec<(i64, f64)>,
-    bm25_results: Vec<(i64, f64)>,
-) -> Vec<i64> {
-    let mut scores: HashMap<i64, f64> = HashMap::new();
-    let rrf_k = 60.0;
-
-    let mut process_results = |results: Vec<(i64, f64)>| {
-        for (rank, (id, _)) in results.into_iter().enumerate() {
-            // Reciprocal Rank Fusion (RRF)
-            // score = 1 / (k + rank)
-            // We use rank + 1.0 because rank is 0-indexed
-            let score = 1.0 / (rrf_k + (rank as f64) + 1.0);
-            *scores.entry(id).or_default() += score;
+fn fusion_contributions(results: &[(i64, f64)], config: &RankingConfig, weight: f64) -> Vec<f64> {
+    match config.ranking_fusion {
+        // Reciprocal Rank Fusion (RRF)
+        // score = weight / (k + rank)
+        // We use rank + 1.0 because rank is 0-indexed
+        FusionMethod::Rrf => (0..results.len())
+            .map(|rank| weight / (config.ranking_rrf_k + (rank as f64) + 1.0))
+            .collect(),
+        FusionMethod::Score => {
+            let (min, max) = results.iter().fold(
+                (f64::INFINITY, f64::NEG_INFINITY),
+                |(min, max), (_, score)| (min.min(*score), max.max(*score)),
+            );
+            results
+                .iter()
+                .map(|(_, score)| {
+                    if max > min {
+                        weight * (score - min) / (max - min)
+                    } else {
+                        weight
+                    }
+                })
+                .collect()
+        }
+    }
+}
+
+/// Merges the results of the retrievers with the fusion method of the config, then adds the
+/// boosts computed from the signals. The audiobooks missing from the signals aren't boosted.
+pub fn combine_results_hybrid(
+    retriever_results: &RetrieverResults,
+    config: &RankingConfig,
+    signals: &HashMap<i64, RankingSignals>,
+) -> Vec<ScoreBreakdown> {
+    let mut breakdowns: HashMap<i64, ScoreBreakdown> = HashMap::new();
+
+    let mut process_results = |results: &[(i64, f64)], weight: f64, is_vector: bool| {
+        let contributions = fusion_contributions(results, config, weight);
+        for (rank, ((id, score), contribution)) in results.iter().zip(contributions).enumerate() {
+            let breakdown = breakdowns
+                .entry(*id)
+                .or_insert_with(|| ScoreBreakdown::new(*id));
+            let rank = Some(u32::try_from(rank + 1).unwrap_or(u32::MAX));
+            if is_vector {
+                breakdown.vector_rank = rank;
+                breakdown.vector_score = Some(*score);
+            } else {
+                breakdown.bm25_rank = rank;
+                breakdown.bm25_score = Some(*score);
+            }
+            breakdown.fusion_score += contribution;
         }
     };
 
-    process_results(vector_results);
-    process_results(bm25_results);
+    process_results(
+        &retriever_results.vector,
+        config.ranking_vector_weight,
+        true,
+    );
+    process_results(&retriever_results.bm25, config.ranking_bm25_weight, false);
 
-    let mut result: Vec<(i64, f64)> = scores.into_iter().collect();
-    // Sort by score descending
-    result.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+    // The fusion scores are relative to the best result, so that the boosts weigh the same
+    // whatever the fusion method.
+    let best_fusion_score = breakdowns
+        .values()
+        .map(|breakdown| breakdown.fusion_score)
+        .fold(0.0, f64::max);
+    let mut result: Vec<ScoreBreakdown> = breakdowns
+        .into_values()
+        .map(|mut breakdown| {
+            if best_fusion_score > 0.0 {
+                breakdown.fusion_score /= best_fusion_score;
+            }
+            if let Some(signals) = signals.get(&breakdown.audiobook_id) {
+                if config.ranking_recency_half_life_days > 0.0 {
+                    breakdown.recency_boost = config.ranking_recency_boost
+                        * (-signals.age_days.max(0.0) / config.ranking_recency_half_life_days)
+                            .exp2();
+                }
+                breakdown.rating_boost = signals.rating.map_or(0.0, |rating| {
+                    config.ranking_rating_boost * rating / MAX_RATING
+                });
+            }
+            breakdown.score =
+                breakdown.fusion_score + breakdown.recency_boost + breakdown.rating_boost;
+            breakdown
+        })
+        .collect();
+    // Sort by score descending, ties by id so that the order doesn't depend on the map
+    result.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then(a.audiobook_id.cmp(&b.audiobook_id))
+    });
 
-    let res = result.into_iter().map(|(id, _)| id).collect();
-    debug!("Final results {:?}", &res);
-    res
+    debug!(
+        "Final results {:?}",
+        result
+            .iter()
+            .map(|breakdown| breakdown.audiobook_id)
+            .collect::<Vec<_>>()
+    );
+    result
 }
 
 /// Checks if an audiobook with the given path already exists.
@@ -133,3 +568,171 @@ pub async fn does_audiobook_exists(pool: &PgPool, path: &str) -> Result<bool, Ap
         .await
         .map_err(|e| AppError::GenericError(e.to_string()))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn retriever_results(vector: &[i64], bm25: &[i64]) -> RetrieverResults {
+        // Scores decreasing with the rank, the BM25 ones on another scale.
+        let scored = |ids: &[i64], scale: f64| {
+            ids.iter()
+                .zip(1..)
+                .map(|(id, rank)| (*id, scale / f64::from(rank)))
+                .collect()
+        };
+        RetrieverResults {
+            vector: scored(vector, 1.0),
+            bm25: scored(bm25, 20.0),
+        }
+    }
+
+    fn ranked_ids(
+        retriever_results: &RetrieverResults,
+        config: &RankingConfig,
+        signals: &HashMap<i64, RankingSignals>,
+    ) -> Vec<i64> {
+        combine_results_hybrid(retriever_results, config, signals)
+            .into_iter()
+            .map(|breakdown| breakdown.audiobook_id)
+            .collect()
+    }
+
+    fn rrf(rrf_k: f64) -> RankingConfig {
+        RankingConfig {
+            ranking_rrf_k: rrf_k,
+            ..RankingConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_page_range() {
+        assert_eq!(page_range(50, 1, 24), 0..24);
+        assert_eq!(page_range(50, 3, 24), 48..50);
+        assert_eq!(page_range(50, 4, 24), 50..50);
+        // The pages start from 1, 0 is the first page too.
+        assert_eq!(page_range(50, 0, 24), 0..24);
+    }
+
+    #[test]
+    fn test_uploaded_within_days_is_clamped() {
+        let query = |days| SearchQuery {
+            uploaded_within_days: days,
+            ..SearchQuery::new(String::from("dragons"))
+        };
+        assert_eq!(uploaded_within_days(&query(None)), None);
+        assert_eq!(uploaded_within_days(&query(Some(30))), Some(30));
+        assert_eq!(uploaded_within_days(&query(Some(3_000_000))), Some(36_500));
+        assert_eq!(uploaded_within_days(&query(Some(u32::MAX))), Some(36_500));
+    }
+
+    #[test]
+    fn test_ranked_search_page() {
+        let ranked_search = RankedSearch {
+            score_breakdowns: (1..=5).map(ScoreBreakdown::new).collect(),
+            facets: SearchFacets::default(),
+        };
+        let page = ranked_search.page(2, 2, false);
+        assert_eq!(page.audiobook_ids, vec![3, 4]);
+        assert_eq!(page.total_hits, 5);
+        assert!(page.score_breakdowns.is_empty());
+        let page = ranked_search.page(3, 2, true);
+        assert_eq!(page.audiobook_ids, vec![5]);
+        assert_eq!(page.score_breakdowns, vec![ScoreBreakdown::new(5)]);
+    }
+
+    #[test]
+    fn test_combine_results_hybrid_favors_the_audiobooks_found_by_both() {
+        let results = retriever_results(&[1, 2, 3], &[3, 4]);
+        assert_eq!(
+            ranked_ids(&results, &RankingConfig::default(), &HashMap::new()),
+            vec![3, 1, 2, 4]
+        );
+    }
+
+    #[test]
+    fn test_combine_results_hybrid_small_rrf_k_favors_the_top_ranks() {
+        let results = retriever_results(&[1, 2], &[3, 4, 2]);
+        assert_eq!(
+            ranked_ids(&results, &rrf(0.0), &HashMap::new()),
+            vec![1, 3, 2, 4]
+        );
+        assert_eq!(
+            ranked_ids(&results, &rrf(60.0), &HashMap::new()),
+            vec![2, 1, 3, 4]
+        );
+    }
+
+    #[test]
+    fn test_combine_results_hybrid_weights_the_retrievers() {
+        let results = retriever_results(&[1, 2], &[3, 4]);
+        let config = RankingConfig {
+            ranking_bm25_weight: 2.0,
+            ..RankingConfig::default()
+        };
+        assert_eq!(
+            ranked_ids(&results, &config, &HashMap::new()),
+            vec![3, 4, 1, 2]
+        );
+    }
+
+    #[test]
+    fn test_combine_results_hybrid_score_fusion_uses_the_scores() {
+        let mut results = retriever_results(&[1, 2], &[3, 2, 4]);
+        // The second vector result is almost as close as the first one.
+        results.vector[1].1 = 0.99;
+        results.vector.push((5, 0.0));
+        let config = RankingConfig {
+            ranking_fusion: FusionMethod::Score,
+            ..RankingConfig::default()
+        };
+        let breakdowns = combine_results_hybrid(&results, &config, &HashMap::new());
+        assert_eq!(breakdowns[0].audiobook_id, 2);
+        assert!((breakdowns[0].fusion_score - 1.0).abs() < 1e-9);
+        assert_eq!(breakdowns[0].vector_rank, Some(2));
+        assert_eq!(breakdowns[0].bm25_rank, Some(2));
+        assert_eq!(breakdowns.last().unwrap().audiobook_id, 5);
+    }
+
+    #[test]
+    fn test_combine_results_hybrid_boosts_the_recent_and_well_rated() {
+        let results = retriever_results(&[1, 2, 3], &[1, 2, 3]);
+        let signals = HashMap::from([
+            (
+                1,
+                RankingSignals {
+                    age_days: 3650.0,
+                    rating: None,
+                },
+            ),
+            (
+                2,
+                RankingSignals {
+                    age_days: 3650.0,
+                    rating: Some(5.0),
+                },
+            ),
+            (
+                3,
+                RankingSignals {
+                    age_days: 0.0,
+                    rating: None,
+                },
+            ),
+        ]);
+        let config = RankingConfig {
+            ranking_recency_boost: 0.5,
+            ranking_rating_boost: 0.2,
+            ..RankingConfig::default()
+        };
+        let breakdowns = combine_results_hybrid(&results, &config, &signals);
+        let ids: Vec<i64> = breakdowns.iter().map(|b| b.audiobook_id).collect();
+        assert_eq!(ids, vec![3, 2, 1]);
+        assert!((breakdowns[0].recency_boost - 0.5).abs() < 1e-9);
+        assert!((breakdowns[1].rating_boost - 0.2).abs() < 1e-9);
+        assert_eq!(
+            ranked_ids(&results, &RankingConfig::default(), &signals),
+            vec![1, 2, 3]
+        );
+    }
+}
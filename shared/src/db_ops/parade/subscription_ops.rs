@@ -1,9 +1,17 @@
 use entities_lib::entities::subscription::{Subscription, SubscriptionType};
-use entities_lib::{Author, Category, Reader, Series};
+use entities_lib::{Author, Category, Language, Reader, Series};
 use sqlx::{FromRow, PgPool};
 use tracing::instrument;
 
 use crate::db_ops::AppError;
+use crate::db_ops::parade::audiobook_ops::language_codes;
+
+/// Condition on the subscription `alias`, keeping it only if it covers the language of the
+/// audiobook `ab`.
+#[must_use]
+pub fn subscription_language_condition(alias: &str) -> String {
+    format!("(cardinality({alias}.languages) = 0 OR ab.language = ANY({alias}.languages))")
+}
 
 // TODO:make this return an i64 when done switching to ids.
 /// Returns the required SQL table and column names for a given subscription type.
@@ -49,7 +57,8 @@ fn get_subscription_sql_info(
     }
 }
 
-/// Adds a new subscription for a user.
+/// Adds a new subscription for a user, in the preferred languages of the user if the
+/// subscription has no languages.
 ///
 /// # Errors
 /// Returns an error if the database operation fails.
@@ -59,8 +68,11 @@ pub async fn add_subscription(pool: &PgPool, subscription: Subscription) -> Resu
         get_subscription_sql_info(&subscription.subscription_type);
 
     let query_str = format!(
-        "INSERT INTO public.{join_table} (user_id, {target_id_col})
-         SELECT $1, id
+        "INSERT INTO public.{join_table} (user_id, {target_id_col}, languages)
+         SELECT $1, id, CASE
+             WHEN cardinality($3::TEXT[]) > 0 THEN $3::TEXT[]
+             ELSE (SELECT preferred_languages FROM public.users WHERE id = $1)
+         END
          FROM public.{target_table}
          WHERE {target_value_col} = $2
          ON CONFLICT DO NOTHING",
@@ -69,6 +81,7 @@ pub async fn add_subscription(pool: &PgPool, subscription: Subscription) -> Resu
     sqlx::query(&query_str)
         .bind(subscription.user_id)
         .bind(target_value)
+        .bind(language_codes(&subscription.languages))
         .execute(pool)
         .await
         .map_err(|e| AppError::GenericError(e.to_string()))?;
@@ -142,6 +155,7 @@ struct SubscriptionRow {
     subscription_id: i64,
     subscription_kind: String,
     subscription_value: String,
+    languages: Vec<String>,
 }
 
 /// Returns all the subscriptions for the given user.
@@ -161,7 +175,8 @@ pub async fn list_subscriptions(
         SELECT 
             'author' AS subscription_kind,
             a.name AS subscription_value,
-            a.id  as subscription_id
+            a.id  as subscription_id,
+            uan.languages
         FROM public.user_author_notification uan
         JOIN public.author a ON uan.author_id = a.id
         WHERE uan.user_id = $1
@@ -171,7 +186,8 @@ pub async fn list_subscriptions(
         SELECT 
             'reader' AS subscription_kind, 
             r.name AS subscription_value,
-            r.id AS subscription_id
+            r.id AS subscription_id,
+            urn.languages
         FROM public.user_reader_notification urn
         JOIN public.reader r ON urn.reader_id = r.id
         WHERE urn.user_id = $1
@@ -181,7 +197,8 @@ pub async fn list_subscriptions(
         SELECT 
             'series' AS subscription_kind,
             s.title AS subscription_value,
-            s.id AS subscription_id
+            s.id AS subscription_id,
+            usn.languages
         FROM public.user_series_notification usn
         JOIN public.series s ON usn.series_id = s.id
         WHERE usn.user_id = $1
@@ -191,7 +208,8 @@ pub async fn list_subscriptions(
         SELECT 
             'category' AS subscription_kind,
             s.name AS subscription_value,
-            s.id AS subscription_id
+            s.id AS subscription_id,
+            ucn.languages
         FROM public.user_category_notification ucn
         JOIN public.category s ON ucn.category_id = s.id
         WHERE ucn.user_id = $1
@@ -236,9 +254,85 @@ pub async fn list_subscriptions(
             Subscription {
                 user_id,
                 subscription_type,
+                languages: row
+                    .languages
+                    .iter()
+                    .map(|code| Language::from_code(code))
+                    .collect(),
             }
         })
         .collect();
 
     Ok(subscriptions)
 }
+
+/// Whether the user is notified of every new edition of a work, rather than only of the first.
+///
+/// # Errors
+/// If the query fails.
+#[instrument(skip(pool))]
+pub async fn get_notify_new_editions(pool: &PgPool, user_id: i64) -> Result<bool, AppError> {
+    sqlx::query_scalar("SELECT notify_new_editions FROM public.users WHERE id = $1")
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| AppError::GenericError(e.to_string()))?
+        .ok_or_else(|| AppError::NotFound(format!("No user with id {user_id}")))
+}
+
+/// Sets whether the user is notified of every new edition of a work.
+///
+/// # Errors
+/// If the query fails.
+#[instrument(skip(pool))]
+pub async fn set_notify_new_editions(
+    pool: &PgPool,
+    user_id: i64,
+    notify_new_editions: bool,
+) -> Result<(), AppError> {
+    sqlx::query("UPDATE public.users SET notify_new_editions = $2 WHERE id = $1")
+        .bind(user_id)
+        .bind(notify_new_editions)
+        .execute(pool)
+        .await
+        .map_err(|e| AppError::GenericError(e.to_string()))?;
+    Ok(())
+}
+
+/// The languages the user wants to see, all of them if it's empty.
+///
+/// # Errors
+/// If the query fails.
+#[instrument(skip(pool))]
+pub async fn get_preferred_languages(
+    pool: &PgPool,
+    user_id: i64,
+) -> Result<Vec<Language>, AppError> {
+    let codes: Vec<String> =
+        sqlx::query_scalar("SELECT preferred_languages FROM public.users WHERE id = $1")
+            .bind(user_id)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| AppError::GenericError(e.to_string()))?
+            .ok_or_else(|| AppError::NotFound(format!("No user with id {user_id}")))?;
+    Ok(codes.iter().map(|code| Language::from_code(code)).collect())
+}
+
+/// Sets the languages the user wants to see, all of them if `languages` is empty.
+///
+/// # Errors
+/// If the query fails.
+#[instrument(skip(pool))]
+pub async fn set_preferred_languages(
+    pool: &PgPool,
+    user_id: i64,
+    languages: &[Language],
+) -> Result<(), AppError> {
+    sqlx::query("UPDATE public.users SET preferred_languages = $2 WHERE id = $1")
+        .bind(user_id)
+        .bind(language_codes(languages))
+        .execute(pool)
+        .await
+        .map_err(|e| AppError::GenericError(e.to_string()))?;
+    Ok(())
+}
@@ -0,0 +1,302 @@
+//! The curated category taxonomy, and the aliases mapping the free-form categories onto it.
+
+use std::collections::HashMap;
+use std::fmt::Display;
+
+use sqlx::{FromRow, PgPool};
+use tracing::instrument;
+
+use crate::db_ops::AppError;
+use crate::db_ops::parade::subscription_ops::subscription_language_condition;
+use crate::entity_names::normalize_entity_name;
+
+/// Separator of the names in a taxonomy path, e.g. "Fiction > Science Fiction > Space Opera".
+pub const TAXONOMY_PATH_SEPARATOR: &str = " > ";
+
+/// Recursive CTE `category_tree (root_id, category_id)` pairing every taxonomy node with itself
+/// and with each of its descendants.
+pub(crate) const CATEGORY_TREE_CTE: &str = "
+    RECURSIVE category_tree (root_id, category_id) AS (
+        SELECT category_id, category_id FROM category_taxonomy
+        UNION ALL
+        SELECT t.root_id, ct.category_id
+        FROM category_tree t
+        JOIN category_taxonomy ct ON ct.parent_id = t.category_id
+    )
+";
+
+/// Who decided what an alias maps to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AliasSource {
+    Curated,
+    Llm,
+    Manual,
+}
+
+impl Display for AliasSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AliasSource::Curated => write!(f, "curated"),
+            AliasSource::Llm => write!(f, "llm"),
+            AliasSource::Manual => write!(f, "manual"),
+        }
+    }
+}
+
+/// A node of the taxonomy, with the names of its ancestors.
+#[derive(Debug, Clone, PartialEq, Eq, FromRow)]
+pub struct TaxonomyNode {
+    pub category_id: i64,
+    pub name: String,
+    pub parent_id: Option<i64>,
+    /// Names from the root down to this node, separated by `TAXONOMY_PATH_SEPARATOR`.
+    pub path: String,
+}
+
+/// What a known alias maps to.
+#[derive(Debug, Clone, PartialEq, Eq, FromRow)]
+pub struct CategoryAlias {
+    pub alias: String,
+    /// `None` if the alias doesn't fit the taxonomy.
+    pub category_id: Option<i64>,
+    pub category_name: Option<String>,
+}
+
+/// A category linked to audiobooks that isn't part of the taxonomy.
+#[derive(Debug, Clone, PartialEq, Eq, FromRow)]
+pub struct UnmappedCategory {
+    pub id: i64,
+    pub name: String,
+    pub audiobooks_count: i64,
+}
+
+/// The key under which a category string is stored in `category_alias`.
+#[must_use]
+pub fn category_alias_key(name: &str) -> String {
+    normalize_entity_name(name).to_lowercase()
+}
+
+/// Returns the whole taxonomy, ordered by path.
+///
+/// # Errors
+/// If the query fails.
+#[instrument(skip(pool))]
+pub async fn get_taxonomy(pool: &PgPool) -> Result<Vec<TaxonomyNode>, AppError> {
+    sqlx::query_as::<_, TaxonomyNode>(
+        r"
+        WITH RECURSIVE nodes (category_id, name, parent_id, path) AS (
+            SELECT ct.category_id, c.name, ct.parent_id, c.name
+            FROM category_taxonomy ct
+            JOIN category c ON c.id = ct.category_id
+            WHERE ct.parent_id IS NULL
+            UNION ALL
+            SELECT ct.category_id, c.name, ct.parent_id, n.path || $1 || c.name
+            FROM nodes n
+            JOIN category_taxonomy ct ON ct.parent_id = n.category_id
+            JOIN category c ON c.id = ct.category_id
+        )
+        SELECT category_id, name, parent_id, path FROM nodes ORDER BY path
+        ",
+    )
+    .bind(TAXONOMY_PATH_SEPARATOR)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| AppError::GenericError(e.to_string()))
+}
+
+/// Returns the known aliases among `aliases`, keyed by alias. The aliases are expected to be
+/// built with `category_alias_key`.
+///
+/// # Errors
+/// If the query fails.
+#[instrument(skip(pool))]
+pub async fn get_category_aliases(
+    pool: &PgPool,
+    aliases: &[String],
+) -> Result<HashMap<String, CategoryAlias>, AppError> {
+    let rows = sqlx::query_as::<_, CategoryAlias>(
+        r"
+        SELECT ca.alias, ca.category_id, c.name AS category_name
+        FROM category_alias ca
+        LEFT JOIN category c ON c.id = ca.category_id
+        WHERE ca.alias = ANY($1)
+        ",
+    )
+    .bind(aliases)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| AppError::GenericError(e.to_string()))?;
+    Ok(rows
+        .into_iter()
+        .map(|row| (row.alias.clone(), row))
+        .collect())
+}
+
+/// Records what `alias` maps to. The aliases decided by the LLM never overwrite the others.
+///
+/// # Errors
+/// If the upsert fails.
+#[instrument(skip(pool))]
+pub async fn upsert_category_alias(
+    pool: &PgPool,
+    alias: &str,
+    category_id: Option<i64>,
+    source: AliasSource,
+) -> Result<(), AppError> {
+    sqlx::query(
+        r"
+        INSERT INTO category_alias (alias, category_id, source)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (alias) DO UPDATE SET
+            category_id = EXCLUDED.category_id,
+            source = EXCLUDED.source,
+            created_at = CURRENT_TIMESTAMP
+        WHERE EXCLUDED.source <> 'llm'
+        ",
+    )
+    .bind(alias)
+    .bind(category_id)
+    .bind(source.to_string())
+    .execute(pool)
+    .await
+    .map_err(|e| AppError::GenericError(e.to_string()))?;
+    Ok(())
+}
+
+/// Adds a node named `name` to the taxonomy, under the node named `parent` or as a root. The
+/// category is created if it doesn't exist yet, and its name becomes an alias of it.
+///
+/// # Errors
+/// - If `parent` isn't part of the taxonomy.
+/// - If any of the queries fails.
+#[instrument(skip(pool))]
+pub async fn add_taxonomy_node(
+    pool: &PgPool,
+    name: &str,
+    parent: Option<&str>,
+) -> Result<i64, AppError> {
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| AppError::GenericError(e.to_string()))?;
+    let parent_id = match parent {
+        Some(parent) => Some(
+            sqlx::query_scalar::<_, i64>(
+                r"
+                SELECT ct.category_id
+                FROM category_taxonomy ct
+                JOIN category c ON c.id = ct.category_id
+                WHERE lower(c.name) = lower($1)
+                ",
+            )
+            .bind(parent)
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(|e| AppError::GenericError(e.to_string()))?
+            .ok_or_else(|| AppError::NotFound(format!("No taxonomy node named {parent}")))?,
+        ),
+        None => None,
+    };
+    let name = normalize_entity_name(name);
+    let category_id: i64 = sqlx::query_scalar(
+        "INSERT INTO category (name) VALUES ($1) ON CONFLICT (name) DO UPDATE SET name = EXCLUDED.name RETURNING id",
+    )
+    .bind(&name)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| AppError::GenericError(e.to_string()))?;
+    sqlx::query(
+        r"
+        INSERT INTO category_taxonomy (category_id, parent_id) VALUES ($1, $2)
+        ON CONFLICT (category_id) DO UPDATE SET parent_id = EXCLUDED.parent_id
+        ",
+    )
+    .bind(category_id)
+    .bind(parent_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| AppError::GenericError(e.to_string()))?;
+    sqlx::query(
+        r"
+        INSERT INTO category_alias (alias, category_id, source) VALUES ($1, $2, 'curated')
+        ON CONFLICT (alias) DO UPDATE SET category_id = EXCLUDED.category_id, source = 'curated'
+        ",
+    )
+    .bind(category_alias_key(&name))
+    .bind(category_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| AppError::GenericError(e.to_string()))?;
+    tx.commit()
+        .await
+        .map_err(|e| AppError::GenericError(e.to_string()))?;
+    Ok(category_id)
+}
+
+/// Returns the categories linked to audiobooks that aren't part of the taxonomy, the most used
+/// first.
+///
+/// # Errors
+/// If the query fails.
+#[instrument(skip(pool))]
+pub async fn get_unmapped_categories(
+    pool: &PgPool,
+    limit: i64,
+) -> Result<Vec<UnmappedCategory>, AppError> {
+    sqlx::query_as::<_, UnmappedCategory>(
+        r"
+        SELECT c.id, c.name, COUNT(abc.audiobook_id) AS audiobooks_count
+        FROM category c
+        JOIN audiobook_category abc ON abc.category_id = c.id
+        WHERE NOT EXISTS (SELECT 1 FROM category_taxonomy ct WHERE ct.category_id = c.id)
+        GROUP BY c.id, c.name
+        ORDER BY COUNT(abc.audiobook_id) DESC, c.id
+        LIMIT $1
+        ",
+    )
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| AppError::GenericError(e.to_string()))
+}
+
+/// Returns the users subscribed to any category of the audiobook, or to any of their ancestors in
+/// the taxonomy, in the language of the audiobook.
+///
+/// # Errors
+/// If the query fails.
+#[instrument(skip(pool))]
+pub async fn get_category_subscribers(
+    pool: &PgPool,
+    audiobook_id: i64,
+) -> Result<Vec<i64>, AppError> {
+    let language_condition = subscription_language_condition("ucn");
+    sqlx::query_scalar(&format!(
+        r"
+        WITH {CATEGORY_TREE_CTE}
+        SELECT DISTINCT ucn.user_id
+        FROM audiobook_category abc
+        JOIN audiobook ab ON ab.id = abc.audiobook_id
+        JOIN user_category_notification ucn ON ucn.category_id = abc.category_id
+            OR ucn.category_id IN (
+                SELECT root_id FROM category_tree WHERE category_id = abc.category_id
+            )
+        WHERE abc.audiobook_id = $1 AND {language_condition}
+        "
+    ))
+    .bind(audiobook_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| AppError::GenericError(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_category_alias_key() {
+        assert_eq!(category_alias_key("  Sci\u{2013}Fi "), "sci-fi");
+        assert_eq!(category_alias_key("Science  Fiction,"), "science fiction");
+    }
+}
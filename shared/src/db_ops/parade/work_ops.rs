@@ -0,0 +1,288 @@
+//! Grouping of the audiobooks into works, so that the editions of the same book uploaded several
+//! times are listed and notified together.
+
+use sqlx::{FromRow, PgPool, Postgres, Transaction};
+use tracing::instrument;
+
+use crate::db_ops::AppError;
+
+/// Words of a title that describe the upload rather than the book.
+const EDITION_WORDS: [&str; 6] = [
+    "unabridged",
+    "abridged",
+    "audiobook",
+    "dramatized",
+    "dramatised",
+    "retail",
+];
+
+/// Leading articles ignored when comparing titles.
+const ARTICLES: [&str; 3] = ["the", "a", "an"];
+
+#[derive(Debug, Clone, PartialEq, Eq, FromRow)]
+pub struct UngroupedAudiobook {
+    pub id: i64,
+    pub title: String,
+}
+
+/// The key under which the editions of a work are grouped: the title in lower case, without the
+/// bracketed parts, the punctuation, the leading article and the words describing the upload.
+#[must_use]
+pub fn work_title_key(title: &str) -> String {
+    let mut unbracketed = String::with_capacity(title.len());
+    let mut depth = 0_usize;
+    for c in title.chars() {
+        match c {
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth = depth.saturating_sub(1),
+            c if depth == 0 => unbracketed.push(c),
+            _ => {}
+        }
+    }
+    let lowercase = unbracketed.to_lowercase();
+    let words: Vec<&str> = lowercase
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty() && !EDITION_WORDS.contains(word))
+        .collect();
+    let words = match words.split_first() {
+        Some((first, rest)) if ARTICLES.contains(first) && !rest.is_empty() => rest,
+        _ => words.as_slice(),
+    };
+    words.join(" ")
+}
+
+/// Puts the audiobook in the work of its other editions, or in a new work if it has none. The
+/// editions share the title key and the series volume, and an author unless neither has any.
+/// When several works match, the one with the closest description embedding is picked. An
+/// audiobook split out of its work by [`split_edition`] stays in its own work, and the other
+/// editions aren't grouped with it.
+///
+/// The authors and the embedding of the audiobook must be stored already.
+///
+/// # Errors
+/// If any of the queries fails.
+pub async fn assign_work(
+    tx: &mut Transaction<'_, Postgres>,
+    audiobook_id: i64,
+    title: &str,
+) -> Result<i64, AppError> {
+    let title_key = work_title_key(title);
+    let (previous_work_id, work_pinned): (Option<i64>, bool) =
+        sqlx::query_as("SELECT work_id, work_pinned FROM audiobook WHERE id = $1")
+            .bind(audiobook_id)
+            .fetch_one(&mut **tx)
+            .await
+            .map_err(|e| AppError::GenericError(e.to_string()))?;
+    if let (Some(work_id), true) = (previous_work_id, work_pinned) {
+        return Ok(work_id);
+    }
+
+    let matching_work_id: Option<i64> = sqlx::query_scalar(
+        r"
+        SELECT w.id
+        FROM work w
+        JOIN audiobook ed ON ed.work_id = w.id
+        JOIN audiobook ab ON ab.id = $1
+        WHERE w.title_key = $2
+            AND w.series_volume IS NOT DISTINCT FROM ab.series_volume
+            AND ed.id <> ab.id
+            AND NOT ed.work_pinned
+            AND (
+                EXISTS (
+                    SELECT 1
+                    FROM audiobook_author a
+                    JOIN audiobook_author b ON b.author_id = a.author_id
+                    WHERE a.audiobook_id = ab.id AND b.audiobook_id = ed.id
+                )
+                OR (
+                    NOT EXISTS (SELECT 1 FROM audiobook_author WHERE audiobook_id = ab.id)
+                    AND NOT EXISTS (SELECT 1 FROM audiobook_author WHERE audiobook_id = ed.id)
+                )
+            )
+        ORDER BY
+            ed.optimized_description_embedding <=> ab.optimized_description_embedding ASC NULLS LAST,
+            w.id
+        LIMIT 1
+        ",
+    )
+    .bind(audiobook_id)
+    .bind(&title_key)
+    .fetch_optional(&mut **tx)
+    .await
+    .map_err(|e| AppError::GenericError(e.to_string()))?;
+
+    let work_id = match matching_work_id {
+        Some(work_id) => work_id,
+        None => sqlx::query_scalar(
+            r"
+            INSERT INTO work (title_key, series_volume)
+            SELECT $2, series_volume FROM audiobook WHERE id = $1
+            RETURNING id
+            ",
+        )
+        .bind(audiobook_id)
+        .bind(&title_key)
+        .fetch_one(&mut **tx)
+        .await
+        .map_err(|e| AppError::GenericError(e.to_string()))?,
+    };
+    sqlx::query("UPDATE audiobook SET work_id = $2 WHERE id = $1")
+        .bind(audiobook_id)
+        .bind(work_id)
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| AppError::GenericError(e.to_string()))?;
+    if let Some(previous_work_id) = previous_work_id.filter(|id| *id != work_id) {
+        delete_work_if_empty(tx, previous_work_id).await?;
+    }
+    Ok(work_id)
+}
+
+/// Moves the audiobook out of its work, into a new work of its own, e.g. when it was wrongly
+/// grouped with other editions. It's pinned there, so [`assign_work`] doesn't group it again.
+///
+/// # Errors
+/// - If the audiobook doesn't exist.
+/// - If any of the queries fails.
+#[instrument(skip(pool))]
+pub async fn split_edition(pool: &PgPool, audiobook_id: i64) -> Result<i64, AppError> {
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| AppError::GenericError(e.to_string()))?;
+    let (title, previous_work_id): (String, Option<i64>) =
+        sqlx::query_as("SELECT title, work_id FROM audiobook WHERE id = $1")
+            .bind(audiobook_id)
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(|e| AppError::GenericError(e.to_string()))?
+            .ok_or_else(|| AppError::NotFound(format!("No audiobook with id {audiobook_id}")))?;
+    let work_id: i64 = sqlx::query_scalar(
+        r"
+        INSERT INTO work (title_key, series_volume)
+        SELECT $2, series_volume FROM audiobook WHERE id = $1
+        RETURNING id
+        ",
+    )
+    .bind(audiobook_id)
+    .bind(work_title_key(&title))
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| AppError::GenericError(e.to_string()))?;
+    sqlx::query("UPDATE audiobook SET work_id = $2, work_pinned = TRUE WHERE id = $1")
+        .bind(audiobook_id)
+        .bind(work_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| AppError::GenericError(e.to_string()))?;
+    if let Some(previous_work_id) = previous_work_id {
+        delete_work_if_empty(&mut tx, previous_work_id).await?;
+    }
+    tx.commit()
+        .await
+        .map_err(|e| AppError::GenericError(e.to_string()))?;
+    Ok(work_id)
+}
+
+/// Returns the audiobooks that don't belong to a work yet, the oldest first.
+///
+/// # Errors
+/// If the query fails.
+#[instrument(skip(pool))]
+pub async fn get_ungrouped_audiobooks(
+    pool: &PgPool,
+    limit: i64,
+) -> Result<Vec<UngroupedAudiobook>, AppError> {
+    sqlx::query_as::<_, UngroupedAudiobook>(
+        "SELECT id, title FROM audiobook WHERE work_id IS NULL ORDER BY id LIMIT $1",
+    )
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| AppError::GenericError(e.to_string()))
+}
+
+async fn delete_work_if_empty(
+    tx: &mut Transaction<'_, Postgres>,
+    work_id: i64,
+) -> Result<(), AppError> {
+    sqlx::query(
+        r"
+        DELETE FROM work w
+        WHERE w.id = $1 AND NOT EXISTS (SELECT 1 FROM audiobook WHERE work_id = w.id)
+        ",
+    )
+    .bind(work_id)
+    .execute(&mut **tx)
+    .await
+    .map_err(|e| AppError::GenericError(e.to_string()))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn insert_audiobook(pool: &PgPool, title: &str) -> i64 {
+        sqlx::query_scalar(
+            r"
+            INSERT INTO audiobook (
+                title, cover_url, description, description_for_embeddings, format, language, path,
+                timestamp_created, timestamp_ingested, very_short_description
+            )
+            VALUES ($1, '', '', '', 'MP3', 'en', gen_random_uuid()::TEXT, NOW(), NOW(), '')
+            RETURNING id
+            ",
+        )
+        .bind(title)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+    }
+
+    async fn assign(pool: &PgPool, audiobook_id: i64, title: &str) -> i64 {
+        let mut tx = pool.begin().await.unwrap();
+        let work_id = assign_work(&mut tx, audiobook_id, title).await.unwrap();
+        tx.commit().await.unwrap();
+        work_id
+    }
+
+    #[sqlx::test(migrations = "../model/migrations")]
+    async fn test_split_editions_are_not_grouped_again(pool: PgPool) {
+        let first = insert_audiobook(&pool, "The Way of Kings").await;
+        let second = insert_audiobook(&pool, "The Way of Kings (Unabridged)").await;
+        let work_id = assign(&pool, first, "The Way of Kings").await;
+        assert_eq!(
+            assign(&pool, second, "The Way of Kings (Unabridged)").await,
+            work_id
+        );
+
+        let split_work_id = split_edition(&pool, second).await.unwrap();
+        assert_ne!(split_work_id, work_id);
+        // E.g. when the audiobook is reprocessed.
+        assert_eq!(
+            assign(&pool, second, "The Way of Kings (Unabridged)").await,
+            split_work_id
+        );
+        assert_ne!(
+            assign(&pool, first, "The Way of Kings").await,
+            split_work_id
+        );
+    }
+
+    #[test]
+    fn test_work_title_key() {
+        assert_eq!(work_title_key("The Way of Kings"), "way of kings");
+        assert_eq!(
+            work_title_key("The Way of Kings (Unabridged) [MP3]"),
+            "way of kings"
+        );
+        assert_eq!(
+            work_title_key("Way of Kings: Unabridged Audiobook"),
+            "way of kings"
+        );
+        assert_eq!(work_title_key("Dune - Book 1"), "dune book 1");
+        assert_eq!(work_title_key("The"), "the");
+        assert_eq!(work_title_key("A Game of Thrones"), "game of thrones");
+    }
+}
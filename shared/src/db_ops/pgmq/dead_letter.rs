@@ -0,0 +1,219 @@
+//! Dead-letter handling for PGMQ queues.
+//!
+//! Messages are read with a visibility timeout and only deleted once they have been processed
+//! successfully. When processing fails the message becomes visible again after the timeout, and
+//! after `max_retries` failed reads it is moved to the dead-letter queue of the source queue.
+
+use chrono::{DateTime, Utc};
+use pgmq::types::{PGMQ_SCHEMA, QUEUE_PREFIX};
+use pgmq::{Message, PGMQueue, PgmqError};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use tracing::{info, warn};
+
+/// Suffix appended to a queue name to obtain the name of its dead-letter queue.
+const DEAD_LETTER_SUFFIX: &str = "_dlq";
+
+/// Returns the name of the dead-letter queue associated with `queue_name`.
+#[must_use]
+pub fn dead_letter_queue_name(queue_name: &str) -> String {
+    format!("{queue_name}{DEAD_LETTER_SUFFIX}")
+}
+
+/// A message that failed processing too many times, together with the reason of the last failure.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeadLetter<T> {
+    /// Queue the task was originally read from.
+    pub source_queue: String,
+    /// Number of times the task was read before being dead-lettered.
+    pub failures: i32,
+    /// Error returned by the last processing attempt.
+    pub last_error: String,
+    /// When the task was first enqueued on the source queue.
+    pub first_enqueued_at: DateTime<Utc>,
+    /// The original task.
+    pub task: T,
+}
+
+/// A dead letter as stored in the dead-letter queue.
+#[derive(Debug, Clone, FromRow)]
+pub struct DeadLetterRow {
+    pub msg_id: i64,
+    pub enqueued_at: DateTime<Utc>,
+    pub message: serde_json::Value,
+}
+
+/// Acknowledges a message that has been processed successfully by deleting it.
+///
+/// # Errors
+/// If the deletion fails.
+pub async fn ack<T>(
+    queue: &PGMQueue,
+    queue_name: &str,
+    message: &Message<T>,
+) -> Result<(), PgmqError> {
+    queue.delete(queue_name, message.msg_id).await?;
+    Ok(())
+}
+
+/// Records a processing failure for `message`.
+///
+/// If the message has already been read `max_retries` times it is moved to the dead-letter queue,
+/// otherwise it is left in place and will be retried once its visibility timeout expires.
+///
+/// Returns `true` if the message was dead-lettered.
+///
+/// # Errors
+/// If sending to the dead-letter queue or deleting from the source queue fails.
+pub async fn nack<T: Serialize + Clone>(
+    queue: &PGMQueue,
+    queue_name: &str,
+    message: &Message<T>,
+    error: &str,
+    max_retries: i32,
+) -> Result<bool, PgmqError> {
+    if message.read_ct < max_retries {
+        warn!(
+            "Message {} on queue {} failed (attempt {}/{}): {}",
+            message.msg_id, queue_name, message.read_ct, max_retries, error
+        );
+        return Ok(false);
+    }
+
+    let dead_letter = DeadLetter {
+        source_queue: queue_name.to_string(),
+        failures: message.read_ct,
+        last_error: error.to_string(),
+        first_enqueued_at: message.enqueued_at,
+        task: message.message.clone(),
+    };
+    let dlq_name = dead_letter_queue_name(queue_name);
+    queue.send(&dlq_name, &dead_letter).await?;
+    queue.delete(queue_name, message.msg_id).await?;
+    warn!(
+        "Message {} on queue {} moved to {} after {} attempts: {}",
+        message.msg_id, queue_name, dlq_name, message.read_ct, error
+    );
+    Ok(true)
+}
+
+/// Lists the dead letters of `queue_name` without consuming them.
+///
+/// # Errors
+/// If the queue name is invalid or the query fails.
+pub async fn list_dead_letters(
+    queue: &PGMQueue,
+    queue_name: &str,
+    limit: i64,
+) -> Result<Vec<DeadLetterRow>, PgmqError> {
+    let table = dead_letter_table(queue_name)?;
+    let rows = sqlx::query_as::<_, DeadLetterRow>(&format!(
+        "SELECT msg_id, enqueued_at, message FROM {table} ORDER BY msg_id ASC LIMIT $1"
+    ))
+    .bind(limit)
+    .fetch_all(&queue.connection)
+    .await?;
+    Ok(rows)
+}
+
+/// Moves dead letters back to their source queue, resetting their retry count.
+///
+/// Tasks are requeued as raw JSON, so this works for any kind of queue.
+///
+/// If `msg_id` is `None` every dead letter is requeued. Returns the number of requeued tasks.
+///
+/// # Errors
+/// If reading from the dead-letter queue or sending to the source queue fails.
+pub async fn requeue_dead_letters(
+    queue: &PGMQueue,
+    queue_name: &str,
+    msg_id: Option<i64>,
+) -> Result<u64, PgmqError> {
+    let dlq_name = dead_letter_queue_name(queue_name);
+    let mut requeued = 0;
+    for row in list_dead_letters(queue, queue_name, i64::MAX).await? {
+        if msg_id.is_some_and(|id| id != row.msg_id) {
+            continue;
+        }
+        let dead_letter: DeadLetter<serde_json::Value> = serde_json::from_value(row.message)?;
+        queue.send(queue_name, &dead_letter.task).await?;
+        queue.delete(&dlq_name, row.msg_id).await?;
+        requeued += 1;
+    }
+    info!("Requeued {} dead letters onto {}", requeued, queue_name);
+    Ok(requeued)
+}
+
+/// Deletes every dead letter of `queue_name`. Returns the number of deleted messages.
+///
+/// # Errors
+/// If the purge fails.
+pub async fn purge_dead_letters(queue: &PGMQueue, queue_name: &str) -> Result<u64, PgmqError> {
+    queue.purge(&dead_letter_queue_name(queue_name)).await
+}
+
+fn dead_letter_table(queue_name: &str) -> Result<String, PgmqError> {
+    let dlq_name = dead_letter_queue_name(queue_name);
+    let is_valid = dlq_name
+        .bytes()
+        .all(|c| c.is_ascii_alphanumeric() || c == b'_');
+    if !is_valid {
+        return Err(PgmqError::InvalidQueueName { name: dlq_name });
+    }
+    Ok(format!("{PGMQ_SCHEMA}.{QUEUE_PREFIX}_{dlq_name}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::PgPool;
+
+    const QUEUE_NAME: &str = "tasks";
+
+    async fn create_queues(pool: PgPool) -> Result<PGMQueue, PgmqError> {
+        let queue = PGMQueue::new_with_pool(pool).await;
+        queue.create(QUEUE_NAME).await?;
+        queue.create(&dead_letter_queue_name(QUEUE_NAME)).await?;
+        Ok(queue)
+    }
+
+    #[sqlx::test]
+    async fn test_nack_under_max_retries_redelivers(pool: PgPool) -> Result<(), PgmqError> {
+        let queue = create_queues(pool).await?;
+        let msg_id = queue.send(QUEUE_NAME, &String::from("task")).await?;
+
+        // A visibility timeout of 0 makes the message visible again right away.
+        let message = queue.read::<String>(QUEUE_NAME, Some(0)).await?.unwrap();
+        assert!(!nack(&queue, QUEUE_NAME, &message, "boom", 3).await?);
+
+        let redelivered = queue.read::<String>(QUEUE_NAME, Some(0)).await?.unwrap();
+        assert_eq!(redelivered.msg_id, msg_id);
+        assert_eq!(redelivered.read_ct, 2);
+        assert!(list_dead_letters(&queue, QUEUE_NAME, 10).await?.is_empty());
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_nack_at_max_retries_dead_letters(pool: PgPool) -> Result<(), PgmqError> {
+        let queue = create_queues(pool).await?;
+        queue.send(QUEUE_NAME, &String::from("task")).await?;
+
+        let max_retries = 2;
+        let mut dead_lettered = Vec::new();
+        for _ in 0..max_retries {
+            let message = queue.read::<String>(QUEUE_NAME, Some(0)).await?.unwrap();
+            dead_lettered.push(nack(&queue, QUEUE_NAME, &message, "boom", max_retries).await?);
+        }
+        assert_eq!(dead_lettered, vec![false, true]);
+        assert!(queue.read::<String>(QUEUE_NAME, Some(0)).await?.is_none());
+
+        let rows = list_dead_letters(&queue, QUEUE_NAME, 10).await?;
+        assert_eq!(rows.len(), 1);
+        let dead_letter: DeadLetter<String> = serde_json::from_value(rows[0].message.clone())?;
+        assert_eq!(dead_letter.source_queue, QUEUE_NAME);
+        assert_eq!(dead_letter.failures, max_retries);
+        assert_eq!(dead_letter.last_error, "boom");
+        assert_eq!(dead_letter.task, "task");
+        Ok(())
+    }
+}
@@ -1,9 +1,12 @@
 use pgmq::{PGMQueue, PgmqError};
 use tracing::{error, info};
 
+use crate::db_ops::pgmq::dead_letter::dead_letter_queue_name;
 use crate::private_args::Args;
 
-/// Connects to PGMQ and creates the queue (if it doesn't exists).
+pub mod dead_letter;
+
+/// Connects to PGMQ and creates the queue and its dead-letter queue (if they don't exist).
 ///
 /// # Errors
 /// If the queue creation fails.
@@ -22,14 +25,14 @@ pub async fn get_pgmq_queue(args: &Args, queue_name: &str) -> Result<PGMQueue, P
         .await
         .expect("Failed to connect to postgres");
 
-    match queue.create(queue_name).await {
-        Ok(()) => {
-            info!("PgmqQueue {} was successfully created", queue_name);
-            Ok(queue)
-        }
-        Err(e) => {
-            error!("{}", e);
-            Err(e)
+    for name in [queue_name.to_string(), dead_letter_queue_name(queue_name)] {
+        match queue.create(&name).await {
+            Ok(()) => info!("PgmqQueue {} was successfully created", name),
+            Err(e) => {
+                error!("{}", e);
+                return Err(e);
+            }
         }
     }
+    Ok(queue)
 }
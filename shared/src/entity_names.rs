@@ -0,0 +1,142 @@
+//! Normalization of the names of the authors, readers, series, categories and keywords, so that
+//! the same entity written in slightly different ways is stored once.
+//!
+//! The normalized names are still matched ignoring case when they are stored, see
+//! `db_ops::parade::entity_ops::upsert_entity`.
+
+/// Suffixes that can follow a comma in a person name, e.g. "Martin Luther King, Jr.".
+const NAME_SUFFIXES: [&str; 7] = ["jr", "sr", "ii", "iii", "iv", "phd", "md"];
+
+/// Normalizes the whitespace and the punctuation of a series title, category or keyword.
+#[must_use]
+pub fn normalize_entity_name(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| match c {
+            '\u{2018}' | '\u{2019}' | '\u{201B}' => '\'',
+            '\u{201C}' | '\u{201D}' => '"',
+            '\u{2010}'..='\u{2015}' => '-',
+            c => c,
+        })
+        .collect();
+    name.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .replace(" ,", ",")
+        .replace(" ;", ";")
+        .trim_matches(|c: char| c.is_whitespace() || matches!(c, ',' | ';' | ':'))
+        .to_string()
+}
+
+/// Normalizes the name of an author or a reader. On top of `normalize_entity_name` this puts
+/// "Last, First" names in "First Last" order, writes the initials without spaces, e.g.
+/// "J. R. R." as "J.R.R.", and capitalizes the names written all in lower or upper case.
+#[must_use]
+pub fn normalize_person_name(name: &str) -> String {
+    let name = normalize_entity_name(name);
+    let name = reorder_last_first(&name);
+    let name = join_initials(&name);
+    fix_case(&name)
+}
+
+fn reorder_last_first(name: &str) -> String {
+    let parts: Vec<&str> = name.split(',').map(str::trim).collect();
+    match parts.as_slice() {
+        [last, first] if !last.is_empty() && !first.is_empty() && !is_name_suffix(first) => {
+            format!("{first} {last}")
+        }
+        _ => name.to_string(),
+    }
+}
+
+fn is_name_suffix(s: &str) -> bool {
+    let s = s.replace('.', "").to_lowercase();
+    NAME_SUFFIXES.contains(&s.as_str())
+}
+
+/// Whether `token` is made of initials only, e.g. "J." or "J.R.".
+fn is_initials(token: &str) -> bool {
+    let letters = token.chars().filter(|c| c.is_alphabetic()).count();
+    letters > 0
+        && token.ends_with('.')
+        && token.split('.').all(|part| part.chars().count() <= 1)
+        && token.chars().all(|c| c.is_alphabetic() || c == '.')
+}
+
+fn join_initials(name: &str) -> String {
+    let mut tokens: Vec<String> = Vec::new();
+    for token in name.split(' ') {
+        match tokens.last_mut() {
+            Some(last) if is_initials(last) && is_initials(token) => last.push_str(token),
+            _ => tokens.push(token.to_string()),
+        }
+    }
+    tokens.join(" ")
+}
+
+fn fix_case(name: &str) -> String {
+    let has_lower = name.chars().any(char::is_lowercase);
+    let has_upper = name.chars().any(char::is_uppercase);
+    if has_lower && has_upper {
+        return name.to_string();
+    }
+    let mut capitalize = true;
+    name.chars()
+        .map(|c| {
+            let c = if capitalize {
+                c.to_uppercase().next().unwrap_or(c)
+            } else {
+                c.to_lowercase().next().unwrap_or(c)
+            };
+            capitalize = matches!(c, ' ' | '-' | '.' | '\'');
+            c
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_person_name() {
+        for name in [
+            "J.R.R. Tolkien",
+            "J. R. R. Tolkien",
+            "Tolkien, J.R.R.",
+            "  Tolkien ,  J. R. R. ",
+            "J.R.R. TOLKIEN",
+        ] {
+            assert_eq!(normalize_person_name(name), "J.R.R. Tolkien", "{name}");
+        }
+        assert_eq!(
+            normalize_person_name("brandon sanderson"),
+            "Brandon Sanderson"
+        );
+        assert_eq!(
+            normalize_person_name("Ursula K. Le Guin"),
+            "Ursula K. Le Guin"
+        );
+        assert_eq!(
+            normalize_person_name("Martin Luther King, Jr."),
+            "Martin Luther King, Jr."
+        );
+        assert_eq!(
+            normalize_person_name("jean-luc o\u{2019}brien"),
+            "Jean-Luc O'Brien"
+        );
+    }
+
+    #[test]
+    fn test_normalize_entity_name() {
+        assert_eq!(
+            normalize_entity_name("  Science   Fiction "),
+            "Science Fiction"
+        );
+        assert_eq!(normalize_entity_name("Sci\u{2013}Fi,"), "Sci-Fi");
+        assert_eq!(
+            normalize_entity_name("The Stormlight Archive"),
+            "The Stormlight Archive"
+        );
+    }
+}
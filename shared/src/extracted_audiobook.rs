@@ -0,0 +1,317 @@
+//! The audiobook data extracted from an audiobook page, either by the LLM or by the selectors.
+//!
+//! The JSON schema given to the LLM is derived from `ExtractedAudiobook`, so the prompt and the
+//! type can't drift apart.
+
+use std::fmt::Display;
+
+use chrono::NaiveDate;
+use schemars::JsonSchema;
+use schemars::generate::{SchemaGenerator, SchemaSettings};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use url::Url;
+
+/// Format of `ExtractedAudiobook::upload_date`.
+pub const UPLOAD_DATE_FORMAT: &str = "%Y-%m-%d";
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ExtractedAudiobook {
+    /// The full title of the audiobook.
+    pub title: String,
+
+    /// A list of categories or genres associated with the audiobook. e.g., 'Fiction', 'Science
+    /// Fiction'.
+    pub categories: Vec<String>,
+
+    /// The language the audiobook is narrated in.
+    pub language: String,
+
+    /// A list of keywords or tags related to the audiobook's content or themes.
+    pub keywords: Vec<String>,
+
+    /// The direct, fully qualified URL to the audiobook's cover image.
+    pub cover_url: String,
+
+    /// The names of the authors who wrote the book.
+    pub authors: Vec<String>,
+
+    /// The names of the narrators or voice actors.
+    pub read_by: Vec<String>,
+
+    /// The audio file format, e.g., 'MP3', 'M4B'.
+    pub format: String,
+
+    /// Set to true if the audiobook is an unabridged version.
+    pub unabridged: bool,
+
+    /// The full HTML content of the audiobook's description section. Remove any other metadata
+    /// that was already parsed as part by other fields from the description.
+    pub description: String,
+
+    /// The bitrate of the audio file, if available. e.g., '128kbps'.
+    pub bitrate: Option<String>,
+
+    /// The total file size of the audiobook, if specified. e.g., '1.2 GB'.
+    pub file_size: Option<String>,
+
+    /// The total runtime of the audiobook, preferably in HH:MM:SS format.
+    pub runtime: Option<String>,
+
+    /// Set to true if the audiobook is part of a series.
+    pub is_part_of_series: Option<bool>,
+
+    /// The title of the series the audiobook belongs to. Extract this even if it's part of the
+    /// main title.
+    pub series: Option<String>,
+
+    /// The volume or book number within the series. e.g., 'Book 1', 'Volume 3'.
+    pub series_volume: Option<String>,
+
+    /// The date of the upload in the format YYYY-MM-DD. E.g. 2025-09-18.
+    pub upload_date: Option<String>,
+}
+
+/// A reason why an extraction can't be stored.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValidationError {
+    /// The extracted value doesn't have the expected shape.
+    Malformed(String),
+    MissingTitle,
+    NoAuthors,
+    InvalidCoverUrl(String),
+    InvalidUploadDate(String),
+    /// Neither the upload date nor the submission date are available.
+    MissingCreationDate,
+}
+
+impl Display for ValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ValidationError::Malformed(e) => write!(f, "malformed extraction: {e}"),
+            ValidationError::MissingTitle => write!(f, "missing title"),
+            ValidationError::NoAuthors => write!(f, "no authors"),
+            ValidationError::InvalidCoverUrl(url) => write!(f, "invalid cover url: {url}"),
+            ValidationError::InvalidUploadDate(date) => write!(f, "invalid upload date: {date}"),
+            ValidationError::MissingCreationDate => {
+                write!(
+                    f,
+                    "neither the upload date nor the submission date are available"
+                )
+            }
+        }
+    }
+}
+
+/// An extraction that failed validation, together with the raw extracted value.
+#[derive(Debug, Clone)]
+pub struct InvalidExtraction {
+    pub raw: Value,
+    pub errors: Vec<ValidationError>,
+}
+
+impl Display for InvalidExtraction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let errors: Vec<String> = self.errors.iter().map(ToString::to_string).collect();
+        write!(f, "Invalid extraction: {}", errors.join(", "))
+    }
+}
+
+impl std::error::Error for InvalidExtraction {}
+
+impl ExtractedAudiobook {
+    /// Returns the JSON schema of the extraction, in the OpenAPI flavour understood by Gemini.
+    ///
+    /// # Panics
+    /// If the generated schema is not an object.
+    #[must_use]
+    pub fn schema() -> Value {
+        let generator = SchemaGenerator::new(SchemaSettings::openapi3().with(|s| {
+            s.inline_subschemas = true;
+            s.meta_schema = None;
+        }));
+        let mut schema = generator.into_root_schema_for::<ExtractedAudiobook>();
+        // Root schemas always include a title, which the LLM doesn't need.
+        schema.remove("title");
+        schema.to_value()
+    }
+
+    /// Deserializes and validates a raw extraction.
+    ///
+    /// # Errors
+    /// If the value doesn't have the expected shape or fails `validate`.
+    pub fn from_value(raw: Value) -> Result<Self, InvalidExtraction> {
+        let extracted: ExtractedAudiobook = match serde_json::from_value(raw.clone()) {
+            Ok(extracted) => extracted,
+            Err(e) => {
+                return Err(InvalidExtraction {
+                    raw,
+                    errors: vec![ValidationError::Malformed(e.to_string())],
+                });
+            }
+        };
+        extracted
+            .validate()
+            .map_err(|errors| InvalidExtraction { raw, errors })?;
+        Ok(extracted)
+    }
+
+    /// Checks that the extraction can be stored.
+    ///
+    /// # Errors
+    /// All the problems found in the extraction.
+    pub fn validate(&self) -> Result<(), Vec<ValidationError>> {
+        let mut errors = Vec::new();
+        if self.title.trim().is_empty() {
+            errors.push(ValidationError::MissingTitle);
+        }
+        if self.authors.iter().all(|author| author.trim().is_empty()) {
+            errors.push(ValidationError::NoAuthors);
+        }
+        if !self.cover_url.is_empty() {
+            let is_valid = Url::parse(&self.cover_url)
+                .is_ok_and(|url| url.scheme() == "http" || url.scheme() == "https");
+            if !is_valid {
+                errors.push(ValidationError::InvalidCoverUrl(self.cover_url.clone()));
+            }
+        }
+        if let Some(upload_date) = &self.upload_date
+            && NaiveDate::parse_from_str(upload_date, UPLOAD_DATE_FORMAT).is_err()
+        {
+            errors.push(ValidationError::InvalidUploadDate(upload_date.clone()));
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Returns the parsed upload date, if any.
+    #[must_use]
+    pub fn upload_date(&self) -> Option<NaiveDate> {
+        self.upload_date
+            .as_deref()
+            .and_then(|date| NaiveDate::parse_from_str(date, UPLOAD_DATE_FORMAT).ok())
+    }
+
+    /// Returns the upload date, or `submission_date` when the upload date is missing.
+    ///
+    /// # Errors
+    /// `InvalidExtraction` if neither date is available, retrying won't find one.
+    pub fn creation_date(
+        &self,
+        submission_date: Option<NaiveDate>,
+    ) -> Result<NaiveDate, InvalidExtraction> {
+        self.upload_date()
+            .or(submission_date)
+            .ok_or_else(|| InvalidExtraction {
+                raw: serde_json::to_value(self).unwrap_or_default(),
+                errors: vec![ValidationError::MissingCreationDate],
+            })
+    }
+
+    /// Returns the series title, if the audiobook is part of one.
+    #[must_use]
+    pub fn series_title(&self) -> Option<&str> {
+        self.series.as_deref().filter(|series| !series.is_empty())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn valid_extraction() -> Value {
+        json!({
+            "title": "The Well of Ascension",
+            "categories": ["Fantasy"],
+            "language": "English",
+            "keywords": [],
+            "cover_url": "https://example.com/cover.jpg",
+            "authors": ["Brandon Sanderson"],
+            "read_by": ["Michael Kramer"],
+            "format": "M4B",
+            "unabridged": true,
+            "description": "<p>Evil has been defeated.</p>",
+            "upload_date": "2025-09-18",
+        })
+    }
+
+    #[test]
+    fn test_schema_matches_struct() {
+        let schema = ExtractedAudiobook::schema();
+        assert_eq!(schema["type"], "object");
+        assert!(schema.get("title").is_none());
+        assert!(schema.get("$schema").is_none());
+        assert_eq!(
+            schema["properties"]["title"]["description"],
+            "The full title of the audiobook."
+        );
+        assert_eq!(schema["properties"]["series"]["nullable"], true);
+        let required = schema["required"].as_array().unwrap();
+        assert!(required.contains(&json!("authors")));
+        assert!(!required.contains(&json!("series")));
+    }
+
+    #[test]
+    fn test_from_value_accepts_valid_extraction() {
+        let extracted = ExtractedAudiobook::from_value(valid_extraction()).unwrap();
+        assert_eq!(extracted.title, "The Well of Ascension");
+        assert_eq!(extracted.series, None);
+        assert_eq!(
+            extracted.upload_date(),
+            NaiveDate::from_ymd_opt(2025, 9, 18)
+        );
+    }
+
+    #[test]
+    fn test_from_value_reports_validation_errors() {
+        let mut raw = valid_extraction();
+        raw["title"] = json!(" ");
+        raw["authors"] = json!([]);
+        raw["cover_url"] = json!("not a url");
+        raw["upload_date"] = json!("18/09/2025");
+
+        let invalid = ExtractedAudiobook::from_value(raw).unwrap_err();
+        assert_eq!(
+            invalid.errors,
+            vec![
+                ValidationError::MissingTitle,
+                ValidationError::NoAuthors,
+                ValidationError::InvalidCoverUrl("not a url".to_string()),
+                ValidationError::InvalidUploadDate("18/09/2025".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_from_value_reports_malformed_extraction() {
+        let mut raw = valid_extraction();
+        raw.as_object_mut().unwrap().remove("authors");
+
+        let invalid = ExtractedAudiobook::from_value(raw).unwrap_err();
+        assert!(matches!(
+            invalid.errors.as_slice(),
+            [ValidationError::Malformed(_)]
+        ));
+    }
+
+    #[test]
+    fn test_creation_date_falls_back_to_the_submission_date() {
+        let mut raw = valid_extraction();
+        raw["upload_date"] = Value::Null;
+        let extracted = ExtractedAudiobook::from_value(raw).unwrap();
+        let submission_date = NaiveDate::from_ymd_opt(2025, 9, 1);
+        assert_eq!(
+            extracted.creation_date(submission_date).ok(),
+            submission_date
+        );
+
+        let invalid = extracted.creation_date(None).unwrap_err();
+        assert_eq!(invalid.errors, vec![ValidationError::MissingCreationDate]);
+        assert_eq!(invalid.raw["title"], "The Well of Ascension");
+    }
+}
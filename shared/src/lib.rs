@@ -7,6 +7,8 @@ use entities_lib::Environment;
 pub mod auth_user;
 pub mod db_ops;
 pub mod db_trait;
+pub mod entity_names;
+pub mod extracted_audiobook;
 pub mod password_handler;
 pub mod private_args;
 pub mod sql_user;
@@ -1,7 +1,40 @@
 use clap;
-use clap::Parser;
+use clap::{Parser, ValueEnum};
+use entities_lib::RankingConfig;
 use entities_lib::entities::shareable_args::ShareableArgsValues;
 
+/// Strategy used to turn the scraped html into structured data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Extractor {
+    /// Only use the CSS-selector based extractor.
+    Selectors,
+    /// Only use the LLM.
+    Llm,
+    /// Use the CSS-selector based extractor, falling back to the LLM when it fails.
+    SelectorsThenLlm,
+}
+
+/// Provider used for text generation and embeddings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum LlmProvider {
+    /// Google Gemini.
+    Gemini,
+    /// Any server implementing the OpenAI API, e.g. Ollama or llama.cpp.
+    OpenaiCompatible,
+}
+
+/// Whether calls to the LLM provider and to the metadata providers are recorded to or replayed from
+/// cassettes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum CassetteMode {
+    /// Call the services directly.
+    Off,
+    /// Call the services and save the request/response pairs.
+    Record,
+    /// Serve the saved responses, failing on unmatched requests.
+    Replay,
+}
+
 #[derive(Debug, Parser, Clone)]
 #[command(version, about, long_about = None)]
 pub struct Args {
@@ -41,14 +74,85 @@ pub struct Args {
     #[arg(long, default_value_t=String::from("127.0.0.1:5433"))]
     pub pgmq_url: String,
 
+    /// Seconds a message stays invisible to other readers while it's being processed.
+    #[arg(long, default_value_t = 600)]
+    pub pgmq_visibility_timeout_seconds: i32,
+
+    /// Number of failed reads after which a message is moved to the dead-letter queue.
+    #[arg(long, default_value_t = 3)]
+    pub pgmq_max_retries: i32,
+
+    /// Number of workers processing the scraping queues concurrently.
+    #[arg(long, default_value_t = 4)]
+    pub scrape_workers: usize,
+
+    /// Requests per minute allowed to each audiobookbay mirror, shared by all the workers.
+    #[arg(long, default_value_t = 2.0, value_parser = parse_positive_f64)]
+    pub scrape_requests_per_minute: f64,
+
+    /// Number of requests that can be sent to a mirror back to back before being rate limited.
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..))]
+    pub scrape_burst: u32,
+
+    /// Maximum number of concurrent calls to the LLM provider.
+    #[arg(long, default_value_t = 2)]
+    pub llm_max_concurrent_requests: usize,
+
     #[clap(flatten)]
     pub shared: ShareableArgsValues,
 
+    /// Kept out of the shareable args, which are sent to the browsers.
+    #[clap(flatten)]
+    pub ranking: RankingConfig,
+
+    /// Provider used for text generation and embeddings.
+    #[arg(long, value_enum, default_value_t = LlmProvider::Gemini)]
+    pub llm_provider: LlmProvider,
+
+    /// Required when using the gemini provider.
     #[arg(long)]
-    pub gemini_api_key: String,
+    pub gemini_api_key: Option<String>,
+
+    /// Base URL of the OpenAI-compatible API.
+    #[arg(long, default_value_t=String::from("http://localhost:11434/v1"))]
+    pub openai_base_url: String,
 
     #[arg(long)]
-    pub hardcover_api_key: String,
+    pub openai_api_key: Option<String>,
+
+    #[arg(long, default_value_t=String::from("llama3.1"))]
+    pub openai_generation_model_name: String,
+
+    #[arg(long, default_value_t=String::from("nomic-embed-text"))]
+    pub openai_embedding_model_name: String,
+
+    /// Without it the audiobooks aren't matched on Hardcover, and the Hardcover commands fail.
+    #[arg(long)]
+    pub hardcover_api_key: Option<String>,
+
+    /// Metadata matches (Hardcover, Open Library) scoring below this, in [0, 1], are rejected.
+    #[arg(long, default_value_t = 0.6)]
+    pub metadata_min_match_score: f64,
+
+    /// Base URL of the Open Library API.
+    #[arg(long, default_value_t=String::from("https://openlibrary.org"))]
+    pub open_library_base_url: String,
+
+    /// Don't look the audiobooks up on Open Library.
+    #[arg(long)]
+    pub disable_open_library: bool,
+
+    /// Records or replays the calls to the LLM provider and to the metadata providers.
+    #[arg(long, value_enum, default_value_t = CassetteMode::Off)]
+    pub cassette_mode: CassetteMode,
+
+    /// Directory where the cassettes are stored.
+    #[arg(long, default_value_t=String::from("cassettes"))]
+    pub cassette_dir: String,
+
+    /// How to extract the audiobook data from the scraped pages.
+    #[arg(long, value_enum, default_value_t = Extractor::Llm)]
+    pub extractor: Extractor,
 
     #[arg(long, default_value_t = 365)]
     pub cookie_and_session_duration_days: i64,
@@ -72,4 +176,22 @@ pub struct Args {
     #[arg(long, default_value_t = 1)]
     #[arg(default_value_if("environment", "prod", "360"))]
     pub meta_cache_max_capacity: u64,
+
+    /// How long the ranked results of a search are kept for its other pages, in seconds.
+    #[arg(long, default_value_t = 5)]
+    #[arg(default_value_if("environment", "prod", "600"))]
+    pub search_cache_ttl: u64,
+
+    #[arg(long, default_value_t = 5)]
+    #[arg(default_value_if("environment", "prod", "1000"))]
+    pub search_cache_max_capacity: u64,
+}
+
+/// Parses a number greater than zero, e.g. a rate that is divided by.
+fn parse_positive_f64(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(number) if number > 0.0 && number.is_finite() => Ok(number),
+        Ok(_) => Err(format!("{value} is not a positive number")),
+        Err(e) => Err(e.to_string()),
+    }
 }
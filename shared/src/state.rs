@@ -4,7 +4,7 @@ use std::sync::Arc;
 use argon2::Argon2;
 use axum::extract::FromRef;
 use entities_lib::entities::meta_request::{MetaRequest, MetaResponse};
-use entities_lib::{AudiobookWithData, GetAudioBookRequestType};
+use entities_lib::{AudiobookWithData, GetAudioBookRequestType, LanguageFilter, SearchQuery};
 use leptos::prelude::{LeptosOptions, ServerFnError, use_context};
 use leptos_axum::AxumRouteListing;
 use moka::future::Cache;
@@ -12,10 +12,11 @@ use reqwest::Client;
 use sqlx::postgres::PgPool;
 
 use crate::db_ops::AppError;
+use crate::db_ops::parade::search_ops::RankedSearch;
 use crate::db_trait::DbConnectionLike;
 use crate::password_handler::PasswordHandlerLike;
-use crate::utils::gemini::{GeminiContentEmbedderLike, GeminiContentGeneratorLike};
-use entities_lib::ShareableArgsValues;
+use crate::utils::llm::{ContentEmbedder, ContentGenerator};
+use entities_lib::{RankingConfig, ShareableArgsValues};
 /// This takes advantage of Axum's `SubStates` feature by deriving `FromRef`. This is the only way to have more than one
 /// item in Axum's State. Leptos requires you to have leptosOptions in your State struct for the leptos route handlers
 #[derive(Clone, FromRef)]
@@ -24,12 +25,15 @@ pub struct AppState {
     pub database_connection_pool: PgPool,
     pub routes: Vec<AxumRouteListing>,
     pub password_handler: Argon2<'static>,
-    pub audiobooks_cache: Cache<GetAudioBookRequestType, Result<Vec<AudiobookWithData>, AppError>>,
-    pub meta_requests_cache: Cache<MetaRequest, Result<MetaResponse, AppError>>,
+    pub audiobooks_cache:
+        Cache<(GetAudioBookRequestType, LanguageFilter), Result<Vec<AudiobookWithData>, AppError>>,
+    pub meta_requests_cache: Cache<(MetaRequest, LanguageFilter), Result<MetaResponse, AppError>>,
+    pub search_cache: Cache<SearchQuery, Arc<RankedSearch>>,
     pub shareable_args: ShareableArgsValues,
+    pub ranking: RankingConfig,
     pub http_client: Client,
-    pub embedder: Arc<dyn GeminiContentEmbedderLike>,
-    pub content_generator: Arc<dyn GeminiContentGeneratorLike>,
+    pub embedder: Arc<dyn ContentEmbedder>,
+    pub content_generator: Arc<dyn ContentGenerator>,
 }
 
 impl AppState {
@@ -0,0 +1,289 @@
+//! Record/replay layer for the calls to external services.
+//!
+//! In record mode every request/response pair is saved to a JSON file in `Args::cassette_dir`.
+//! In replay mode the responses are served from those files and unmatched requests fail, so
+//! tests can run end to end without network access or API keys.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, LazyLock, Mutex};
+
+use anyhow::{Error, anyhow};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use tracing::debug;
+
+use crate::private_args::CassetteMode;
+use crate::utils::llm::{ContentEmbedder, ContentGenerator, EmbeddingTask};
+
+/// Cassettes opened by this process, so that every recorder of a file appends to the same list.
+static OPEN_CASSETTES: LazyLock<Mutex<HashMap<PathBuf, Arc<Cassette>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Interaction {
+    kind: String,
+    request: Value,
+    response: Value,
+}
+
+/// A file of recorded interactions with an external service.
+pub struct Cassette {
+    path: PathBuf,
+    mode: CassetteMode,
+    /// Held across the file write so that concurrent recordings are written in order.
+    interactions: tokio::sync::Mutex<Vec<Interaction>>,
+}
+
+impl Cassette {
+    /// Opens the cassette `<dir>/<name>.json`.
+    ///
+    /// # Errors
+    /// - If the mode is `Off`.
+    /// - If the cassette can't be read, or doesn't exist in replay mode.
+    ///
+    /// # Panics
+    /// If the lock on the open cassettes is poisoned.
+    pub fn open(dir: &str, name: &str, mode: CassetteMode) -> Result<Arc<Cassette>, Error> {
+        if mode == CassetteMode::Off {
+            return Err(anyhow!("Cassettes are turned off"));
+        }
+        let path = Path::new(dir).join(format!("{name}.json"));
+        let mut open_cassettes = OPEN_CASSETTES.lock().unwrap();
+        if let Some(cassette) = open_cassettes.get(&path) {
+            return Ok(cassette.clone());
+        }
+
+        let interactions = match std::fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound && mode == CassetteMode::Record => {
+                Vec::new()
+            }
+            Err(e) => return Err(anyhow!("Couldn't read cassette {}: {e}", path.display())),
+        };
+        let cassette = Arc::new(Cassette {
+            path: path.clone(),
+            mode,
+            interactions: tokio::sync::Mutex::new(interactions),
+        });
+        open_cassettes.insert(path, cassette.clone());
+        Ok(cassette)
+    }
+
+    /// Returns the recorded response to `request`, or runs `call` and records its response.
+    ///
+    /// # Errors
+    /// - In replay mode, if no interaction matches `request`.
+    /// - In record mode, if `call` fails or the cassette can't be written.
+    pub async fn call<F, Fut>(&self, kind: &str, request: Value, call: F) -> Result<Value, Error>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<Value, Error>>, {
+        if self.mode == CassetteMode::Replay {
+            return self
+                .interactions
+                .lock()
+                .await
+                .iter()
+                .find(|i| i.kind == kind && i.request == request)
+                .map(|i| i.response.clone())
+                .ok_or_else(|| {
+                    anyhow!(
+                        "No {kind} interaction in {} matches the request {request}",
+                        self.path.display()
+                    )
+                });
+        }
+
+        let response = call().await?;
+        let mut interactions = self.interactions.lock().await;
+        interactions.retain(|i| !(i.kind == kind && i.request == request));
+        interactions.push(Interaction {
+            kind: kind.to_string(),
+            request,
+            response: response.clone(),
+        });
+        let serialized = serde_json::to_string_pretty(&*interactions)? + "\n";
+        if let Some(parent) = self.path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::write(&self.path, serialized).await?;
+        drop(interactions);
+        debug!("Recorded {} interaction in {}", kind, self.path.display());
+        Ok(response)
+    }
+}
+
+/// Content generator that records or replays the calls made to `inner`.
+pub struct CassetteContentGenerator {
+    cassette: Arc<Cassette>,
+    /// `None` in replay mode.
+    inner: Option<Arc<dyn ContentGenerator>>,
+}
+
+impl CassetteContentGenerator {
+    #[must_use]
+    pub fn new(cassette: Arc<Cassette>, inner: Option<Arc<dyn ContentGenerator>>) -> Self {
+        Self { cassette, inner }
+    }
+
+    fn inner(&self) -> Result<&Arc<dyn ContentGenerator>, Error> {
+        self.inner
+            .as_ref()
+            .ok_or_else(|| anyhow!("No content generator to record from"))
+    }
+}
+
+#[async_trait]
+impl ContentGenerator for CassetteContentGenerator {
+    async fn generate_content(&self, prompt: &str) -> Result<String, Error> {
+        let response = self
+            .cassette
+            .call("generate_content", json!({ "prompt": prompt }), || async {
+                Ok(Value::String(self.inner()?.generate_content(prompt).await?))
+            })
+            .await?;
+        response
+            .as_str()
+            .map(ToString::to_string)
+            .ok_or_else(|| anyhow!("The recorded response is not a string"))
+    }
+
+    async fn generate_structured_content(
+        &self,
+        prompt: &str,
+        schema: Value,
+    ) -> Result<Value, Error> {
+        let request = json!({ "prompt": prompt, "schema": schema });
+        self.cassette
+            .call("generate_structured_content", request, || async {
+                self.inner()?
+                    .generate_structured_content(prompt, schema)
+                    .await
+            })
+            .await
+    }
+}
+
+/// Content embedder that records or replays the calls made to `inner`.
+pub struct CassetteContentEmbedder {
+    cassette: Arc<Cassette>,
+    /// `None` in replay mode.
+    inner: Option<Arc<dyn ContentEmbedder>>,
+}
+
+impl CassetteContentEmbedder {
+    #[must_use]
+    pub fn new(cassette: Arc<Cassette>, inner: Option<Arc<dyn ContentEmbedder>>) -> Self {
+        Self { cassette, inner }
+    }
+}
+
+#[async_trait]
+impl ContentEmbedder for CassetteContentEmbedder {
+    async fn embed_content(
+        &self,
+        content: &str,
+        task_type: EmbeddingTask,
+        embedding_dimension: u16,
+    ) -> Result<Vec<f32>, Error> {
+        let request = json!({
+            "content": content,
+            "task_type": format!("{task_type:?}"),
+            "embedding_dimension": embedding_dimension,
+        });
+        let response = self
+            .cassette
+            .call("embed_content", request, || async {
+                let inner = self
+                    .inner
+                    .as_ref()
+                    .ok_or_else(|| anyhow!("No content embedder to record from"))?;
+                let embedding = inner
+                    .embed_content(content, task_type, embedding_dimension)
+                    .await?;
+                Ok(serde_json::to_value(embedding)?)
+            })
+            .await?;
+        Ok(serde_json::from_value(response)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct EchoGenerator;
+
+    #[async_trait]
+    impl ContentGenerator for EchoGenerator {
+        async fn generate_content(&self, prompt: &str) -> Result<String, Error> {
+            Ok(format!("echo: {prompt}"))
+        }
+
+        async fn generate_structured_content(
+            &self,
+            prompt: &str,
+            _schema: Value,
+        ) -> Result<Value, Error> {
+            Ok(json!({ "prompt": prompt }))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_record_then_replay() {
+        let dir = std::env::temp_dir().join(format!("cassette-test-{}", std::process::id()));
+        let dir = dir.to_str().unwrap();
+
+        let recorder = CassetteContentGenerator::new(
+            Cassette::open(dir, "record", CassetteMode::Record).unwrap(),
+            Some(Arc::new(EchoGenerator)),
+        );
+        assert_eq!(
+            recorder.generate_content("hello").await.unwrap(),
+            "echo: hello"
+        );
+
+        // Cassettes are cached per process, so go through a copy to actually read the file back.
+        std::fs::copy(
+            Path::new(dir).join("record.json"),
+            Path::new(dir).join("replay.json"),
+        )
+        .unwrap();
+        let replayer = CassetteContentGenerator::new(
+            Cassette::open(dir, "replay", CassetteMode::Replay).unwrap(),
+            None,
+        );
+        assert_eq!(
+            replayer.generate_content("hello").await.unwrap(),
+            "echo: hello"
+        );
+        assert!(replayer.generate_content("unknown").await.is_err());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_recordings_are_all_written() {
+        let dir =
+            std::env::temp_dir().join(format!("cassette-concurrent-test-{}", std::process::id()));
+        let dir = dir.to_str().unwrap();
+
+        let recorder = CassetteContentGenerator::new(
+            Cassette::open(dir, "record", CassetteMode::Record).unwrap(),
+            Some(Arc::new(EchoGenerator)),
+        );
+        let prompts: Vec<String> = (0..20).map(|i| format!("prompt {i}")).collect();
+        futures_util::future::try_join_all(prompts.iter().map(|p| recorder.generate_content(p)))
+            .await
+            .unwrap();
+
+        let content = std::fs::read_to_string(Path::new(dir).join("record.json")).unwrap();
+        let interactions: Vec<Interaction> = serde_json::from_str(&content).unwrap();
+        assert_eq!(interactions.len(), prompts.len());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -1,35 +1,12 @@
 use anyhow::Error;
 use async_trait::async_trait;
 use gemini_rust::{Gemini, GenerationConfig, TaskType};
-use ndarray::Array1;
-use ndarray_linalg::Norm;
-use tracing::{error, info};
+use tracing::info;
 
-#[async_trait]
-pub trait GeminiContentGeneratorLike: Send + Sync {
-    /// Generate a textual response.
-    async fn generate_content(&self, prompt: &str) -> Result<String, Error>;
-
-    /// Generates a structured response.
-    async fn generate_structured_content(
-        &self,
-        prompt: &str,
-        schema: serde_json::Value,
-    ) -> Result<serde_json::Value, Error>;
-}
+use crate::utils::llm::{ContentEmbedder, ContentGenerator, EmbeddingTask, normalize_embedding};
 
 #[async_trait]
-pub trait GeminiContentEmbedderLike: Send + Sync {
-    async fn embed_content(
-        &self,
-        content: &str,
-        task_type: &TaskType,
-        embedding_dimension: u16,
-    ) -> Result<Vec<f32>, Error>;
-}
-
-#[async_trait]
-impl GeminiContentGeneratorLike for Gemini {
+impl ContentGenerator for Gemini {
     async fn generate_content(&self, prompt: &str) -> Result<String, Error> {
         let response = self
             .generate_content()
@@ -70,30 +47,25 @@ impl GeminiContentGeneratorLike for Gemini {
 }
 
 #[async_trait]
-impl GeminiContentEmbedderLike for Gemini {
+impl ContentEmbedder for Gemini {
     async fn embed_content(
         &self,
         content: &str,
-        task_type: &TaskType,
+        task_type: EmbeddingTask,
         embedding_dimension: u16,
     ) -> Result<Vec<f32>, Error> {
+        let task_type = match task_type {
+            EmbeddingTask::RetrievalDocument => TaskType::RetrievalDocument,
+            EmbeddingTask::RetrievalQuery => TaskType::RetrievalQuery,
+        };
         let response = self
             .embed_content()
             .with_text(content)
-            .with_task_type(task_type.to_owned())
+            .with_task_type(task_type)
             .with_output_dimensionality(i32::from(embedding_dimension))
             .execute()
             .await?;
 
-        let embeddings = Array1::from(response.embedding.values);
-
-        let norm = embeddings.norm_l2();
-
-        if norm.abs() <= 0.0001 {
-            error!("The input vector is a zero vector and cannot be normalized.");
-            return Ok(embeddings.to_vec());
-        }
-        let normed_embedding = &embeddings / norm;
-        Ok(normed_embedding.to_vec())
+        Ok(normalize_embedding(response.embedding.values))
     }
 }
@@ -0,0 +1,193 @@
+//! Provider-agnostic interfaces for text generation and embeddings.
+//!
+//! The concrete provider is picked through `Args::llm_provider`, see `build_content_generator`
+//! and `build_content_embedder`. Both can be wrapped in a cassette, see `utils::cassette`, and
+//! share a concurrency limit, see `ConcurrencyLimitedContentGenerator`.
+
+use std::sync::Arc;
+
+use anyhow::{Error, anyhow};
+use async_trait::async_trait;
+use gemini_rust::Gemini;
+use ndarray::Array1;
+use ndarray_linalg::Norm;
+use tokio::sync::Semaphore;
+use tracing::error;
+
+use crate::private_args::{Args, CassetteMode, LlmProvider};
+use crate::utils::cassette::{Cassette, CassetteContentEmbedder, CassetteContentGenerator};
+use crate::utils::openai_compatible::OpenAiCompatibleClient;
+
+/// What an embedding is going to be used for. Providers that don't support task types ignore it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmbeddingTask {
+    RetrievalDocument,
+    RetrievalQuery,
+}
+
+#[async_trait]
+pub trait ContentGenerator: Send + Sync {
+    /// Generate a textual response.
+    async fn generate_content(&self, prompt: &str) -> Result<String, Error>;
+
+    /// Generates a structured response following the JSON `schema`.
+    async fn generate_structured_content(
+        &self,
+        prompt: &str,
+        schema: serde_json::Value,
+    ) -> Result<serde_json::Value, Error>;
+}
+
+#[async_trait]
+pub trait ContentEmbedder: Send + Sync {
+    /// Embeds `content` into a L2-normalized vector of `embedding_dimension` values.
+    async fn embed_content(
+        &self,
+        content: &str,
+        task_type: EmbeddingTask,
+        embedding_dimension: u16,
+    ) -> Result<Vec<f32>, Error>;
+}
+
+/// Limits the number of in-flight calls to the wrapped generator.
+///
+/// Sharing the same `permits` with a `ConcurrencyLimitedContentEmbedder` makes the generator and
+/// the embedder share the limit.
+pub struct ConcurrencyLimitedContentGenerator {
+    inner: Arc<dyn ContentGenerator>,
+    permits: Arc<Semaphore>,
+}
+
+impl ConcurrencyLimitedContentGenerator {
+    #[must_use]
+    pub fn new(inner: Arc<dyn ContentGenerator>, permits: Arc<Semaphore>) -> Self {
+        ConcurrencyLimitedContentGenerator { inner, permits }
+    }
+}
+
+#[async_trait]
+impl ContentGenerator for ConcurrencyLimitedContentGenerator {
+    async fn generate_content(&self, prompt: &str) -> Result<String, Error> {
+        let _permit = self.permits.acquire().await?;
+        self.inner.generate_content(prompt).await
+    }
+
+    async fn generate_structured_content(
+        &self,
+        prompt: &str,
+        schema: serde_json::Value,
+    ) -> Result<serde_json::Value, Error> {
+        let _permit = self.permits.acquire().await?;
+        self.inner.generate_structured_content(prompt, schema).await
+    }
+}
+
+/// Limits the number of in-flight calls to the wrapped embedder.
+pub struct ConcurrencyLimitedContentEmbedder {
+    inner: Arc<dyn ContentEmbedder>,
+    permits: Arc<Semaphore>,
+}
+
+impl ConcurrencyLimitedContentEmbedder {
+    #[must_use]
+    pub fn new(inner: Arc<dyn ContentEmbedder>, permits: Arc<Semaphore>) -> Self {
+        ConcurrencyLimitedContentEmbedder { inner, permits }
+    }
+}
+
+#[async_trait]
+impl ContentEmbedder for ConcurrencyLimitedContentEmbedder {
+    async fn embed_content(
+        &self,
+        content: &str,
+        task_type: EmbeddingTask,
+        embedding_dimension: u16,
+    ) -> Result<Vec<f32>, Error> {
+        let _permit = self.permits.acquire().await?;
+        self.inner
+            .embed_content(content, task_type, embedding_dimension)
+            .await
+    }
+}
+
+/// Builds the content generator of the configured provider, wrapped in a cassette if
+/// `args.cassette_mode` is not `Off`.
+///
+/// # Errors
+/// - If the provider is misconfigured, e.g. the Gemini API key is missing.
+/// - If the cassette can't be opened.
+pub fn build_content_generator(args: &Args) -> Result<Arc<dyn ContentGenerator>, Error> {
+    let inner = match args.cassette_mode {
+        CassetteMode::Off => return build_provider_content_generator(args),
+        CassetteMode::Record => Some(build_provider_content_generator(args)?),
+        CassetteMode::Replay => None,
+    };
+    let cassette = Cassette::open(&args.cassette_dir, "content_generator", args.cassette_mode)?;
+    Ok(Arc::new(CassetteContentGenerator::new(cassette, inner)))
+}
+
+/// Builds the content embedder of the configured provider, wrapped in a cassette if
+/// `args.cassette_mode` is not `Off`.
+///
+/// # Errors
+/// - If the provider is misconfigured, e.g. the Gemini API key is missing.
+/// - If the cassette can't be opened.
+pub fn build_content_embedder(args: &Args) -> Result<Arc<dyn ContentEmbedder>, Error> {
+    let inner = match args.cassette_mode {
+        CassetteMode::Off => return build_provider_content_embedder(args),
+        CassetteMode::Record => Some(build_provider_content_embedder(args)?),
+        CassetteMode::Replay => None,
+    };
+    let cassette = Cassette::open(&args.cassette_dir, "content_embedder", args.cassette_mode)?;
+    Ok(Arc::new(CassetteContentEmbedder::new(cassette, inner)))
+}
+
+fn build_provider_content_generator(args: &Args) -> Result<Arc<dyn ContentGenerator>, Error> {
+    match args.llm_provider {
+        LlmProvider::Gemini => Ok(Arc::new(build_gemini(
+            args,
+            &args.shared.gemini_extract_html_model_name,
+        )?)),
+        LlmProvider::OpenaiCompatible => Ok(Arc::new(OpenAiCompatibleClient::new(
+            &args.openai_base_url,
+            args.openai_api_key.clone(),
+            &args.openai_generation_model_name,
+        ))),
+    }
+}
+
+fn build_provider_content_embedder(args: &Args) -> Result<Arc<dyn ContentEmbedder>, Error> {
+    match args.llm_provider {
+        LlmProvider::Gemini => Ok(Arc::new(build_gemini(
+            args,
+            &args.shared.gemini_embedding_model_name,
+        )?)),
+        LlmProvider::OpenaiCompatible => Ok(Arc::new(OpenAiCompatibleClient::new(
+            &args.openai_base_url,
+            args.openai_api_key.clone(),
+            &args.openai_embedding_model_name,
+        ))),
+    }
+}
+
+fn build_gemini(args: &Args, model_name: &str) -> Result<Gemini, Error> {
+    let api_key = args
+        .gemini_api_key
+        .as_ref()
+        .ok_or_else(|| anyhow!("--gemini-api-key is required when using the gemini provider"))?;
+    Ok(Gemini::with_model(api_key, model_name.to_string())?)
+}
+
+/// Scales `embedding` to unit length. Zero vectors are returned unchanged.
+#[must_use]
+pub fn normalize_embedding(embedding: Vec<f32>) -> Vec<f32> {
+    let embeddings = Array1::from(embedding);
+    let norm = embeddings.norm_l2();
+
+    if norm.abs() <= 0.0001 {
+        error!("The input vector is a zero vector and cannot be normalized.");
+        return embeddings.to_vec();
+    }
+    let normed_embedding = &embeddings / norm;
+    normed_embedding.to_vec()
+}
@@ -1 +1,4 @@
+pub mod cassette;
 pub mod gemini;
+pub mod llm;
+pub mod openai_compatible;
@@ -0,0 +1,227 @@
+//! Client for any server implementing the OpenAI chat completions and embeddings endpoints,
+//! e.g. a local Ollama or llama.cpp server.
+
+use anyhow::{Error, anyhow};
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::{debug, info};
+
+use crate::utils::llm::{ContentEmbedder, ContentGenerator, EmbeddingTask, normalize_embedding};
+
+pub struct OpenAiCompatibleClient {
+    client: Client,
+    base_url: String,
+    api_key: Option<String>,
+    model: String,
+}
+
+#[derive(Serialize)]
+struct ChatMessage<'a> {
+    role: &'a str,
+    content: &'a str,
+}
+
+#[derive(Deserialize)]
+struct ChatCompletionResponse {
+    choices: Vec<ChatChoice>,
+    usage: Option<serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+struct ChatChoice {
+    message: ChatResponseMessage,
+}
+
+#[derive(Deserialize)]
+struct ChatResponseMessage {
+    content: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct EmbeddingResponse {
+    data: Vec<EmbeddingData>,
+}
+
+#[derive(Deserialize)]
+struct EmbeddingData {
+    embedding: Vec<f32>,
+}
+
+impl OpenAiCompatibleClient {
+    /// Creates a client for `model` served at `base_url`, e.g. `http://localhost:11434/v1`.
+    #[must_use]
+    pub fn new(base_url: &str, api_key: Option<String>, model: &str) -> Self {
+        Self {
+            client: Client::new(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_key,
+            model: model.to_string(),
+        }
+    }
+
+    async fn post(
+        &self,
+        endpoint: &str,
+        body: &serde_json::Value,
+    ) -> Result<reqwest::Response, Error> {
+        let mut request = self
+            .client
+            .post(format!("{}/{endpoint}", self.base_url))
+            .json(body);
+        if let Some(api_key) = &self.api_key {
+            request = request.bearer_auth(api_key);
+        }
+        let response = request.send().await?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let text = response.text().await.unwrap_or_default();
+            return Err(anyhow!(
+                "Request to {endpoint} failed with status {status}: {text}"
+            ));
+        }
+        Ok(response)
+    }
+
+    async fn chat(&self, body: serde_json::Value) -> Result<String, Error> {
+        let response: ChatCompletionResponse =
+            self.post("chat/completions", &body).await?.json().await?;
+        if let Some(usage) = &response.usage {
+            info!("response usage: {:?}", usage);
+        }
+        response
+            .choices
+            .into_iter()
+            .next()
+            .and_then(|choice| choice.message.content)
+            .ok_or_else(|| anyhow!("The response didn't contain any message"))
+    }
+}
+
+#[async_trait]
+impl ContentGenerator for OpenAiCompatibleClient {
+    async fn generate_content(&self, prompt: &str) -> Result<String, Error> {
+        self.chat(json!({
+            "model": self.model,
+            "messages": [ChatMessage { role: "user", content: prompt }],
+        }))
+        .await
+    }
+
+    async fn generate_structured_content(
+        &self,
+        prompt: &str,
+        schema: serde_json::Value,
+    ) -> Result<serde_json::Value, Error> {
+        let text = self
+            .chat(json!({
+                "model": self.model,
+                "messages": [ChatMessage { role: "user", content: prompt }],
+                "response_format": {
+                    "type": "json_schema",
+                    "json_schema": { "name": "response", "schema": schema },
+                },
+            }))
+            .await?;
+        debug!("Structured response: {}", text);
+        Ok(serde_json::from_str(&text)?)
+    }
+}
+
+#[async_trait]
+impl ContentEmbedder for OpenAiCompatibleClient {
+    async fn embed_content(
+        &self,
+        content: &str,
+        _task_type: EmbeddingTask,
+        embedding_dimension: u16,
+    ) -> Result<Vec<f32>, Error> {
+        let response: EmbeddingResponse = self
+            .post(
+                "embeddings",
+                &json!({
+                    "model": self.model,
+                    "input": content,
+                    "dimensions": embedding_dimension,
+                }),
+            )
+            .await?
+            .json()
+            .await?;
+        let mut embedding = response
+            .data
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow!("The response didn't contain any embedding"))?
+            .embedding;
+
+        // Not every server honours `dimensions`. Truncating only makes sense for models trained
+        // with Matryoshka representations, but it's better than failing on the vector column.
+        let embedding_dimension = usize::from(embedding_dimension);
+        if embedding.len() < embedding_dimension {
+            return Err(anyhow!(
+                "Expected an embedding of size {embedding_dimension}, got {}",
+                embedding.len()
+            ));
+        }
+        embedding.truncate(embedding_dimension);
+        Ok(normalize_embedding(embedding))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{body_partial_json, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_generate_structured_content() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .and(header("authorization", "Bearer key"))
+            .and(body_partial_json(json!({"model": "llama"})))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "choices": [{"message": {"role": "assistant", "content": "{\"title\": \"Dune\"}"}}]
+            })))
+            .mount(&mock_server)
+            .await;
+
+        let client = OpenAiCompatibleClient::new(
+            &format!("{}/v1/", mock_server.uri()),
+            Some("key".to_string()),
+            "llama",
+        );
+        let result = client
+            .generate_structured_content("prompt", json!({"type": "object"}))
+            .await
+            .unwrap();
+        assert_eq!(result, json!({"title": "Dune"}));
+    }
+
+    #[tokio::test]
+    async fn test_embed_content_truncates_and_normalizes() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/embeddings"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "data": [{"embedding": [3.0, 4.0, 12.0]}]
+            })))
+            .mount(&mock_server)
+            .await;
+
+        let client = OpenAiCompatibleClient::new(&mock_server.uri(), None, "nomic-embed-text");
+        let embedding = client
+            .embed_content("content", EmbeddingTask::RetrievalDocument, 2)
+            .await
+            .unwrap();
+        assert_eq!(embedding, vec![0.6, 0.8]);
+
+        let too_short = client
+            .embed_content("content", EmbeddingTask::RetrievalDocument, 4)
+            .await;
+        assert!(too_short.is_err());
+    }
+}